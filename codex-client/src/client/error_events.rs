@@ -0,0 +1,126 @@
+use serde::Serialize;
+use serde_json::Value;
+
+/// Coarse classification of an error reported by codex mid-turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum CodexErrorCategory {
+    RateLimit,
+    Auth,
+    ToolFailure,
+    Unknown,
+}
+
+impl CodexErrorCategory {
+    pub fn is_retryable(self) -> bool {
+        matches!(self, CodexErrorCategory::RateLimit | CodexErrorCategory::ToolFailure)
+    }
+}
+
+/// Payload of the `codex-error` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodexErrorPayload {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<String>,
+    pub category: CodexErrorCategory,
+    pub message: String,
+    pub retryable: bool,
+}
+
+/// Classify an error message, preferring codex's structured error info when
+/// the installed version provides it.
+pub(crate) fn classify_error(message: &str, error_info: Option<&str>) -> CodexErrorCategory {
+    let haystack = format!("{} {}", error_info.unwrap_or_default(), message).to_lowercase();
+    let contains_any = |needles: &[&str]| needles.iter().any(|needle| haystack.contains(needle));
+
+    if contains_any(&[
+        "rate limit",
+        "rate_limit",
+        "usage limit",
+        "usage_limit",
+        "quota",
+        "429",
+        "too many requests",
+    ]) {
+        CodexErrorCategory::RateLimit
+    } else if contains_any(&[
+        "unauthorized",
+        "401",
+        "403",
+        "forbidden",
+        "auth",
+        "login",
+        "api key",
+        "token expired",
+    ]) {
+        CodexErrorCategory::Auth
+    } else if contains_any(&["tool", "exec", "sandbox", "patch", "mcp"]) {
+        CodexErrorCategory::ToolFailure
+    } else {
+        CodexErrorCategory::Unknown
+    }
+}
+
+/// Build a `codex-error` payload from a `codex/event/*` notification, if the
+/// event is an error.
+pub(crate) fn error_payload_from_event(params: &Value) -> Option<CodexErrorPayload> {
+    let msg = &params["msg"];
+    let is_stream_error = match msg["type"].as_str()? {
+        "error" => false,
+        "stream_error" => true,
+        _ => return None,
+    };
+
+    let message = msg["message"].as_str().unwrap_or_default().to_string();
+    let error_info = match &msg["codex_error_info"] {
+        Value::Null => None,
+        Value::String(info) => Some(info.clone()),
+        other => Some(other.to_string()),
+    };
+    let category = classify_error(&message, error_info.as_deref());
+    Some(CodexErrorPayload {
+        conversation_id: params["conversationId"].as_str().map(String::from),
+        category,
+        // codex retries stream errors on its own, so they are always transient
+        retryable: is_stream_error || category.is_retryable(),
+        message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_classify_error() {
+        assert_eq!(
+            classify_error("You've hit your usage limit", None),
+            CodexErrorCategory::RateLimit
+        );
+        assert_eq!(
+            classify_error("unexpected status 401 Unauthorized", None),
+            CodexErrorCategory::Auth
+        );
+        assert_eq!(
+            classify_error("exec command failed", None),
+            CodexErrorCategory::ToolFailure
+        );
+        assert_eq!(classify_error("boom", None), CodexErrorCategory::Unknown);
+    }
+
+    #[test]
+    fn test_error_payload_from_event() {
+        let params = json!({
+            "conversationId": "abc",
+            "msg": { "type": "error", "message": "429 Too Many Requests" }
+        });
+        let payload = error_payload_from_event(&params).unwrap();
+        assert_eq!(payload.category, CodexErrorCategory::RateLimit);
+        assert!(payload.retryable);
+        assert_eq!(payload.conversation_id.as_deref(), Some("abc"));
+
+        let not_error = json!({ "msg": { "type": "task_started" } });
+        assert!(error_payload_from_event(&not_error).is_none());
+    }
+}
@@ -16,15 +16,19 @@ use codex_app_server_protocol::{
 };
 use log::{debug, error, info, warn};
 use serde::Serialize;
-use serde_json::Value;
+use serde_json::{json, Value};
 use tokio::process::ChildStdin;
 use tokio::sync::Mutex;
 
+use codex_protocol::protocol::ReviewDecision;
 use codex_protocol::ConversationId;
 
 use crate::events::EventBus;
-use super::transport::send_error;
-use super::{PendingRequestKind, PendingServerRequest, PendingServerRequestMap};
+use crate::session::{is_output_truncated, SessionRegistry};
+use super::error_events::{error_payload_from_event, CodexErrorCategory};
+use super::protocol_check::{MismatchKind, ProtocolMonitor};
+use super::transport::{respond_with_review_decision, send_error};
+use super::{ExecApprovalDetails, PendingRequestKind, PendingServerRequest, PendingServerRequestMap};
 
 #[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -48,9 +52,72 @@ struct ApplyPatchApprovalNotification {
     params: ApplyPatchApprovalParams,
 }
 
-pub(super) async fn handle_notification(notification: JSONRPCNotification, event_bus: &EventBus) {
+/// Keep the session registry in sync with the turn lifecycle events and
+/// the tools codex reports
+async fn track_turn_state(params: &Value, sessions: &SessionRegistry) {
+    let Some(conversation_id) = params["conversationId"].as_str() else {
+        return;
+    };
+    match params["msg"]["type"].as_str() {
+        Some("task_started") => sessions.begin_turn(conversation_id).await,
+        // codex ends the turn on an error event without a task_complete
+        Some("task_complete") | Some("turn_aborted") | Some("error") => {
+            sessions.end_turn(conversation_id).await
+        }
+        Some("token_count") => {
+            sessions
+                .record_token_usage(conversation_id, &params["msg"]["info"])
+                .await
+        }
+        Some("session_configured") => {
+            sessions
+                .record_runtime_settings(conversation_id, &params["msg"])
+                .await
+        }
+        Some("mcp_list_tools_response") => {
+            sessions
+                .record_mcp_tools(conversation_id, &params["msg"]["tools"])
+                .await
+        }
+        _ => {}
+    }
+}
+
+/// Keep the session registry in sync with the turns of a v2 thread
+async fn track_thread_state(method: &str, params: &Value, sessions: &SessionRegistry) {
+    let Some(thread_id) = params["threadId"].as_str() else {
+        return;
+    };
+    match method {
+        "turn/started" => sessions.begin_turn(thread_id).await,
+        "turn/completed" => sessions.end_turn(thread_id).await,
+        "error" if !params["willRetry"].as_bool().unwrap_or(false) => {
+            sessions.end_turn(thread_id).await
+        }
+        _ => {}
+    }
+}
+
+pub(super) async fn handle_notification(
+    notification: JSONRPCNotification,
+    event_bus: &EventBus,
+    sessions: &SessionRegistry,
+    protocol: &ProtocolMonitor,
+) {
     if notification.method.starts_with("codex/event/") {
         debug!("Forwarding event notification {}", notification.method);
+        if let Some(params) = notification.params.as_ref() {
+            track_turn_state(params, sessions).await;
+        }
+        let error_payload = notification
+            .params
+            .as_ref()
+            .and_then(error_payload_from_event);
+        let conversation_id = notification
+            .params
+            .as_ref()
+            .and_then(|params| params["conversationId"].as_str())
+            .map(String::from);
         let payload = NotificationPayload {
             method: notification.method,
             params: notification.params,
@@ -62,7 +129,85 @@ pub(super) async fn handle_notification(notification: JSONRPCNotification, event
                 return;
             }
         };
+        let mut out_of_tree = Vec::new();
+        let mut patch_progress = None;
+        if let Some(conversation_id) = &conversation_id {
+            let msg = &payload_json["params"]["msg"];
+            out_of_tree = sessions.record_out_of_tree_writes(conversation_id, msg).await;
+            patch_progress = sessions.record_patch_progress(conversation_id, msg).await;
+        }
         event_bus.emit("codex:event", payload_json).await;
+
+        if let Some((event, payload)) = patch_progress {
+            event_bus.emit(event, payload).await;
+        }
+
+        if let (false, Some(conversation_id)) = (out_of_tree.is_empty(), &conversation_id) {
+            warn!("codex wrote outside the working directory of {conversation_id}: {out_of_tree:?}");
+            let payload = json!({ "conversationId": conversation_id, "paths": out_of_tree });
+            event_bus.emit("codex-out-of-tree-write", payload).await;
+        }
+
+        // Errors stay in the event stream above and are also surfaced as a
+        // typed event so the UI can react to them specifically.
+        if let Some(error_payload) = error_payload {
+            warn!(
+                "codex reported {:?} error: {}",
+                error_payload.category, error_payload.message
+            );
+            if let Some(conversation_id) = &conversation_id {
+                sessions.record_error(conversation_id, &error_payload).await;
+            }
+            let throttled = error_payload.category == CodexErrorCategory::RateLimit;
+            match serde_json::to_value(&error_payload) {
+                Ok(json) => event_bus.emit("codex-error", json).await,
+                Err(err) => error!("Failed to serialize codex-error payload: {err}"),
+            }
+            if let (true, Some(conversation_id)) = (throttled, &conversation_id) {
+                // The reset time comes from the last usage snapshot codex sent
+                let status = sessions.rate_limit_status(conversation_id).await.ok();
+                let payload = json!({
+                    "conversationId": conversation_id,
+                    "message": error_payload.message,
+                    "resetAt": status.as_ref().and_then(|status| status.reset_at),
+                    "status": status,
+                });
+                event_bus.emit("codex-rate-limited", payload).await;
+            }
+            if let (true, Some(conversation_id)) =
+                (is_output_truncated(&error_payload.message), &conversation_id)
+            {
+                let payload = json!({
+                    "conversationId": conversation_id,
+                    "message": error_payload.message,
+                });
+                event_bus.emit("codex-output-truncated", payload).await;
+            }
+        }
+        return;
+    }
+
+    // Notifications of v2 threads are forwarded as codex sent them
+    if let Some(thread_id) = notification
+        .params
+        .as_ref()
+        .and_then(|params| params["threadId"].as_str())
+    {
+        debug!(
+            "Forwarding {} notification of thread {thread_id}",
+            notification.method
+        );
+        if let Some(params) = notification.params.as_ref() {
+            track_thread_state(&notification.method, params, sessions).await;
+        }
+        let payload = NotificationPayload {
+            method: notification.method,
+            params: notification.params,
+        };
+        match serde_json::to_value(payload) {
+            Ok(json) => event_bus.emit("codex:notification", json).await,
+            Err(err) => error!("Failed to serialize codex:notification payload: {err}"),
+        }
         return;
     }
 
@@ -98,38 +243,92 @@ pub(super) async fn handle_notification(notification: JSONRPCNotification, event
                 notification.method
             );
         }
-        Err(_) => {
+        Err(err) => {
             debug!(
                 "Unknown notification received from codex app-server: {}",
                 notification.method
             );
+            let message = serde_json::to_value(&notification).unwrap_or_default();
+            protocol
+                .report(
+                    event_bus,
+                    MismatchKind::Notification,
+                    Some(notification.method.as_str()),
+                    err.to_string(),
+                    message,
+                )
+                .await;
         }
     };
 }
 
 pub(super) async fn handle_server_request(
     request: JSONRPCRequest,
-    stdin: &Arc<Mutex<ChildStdin>>,
+    stdin: &Arc<Mutex<Option<ChildStdin>>>,
     event_bus: &EventBus,
     pending_server_requests: &PendingServerRequestMap,
+    sessions: &SessionRegistry,
+    protocol: &ProtocolMonitor,
 ) {
     match ServerRequest::try_from(request.clone()) {
         Ok(ServerRequest::ExecCommandApproval { request_id, params }) => {
-            process_exec_command_request(request_id, params, event_bus, pending_server_requests).await;
+            process_exec_command_request(
+                request_id,
+                params,
+                stdin,
+                event_bus,
+                pending_server_requests,
+                sessions,
+            )
+            .await;
         }
         Ok(ServerRequest::CommandExecutionRequestApproval { request_id, params }) => {
             let converted = convert_command_execution_request(params);
-            process_exec_command_request(request_id, converted, event_bus, pending_server_requests).await;
+            process_exec_command_request(
+                request_id,
+                converted,
+                stdin,
+                event_bus,
+                pending_server_requests,
+                sessions,
+            )
+            .await;
         }
         Ok(ServerRequest::ApplyPatchApproval { request_id, params }) => {
-            process_apply_patch_request(request_id, params, event_bus, pending_server_requests).await;
+            process_apply_patch_request(
+                request_id,
+                params,
+                stdin,
+                event_bus,
+                pending_server_requests,
+                sessions,
+            )
+            .await;
         }
         Ok(ServerRequest::FileChangeRequestApproval { request_id, params }) => {
             let converted = convert_file_change_request(params);
-            process_apply_patch_request(request_id, converted, event_bus, pending_server_requests).await;
+            process_apply_patch_request(
+                request_id,
+                converted,
+                stdin,
+                event_bus,
+                pending_server_requests,
+                sessions,
+            )
+            .await;
         }
         Err(err) => {
             error!("Unsupported server request: {err}");
+            let message = serde_json::to_value(&request).unwrap_or_default();
+            protocol
+                .report(
+                    event_bus,
+                    MismatchKind::Request,
+                    Some(request.method.as_str()),
+                    err.to_string(),
+                    message,
+                )
+                .await;
             let error = JSONRPCErrorError {
                 code: -32601,
                 message: "Unsupported request".to_string(),
@@ -197,16 +396,43 @@ fn convert_file_change_request(params: FileChangeRequestApprovalParams) -> Apply
     }
 }
 
+/// Answer an approval request on the user's behalf when `continue_turn` is
+/// in effect for its conversation. Returns true when the request was handled.
+async fn try_auto_approve(
+    request_id: &RequestId,
+    kind: PendingRequestKind,
+    conversation_id: &str,
+    stdin: &Arc<Mutex<Option<ChildStdin>>>,
+    sessions: &SessionRegistry,
+) -> bool {
+    if !sessions.auto_approves(conversation_id).await {
+        return false;
+    }
+    info!("Auto-approving {:?} request for conversation {conversation_id}", kind);
+    if let Err(err) =
+        respond_with_review_decision(stdin, request_id.clone(), kind, ReviewDecision::Approved).await
+    {
+        error!("Failed to auto-approve request: {err}");
+    }
+    true
+}
+
 async fn process_exec_command_request(
     request_id: RequestId,
     params: ExecCommandApprovalParams,
+    stdin: &Arc<Mutex<Option<ChildStdin>>>,
     event_bus: &EventBus,
     pending_server_requests: &PendingServerRequestMap,
+    sessions: &SessionRegistry,
 ) {
     info!(
         "Exec approval requested for conversation {} call {}",
         params.conversation_id, params.call_id
     );
+    let conversation_id = params.conversation_id.to_string();
+    if try_auto_approve(&request_id, PendingRequestKind::ExecCommand, &conversation_id, stdin, sessions).await {
+        return;
+    }
     let token = request_id_key(&request_id);
     {
         let mut pending = pending_server_requests.lock().await;
@@ -216,6 +442,13 @@ async fn process_exec_command_request(
                 PendingServerRequest {
                     request_id: request_id.clone(),
                     kind: PendingRequestKind::ExecCommand,
+                    conversation_id: conversation_id.clone(),
+                    exec_details: Some(ExecApprovalDetails {
+                        call_id: params.call_id.clone(),
+                        command: params.command.clone(),
+                        cwd: params.cwd.clone(),
+                        reason: params.reason.clone(),
+                    }),
                 },
             )
             .is_some()
@@ -243,14 +476,20 @@ async fn process_exec_command_request(
 async fn process_apply_patch_request(
     request_id: RequestId,
     params: ApplyPatchApprovalParams,
+    stdin: &Arc<Mutex<Option<ChildStdin>>>,
     event_bus: &EventBus,
     pending_server_requests: &PendingServerRequestMap,
+    sessions: &SessionRegistry,
 ) {
     info!(
         "Patch approval requested for conversation {} files={}",
         params.conversation_id,
         params.file_changes.len()
     );
+    let conversation_id = params.conversation_id.to_string();
+    if try_auto_approve(&request_id, PendingRequestKind::ApplyPatch, &conversation_id, stdin, sessions).await {
+        return;
+    }
     let token = request_id_key(&request_id);
     {
         let mut pending = pending_server_requests.lock().await;
@@ -260,6 +499,8 @@ async fn process_apply_patch_request(
                 PendingServerRequest {
                     request_id: request_id.clone(),
                     kind: PendingRequestKind::ApplyPatch,
+                    conversation_id: conversation_id.clone(),
+                    exec_details: None,
                 },
             )
             .is_some()
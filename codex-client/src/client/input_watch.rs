@@ -0,0 +1,63 @@
+//! Noticing user input codex never acted on
+//!
+//! Writing a message to codex's stdin can succeed while codex never reads
+//! it. Each user message starts a watch: if the conversation has emitted no
+//! event once the window has passed, the input is
+//! considered stalled and `codex-input-stalled` is emitted so the UI can
+//! offer `recover_session`.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::warn;
+use serde_json::json;
+
+use crate::events::EventBus;
+use crate::session::SessionRegistry;
+
+/// Window used when the session does not set `input_stall_timeout_ms`
+pub const DEFAULT_INPUT_STALL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Watch one message sent to `conversation_id` through `method`; call
+/// before the message is written
+pub(super) async fn watch_input(
+    sessions: SessionRegistry,
+    event_bus: Arc<EventBus>,
+    conversation_id: &str,
+    method: &'static str,
+) {
+    let Ok(session) = sessions.get(conversation_id).await else {
+        return;
+    };
+    let window = session
+        .config
+        .input_stall_timeout_ms
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_INPUT_STALL_TIMEOUT);
+    let seq_at_send = session.event_seq;
+    let conversation_id = conversation_id.to_string();
+
+    tokio::spawn(async move {
+        tokio::time::sleep(window).await;
+        let Ok(session) = sessions.get(&conversation_id).await else {
+            return;
+        };
+        if session.event_seq != seq_at_send {
+            return;
+        }
+        warn!(
+            "codex has not responded to {method} for {conversation_id} within {}ms",
+            window.as_millis()
+        );
+        event_bus
+            .emit(
+                "codex-input-stalled",
+                json!({
+                    "conversationId": conversation_id,
+                    "method": method,
+                    "waitedMs": window.as_millis() as u64,
+                }),
+            )
+            .await;
+    });
+}
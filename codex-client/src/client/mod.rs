@@ -1,7 +1,9 @@
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::sync::atomic::{AtomicI64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use codex_app_server_protocol::{
     AddConversationListenerParams,
@@ -40,16 +42,35 @@ use serde_json::Value;
 use tokio::process::{Child, ChildStdin};
 use tokio::sync::{oneshot, Mutex};
 
+use crate::config::config_file_overrides;
+use crate::config::proxy::{proxy_env, ProxyConfig};
+use crate::error::CodexError;
 use crate::events::EventBus;
+use crate::session::{LogLevel, LogLine, Session, SessionRegistry};
+use crate::session_files::file::read_first_line;
+use crate::session_files::utils::parse_session_project_path;
 use crate::utils::codex_discovery::discover_codex_command;
 use crate::utils::coder_discovery::discover_coder_command;
 use crate::utils::command::create_tokio_command;
 
+mod error_events;
 mod handlers;
+mod input_watch;
+mod output_queue;
+mod process;
+mod protocol_check;
 mod readers;
 mod transport;
 
-use readers::{spawn_stderr_reader, spawn_stdout_reader};
+pub use error_events::{CodexErrorCategory, CodexErrorPayload};
+pub use input_watch::DEFAULT_INPUT_STALL_TIMEOUT;
+pub use output_queue::{OutputBufferConfig, OverflowPolicy, DEFAULT_OUTPUT_CAPACITY};
+pub use process::{ProcessInfo, ShutdownOutcome};
+pub use protocol_check::{MismatchKind, ProtocolMismatch, EXPECTED_PROTOCOL_REV};
+
+use input_watch::watch_input;
+use protocol_check::ProtocolMonitor;
+use readers::{spawn_stderr_reader, spawn_stdout_reader, StartupLog};
 use transport::{respond_with_review_decision, write_message};
 
 type JsonRpcResult = Result<Value, JSONRPCErrorError>;
@@ -58,11 +79,23 @@ pub(super) type PendingServerRequestMap = Arc<Mutex<HashMap<String, PendingServe
 
 #[derive(Clone)]
 pub struct CodexAppServerClient {
-    _child: Arc<Mutex<Child>>,
-    stdin: Arc<Mutex<ChildStdin>>,
+    child: Arc<Mutex<Child>>,
+    stdin: Arc<Mutex<Option<ChildStdin>>>,
     pending_requests: PendingRequestMap,
     next_request_id: Arc<AtomicI64>,
     pending_server_requests: PendingServerRequestMap,
+    sessions: SessionRegistry,
+    event_bus: Arc<EventBus>,
+    /// Alternate config file the process was started with
+    config_file: Option<PathBuf>,
+    /// URL of the proxy the process was started with, without credentials
+    proxy_url: Option<String>,
+    /// `RUST_LOG` level the process was started with
+    log_level: Option<LogLevel>,
+    started_at: Instant,
+    /// stderr written in the first seconds after the process started
+    startup_log: StartupLog,
+    protocol: ProtocolMonitor,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -75,6 +108,30 @@ pub(super) enum PendingRequestKind {
 pub(super) struct PendingServerRequest {
     pub request_id: RequestId,
     pub kind: PendingRequestKind,
+    pub conversation_id: String,
+    /// Kept for exec requests so the UI can re-fetch them after a reload
+    pub exec_details: Option<ExecApprovalDetails>,
+}
+
+/// Context of a pending exec approval request
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecApprovalDetails {
+    pub call_id: String,
+    pub command: Vec<String>,
+    pub cwd: PathBuf,
+    pub reason: Option<String>,
+}
+
+/// An approval request codex is still waiting on
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingApproval {
+    pub request_token: String,
+    /// "execCommand" or "applyPatch"
+    pub kind: &'static str,
+    /// Set for exec requests only
+    pub exec: Option<ExecApprovalDetails>,
 }
 
 #[derive(Clone, serde::Serialize)]
@@ -90,8 +147,19 @@ impl CodexAppServerClient {
     ///
     /// # Arguments
     /// * `event_bus` - Event bus for emitting events
+    /// * `sessions` - Registry that tracks the conversations of this process
     /// * `client_name` - Name of the client ("codex" or "coder")
-    pub async fn spawn(event_bus: Arc<EventBus>, client_name: &str) -> Result<Arc<Self>, String> {
+    /// * `config_file` - Alternate config.toml applied through `-c` overrides
+    /// * `proxy` - Proxy passed to the process through its environment
+    /// * `output` - Buffering between reading stdout and handling messages
+    pub async fn spawn(
+        event_bus: Arc<EventBus>,
+        sessions: SessionRegistry,
+        client_name: &str,
+        config_file: Option<PathBuf>,
+        proxy: Option<ProxyConfig>,
+        output: OutputBufferConfig,
+    ) -> Result<Arc<Self>, String> {
         // Determine which binary to launch based on client_name
         let normalized = client_name.trim().to_lowercase();
         let (binary_path, label) = if normalized == "coder" {
@@ -111,13 +179,33 @@ impl CodexAppServerClient {
         };
         println!("binary_path {:?}", binary_path);
 
+        // Read the file again right before launching so a file that was
+        // removed or broken since it was selected fails here, not in codex
+        let config_overrides = match &config_file {
+            Some(path) => config_file_overrides(path)?,
+            None => Vec::new(),
+        };
+
+        // codex reads its log level only at startup
+        let log_level = sessions.max_log_level().await;
+
         let mut command = create_tokio_command(binary_path.to_str().unwrap_or(""));
+        for config_override in &config_overrides {
+            command.arg("-c").arg(config_override);
+        }
+        if let Some(level) = log_level {
+            command.env("RUST_LOG", level.as_str());
+        }
+        if let Some(proxy) = &proxy {
+            command.envs(proxy_env(proxy));
+        }
         command
             .arg("app-server")
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .kill_on_drop(true);
+        process::isolate_process_group(&mut command);
 
         let mut child_process = command
             .spawn()
@@ -134,16 +222,26 @@ impl CodexAppServerClient {
         let stderr = child_process.stderr.take();
 
         let child = Arc::new(Mutex::new(child_process));
-        let stdin = Arc::new(Mutex::new(stdin));
+        let stdin = Arc::new(Mutex::new(Some(stdin)));
         let pending_requests: PendingRequestMap = Arc::new(Mutex::new(HashMap::new()));
         let pending_server_requests: PendingServerRequestMap = Arc::new(Mutex::new(HashMap::new()));
+        let startup_log = StartupLog::default();
         let client = Arc::new(Self {
-            _child: child.clone(),
+            child: child.clone(),
             stdin: stdin.clone(),
             pending_requests: pending_requests.clone(),
             next_request_id: Arc::new(AtomicI64::new(1)),
             pending_server_requests: pending_server_requests.clone(),
+            sessions,
+            event_bus: event_bus.clone(),
+            config_file,
+            proxy_url: proxy.map(|proxy| proxy.url),
+            log_level,
+            started_at: Instant::now(),
+            startup_log: startup_log.clone(),
+            protocol: ProtocolMonitor::default(),
         });
+        client.protocol.detect_version(binary_path);
 
         spawn_stdout_reader(
             stdout,
@@ -151,9 +249,12 @@ impl CodexAppServerClient {
             pending_server_requests.clone(),
             stdin.clone(),
             event_bus.clone(),
+            client.sessions.clone(),
+            client.protocol.clone(),
+            output,
         );
         if let Some(stderr) = stderr {
-            spawn_stderr_reader(stderr, event_bus.clone());
+            spawn_stderr_reader(stderr, event_bus.clone(), client.sessions.clone(), startup_log);
         }
 
         Ok(client)
@@ -169,9 +270,13 @@ impl CodexAppServerClient {
             },
         };
         let params_value = serde_json::to_value(params).map_err(|err| err.to_string())?;
-        let response = self
+        let response = match self
             .request::<InitializeResponse>("initialize", Some(params_value))
-            .await?;
+            .await
+        {
+            Ok(response) => response,
+            Err(err) => return Err(self.with_startup_log(err).await),
+        };
         self.send_notification("initialized", None).await?;
         println!(
             "client.initialize duration_ms={}",
@@ -226,7 +331,11 @@ impl CodexAppServerClient {
                 map.extend(overrides_map.clone());
             }
         }
-        self.request("newConversation", Some(params_value)).await
+        let result = self
+            .send_request("newConversation", Some(params_value.clone()))
+            .await?;
+        self.track_session(&params_value, &result).await;
+        serde_json::from_value(result).map_err(|err| err.to_string())
     }
 
     pub async fn resume_conversation(
@@ -244,7 +353,26 @@ impl CodexAppServerClient {
                 map.extend(overrides_map.clone());
             }
         }
-        self.request("resumeConversation", Some(params_value)).await
+        let result = self
+            .send_request("resumeConversation", Some(params_value.clone()))
+            .await?;
+        self.track_session(&params_value, &result).await;
+        serde_json::from_value(result).map_err(|err| err.to_string())
+    }
+
+    /// Shut down a conversation and let codex move its rollout to the
+    /// archived sessions directory.
+    pub async fn archive_conversation(
+        &self,
+        conversation_id: &str,
+        rollout_path: &Path,
+    ) -> Result<(), String> {
+        let params = serde_json::json!({
+            "conversationId": conversation_id,
+            "rolloutPath": rollout_path,
+        });
+        self.send_request("archiveConversation", Some(params)).await?;
+        Ok(())
     }
 
     pub async fn add_conversation_listener(
@@ -270,7 +398,7 @@ impl CodexAppServerClient {
         params: SendUserMessageParams,
     ) -> Result<SendUserMessageResponse, String> {
         let params_value = serde_json::to_value(params).map_err(|err| err.to_string())?;
-        self.request("sendUserMessage", Some(params_value)).await
+        self.send_turn("sendUserMessage", params_value).await
     }
 
     pub async fn turn_start(
@@ -278,7 +406,13 @@ impl CodexAppServerClient {
         params: TurnStartParams,
     ) -> Result<TurnStartResponse, String> {
         let params_value = serde_json::to_value(params).map_err(|err| err.to_string())?;
-        self.request("turn/start", Some(params_value)).await
+        let response = self.send_turn("turn/start", params_value.clone()).await?;
+        if let Some(thread_id) = params_value["threadId"].as_str() {
+            self.sessions
+                .record_runtime_settings(thread_id, &params_value)
+                .await;
+        }
+        Ok(response)
     }
 
     pub async fn interrupt_conversation(
@@ -308,6 +442,242 @@ impl CodexAppServerClient {
             .await
     }
 
+    /// What codex wrote to stderr while starting up: deprecated config keys,
+    /// missing model access and the like
+    pub fn startup_log(&self) -> Vec<LogLine> {
+        self.startup_log.lines()
+    }
+
+    /// Append the startup stderr to a startup error. A process that exits
+    /// right away may still be flushing it, so give the reader a moment.
+    async fn with_startup_log(&self, err: String) -> String {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let lines = self.startup_log();
+        if lines.is_empty() {
+            return err;
+        }
+        let stderr: Vec<&str> = lines.iter().map(|line| line.line.as_str()).collect();
+        format!("{err}\ncodex stderr:\n{}", stderr.join("\n"))
+    }
+
+    /// First message of this process that did not match the protocol
+    /// Codexia was built against
+    pub fn protocol_mismatch(&self) -> Option<ProtocolMismatch> {
+        self.protocol.first_mismatch()
+    }
+
+    /// Log level the process was started with, `None` for codex's default
+    pub fn log_level(&self) -> Option<LogLevel> {
+        self.log_level
+    }
+
+    /// Ask codex to compact a conversation's context with its built-in
+    /// compaction. Returns false when the app-server has no such request.
+    pub async fn compact_conversation(&self, conversation_id: &str) -> Result<bool, String> {
+        let params = serde_json::json!({ "conversationId": conversation_id });
+        Ok(self
+            .send_optional_request("compactConversation", Some(params))
+            .await?
+            .is_some())
+    }
+
+    /// Models offered by the configured provider, `None` when the
+    /// app-server cannot list them
+    pub async fn list_models(&self) -> Result<Option<Value>, String> {
+        self.send_optional_request("model/list", Some(serde_json::json!({})))
+            .await
+    }
+
+    /// Stop the app-server process, killing it (and its process group) when
+    /// it does not exit within `grace`
+    pub async fn shutdown(&self, grace: Duration) -> ShutdownOutcome {
+        // The app-server exits once its input ends, on every platform. A
+        // write blocked on a wedged pipe holds the lock; the kill below
+        // still applies then.
+        if let Ok(mut stdin) = self.stdin.try_lock() {
+            stdin.take();
+        }
+        let mut child = self.child.lock().await;
+        process::terminate_child(&mut child, grace).await
+    }
+
+    /// PID, memory and CPU usage and uptime of the app-server process
+    pub async fn process_info(&self) -> ProcessInfo {
+        let pid = self.child.lock().await.id();
+        let started_at = self.started_at;
+        tokio::task::spawn_blocking(move || process::sample_process(pid, started_at))
+            .await
+            .unwrap_or(ProcessInfo {
+                pid,
+                memory_bytes: None,
+                cpu_percent: None,
+                uptime_secs: started_at.elapsed().as_secs(),
+            })
+    }
+
+    /// Full context of a pending exec approval of `conversation_id`
+    pub async fn get_approval_details(
+        &self,
+        conversation_id: &str,
+        approval_id: &str,
+    ) -> Result<ExecApprovalDetails, CodexError> {
+        let pending = self.pending_server_requests.lock().await;
+        pending
+            .get(approval_id)
+            .filter(|request| request.conversation_id == conversation_id)
+            .and_then(|request| request.exec_details.clone())
+            .ok_or_else(|| CodexError::UnknownApproval(approval_id.to_string()))
+    }
+
+    /// Conversation a pending approval request belongs to
+    pub async fn approval_conversation(&self, request_token: &str) -> Result<String, CodexError> {
+        let pending = self.pending_server_requests.lock().await;
+        pending
+            .get(request_token)
+            .map(|request| request.conversation_id.clone())
+            .ok_or_else(|| CodexError::UnknownApproval(request_token.to_string()))
+    }
+
+    /// Approval requests of `conversation_id` that have not been answered
+    pub async fn pending_approvals(&self, conversation_id: &str) -> Vec<PendingApproval> {
+        let pending = self.pending_server_requests.lock().await;
+        pending
+            .iter()
+            .filter(|(_, request)| request.conversation_id == conversation_id)
+            .map(|(token, request)| PendingApproval {
+                request_token: token.clone(),
+                kind: match request.kind {
+                    PendingRequestKind::ExecCommand => "execCommand",
+                    PendingRequestKind::ApplyPatch => "applyPatch",
+                },
+                exec: request.exec_details.clone(),
+            })
+            .collect()
+    }
+
+    /// Let a turn that is waiting on approvals proceed.
+    ///
+    /// The app-server protocol has no "continue" request distinct from
+    /// answering a specific approval, so this approves every pending request
+    /// of the conversation and auto-approves the ones that arrive until codex
+    /// reports the turn complete or aborted. Returns how many pending
+    /// requests were approved.
+    pub async fn continue_turn(&self, conversation_id: &str) -> Result<usize, String> {
+        self.sessions
+            .update(conversation_id, |session| session.auto_approve_turn = true)
+            .await?;
+
+        let pending: Vec<PendingServerRequest> = {
+            let mut guard = self.pending_server_requests.lock().await;
+            let tokens: Vec<String> = guard
+                .iter()
+                .filter(|(_, request)| request.conversation_id == conversation_id)
+                .map(|(token, _)| token.clone())
+                .collect();
+            tokens
+                .iter()
+                .filter_map(|token| guard.remove(token))
+                .collect()
+        };
+
+        let approved = pending.len();
+        for request in pending {
+            respond_with_review_decision(
+                &self.stdin,
+                request.request_id,
+                request.kind,
+                ReviewDecision::Approved,
+            )
+            .await?;
+        }
+        Ok(approved)
+    }
+
+    /// Record a conversation created or resumed through this client
+    async fn track_session(&self, params: &Value, result: &Value) {
+        let Some(conversation_id) = result["conversationId"].as_str() else {
+            return;
+        };
+        let mut session = Session::new(conversation_id);
+        session.config_file = self.config_file.clone();
+        session.proxy_url = self.proxy_url.clone();
+        session.rollout_path = result["rolloutPath"]
+            .as_str()
+            .or_else(|| params["path"].as_str())
+            .map(PathBuf::from);
+        session.model = result["model"]
+            .as_str()
+            .or_else(|| params["model"].as_str())
+            .map(String::from);
+        session.sandbox_mode = params["sandbox"]
+            .as_str()
+            .or_else(|| params["overrides"]["sandbox"].as_str())
+            .map(String::from);
+        session.approval_policy = params["approvalPolicy"]
+            .as_str()
+            .or_else(|| params["overrides"]["approvalPolicy"].as_str())
+            .map(String::from);
+        session.cwd = params["cwd"]
+            .as_str()
+            .or_else(|| params["overrides"]["cwd"].as_str())
+            .map(PathBuf::from)
+            .or_else(|| {
+                // Resumed conversations inherit the cwd recorded in the rollout
+                let rollout = session.rollout_path.as_ref()?;
+                let first_line = read_first_line(rollout).ok()?;
+                parse_session_project_path(&first_line).map(PathBuf::from)
+            });
+        self.sessions.insert(session).await;
+    }
+
+    /// Send a message that starts a user turn. Every user turn goes through
+    /// here: the message rate is checked, the session is marked busy and
+    /// the turn hook runs before it is sent, and the turn is only counted
+    /// once codex accepted it.
+    async fn send_turn<T>(&self, method: &'static str, params: Value) -> Result<T, String>
+    where
+        T: DeserializeOwned,
+    {
+        let Some(conversation_id) = params["conversationId"]
+            .as_str()
+            .or_else(|| params["threadId"].as_str())
+            .map(String::from)
+        else {
+            return self.request(method, Some(params)).await;
+        };
+        self.sessions.check_message_rate(&conversation_id).await?;
+        let was_busy = self.sessions.begin_send(&conversation_id).await;
+        self.sessions.before_turn(&conversation_id).await;
+        self.watch_input(&params, method).await;
+        let response = match self.request(method, Some(params)).await {
+            Ok(response) => response,
+            Err(err) => {
+                if !was_busy {
+                    self.sessions.abort_send(&conversation_id).await;
+                }
+                return Err(err);
+            }
+        };
+        self.sessions.turn_sent(&conversation_id).await;
+        Ok(response)
+    }
+
+    /// Start watching for codex to act on a user message
+    async fn watch_input(&self, params: &Value, method: &'static str) {
+        let conversation_id = params["conversationId"]
+            .as_str()
+            .or_else(|| params["threadId"].as_str());
+        if let Some(conversation_id) = conversation_id {
+            watch_input(
+                self.sessions.clone(),
+                self.event_bus.clone(),
+                conversation_id,
+                method,
+            )
+            .await;
+        }
+    }
+
     async fn send_notification(&self, method: &str, params: Option<Value>) -> Result<(), String> {
         let notification = JSONRPCNotification {
             method: method.to_string(),
@@ -324,7 +694,35 @@ impl CodexAppServerClient {
         serde_json::from_value(result).map_err(|err| err.to_string())
     }
 
-    async fn send_request(&self, method: &str, params: Option<Value>) -> Result<Value, String> {
+    pub(crate) async fn send_request(&self, method: &str, params: Option<Value>) -> Result<Value, String> {
+        self.send_rpc(method, params).await?.map_err(|json_error| {
+            format!(
+                "codex app-server error {}: {}",
+                json_error.code, json_error.message
+            )
+        })
+    }
+
+    /// Send a request the installed app-server may not know, returning
+    /// `None` when it rejects the method
+    async fn send_optional_request(
+        &self,
+        method: &str,
+        params: Option<Value>,
+    ) -> Result<Option<Value>, String> {
+        match self.send_rpc(method, params).await? {
+            Ok(value) => Ok(Some(value)),
+            Err(json_error) if UNSUPPORTED_METHOD_CODES.contains(&json_error.code) => Ok(None),
+            Err(json_error) => Err(format!(
+                "codex app-server error {}: {}",
+                json_error.code, json_error.message
+            )),
+        }
+    }
+
+    /// Send a request and wait for its response; the outer error is a
+    /// failure to reach the app-server, the inner one its JSON-RPC error
+    async fn send_rpc(&self, method: &str, params: Option<Value>) -> Result<JsonRpcResult, String> {
         let request_id = RequestId::Integer(self.next_request_id.fetch_add(1, Ordering::Relaxed));
         let request = JSONRPCRequest {
             id: request_id.clone(),
@@ -344,14 +742,8 @@ impl CodexAppServerClient {
             return Err(err);
         }
 
-        match rx.await {
-            Ok(Ok(value)) => Ok(value),
-            Ok(Err(json_error)) => Err(format!(
-                "codex app-server error {}: {}",
-                json_error.code, json_error.message
-            )),
-            Err(_) => Err("codex app-server channel closed".to_string()),
-        }
+        rx.await
+            .map_err(|_| "codex app-server channel closed".to_string())
     }
 
     async fn respond_pending_request(
@@ -374,6 +766,31 @@ impl CodexAppServerClient {
             ));
         }
 
-        respond_with_review_decision(&self.stdin, pending.request_id, pending.kind, decision).await
+        let approved = is_approval(&decision);
+        let call_id = pending.exec_details.as_ref().map(|details| details.call_id.clone());
+        respond_with_review_decision(&self.stdin, pending.request_id, pending.kind, decision)
+            .await?;
+        if let Some(call_id) = call_id {
+            self.sessions
+                .command_proposals()
+                .record_decision(&pending.conversation_id, &call_id, approved)
+                .await;
+        }
+        Ok(())
+    }
+}
+
+/// Whether a decision lets codex go ahead: `approved`,
+/// `approved_for_session` and the variants with an amendment
+fn is_approval(decision: &ReviewDecision) -> bool {
+    match serde_json::to_value(decision) {
+        Ok(Value::String(name)) => name.starts_with("approved"),
+        Ok(Value::Object(map)) => map.keys().any(|name| name.starts_with("approved")),
+        _ => false,
     }
 }
+
+/// JSON-RPC errors of an app-server that does not know a method: unknown
+/// methods fail to deserialize (-32600) or are rejected as not found
+/// (-32601)
+const UNSUPPORTED_METHOD_CODES: &[i64] = &[-32600, -32601];
@@ -0,0 +1,173 @@
+//! Bounded queue between the stdout reader and the message handlers
+//!
+//! The reader only moves lines off the pipe; handling them (session
+//! bookkeeping, emitting events) happens on a second task. When handlers
+//! fall behind, the queue either stops reading, so codex blocks on a full
+//! pipe, or drops the oldest streaming deltas to make room.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::Notify;
+
+/// Lines buffered when nothing else was configured
+pub const DEFAULT_OUTPUT_CAPACITY: usize = 1024;
+
+/// What to do with a new line when the queue is full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OverflowPolicy {
+    /// Wait for the handlers; codex blocks once the pipe fills up
+    #[default]
+    Block,
+    /// Discard the oldest queued delta notification. Responses, requests
+    /// and complete events are never dropped; without a delta to discard
+    /// the queue blocks.
+    DropOldest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputBufferConfig {
+    pub capacity: usize,
+    pub policy: OverflowPolicy,
+}
+
+impl Default for OutputBufferConfig {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_OUTPUT_CAPACITY,
+            policy: OverflowPolicy::default(),
+        }
+    }
+}
+
+struct QueuedLine {
+    line: String,
+    droppable: bool,
+}
+
+/// Streaming deltas are superseded by the complete event that follows them
+fn is_delta_notification(line: &str) -> bool {
+    let Ok(value) = serde_json::from_str::<Value>(line) else {
+        return false;
+    };
+    if value.get("id").is_some() {
+        return false;
+    }
+    let method = value["method"].as_str().unwrap_or_default();
+    let msg_type = value["params"]["msg"]["type"].as_str().unwrap_or_default();
+    method.ends_with("delta") || msg_type.ends_with("_delta")
+}
+
+pub(super) struct OutputQueue {
+    lines: std::sync::Mutex<VecDeque<QueuedLine>>,
+    config: OutputBufferConfig,
+    readable: Notify,
+    writable: Notify,
+    closed: AtomicBool,
+    dropped: AtomicU64,
+}
+
+impl OutputQueue {
+    pub(super) fn new(config: OutputBufferConfig) -> Self {
+        Self {
+            lines: std::sync::Mutex::new(VecDeque::new()),
+            config: OutputBufferConfig {
+                capacity: config.capacity.max(1),
+                policy: config.policy,
+            },
+            readable: Notify::new(),
+            writable: Notify::new(),
+            closed: AtomicBool::new(false),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Queue a line, waiting for room unless the policy allows a drop
+    pub(super) async fn push(&self, line: String) {
+        let droppable =
+            self.config.policy == OverflowPolicy::DropOldest && is_delta_notification(&line);
+        let mut entry = Some(QueuedLine { line, droppable });
+        loop {
+            {
+                let mut lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+                if lines.len() >= self.config.capacity
+                    && self.config.policy == OverflowPolicy::DropOldest
+                {
+                    if let Some(index) = lines.iter().position(|queued| queued.droppable) {
+                        lines.remove(index);
+                        let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                        warn!(
+                            "codex output queue full ({} lines); dropped a delta, {dropped} so far",
+                            self.config.capacity
+                        );
+                    }
+                }
+                if lines.len() < self.config.capacity {
+                    lines.extend(entry.take());
+                    drop(lines);
+                    self.readable.notify_one();
+                    return;
+                }
+            }
+            self.writable.notified().await;
+        }
+    }
+
+    /// Next line, or `None` once the reader closed the queue and it is empty
+    pub(super) async fn pop(&self) -> Option<String> {
+        loop {
+            {
+                let mut lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+                if let Some(queued) = lines.pop_front() {
+                    drop(lines);
+                    self.writable.notify_one();
+                    return Some(queued.line);
+                }
+            }
+            if self.closed.load(Ordering::Acquire) {
+                return None;
+            }
+            self.readable.notified().await;
+        }
+    }
+
+    pub(super) fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.readable.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_drop_oldest_keeps_complete_events() {
+        let queue = OutputQueue::new(OutputBufferConfig {
+            capacity: 2,
+            policy: OverflowPolicy::DropOldest,
+        });
+        let event = |kind: &str| {
+            json!({
+                "method": format!("codex/event/{kind}"),
+                "params": { "msg": { "type": kind } },
+            })
+            .to_string()
+        };
+        let response = json!({ "id": 1, "result": {} }).to_string();
+        queue.push(event("agent_message_delta")).await;
+        queue.push(event("task_complete")).await;
+        queue.push(response.clone()).await;
+        queue.close();
+
+        assert_eq!(queue.pop().await, Some(event("task_complete")));
+        assert_eq!(queue.pop().await, Some(response));
+        assert_eq!(queue.pop().await, None);
+    }
+}
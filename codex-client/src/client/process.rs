@@ -0,0 +1,116 @@
+//! Stopping and inspecting the app-server process
+//!
+//! On unix the app-server is spawned as the leader of its own process group,
+//! so the commands it runs on codex's behalf can be stopped together with it.
+
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+use serde::Serialize;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tokio::process::{Child, Command};
+
+/// How the app-server process was stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ShutdownOutcome {
+    /// The process exited on its own after being asked to terminate
+    Graceful,
+    /// The process ignored the request and was killed
+    Forced,
+}
+
+/// Resource usage of the app-server process. Metrics the platform cannot
+/// provide are `None`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessInfo {
+    pub pid: Option<u32>,
+    pub memory_bytes: Option<u64>,
+    /// Share of one CPU core, so values above 100 are possible
+    pub cpu_percent: Option<f32>,
+    pub uptime_secs: u64,
+}
+
+/// Sample memory and CPU usage of `pid`. CPU usage is measured over
+/// sysinfo's minimum update interval, so this blocks for a moment.
+pub(super) fn sample_process(pid: Option<u32>, started_at: Instant) -> ProcessInfo {
+    let mut info = ProcessInfo {
+        pid,
+        memory_bytes: None,
+        cpu_percent: None,
+        uptime_secs: started_at.elapsed().as_secs(),
+    };
+    let Some(pid) = pid.map(Pid::from_u32) else {
+        return info;
+    };
+
+    let mut system = System::new();
+    let refresh = ProcessRefreshKind::nothing().with_memory().with_cpu();
+    system.refresh_processes_specifics(ProcessesToUpdate::Some(&[pid]), true, refresh);
+    std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+    system.refresh_processes_specifics(ProcessesToUpdate::Some(&[pid]), true, refresh);
+
+    if let Some(process) = system.process(pid) {
+        info.memory_bytes = Some(process.memory()).filter(|bytes| *bytes > 0);
+        info.cpu_percent = Some(process.cpu_usage());
+    }
+    info
+}
+
+/// Put the spawned process in a new process group led by itself
+pub(super) fn isolate_process_group(command: &mut Command) {
+    #[cfg(unix)]
+    command.process_group(0);
+    #[cfg(not(unix))]
+    let _ = command;
+}
+
+#[cfg(unix)]
+fn signal_group(pgid: u32, signal: libc::c_int) {
+    // SAFETY: killpg only sends a signal; a stale group id yields ESRCH
+    unsafe {
+        libc::killpg(pgid as libc::pid_t, signal);
+    }
+}
+
+/// Ask the process group to exit where the platform has signals
+fn request_terminate(pid: Option<u32>) {
+    #[cfg(unix)]
+    if let Some(pid) = pid {
+        signal_group(pid, libc::SIGTERM);
+    }
+    #[cfg(not(unix))]
+    let _ = pid;
+}
+
+/// Terminate `child` and, on unix, its process group: request a graceful
+/// exit, wait up to `grace`, then kill whatever is left. The caller has
+/// closed the child's stdin, which is the only exit request on platforms
+/// without signals.
+pub(super) async fn terminate_child(child: &mut Child, grace: Duration) -> ShutdownOutcome {
+    if let Ok(Some(status)) = child.try_wait() {
+        info!("codex app-server already exited with {status}");
+        return ShutdownOutcome::Graceful;
+    }
+
+    let pid = child.id();
+    request_terminate(pid);
+    let outcome = if tokio::time::timeout(grace, child.wait()).await.is_ok() {
+        ShutdownOutcome::Graceful
+    } else {
+        warn!("codex app-server did not exit within {grace:?}; killing it");
+        if let Err(err) = child.kill().await {
+            warn!("Failed to kill codex app-server: {err}");
+        }
+        ShutdownOutcome::Forced
+    };
+
+    // Reap anything the app-server left behind in its group
+    #[cfg(unix)]
+    if let Some(pid) = pid {
+        signal_group(pid, libc::SIGKILL);
+    }
+
+    outcome
+}
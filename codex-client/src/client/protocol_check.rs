@@ -0,0 +1,159 @@
+//! Detection of codex binaries that speak a different protocol version
+//!
+//! Codexia is built against the protocol crates of one codex revision. A
+//! newer or older binary can send messages, notifications or requests those
+//! types cannot parse. The first one is reported as
+//! `codex-protocol-mismatch`; the stream keeps going so everything that
+//! still parses keeps working.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use codex_app_server_protocol::JSONRPCMessage;
+use log::warn;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::events::EventBus;
+use crate::utils::command::create_command;
+
+/// codex revision of the protocol crates; keep in sync with Cargo.toml
+pub const EXPECTED_PROTOCOL_REV: &str = "163a7e31";
+
+/// What could not be understood
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MismatchKind {
+    /// A line that is JSON but not a JSON-RPC message
+    Message,
+    Notification,
+    Request,
+}
+
+/// Payload of `codex-protocol-mismatch`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolMismatch {
+    pub kind: MismatchKind,
+    pub method: Option<String>,
+    pub error: String,
+    /// The offending message as received
+    pub message: Value,
+    pub expected_revision: &'static str,
+    /// `codex --version` of the running binary, when it was known by the
+    /// time of the mismatch
+    pub detected_version: Option<String>,
+}
+
+/// Remembers the first mismatch of an app-server process
+#[derive(Clone, Default)]
+pub(super) struct ProtocolMonitor {
+    reported: Arc<AtomicBool>,
+    first: Arc<Mutex<Option<ProtocolMismatch>>>,
+    /// Version of the binary, read once when the process is spawned
+    version: Arc<OnceLock<Option<String>>>,
+}
+
+/// `<binary> -V`, or `None` when it fails
+fn binary_version(binary_path: &Path) -> Option<String> {
+    let output = create_command(&binary_path.to_string_lossy())
+        .arg("-V")
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+impl ProtocolMonitor {
+    /// Read the version of the spawned binary in the background, so a
+    /// mismatch can name it without running a process on the reader task
+    pub(super) fn detect_version(&self, binary_path: PathBuf) {
+        let version = self.version.clone();
+        tokio::task::spawn_blocking(move || {
+            let _ = version.set(binary_version(&binary_path));
+        });
+    }
+
+    pub(super) fn first_mismatch(&self) -> Option<ProtocolMismatch> {
+        self.first
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Log every mismatch; emit and keep only the first one
+    pub(super) async fn report(
+        &self,
+        event_bus: &EventBus,
+        kind: MismatchKind,
+        method: Option<&str>,
+        error: String,
+        message: Value,
+    ) {
+        warn!("codex protocol mismatch ({kind:?} {method:?}): {error}");
+        if self.reported.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let mismatch = ProtocolMismatch {
+            kind,
+            method: method.map(String::from),
+            error,
+            message,
+            expected_revision: EXPECTED_PROTOCOL_REV,
+            detected_version: self.version.get().cloned().flatten(),
+        };
+        *self
+            .first
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(mismatch.clone());
+        match serde_json::to_value(&mismatch) {
+            Ok(payload) => event_bus.emit("codex-protocol-mismatch", payload).await,
+            Err(err) => log::error!("Failed to serialize codex-protocol-mismatch payload: {err}"),
+        }
+    }
+}
+
+/// Reduce a message that failed to parse to the JSON-RPC fields the client
+/// needs, so a response still resolves its request and an event notification
+/// still reaches the UI
+pub(super) fn salvage_message(value: &Value) -> Option<JSONRPCMessage> {
+    let minimal = match (value.get("id"), value.get("method")) {
+        (Some(id), Some(method)) => {
+            json!({ "id": id, "method": method, "params": value["params"] })
+        }
+        (None, Some(method)) => json!({ "method": method, "params": value["params"] }),
+        (Some(id), None) if value.get("result").is_some() => {
+            json!({ "id": id, "result": value["result"] })
+        }
+        (Some(id), None) if value.get("error").is_some() => {
+            json!({ "id": id, "error": value["error"] })
+        }
+        _ => return None,
+    };
+    serde_json::from_value(minimal).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_salvage_message() {
+        let response = json!({ "id": 7, "result": { "ok": true }, "extra": [1, 2] });
+        assert!(matches!(
+            salvage_message(&response),
+            Some(JSONRPCMessage::Response(_))
+        ));
+
+        let event = json!({ "method": "codex/event/new_thing", "params": { "msg": {} } });
+        assert!(matches!(
+            salvage_message(&event),
+            Some(JSONRPCMessage::Notification(_))
+        ));
+
+        assert!(salvage_message(&json!({ "unrelated": true })).is_none());
+    }
+}
@@ -1,33 +1,100 @@
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use codex_app_server_protocol::JSONRPCMessage;
 use log::{debug, error, info, warn};
-use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
 use tokio::process::{ChildStderr, ChildStdin, ChildStdout};
 use tokio::sync::Mutex;
 
 use crate::events::EventBus;
+use crate::session::{LogLine, SessionRegistry};
 use super::handlers::{handle_notification, handle_server_request};
+use super::output_queue::{OutputBufferConfig, OutputQueue};
+use super::protocol_check::{salvage_message, MismatchKind, ProtocolMonitor};
 use super::transport::{notify_pending_error, notify_pending_response};
 use super::{PendingRequestMap, PendingServerRequestMap};
 
+/// Next line of `reader` without its line ending, or `None` at the end of
+/// the stream.
+///
+/// Tools codex runs can print bytes that are not UTF-8, which `lines()`
+/// treats as a fatal error that would end the read loop. Lines are read as
+/// bytes instead, so a multibyte character split across reads is reassembled
+/// before decoding, and invalid bytes become U+FFFD rather than costing the
+/// line.
+async fn next_line_lossy<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    buf: &mut Vec<u8>,
+) -> std::io::Result<Option<String>> {
+    buf.clear();
+    if reader.read_until(b'\n', buf).await? == 0 {
+        return Ok(None);
+    }
+    let line = buf.strip_suffix(b"\n").unwrap_or(buf.as_slice());
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    Ok(Some(String::from_utf8_lossy(line).into_owned()))
+}
+
 pub(super) fn spawn_stdout_reader(
     stdout: ChildStdout,
     pending_requests: PendingRequestMap,
     pending_server_requests: PendingServerRequestMap,
-    stdin: Arc<Mutex<ChildStdin>>,
+    stdin: Arc<Mutex<Option<ChildStdin>>>,
     event_bus: Arc<EventBus>,
+    sessions: SessionRegistry,
+    protocol: ProtocolMonitor,
+    output: OutputBufferConfig,
 ) {
+    let queue = Arc::new(OutputQueue::new(output));
+
+    let reader_queue = queue.clone();
     tokio::spawn(async move {
-        let mut reader = BufReader::new(stdout).lines();
-        while let Ok(Some(line)) = reader.next_line().await {
-            let trimmed = line.trim();
-            if trimmed.is_empty() {
-                continue;
+        let mut reader = BufReader::new(stdout);
+        let mut buf = Vec::new();
+        loop {
+            let line = match next_line_lossy(&mut reader, &mut buf).await {
+                Ok(Some(line)) => line,
+                Ok(None) => break,
+                Err(err) => {
+                    error!("Failed to read codex app-server stdout: {err}");
+                    break;
+                }
+            };
+            if !line.trim().is_empty() {
+                reader_queue.push(line).await;
             }
+        }
+        info!("codex app-server stdout closed");
+        reader_queue.close();
+    });
+
+    tokio::spawn(async move {
+        while let Some(line) = queue.pop().await {
+            let trimmed = line.trim();
             debug!("codex app-server stdout: {trimmed}");
 
-            match serde_json::from_str::<JSONRPCMessage>(trimmed) {
+            let message = match serde_json::from_str::<JSONRPCMessage>(trimmed) {
+                Ok(message) => Ok(message),
+                Err(err) => match serde_json::from_str::<serde_json::Value>(trimmed) {
+                    // Valid JSON of an unexpected shape: a protocol change
+                    Ok(value) => {
+                        let method = value["method"].as_str().map(String::from);
+                        protocol
+                            .report(
+                                &event_bus,
+                                MismatchKind::Message,
+                                method.as_deref(),
+                                err.to_string(),
+                                value.clone(),
+                            )
+                            .await;
+                        salvage_message(&value).ok_or(err)
+                    }
+                    Err(_) => Err(err),
+                },
+            };
+            match message {
                 Ok(JSONRPCMessage::Response(response)) => {
                     debug!("JSON-RPC response {:?}", response.id);
                     notify_pending_response(&pending_requests, response).await;
@@ -55,32 +122,96 @@ pub(super) fn spawn_stdout_reader(
                 }
                 Ok(JSONRPCMessage::Notification(notification)) => {
                     debug!("JSON-RPC notification {}", notification.method);
-                    handle_notification(notification, &event_bus).await;
+                    handle_notification(notification, &event_bus, &sessions, &protocol).await;
                 }
                 Ok(JSONRPCMessage::Request(request)) => {
                     info!("JSON-RPC request {}", request.method);
-                    handle_server_request(request, &stdin, &event_bus, &pending_server_requests)
-                        .await;
+                    handle_server_request(
+                        request,
+                        &stdin,
+                        &event_bus,
+                        &pending_server_requests,
+                        &sessions,
+                        &protocol,
+                    )
+                    .await;
                 }
                 Err(err) => {
                     error!("Failed to parse JSON-RPC message: {err}. Payload: {trimmed}");
                 }
             }
         }
-        info!("codex app-server stdout closed");
     });
 }
 
-pub(super) fn spawn_stderr_reader(stderr: ChildStderr, event_bus: Arc<EventBus>) {
+/// How long after launch stderr counts as startup output
+const STARTUP_WINDOW: Duration = Duration::from_secs(5);
+/// Cap on captured startup lines, in case codex logs verbosely
+const STARTUP_LINES: usize = 200;
+
+/// stderr lines written during `STARTUP_WINDOW`
+#[derive(Clone, Default)]
+pub(super) struct StartupLog(Arc<std::sync::Mutex<Vec<LogLine>>>);
+
+impl StartupLog {
+    fn push(&self, line: &str) {
+        let mut lines = self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if lines.len() < STARTUP_LINES {
+            lines.push(LogLine {
+                timestamp: chrono::Utc::now(),
+                line: line.to_string(),
+            });
+        }
+    }
+
+    pub(super) fn lines(&self) -> Vec<LogLine> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+}
+
+pub(super) fn spawn_stderr_reader(
+    stderr: ChildStderr,
+    event_bus: Arc<EventBus>,
+    sessions: SessionRegistry,
+    startup_log: StartupLog,
+) {
     tokio::spawn(async move {
-        let mut reader = BufReader::new(stderr).lines();
-        while let Ok(Some(line)) = reader.next_line().await {
+        let started = Instant::now();
+        let mut reader = BufReader::new(stderr);
+        let mut buf = Vec::new();
+        while let Ok(Some(line)) = next_line_lossy(&mut reader, &mut buf).await {
             let trimmed = line.trim();
             if !trimmed.is_empty() {
                 debug!("codex app-server stderr: {trimmed}");
+                if started.elapsed() < STARTUP_WINDOW {
+                    startup_log.push(trimmed);
+                }
+                sessions.record_log_line(trimmed).await;
             }
         }
         info!("codex app-server stderr closed; process exited");
         event_bus.emit("codex:process-exited", serde_json::json!({})).await;
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_next_line_lossy_keeps_split_and_invalid_bytes() {
+        // Reading a byte at a time splits the two bytes of "é"
+        let input: &[u8] = b"{\"text\":\"caf\xc3\xa9\"}\r\nbad \xff byte\nlast";
+        let mut reader = BufReader::with_capacity(1, input);
+        let mut buf = Vec::new();
+
+        let mut lines = Vec::new();
+        while let Some(line) = next_line_lossy(&mut reader, &mut buf).await.unwrap() {
+            lines.push(line);
+        }
+        assert_eq!(lines, vec!["{\"text\":\"café\"}", "bad \u{fffd} byte", "last"]);
+    }
+}
@@ -50,7 +50,7 @@ pub(super) async fn notify_pending_error(
 }
 
 pub(super) async fn respond_with_review_decision(
-    stdin: &Arc<Mutex<ChildStdin>>,
+    stdin: &Arc<Mutex<Option<ChildStdin>>>,
     request_id: RequestId,
     kind: PendingRequestKind,
     decision: ReviewDecision,
@@ -69,7 +69,7 @@ pub(super) async fn respond_with_review_decision(
 }
 
 pub(super) async fn send_response(
-    stdin: &Arc<Mutex<ChildStdin>>,
+    stdin: &Arc<Mutex<Option<ChildStdin>>>,
     id: RequestId,
     result: Value,
 ) -> Result<(), String> {
@@ -78,7 +78,7 @@ pub(super) async fn send_response(
 }
 
 pub(super) async fn send_error(
-    stdin: &Arc<Mutex<ChildStdin>>,
+    stdin: &Arc<Mutex<Option<ChildStdin>>>,
     id: RequestId,
     error: JSONRPCErrorError,
 ) -> Result<(), String> {
@@ -87,7 +87,7 @@ pub(super) async fn send_error(
 }
 
 pub(super) async fn write_message<T>(
-    stdin: &Arc<Mutex<ChildStdin>>,
+    stdin: &Arc<Mutex<Option<ChildStdin>>>,
     message: &T,
 ) -> Result<(), String>
 where
@@ -96,11 +96,14 @@ where
     let mut json = serde_json::to_vec(message).map_err(|err| err.to_string())?;
     json.push(b'\n');
     let mut guard = stdin.lock().await;
-    guard
+    let stdin = guard
+        .as_mut()
+        .ok_or_else(|| "The codex app-server is shutting down".to_string())?;
+    stdin
         .write_all(&json)
         .await
         .map_err(|err| format!("Failed to write to codex app-server: {err}"))?;
-    guard
+    stdin
         .flush()
         .await
         .map_err(|err| format!("Failed to flush codex app-server stdin: {err}"))
@@ -1,15 +1,17 @@
 pub mod project;
 pub mod provider;
 pub mod profile;
+pub mod proxy;
 pub mod toml_helpers;
+pub mod validate;
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use super::mcp::McpServerConfig;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CodexConfig {
     #[serde(default)]
     pub projects: HashMap<String, project::ProjectConfig>,
@@ -25,3 +27,74 @@ pub fn get_config_path() -> Result<PathBuf, String> {
     let home_dir = dirs::home_dir().ok_or("Could not find home directory")?;
     Ok(home_dir.join(".codex").join("config.toml"))
 }
+
+/// Check that an alternate config file exists and parses, returning its
+/// canonical path
+pub fn resolve_config_file(path: &str) -> Result<PathBuf, String> {
+    let expanded = match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .ok_or("Could not find home directory")?
+            .join(rest),
+        None => PathBuf::from(path),
+    };
+    if !expanded.is_file() {
+        return Err(format!("Config file {} does not exist", expanded.display()));
+    }
+    config_file_overrides(&expanded)?;
+    expanded
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve config file {}: {}", expanded.display(), e))
+}
+
+/// Translate an alternate config file into codex `-c key=value` overrides.
+///
+/// Each top-level key becomes one override, so a section present in the file
+/// replaces the same section of the default config while everything else
+/// still comes from codex's normal config discovery.
+pub fn config_file_overrides(path: &Path) -> Result<Vec<String>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?;
+    overrides_from_toml(&content)
+        .map_err(|e| format!("Failed to parse config file {}: {}", path.display(), e))
+}
+
+fn overrides_from_toml(content: &str) -> Result<Vec<String>, String> {
+    let table: toml::Table = toml::from_str(content).map_err(|e| e.to_string())?;
+    Ok(table
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect())
+}
+
+/// Read ~/.codex/config.toml, or an empty config when it does not exist
+pub fn load_codex_config() -> Result<CodexConfig, String> {
+    let config_path = get_config_path()?;
+
+    if !config_path.exists() {
+        return Ok(CodexConfig::default());
+    }
+
+    let content = std::fs::read_to_string(&config_path)
+        .map_err(|e| format!("Failed to read config file: {}", e))?;
+
+    toml::from_str(&content).map_err(|e| format!("Failed to parse config file: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides_from_toml() {
+        let overrides = overrides_from_toml(
+            "model = \"o3\"\n[sandbox_workspace_write]\nnetwork_access = true\n",
+        )
+        .unwrap();
+        assert_eq!(overrides.len(), 2);
+        assert!(overrides.contains(&"model=\"o3\"".to_string()));
+        assert!(overrides
+            .iter()
+            .any(|o| o.starts_with("sandbox_workspace_write={") && o.contains("network_access = true")));
+        assert!(overrides_from_toml("not toml [").is_err());
+    }
+}
@@ -0,0 +1,292 @@
+//! Checks of a new session's options before codex is asked to start it
+//!
+//! Nothing is spawned or written: commands are looked up on `PATH` and the
+//! model list is only consulted when an app-server is already running.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::{load_codex_config, CodexConfig};
+use crate::mcp::{validate_mcp_server, McpServerConfig};
+use crate::session::SandboxMode;
+use crate::state::ClientState;
+
+/// Values codex accepts for `approvalPolicy`
+const APPROVAL_POLICIES: &[&str] = &["untrusted", "on-failure", "on-request", "never"];
+
+/// Providers codex defines itself, which config.toml need not list
+const BUILTIN_PROVIDERS: &[&str] = &["openai", "oss"];
+
+/// Options of a session as the new-session form submits them; the names
+/// match the `newConversation` params
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStartConfig {
+    pub model: Option<String>,
+    pub cwd: Option<String>,
+    pub approval_policy: Option<String>,
+    pub sandbox: Option<String>,
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub mcp_servers: HashMap<String, McpServerConfig>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IssueSeverity {
+    /// codex would refuse to start the session or fail right away
+    Error,
+    /// The session starts, but probably not the way the user meant
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigIssue {
+    /// Form field at fault, e.g. "cwd" or "mcpServers.github.env.TOKEN"
+    pub field: String,
+    pub severity: IssueSeverity,
+    pub message: String,
+}
+
+fn issue(
+    field: impl Into<String>,
+    severity: IssueSeverity,
+    message: impl Into<String>,
+) -> ConfigIssue {
+    ConfigIssue {
+        field: field.into(),
+        severity,
+        message: message.into(),
+    }
+}
+
+fn check_sandbox(config: &SessionStartConfig, issues: &mut Vec<ConfigIssue>) {
+    let sandbox = match config.sandbox.as_deref() {
+        Some(value) => match SandboxMode::parse(value) {
+            Some(mode) => Some(mode),
+            None => {
+                issues.push(issue(
+                    "sandbox",
+                    IssueSeverity::Error,
+                    format!("Unknown sandbox mode '{value}'"),
+                ));
+                None
+            }
+        },
+        None => None,
+    };
+    let approval = config.approval_policy.as_deref();
+    if let Some(policy) = approval.filter(|policy| !APPROVAL_POLICIES.contains(policy)) {
+        issues.push(issue(
+            "approvalPolicy",
+            IssueSeverity::Error,
+            format!("Unknown approval policy '{policy}'"),
+        ));
+    }
+    match (sandbox, approval) {
+        (Some(SandboxMode::DangerFullAccess), Some("never")) => issues.push(issue(
+            "approvalPolicy",
+            IssueSeverity::Warning,
+            "codex will run any command without a sandbox and without asking",
+        )),
+        (Some(SandboxMode::ReadOnly), Some("never")) => issues.push(issue(
+            "approvalPolicy",
+            IssueSeverity::Warning,
+            "Commands that need to write will fail, since codex never asks to escalate",
+        )),
+        _ => {}
+    }
+}
+
+fn check_profile(
+    config: &SessionStartConfig,
+    codex_config: &CodexConfig,
+    env: &dyn Fn(&str) -> Option<String>,
+    issues: &mut Vec<ConfigIssue>,
+) {
+    let Some(name) = config.profile.as_deref() else {
+        return;
+    };
+    let Some(profile) = codex_config.profiles.get(name) else {
+        issues.push(issue(
+            "profile",
+            IssueSeverity::Error,
+            format!("Profile '{name}' is not defined in config.toml"),
+        ));
+        return;
+    };
+    let provider_name = profile.model_provider.as_str();
+    let Some(provider) = codex_config.model_providers.get(provider_name) else {
+        if !BUILTIN_PROVIDERS.contains(&provider_name) {
+            issues.push(issue(
+                "profile",
+                IssueSeverity::Error,
+                format!("Profile '{name}' uses unknown model provider '{provider_name}'"),
+            ));
+        }
+        return;
+    };
+    match provider.env_key.as_deref().map(str::trim) {
+        Some("") => issues.push(issue(
+            format!("modelProviders.{provider_name}.envKey"),
+            IssueSeverity::Error,
+            format!("Model provider '{provider_name}' has an empty env_key"),
+        )),
+        Some(key) if !env(key).is_some_and(|value| !value.trim().is_empty()) => {
+            issues.push(issue(
+                "profile",
+                IssueSeverity::Error,
+                format!("Environment variable {key} for provider '{provider_name}' is not set"),
+            ))
+        }
+        _ => {}
+    }
+}
+
+fn check_mcp_servers(config: &SessionStartConfig, issues: &mut Vec<ConfigIssue>) {
+    let mut names: Vec<&String> = config.mcp_servers.keys().collect();
+    names.sort();
+    for name in names {
+        let server = &config.mcp_servers[name];
+        let field = format!("mcpServers.{name}");
+        let (enabled, env) = match server {
+            McpServerConfig::Stdio { enabled, env, .. } => (*enabled, env.as_ref()),
+            McpServerConfig::Http { enabled, .. } | McpServerConfig::Sse { enabled, .. } => {
+                (*enabled, None)
+            }
+        };
+        if !enabled {
+            continue;
+        }
+        if let Err(message) = validate_mcp_server(name, server) {
+            issues.push(issue(field.clone(), IssueSeverity::Error, message));
+        }
+        let mut keys: Vec<(&String, &String)> = env.into_iter().flatten().collect();
+        keys.sort();
+        for (key, value) in keys {
+            if key.trim().is_empty() {
+                issues.push(issue(
+                    format!("{field}.env"),
+                    IssueSeverity::Error,
+                    format!("MCP server '{name}' has an environment variable without a name"),
+                ));
+            } else if value.is_empty() {
+                issues.push(issue(
+                    format!("{field}.env.{key}"),
+                    IssueSeverity::Warning,
+                    format!("Environment variable {key} of MCP server '{name}' is empty"),
+                ));
+            }
+        }
+    }
+}
+
+/// Every check that needs no running codex
+fn check_config(
+    config: &SessionStartConfig,
+    codex_config: &CodexConfig,
+    env: &dyn Fn(&str) -> Option<String>,
+) -> Vec<ConfigIssue> {
+    let mut issues = Vec::new();
+    if config.model.as_deref().is_some_and(|model| model.trim().is_empty()) {
+        issues.push(issue("model", IssueSeverity::Error, "Model must not be empty"));
+    }
+    match config.cwd.as_deref() {
+        Some(cwd) if cwd.trim().is_empty() => issues.push(issue(
+            "cwd",
+            IssueSeverity::Error,
+            "Working directory must not be empty",
+        )),
+        Some(cwd) if !Path::new(cwd).is_dir() => issues.push(issue(
+            "cwd",
+            IssueSeverity::Error,
+            format!("{cwd} is not a directory"),
+        )),
+        _ => {}
+    }
+    check_sandbox(config, &mut issues);
+    check_profile(config, codex_config, env, &mut issues);
+    check_mcp_servers(config, &mut issues);
+    issues
+}
+
+/// Whether a running app-server offers `model`; `None` when that cannot be
+/// told without starting one
+async fn model_offered(state: &ClientState, model: &str) -> Option<bool> {
+    let client = { state.client.lock().await.clone() }?;
+    let models = client.list_models().await.ok()??;
+    let models = models["data"].as_array()?;
+    Some(models.iter().any(|entry| {
+        entry["id"].as_str() == Some(model) || entry["model"].as_str() == Some(model)
+    }))
+}
+
+/// Errors and warnings for the options of a session about to be started,
+/// each naming the field at fault
+pub async fn validate_config(state: &ClientState, config: &SessionStartConfig) -> Vec<ConfigIssue> {
+    let (codex_config, config_error) = match load_codex_config() {
+        Ok(codex_config) => (codex_config, None),
+        Err(err) => (CodexConfig::default(), Some(err)),
+    };
+    let mut issues = check_config(config, &codex_config, &|key| std::env::var(key).ok());
+    if let Some(err) = config_error {
+        issues.push(issue("profile", IssueSeverity::Warning, err));
+    }
+    if let Some(model) = config.model.as_deref().filter(|model| !model.trim().is_empty()) {
+        if model_offered(state, model).await == Some(false) {
+            issues.push(issue(
+                "model",
+                IssueSeverity::Error,
+                format!("The model provider does not offer '{model}'"),
+            ));
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_config() {
+        let mut config = SessionStartConfig {
+            cwd: Some(std::env::temp_dir().display().to_string()),
+            sandbox: Some("danger-full-access".to_string()),
+            approval_policy: Some("never".to_string()),
+            profile: Some("missing".to_string()),
+            ..Default::default()
+        };
+        config.mcp_servers.insert(
+            "tools".to_string(),
+            McpServerConfig::Stdio {
+                command: "codexia-no-such-command".to_string(),
+                args: Vec::new(),
+                env: Some(HashMap::from([("TOKEN".to_string(), String::new())])),
+                enabled: true,
+            },
+        );
+
+        let issues = check_config(&config, &CodexConfig::default(), &|_| None);
+        let fields: Vec<(&str, IssueSeverity)> =
+            issues.iter().map(|i| (i.field.as_str(), i.severity)).collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("approvalPolicy", IssueSeverity::Warning),
+                ("profile", IssueSeverity::Error),
+                ("mcpServers.tools", IssueSeverity::Error),
+                ("mcpServers.tools.env.TOKEN", IssueSeverity::Warning),
+            ]
+        );
+
+        config.cwd = Some(String::new());
+        config.sandbox = Some("sandboxed".to_string());
+        let issues = check_config(&config, &CodexConfig::default(), &|_| None);
+        assert_eq!(issues[0].field, "cwd");
+        assert_eq!(issues[1].field, "sandbox");
+    }
+}
@@ -0,0 +1,73 @@
+use std::fmt;
+use std::time::Duration;
+
+/// Typed errors raised by codex-client itself.
+///
+/// Tauri commands surface errors as strings, so every variant renders a
+/// stable, human readable message via `Display` and converts into `String`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodexError {
+    /// No session is registered under the given id
+    UnknownSession(String),
+    /// A session id that is empty, too long or has characters other than
+    /// letters, digits, `-` and `_`
+    InvalidSessionId(String),
+    /// A user message arrived before `min_message_interval_ms` elapsed
+    RateLimited { retry_after: Duration },
+    /// The approval request was already answered or never existed
+    UnknownApproval(String),
+    /// Events after `since` were already dropped from the replay buffer
+    EventsUnavailable { conversation_id: String, since: u64 },
+    /// The installed codex cannot do what was asked
+    Unsupported(String),
+    /// codex has not run a command in the session yet
+    NoExecOutput(String),
+    /// codex has not reported usage limits for the session yet
+    NoRateLimitStatus(String),
+    /// The app-server did not answer `initialize` in time and was stopped
+    StartupTimeout { timeout: Duration },
+    /// Session options that cannot be applied, e.g. paths outside the
+    /// working directory
+    InvalidConfig(String),
+}
+
+impl fmt::Display for CodexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodexError::UnknownSession(id) => write!(f, "Unknown session: {id}"),
+            CodexError::InvalidSessionId(id) => write!(f, "InvalidSessionId: {id:?}"),
+            CodexError::RateLimited { retry_after } => write!(
+                f,
+                "RateLimited: retry after {}ms",
+                retry_after.as_millis()
+            ),
+            CodexError::UnknownApproval(id) => write!(f, "Unknown approval: {id}"),
+            CodexError::EventsUnavailable {
+                conversation_id,
+                since,
+            } => write!(
+                f,
+                "Events of session {conversation_id} after seq {since} are no longer available"
+            ),
+            CodexError::Unsupported(reason) => write!(f, "Unsupported: {reason}"),
+            CodexError::NoExecOutput(id) => write!(f, "Session {id} has not run a command yet"),
+            CodexError::NoRateLimitStatus(id) => {
+                write!(f, "codex has not reported rate limits for session {id} yet")
+            }
+            CodexError::StartupTimeout { timeout } => write!(
+                f,
+                "StartupTimeout: codex did not start within {}s",
+                timeout.as_secs()
+            ),
+            CodexError::InvalidConfig(reason) => write!(f, "InvalidConfig: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for CodexError {}
+
+impl From<CodexError> for String {
+    fn from(err: CodexError) -> Self {
+        err.to_string()
+    }
+}
@@ -1,17 +1,43 @@
+use async_trait::async_trait;
 use serde_json::Value as JsonValue;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 
 /// Event callback type
 /// Accepts event name and JSON data
 pub type EventCallback = Arc<dyn Fn(&str, JsonValue) -> Result<(), String> + Send + Sync>;
 
+/// Sees every event before its subscribers do and may add to its data
+#[async_trait]
+pub trait EventRecorder: Send + Sync {
+    async fn record(&self, event: &str, data: &mut JsonValue);
+}
+
+/// Conversation an event belongs to, wherever the payload keeps it
+pub fn event_conversation_id(data: &JsonValue) -> Option<&str> {
+    ["conversationId", "threadId"].iter().find_map(|key| {
+        data["params"][*key]
+            .as_str()
+            .or_else(|| data[*key].as_str())
+    })
+}
+
+/// Handle returned by `subscribe`, used to remove that one subscriber
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
 /// EventBus - replaces Tauri's event system
 /// Provides publish/subscribe pattern for event management
 #[derive(Clone)]
 pub struct EventBus {
-    subscribers: Arc<RwLock<HashMap<String, Vec<EventCallback>>>>,
+    subscribers: Arc<RwLock<HashMap<String, Vec<(SubscriptionId, EventCallback)>>>>,
+    next_id: Arc<AtomicU64>,
+    recorder: Option<Arc<dyn EventRecorder>>,
+    /// Held while an event is recorded and delivered, so subscribers get
+    /// events in the order they were recorded
+    delivery: Arc<Mutex<()>>,
 }
 
 impl EventBus {
@@ -19,6 +45,17 @@ impl EventBus {
     pub fn new() -> Self {
         Self {
             subscribers: Arc::new(RwLock::new(HashMap::new())),
+            next_id: Arc::new(AtomicU64::new(1)),
+            recorder: None,
+            delivery: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Create an event bus that passes every event to `recorder` first
+    pub fn with_recorder(recorder: Arc<dyn EventRecorder>) -> Self {
+        Self {
+            recorder: Some(recorder),
+            ..Self::new()
         }
     }
 
@@ -27,13 +64,15 @@ impl EventBus {
     /// # Arguments
     /// * `event` - Event name
     /// * `callback` - Event callback function
-    pub async fn subscribe(&self, event: impl Into<String>, callback: EventCallback) {
+    pub async fn subscribe(&self, event: impl Into<String>, callback: EventCallback) -> SubscriptionId {
         let event = event.into();
+        let id = SubscriptionId(self.next_id.fetch_add(1, Ordering::Relaxed));
         let mut subscribers = self.subscribers.write().await;
         subscribers
             .entry(event)
             .or_insert_with(Vec::new)
-            .push(callback);
+            .push((id, callback));
+        id
     }
 
     /// Emit an event
@@ -41,13 +80,17 @@ impl EventBus {
     /// # Arguments
     /// * `event` - Event name
     /// * `data` - Event data (JSON format)
-    pub async fn emit(&self, event: impl Into<String>, data: JsonValue) {
+    pub async fn emit(&self, event: impl Into<String>, mut data: JsonValue) {
         let event = event.into();
+        let _delivery = self.delivery.lock().await;
+        if let Some(recorder) = &self.recorder {
+            recorder.record(&event, &mut data).await;
+        }
         let subscribers = self.subscribers.read().await;
 
         if let Some(callbacks) = subscribers.get(&event) {
             log::debug!("[EventBus] Emitting event: {} to {} subscribers", event, callbacks.len());
-            for callback in callbacks {
+            for (_, callback) in callbacks {
                 // Ignore callback errors, continue notifying other subscribers
                 let _ = callback(&event, data.clone());
             }
@@ -68,6 +111,15 @@ impl EventBus {
         subscribers.remove(event);
     }
 
+    /// Remove a single subscriber, leaving the others of its event in place
+    pub async fn remove_subscription(&self, id: SubscriptionId) {
+        let mut subscribers = self.subscribers.write().await;
+        for callbacks in subscribers.values_mut() {
+            callbacks.retain(|(subscription, _)| *subscription != id);
+        }
+        subscribers.retain(|_, callbacks| !callbacks.is_empty());
+    }
+
     /// Get all subscribed event names
     pub async fn event_names(&self) -> Vec<String> {
         let subscribers = self.subscribers.read().await;
@@ -162,4 +214,16 @@ mod tests {
 
         assert_eq!(bus.event_names().await.len(), 0);
     }
+
+    #[tokio::test]
+    async fn test_remove_subscription() {
+        let bus = EventBus::new();
+
+        let first = bus.subscribe("test", Arc::new(|_, _| Ok(()))).await;
+        bus.subscribe("test", Arc::new(|_, _| Ok(()))).await;
+
+        bus.remove_subscription(first).await;
+        assert_eq!(bus.event_names().await.len(), 1);
+        assert_eq!(bus.subscribers.read().await["test"].len(), 1);
+    }
 }
@@ -1,8 +1,10 @@
 pub mod client;
 pub mod config;
 pub mod db;
+pub mod error;
 pub mod events;
 pub mod services;
+pub mod session;
 pub mod session_files;
 pub mod state;
 pub mod transport;
@@ -10,13 +12,16 @@ pub mod utils;
 pub mod mcp;
 
 pub use client::CodexAppServerClient;
-pub use events::EventBus;
+pub use error::CodexError;
+pub use events::{EventBus, EventRecorder, SubscriptionId};
+pub use session::{SessionConfig, SessionRegistry};
 pub use state::ClientState;
 
 // Re-export commonly used types
 pub use config::CodexConfig;
 pub use config::project::ProjectConfig;
 pub use config::provider::ModelProvider as ProviderConfig;
+pub use config::proxy::ProxyConfig;
 pub use config::profile::Profile as ProfileConfig;
 pub use mcp::McpServerConfig;
 
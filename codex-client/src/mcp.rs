@@ -1,11 +1,18 @@
 use serde::{de::Deserializer, Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
 use std::str::FromStr;
+use std::time::Duration;
+use serde_json::json;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{ChildStdin, ChildStdout};
 use toml_edit::{value, Document, Item, Table, Value};
 
 use super::config::{get_config_path, CodexConfig};
 use super::config::toml_helpers::{serialize_to_table, write_document_with_backup};
+use crate::utils::command::create_tokio_command;
 
 fn default_enabled() -> bool {
     true
@@ -136,6 +143,16 @@ impl<'de> Deserialize<'de> for McpServerConfig {
     }
 }
 
+impl McpServerConfig {
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            McpServerConfig::Stdio { enabled, .. }
+            | McpServerConfig::Http { enabled, .. }
+            | McpServerConfig::Sse { enabled, .. } => *enabled,
+        }
+    }
+}
+
 pub async fn read_mcp_servers() -> Result<HashMap<String, McpServerConfig>, String> {
     let config_path = get_config_path()?;
 
@@ -259,3 +276,305 @@ pub async fn set_mcp_server_enabled(name: String, enabled: bool) -> Result<(), S
 
     Ok(())
 }
+
+/// Locate `command` the way a shell would: as a path when it contains a
+/// separator, otherwise in each `PATH` directory
+fn find_command(command: &str) -> Option<PathBuf> {
+    let path = Path::new(command);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    let extensions: &[&str] = if cfg!(windows) {
+        &["", ".exe", ".cmd", ".bat"]
+    } else {
+        &[""]
+    };
+    let search_path = std::env::var_os("PATH")?;
+    std::env::split_paths(&search_path).find_map(|dir| {
+        extensions
+            .iter()
+            .map(|ext| dir.join(format!("{command}{ext}")))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+/// Check a server definition before handing it to codex: the name must be
+/// usable as a config key and a stdio command must exist
+pub fn validate_mcp_server(name: &str, config: &McpServerConfig) -> Result<(), String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(format!(
+            "Invalid MCP server name '{name}': use letters, digits, '_' or '-'"
+        ));
+    }
+    match config {
+        McpServerConfig::Stdio { command, .. } => {
+            if find_command(command).is_none() {
+                return Err(format!(
+                    "MCP server '{name}': command '{command}' was not found"
+                ));
+            }
+        }
+        McpServerConfig::Http { url, .. } | McpServerConfig::Sse { url, .. } => {
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                return Err(format!("MCP server '{name}': '{url}' is not an http(s) URL"));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Per-conversation codex config overrides (`mcp_servers.<name>` keys) that
+/// add `servers` on top of the ones in config.toml
+pub fn mcp_config_overrides(
+    servers: &HashMap<String, McpServerConfig>,
+) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+    servers
+        .iter()
+        .map(|(name, config)| {
+            validate_mcp_server(name, config)?;
+            // codex's own shape: the transport follows from command vs url
+            let value = match config {
+                McpServerConfig::Stdio {
+                    command,
+                    args,
+                    env,
+                    enabled,
+                } => json!({
+                    "command": command,
+                    "args": args,
+                    "env": env.clone().unwrap_or_default(),
+                    "enabled": enabled,
+                }),
+                McpServerConfig::Http { url, enabled } | McpServerConfig::Sse { url, enabled } => {
+                    json!({ "url": url, "enabled": enabled })
+                }
+            };
+            Ok((format!("mcp_servers.{name}"), value))
+        })
+        .collect()
+}
+
+/// How long each step of `test_mcp_server` may take
+const MCP_TEST_STEP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// MCP protocol version announced by `test_mcp_server`
+const MCP_PROTOCOL_VERSION: &str = "2025-06-18";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum McpTestFailure {
+    /// The command could not be started
+    Spawn,
+    /// The server did not answer in time
+    Timeout,
+    /// The server answered with an error or something that is not MCP
+    Protocol,
+}
+
+/// Outcome of `test_mcp_server`
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum McpTestResult {
+    #[serde(rename_all = "camelCase")]
+    Ok {
+        /// `serverInfo` from the initialize response
+        server_info: Option<serde_json::Value>,
+        tools: Vec<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Failed {
+        failure: McpTestFailure,
+        message: String,
+        /// Last lines the server wrote to stderr
+        stderr: Option<String>,
+    },
+}
+
+type McpLines = Lines<BufReader<ChildStdout>>;
+
+async fn write_mcp_message(
+    stdin: &mut ChildStdin,
+    message: &serde_json::Value,
+) -> std::io::Result<()> {
+    stdin.write_all(format!("{message}\n").as_bytes()).await?;
+    stdin.flush().await
+}
+
+/// Send one JSON-RPC request and wait for its response, skipping
+/// notifications, server requests and log lines on stdout
+async fn mcp_request(
+    stdin: &mut ChildStdin,
+    lines: &mut McpLines,
+    id: u64,
+    method: &str,
+    params: serde_json::Value,
+) -> Result<serde_json::Value, (McpTestFailure, String)> {
+    let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+    write_mcp_message(stdin, &request)
+        .await
+        .map_err(|e| (McpTestFailure::Protocol, format!("Failed to write {method}: {e}")))?;
+
+    let read_response = async {
+        loop {
+            let line = match lines.next_line().await {
+                Ok(Some(line)) => line,
+                Ok(None) => {
+                    return Err((
+                        McpTestFailure::Protocol,
+                        format!("Server exited before answering {method}"),
+                    ))
+                }
+                Err(e) => return Err((McpTestFailure::Protocol, e.to_string())),
+            };
+            let Ok(response) = serde_json::from_str::<serde_json::Value>(&line) else {
+                continue;
+            };
+            if response["id"].as_u64() != Some(id) || response.get("method").is_some() {
+                continue;
+            }
+            if let Some(error) = response.get("error") {
+                let message = error["message"].as_str().unwrap_or("unknown error");
+                return Err((McpTestFailure::Protocol, format!("{method} failed: {message}")));
+            }
+            return Ok(response["result"].clone());
+        }
+    };
+    tokio::time::timeout(MCP_TEST_STEP_TIMEOUT, read_response)
+        .await
+        .map_err(|_| {
+            (
+                McpTestFailure::Timeout,
+                format!("No answer to {method} within {}s", MCP_TEST_STEP_TIMEOUT.as_secs()),
+            )
+        })?
+}
+
+/// Launch a stdio MCP server, run the initialize handshake, list its tools
+/// and stop it again
+pub async fn test_mcp_server(
+    name: &str,
+    command: &str,
+    args: &[String],
+    env: Option<&HashMap<String, String>>,
+) -> McpTestResult {
+    let mut cmd = create_tokio_command(command);
+    cmd.args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(env) = env {
+        cmd.envs(env);
+    }
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            return McpTestResult::Failed {
+                failure: McpTestFailure::Spawn,
+                message: format!("Failed to start MCP server '{name}': {e}"),
+                stderr: None,
+            }
+        }
+    };
+
+    let (Some(mut stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+        return McpTestResult::Failed {
+            failure: McpTestFailure::Spawn,
+            message: format!("MCP server '{name}' has no stdio pipes"),
+            stderr: None,
+        };
+    };
+    let stderr_task = child.stderr.take().map(|mut stderr| {
+        tokio::spawn(async move {
+            let mut output = String::new();
+            let _ = stderr.read_to_string(&mut output).await;
+            output
+        })
+    });
+    let mut lines = BufReader::new(stdout).lines();
+
+    let outcome = async {
+        let initialized = mcp_request(
+            &mut stdin,
+            &mut lines,
+            1,
+            "initialize",
+            json!({
+                "protocolVersion": MCP_PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": { "name": "codexia", "version": env!("CARGO_PKG_VERSION") },
+            }),
+        )
+        .await?;
+        let initialized_notification =
+            json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        let _ = write_mcp_message(&mut stdin, &initialized_notification).await;
+        let listed = mcp_request(&mut stdin, &mut lines, 2, "tools/list", json!({})).await?;
+        let tools: Vec<String> = listed["tools"]
+            .as_array()
+            .map(|tools| {
+                tools
+                    .iter()
+                    .filter_map(|tool| tool["name"].as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok::<_, (McpTestFailure, String)>((initialized.get("serverInfo").cloned(), tools))
+    }
+    .await;
+
+    drop(stdin);
+    let _ = child.kill().await;
+    // A server that left children holding stderr open must not block us
+    let stderr = match stderr_task {
+        Some(task) => tokio::time::timeout(Duration::from_secs(1), task)
+            .await
+            .ok()
+            .and_then(Result::ok),
+        None => None,
+    };
+
+    match outcome {
+        Ok((server_info, tools)) => McpTestResult::Ok { server_info, tools },
+        Err((failure, message)) => McpTestResult::Failed {
+            failure,
+            message,
+            stderr: stderr.map(|output| last_lines(&output, 20)).filter(|s| !s.is_empty()),
+        },
+    }
+}
+
+fn last_lines(text: &str, count: usize) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    lines[lines.len().saturating_sub(count)..].join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_mcp_server() {
+        let http = McpServerConfig::Http {
+            url: "https://example.com/mcp".to_string(),
+            enabled: true,
+        };
+        assert!(validate_mcp_server("docs", &http).is_ok());
+        assert!(validate_mcp_server("docs.v2", &http).is_err());
+
+        let missing = McpServerConfig::Stdio {
+            command: "codexia-no-such-mcp-server".to_string(),
+            args: Vec::new(),
+            env: None,
+            enabled: true,
+        };
+        assert!(validate_mcp_server("local", &missing).is_err());
+
+        let overrides = mcp_config_overrides(&HashMap::from([("docs".to_string(), http)])).unwrap();
+        assert_eq!(overrides["mcp_servers.docs"]["url"], "https://example.com/mcp");
+    }
+}
@@ -0,0 +1,82 @@
+//! codex accounts
+//!
+//! codex keeps a single credential in `~/.codex/auth.json`; there is no
+//! store of several accounts to pick from. Listing reports that one account
+//! and switching fails with `CodexError::Unsupported` for any other id, so
+//! the UI can offer switching once codex supports it.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::CodexError;
+use crate::state::{get_client, ClientState};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountInfo {
+    /// Email for ChatGPT accounts, the auth method otherwise
+    pub id: String,
+    /// "chatgpt" or "apiKey"
+    pub method: String,
+    pub email: Option<String>,
+    pub plan_type: Option<String>,
+    pub active: bool,
+}
+
+/// Build the account entry from an `account/read` response
+fn account_from_response(response: &Value) -> Option<AccountInfo> {
+    let account = response.get("account").filter(|account| !account.is_null())?;
+    let method = account["type"].as_str().unwrap_or("unknown").to_string();
+    let email = account["email"].as_str().map(String::from);
+    Some(AccountInfo {
+        id: email.clone().unwrap_or_else(|| method.clone()),
+        method,
+        email,
+        plan_type: account["planType"].as_str().map(String::from),
+        active: true,
+    })
+}
+
+/// Accounts codex can use; at most the one it is logged in with
+pub async fn list_codex_accounts(state: &ClientState) -> Result<Vec<AccountInfo>, String> {
+    let client = get_client(state).await?;
+    let response = client.get_account(false).await?;
+    let response = serde_json::to_value(response).map_err(|err| err.to_string())?;
+    Ok(account_from_response(&response).into_iter().collect())
+}
+
+/// Make `account_id` the account new sessions use. Only the current account
+/// can be selected until codex stores more than one credential.
+pub async fn switch_codex_account(state: &ClientState, account_id: &str) -> Result<(), String> {
+    let accounts = list_codex_accounts(state).await?;
+    if accounts.iter().any(|account| account.id == account_id) {
+        return Ok(());
+    }
+    Err(CodexError::Unsupported(
+        "this codex version stores a single account; log out and log in to change accounts"
+            .to_string(),
+    )
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_account_from_response() {
+        let chatgpt = json!({
+            "account": { "type": "chatgpt", "email": "dev@example.com", "planType": "plus" },
+            "requiresOpenaiAuth": true,
+        });
+        let account = account_from_response(&chatgpt).unwrap();
+        assert_eq!(account.id, "dev@example.com");
+        assert_eq!(account.plan_type.as_deref(), Some("plus"));
+
+        let api_key = json!({ "account": { "type": "apiKey" } });
+        assert_eq!(account_from_response(&api_key).unwrap().id, "apiKey");
+
+        assert!(account_from_response(&json!({ "account": null })).is_none());
+    }
+}
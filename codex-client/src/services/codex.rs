@@ -1,3 +1,5 @@
+use serde::Serialize;
+
 use crate::utils::codex_discovery::discover_codex_command;
 use crate::utils::command::create_command;
 
@@ -23,3 +25,109 @@ pub async fn check_codex_version() -> Result<String, String> {
         Err(format!("Codex binary returned error: {}", err_msg))
     }
 }
+
+/// Whether codex has credentials to start a session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum AuthStatus {
+    #[serde(rename_all = "camelCase")]
+    Authenticated {
+        /// "chatgpt", "apiKey" or codex's own wording for other methods
+        method: String,
+        /// Masked API key when codex reports one
+        account: Option<String>,
+    },
+    NotAuthenticated,
+    /// This codex version has no way to query the login state
+    Unknown { reason: String },
+}
+
+/// Interpret the output of `codex login status`
+fn parse_login_status(output: &str) -> Option<AuthStatus> {
+    let line = output
+        .lines()
+        .map(str::trim)
+        .find(|line| line.starts_with("Logged in") || line.starts_with("Not logged in"))?;
+    if line.starts_with("Not logged in") {
+        return Some(AuthStatus::NotAuthenticated);
+    }
+    let method = line
+        .strip_prefix("Logged in using")
+        .unwrap_or_default()
+        .trim();
+    let status = if method.eq_ignore_ascii_case("chatgpt") {
+        AuthStatus::Authenticated {
+            method: "chatgpt".to_string(),
+            account: None,
+        }
+    } else if let Some(key) = method.strip_prefix("an API key") {
+        let key = key.trim_start_matches([' ', '-']).trim();
+        AuthStatus::Authenticated {
+            method: "apiKey".to_string(),
+            account: (!key.is_empty()).then(|| key.to_string()),
+        }
+    } else {
+        AuthStatus::Authenticated {
+            method: method.to_string(),
+            account: None,
+        }
+    };
+    Some(status)
+}
+
+/// Ask codex whether the user is logged in, via `codex login status`
+pub async fn check_codex_auth() -> Result<AuthStatus, String> {
+    let path = match discover_codex_command() {
+        Some(p) => p.to_string_lossy().to_string(),
+        None => "codex".to_string(),
+    };
+
+    let output = create_command(&path)
+        .args(["login", "status"])
+        .output()
+        .map_err(|e| format!("Failed to execute codex binary: {}", e))?;
+
+    // codex prints the status on stderr in some versions and stdout in others
+    let text = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(parse_login_status(&text).unwrap_or_else(|| AuthStatus::Unknown {
+        reason: format!(
+            "codex login status is not supported by this codex version: {}",
+            text.trim()
+        ),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_login_status() {
+        assert_eq!(
+            parse_login_status("Logged in using ChatGPT\n"),
+            Some(AuthStatus::Authenticated {
+                method: "chatgpt".to_string(),
+                account: None,
+            })
+        );
+        assert_eq!(
+            parse_login_status("Logged in using an API key - sk-proj-***ABCDE\n"),
+            Some(AuthStatus::Authenticated {
+                method: "apiKey".to_string(),
+                account: Some("sk-proj-***ABCDE".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_login_status("Not logged in\n"),
+            Some(AuthStatus::NotAuthenticated)
+        );
+        assert_eq!(
+            parse_login_status("error: unrecognized subcommand 'status'"),
+            None
+        );
+    }
+}
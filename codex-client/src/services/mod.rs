@@ -1,2 +1,5 @@
+pub mod accounts;
 pub mod codex;
+pub mod prompt_templates;
 pub mod coder;
+pub mod oneshot;
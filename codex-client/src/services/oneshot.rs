@@ -0,0 +1,148 @@
+//! One prompt, one answer: `codex exec` without a tracked session
+//!
+//! The process is separate from the shared app-server and nothing is
+//! recorded in the session registry, so scripted queries leave no trace in
+//! the UI.
+
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::time::{timeout_at, Instant};
+
+use crate::utils::codex_discovery::discover_codex_command;
+use crate::utils::command::create_tokio_command;
+
+/// How long a query may run when the caller does not say
+pub const DEFAULT_ONESHOT_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OneshotConfig {
+    pub model: Option<String>,
+    /// Defaults to the app's working directory
+    pub cwd: Option<PathBuf>,
+    /// codex sandbox mode; read-only unless set, since a query should not
+    /// change files
+    pub sandbox: Option<String>,
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OneshotResult {
+    /// Agent messages in order, separated by blank lines
+    pub output: String,
+    /// The timeout expired and `output` is what arrived before it
+    pub timed_out: bool,
+}
+
+/// Text of an agent message in a line of `codex exec --json` output. Newer
+/// codex versions report items, older ones protocol events.
+fn agent_message(line: &str) -> Option<String> {
+    let event: Value = serde_json::from_str(line).ok()?;
+    let item = &event["item"];
+    if event["type"] == "item.completed" && item["type"] == "agent_message" {
+        return item["text"].as_str().map(String::from);
+    }
+    let msg = &event["msg"];
+    if msg["type"] == "agent_message" {
+        return msg["message"].as_str().map(String::from);
+    }
+    None
+}
+
+/// Run `prompt` through a fresh `codex exec` and return codex's answer
+pub async fn codex_oneshot(config: &OneshotConfig, prompt: &str) -> Result<OneshotResult, String> {
+    if prompt.trim().is_empty() {
+        return Err("Prompt is empty".to_string());
+    }
+    let binary = discover_codex_command().ok_or_else(|| {
+        "Unable to locate codex binary. Install Codex CLI or set CODEX_PATH.".to_string()
+    })?;
+    let timeout = config
+        .timeout_ms
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_ONESHOT_TIMEOUT);
+
+    let mut command = create_tokio_command(binary.to_str().unwrap_or(""));
+    command
+        .args(["exec", "--json", "--skip-git-repo-check", "--sandbox"])
+        .arg(config.sandbox.as_deref().unwrap_or("read-only"));
+    if let Some(model) = &config.model {
+        command.arg("--model").arg(model);
+    }
+    if let Some(cwd) = &config.cwd {
+        command.arg("--cd").arg(cwd);
+    }
+    // `-` reads the prompt from stdin, which has no length limit
+    command
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true);
+
+    let mut child = command
+        .spawn()
+        .map_err(|err| format!("Failed to start codex exec: {err}"))?;
+    let mut stdin = child.stdin.take().ok_or("codex exec has no stdin")?;
+    stdin
+        .write_all(prompt.as_bytes())
+        .await
+        .map_err(|err| format!("Failed to send the prompt to codex: {err}"))?;
+    drop(stdin);
+    let stdout = child.stdout.take().ok_or("codex exec has no stdout")?;
+
+    let deadline = Instant::now() + timeout;
+    let mut lines = BufReader::new(stdout).lines();
+    let mut messages = Vec::new();
+    let mut timed_out = false;
+    loop {
+        match timeout_at(deadline, lines.next_line()).await {
+            Ok(Ok(Some(line))) => messages.extend(agent_message(&line)),
+            Ok(Ok(None)) => break,
+            Ok(Err(err)) => return Err(format!("Failed to read codex output: {err}")),
+            Err(_) => {
+                timed_out = true;
+                break;
+            }
+        }
+    }
+
+    if timed_out {
+        let _ = child.kill().await;
+    } else {
+        let status = child
+            .wait()
+            .await
+            .map_err(|err| format!("Failed to wait for codex exec: {err}"))?;
+        if !status.success() && messages.is_empty() {
+            return Err(format!("codex exec failed with {status}"));
+        }
+    }
+
+    Ok(OneshotResult {
+        output: messages.join("\n\n"),
+        timed_out,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agent_message() {
+        let item = r#"{"type":"item.completed","item":{"type":"agent_message","text":"Hi"}}"#;
+        assert_eq!(agent_message(item).as_deref(), Some("Hi"));
+        let event = r#"{"id":"0","msg":{"type":"agent_message","message":"Hello"}}"#;
+        assert_eq!(agent_message(event).as_deref(), Some("Hello"));
+        let reasoning = r#"{"type":"item.completed","item":{"type":"reasoning","text":"..."}}"#;
+        assert_eq!(agent_message(reasoning), None);
+        assert_eq!(agent_message("Reading prompt from stdin..."), None);
+    }
+}
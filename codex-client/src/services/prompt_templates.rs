@@ -0,0 +1,178 @@
+//! Saved prompts with `{{variable}}` placeholders
+//!
+//! Templates are kept in `~/.codexia/prompt_templates.json`, ordered by
+//! name.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::state::{get_client, ClientState};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptTemplate {
+    pub name: String,
+    pub body: String,
+    /// Placeholders of `body`, in order of first use
+    #[serde(default)]
+    pub variables: Vec<String>,
+}
+
+fn templates_path() -> Result<PathBuf, String> {
+    let home_dir = dirs::home_dir().ok_or("Could not get home directory")?;
+    Ok(home_dir.join(".codexia").join("prompt_templates.json"))
+}
+
+fn read_templates() -> Result<BTreeMap<String, PromptTemplate>, String> {
+    let path = templates_path()?;
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let templates: Vec<PromptTemplate> = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    Ok(templates
+        .into_iter()
+        .map(|template| (template.name.clone(), template))
+        .collect())
+}
+
+fn write_templates(templates: &BTreeMap<String, PromptTemplate>) -> Result<(), String> {
+    let path = templates_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let list: Vec<&PromptTemplate> = templates.values().collect();
+    let content = serde_json::to_string_pretty(&list).map_err(|e| e.to_string())?;
+    std::fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Byte ranges and trimmed names of the `{{name}}` placeholders in `body`
+fn placeholders(body: &str) -> Vec<(usize, usize, &str)> {
+    let mut found = Vec::new();
+    let mut offset = 0;
+    while let Some(open) = body[offset..].find("{{") {
+        let start = offset + open;
+        let Some(close) = body[start + 2..].find("}}") else {
+            break;
+        };
+        let end = start + 2 + close + 2;
+        let name = body[start + 2..end - 2].trim();
+        if !name.is_empty() {
+            found.push((start, end, name));
+        }
+        offset = end;
+    }
+    found
+}
+
+/// Placeholder names of `body`, each once, in order of first use
+fn template_variables(body: &str) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    placeholders(body)
+        .into_iter()
+        .filter(|(_, _, name)| seen.insert(*name))
+        .map(|(_, _, name)| name.to_string())
+        .collect()
+}
+
+/// Fill every placeholder of `body`, failing with the names of the ones
+/// `variables` has no value for
+pub fn render_template(body: &str, variables: &HashMap<String, String>) -> Result<String, String> {
+    let found = placeholders(body);
+    let missing: BTreeSet<&str> = found
+        .iter()
+        .map(|(_, _, name)| *name)
+        .filter(|name| !variables.contains_key(*name))
+        .collect();
+    if !missing.is_empty() {
+        let names: Vec<&str> = missing.into_iter().collect();
+        return Err(format!("Missing template variables: {}", names.join(", ")));
+    }
+
+    let mut rendered = String::with_capacity(body.len());
+    let mut last = 0;
+    for (start, end, name) in found {
+        rendered.push_str(&body[last..start]);
+        rendered.push_str(&variables[name]);
+        last = end;
+    }
+    rendered.push_str(&body[last..]);
+    Ok(rendered)
+}
+
+pub fn list_prompt_templates() -> Result<Vec<PromptTemplate>, String> {
+    Ok(read_templates()?.into_values().collect())
+}
+
+/// Create a template or replace the one with the same name
+pub fn save_prompt_template(name: &str, body: &str) -> Result<PromptTemplate, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Template name must not be empty".to_string());
+    }
+    if body.trim().is_empty() {
+        return Err(format!("Template {name} has an empty body"));
+    }
+    let template = PromptTemplate {
+        name: name.to_string(),
+        body: body.to_string(),
+        variables: template_variables(body),
+    };
+    let mut templates = read_templates()?;
+    templates.insert(template.name.clone(), template.clone());
+    write_templates(&templates)?;
+    Ok(template)
+}
+
+/// Render template `name` and send it to the session as a user message
+pub async fn send_template(
+    state: &ClientState,
+    session_id: &str,
+    name: &str,
+    variables: &HashMap<String, String>,
+) -> Result<String, String> {
+    let template = read_templates()?
+        .remove(name.trim())
+        .ok_or_else(|| format!("Unknown prompt template: {name}"))?;
+    let text = render_template(&template.body, variables)?;
+    state.sessions.get(session_id).await?;
+
+    let params = serde_json::from_value(json!({
+        "conversationId": session_id,
+        "items": [{ "type": "text", "data": { "text": text } }],
+    }))
+    .map_err(|err| err.to_string())?;
+    let client = get_client(state).await?;
+    client.send_user_message(params).await?;
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_template() {
+        let body = "Write tests for {{ file }} using {{framework}}; keep {{file}} unchanged";
+        assert_eq!(template_variables(body), vec!["file", "framework"]);
+
+        let mut variables = HashMap::from([("file".to_string(), "src/lib.rs".to_string())]);
+        assert_eq!(
+            render_template(body, &variables),
+            Err("Missing template variables: framework".to_string())
+        );
+
+        variables.insert("framework".to_string(), "proptest".to_string());
+        assert_eq!(
+            render_template(body, &variables).unwrap(),
+            "Write tests for src/lib.rs using proptest; keep src/lib.rs unchanged"
+        );
+        assert_eq!(render_template("no {{ }} here {{", &variables).unwrap(), "no {{ }} here {{");
+    }
+}
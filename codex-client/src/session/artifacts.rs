@@ -0,0 +1,84 @@
+//! A directory for the files codex generates
+//!
+//! A session with `artifacts_dir` in its config asks codex, through its
+//! developer instructions, to put reports, scratch scripts and other
+//! generated output in that directory instead of next to the sources. The
+//! directory must be inside the working directory or next to it, and is
+//! created before the conversation starts.
+
+use std::path::{Path, PathBuf};
+
+use super::out_of_tree::resolve;
+use super::Session;
+
+/// Absolute location of `dir`, relative to `cwd`, after checking it is a
+/// subdirectory or a sibling of `cwd`
+pub fn resolve_artifacts_dir(cwd: &Path, dir: &str) -> Result<PathBuf, String> {
+    let cwd = resolve(Path::new("/"), cwd);
+    let resolved = resolve(&cwd, Path::new(dir));
+    let inside = resolved.starts_with(&cwd) && resolved != cwd;
+    let adjacent = resolved.parent().is_some() && resolved.parent() == cwd.parent();
+    if !inside && !adjacent {
+        return Err(format!(
+            "Artifacts directory {} must be inside or next to {}",
+            resolved.display(),
+            cwd.display()
+        ));
+    }
+    Ok(resolved)
+}
+
+/// Resolve the session's artifacts directory and create it
+pub fn prepare_artifacts_dir(cwd: &Path, dir: &str) -> Result<PathBuf, String> {
+    let resolved = resolve_artifacts_dir(cwd, dir)?;
+    std::fs::create_dir_all(&resolved)
+        .map_err(|e| format!("Failed to create {}: {}", resolved.display(), e))?;
+    Ok(resolved)
+}
+
+/// Developer instructions codex receives: the user's standing instructions
+/// followed by where generated files go
+pub fn developer_instructions(
+    instructions: Option<&str>,
+    artifacts_dir: Option<&Path>,
+) -> Option<String> {
+    let artifacts = artifacts_dir.map(|dir| {
+        format!(
+            "Write files you generate that are not part of the project's sources, such as \
+             reports, scratch scripts and exported data, to {}.",
+            dir.display()
+        )
+    });
+    match (instructions, artifacts) {
+        (Some(instructions), Some(artifacts)) => Some(format!("{instructions}\n\n{artifacts}")),
+        (Some(instructions), None) => Some(instructions.to_string()),
+        (None, artifacts) => artifacts,
+    }
+}
+
+/// The session's resolved artifacts directory, if it has a valid one
+pub fn session_artifacts_dir(session: &Session) -> Option<PathBuf> {
+    let dir = session.config.artifacts_dir.as_deref()?;
+    resolve_artifacts_dir(session.cwd.as_deref()?, dir).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_artifacts_dir() {
+        let cwd = Path::new("/work/project");
+        assert_eq!(
+            resolve_artifacts_dir(cwd, "out/codex").unwrap(),
+            PathBuf::from("/work/project/out/codex")
+        );
+        assert_eq!(
+            resolve_artifacts_dir(cwd, "../project-artifacts").unwrap(),
+            PathBuf::from("/work/project-artifacts")
+        );
+        assert!(resolve_artifacts_dir(cwd, ".").is_err());
+        assert!(resolve_artifacts_dir(cwd, "../../elsewhere").is_err());
+        assert!(resolve_artifacts_dir(cwd, "/tmp/artifacts").is_err());
+    }
+}
@@ -0,0 +1,116 @@
+//! Lines codex wrote through its own patches
+//!
+//! Every `patch_apply_begin` event carries the changes of one patch; once
+//! the matching `patch_apply_end` reports success, the lines the patch added
+//! are counted per file. Edits codex makes through shell commands do not go
+//! through patches and are not recorded.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde_json::Value;
+use tokio::sync::RwLock;
+
+/// Added lines of each file, with how often each line was added
+pub type LineCounts = HashMap<String, usize>;
+
+#[derive(Clone, Default)]
+pub struct PatchLedger {
+    /// Changes of patches that started but did not finish, by call id
+    pending: Arc<RwLock<HashMap<String, Value>>>,
+    /// Lines added by applied patches, by conversation and absolute path
+    applied: Arc<RwLock<HashMap<String, HashMap<PathBuf, LineCounts>>>>,
+}
+
+/// Lines a single file change adds: the content of a new file, or the `+`
+/// lines of an update's unified diff
+fn added_lines(change: &Value) -> Vec<String> {
+    if let Some(content) = change["add"]["content"].as_str() {
+        return content.lines().map(String::from).collect();
+    }
+    let Some(diff) = change["update"]["unified_diff"].as_str() else {
+        return Vec::new();
+    };
+    diff.lines()
+        .filter(|line| !line.starts_with("+++"))
+        .filter_map(|line| line.strip_prefix('+'))
+        .map(String::from)
+        .collect()
+}
+
+impl PatchLedger {
+    /// Follow the patch events of a conversation's event stream
+    pub async fn record_event(&self, conversation_id: &str, msg: &Value) {
+        let Some(call_id) = msg["call_id"].as_str() else {
+            return;
+        };
+        match msg["type"].as_str() {
+            Some("patch_apply_begin") => {
+                self.pending
+                    .write()
+                    .await
+                    .insert(call_id.to_string(), msg["changes"].clone());
+            }
+            Some("patch_apply_end") => {
+                let Some(changes) = self.pending.write().await.remove(call_id) else {
+                    return;
+                };
+                if msg["success"].as_bool() != Some(true) {
+                    return;
+                }
+                let Some(changes) = changes.as_object() else {
+                    return;
+                };
+                let mut applied = self.applied.write().await;
+                let files = applied.entry(conversation_id.to_string()).or_default();
+                for (path, change) in changes {
+                    // A moved file's new lines belong to its destination
+                    let path = change["update"]["move_path"]
+                        .as_str()
+                        .unwrap_or(path.as_str());
+                    let counts = files.entry(PathBuf::from(path)).or_default();
+                    for line in added_lines(change) {
+                        *counts.entry(line).or_default() += 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Lines codex's patches added to `path` during the conversation
+    pub async fn codex_lines(&self, conversation_id: &str, path: &Path) -> LineCounts {
+        self.applied
+            .read()
+            .await
+            .get(conversation_id)
+            .and_then(|files| files.get(path))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub async fn forget(&self, conversation_id: &str) {
+        self.applied.write().await.remove(conversation_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_added_lines() {
+        let add = json!({ "add": { "content": "fn main() {\n}\n" } });
+        assert_eq!(added_lines(&add), vec!["fn main() {", "}"]);
+
+        let update = json!({ "update": {
+            "unified_diff": "--- a/x.rs\n+++ b/x.rs\n@@ -1 +1,2 @@\n-old\n+new\n+more\n",
+            "move_path": null,
+        } });
+        assert_eq!(added_lines(&update), vec!["new", "more"]);
+
+        assert!(added_lines(&json!({ "delete": { "content": "gone" } })).is_empty());
+    }
+}
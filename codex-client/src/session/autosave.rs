@@ -0,0 +1,180 @@
+//! Codexia's own copy of each session's event stream on disk
+//!
+//! codex appends to its rollout as it goes, but the events Codexia received
+//! and rendered only live in memory. They are written to
+//! `~/.codexia/transcripts/<conversation id>.jsonl` a moment after the last
+//! event and at the end of every turn, so a crash loses at most the flush
+//! interval. Each write appends the events recorded since the previous one,
+//! which are then dropped from memory. Streaming deltas are skipped: the
+//! complete message follows them.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+use super::registry::validate_session_id;
+
+/// Delay between the last event and the write
+pub const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Default)]
+struct Journal {
+    /// Events not written yet
+    pending: Vec<Value>,
+    /// Bumped on every recorded event
+    generation: u64,
+}
+
+#[derive(Clone)]
+pub struct TranscriptAutosave {
+    journals: Arc<Mutex<HashMap<String, Journal>>>,
+    interval_ms: Arc<AtomicU64>,
+    /// Serializes writes so events are appended in the order they came
+    flush_lock: Arc<Mutex<()>>,
+}
+
+impl Default for TranscriptAutosave {
+    fn default() -> Self {
+        Self {
+            journals: Arc::default(),
+            interval_ms: Arc::new(AtomicU64::new(DEFAULT_FLUSH_INTERVAL.as_millis() as u64)),
+            flush_lock: Arc::default(),
+        }
+    }
+}
+
+fn transcripts_dir() -> Result<PathBuf, String> {
+    let home_dir = dirs::home_dir().ok_or("Could not get home directory")?;
+    Ok(home_dir.join(".codexia").join("transcripts"))
+}
+
+/// Where the events of `conversation_id` are saved
+pub fn transcript_path(conversation_id: &str) -> Result<PathBuf, String> {
+    let conversation_id = validate_session_id(conversation_id)?;
+    Ok(transcripts_dir()?.join(format!("{conversation_id}.jsonl")))
+}
+
+/// Append `contents` to `path`, creating the file and its directory
+fn append(path: &Path, contents: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(contents))
+        .map_err(|e| format!("Failed to append to {}: {}", path.display(), e))
+}
+
+/// Replace `path` with `contents` through a temporary file and a rename, so
+/// a crash mid-write leaves the previous version intact
+pub(super) fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+    std::fs::write(&temp, contents)
+        .map_err(|e| format!("Failed to write {}: {}", temp.display(), e))?;
+    std::fs::rename(&temp, path)
+        .map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+}
+
+impl TranscriptAutosave {
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms.load(Ordering::Relaxed))
+    }
+
+    pub fn set_interval(&self, interval: Duration) {
+        self.interval_ms
+            .store(interval.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Add a `codex:event` payload and schedule a write
+    pub async fn record(&self, conversation_id: &str, data: &Value) {
+        let msg_type = data["params"]["msg"]["type"].as_str().unwrap_or_default();
+        if msg_type.ends_with("_delta") {
+            return;
+        }
+        let generation = {
+            let mut journals = self.journals.lock().await;
+            let journal = journals.entry(conversation_id.to_string()).or_default();
+            journal.pending.push(data.clone());
+            journal.generation += 1;
+            journal.generation
+        };
+
+        if matches!(msg_type, "task_complete" | "turn_aborted") {
+            self.flush(conversation_id).await;
+            return;
+        }
+        // Debounce: only the write scheduled by the latest event runs
+        let autosave = self.clone();
+        let conversation_id = conversation_id.to_string();
+        let interval = self.interval();
+        tokio::spawn(async move {
+            tokio::time::sleep(interval).await;
+            let current = {
+                let journals = autosave.journals.lock().await;
+                journals.get(&conversation_id).map(|journal| journal.generation)
+            };
+            if current == Some(generation) {
+                autosave.flush(&conversation_id).await;
+            }
+        });
+    }
+
+    /// Append the events recorded since the last write, if any
+    pub async fn flush(&self, conversation_id: &str) {
+        let _guard = self.flush_lock.lock().await;
+        let events = {
+            let mut journals = self.journals.lock().await;
+            match journals.get_mut(conversation_id) {
+                Some(journal) if !journal.pending.is_empty() => {
+                    std::mem::take(&mut journal.pending)
+                }
+                _ => return,
+            }
+        };
+        let mut contents = String::new();
+        for event in &events {
+            contents.push_str(&event.to_string());
+            contents.push('\n');
+        }
+
+        let path = match transcript_path(conversation_id) {
+            Ok(path) => path,
+            Err(err) => {
+                log::warn!("Not saving transcript of {conversation_id}: {err}");
+                return;
+            }
+        };
+        let written = tokio::task::spawn_blocking(move || append(&path, contents.as_bytes()))
+            .await
+            .map_err(|err| err.to_string())
+            .and_then(|result| result);
+        if let Err(err) = written {
+            log::warn!("Failed to save transcript of {conversation_id}: {err}");
+            // Keep the events for the next write, ahead of newer ones
+            if let Some(journal) = self.journals.lock().await.get_mut(conversation_id) {
+                journal.pending.splice(0..0, events);
+            }
+        }
+    }
+
+    /// Write what is pending and stop tracking a closed session
+    pub async fn forget(&self, conversation_id: &str) {
+        self.flush(conversation_id).await;
+        self.journals.lock().await.remove(conversation_id);
+    }
+}
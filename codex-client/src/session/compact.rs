@@ -0,0 +1,144 @@
+//! Shrinking the context of a long conversation
+//!
+//! codex's built-in compaction is used when the app-server offers it.
+//! Otherwise codex is asked to summarize the conversation, and the summary
+//! seeds a fresh conversation in the same working directory: codex's own
+//! context cannot be rewritten from the outside. The summarized
+//! conversation is then archived.
+
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::json;
+
+use super::lifecycle::archive_session;
+use super::turns::{run_turn, TurnWatcher};
+use crate::client::CodexAppServerClient;
+use crate::state::{get_client, ClientState};
+
+/// Upper bound for codex to finish a compaction or summary turn
+const COMPACT_TURN_TIMEOUT: Duration = Duration::from_secs(300);
+
+const SUMMARY_PROMPT: &str = "Summarize this conversation so far so that work can continue in a \
+fresh session: the goal, decisions made, files touched, current state and remaining next steps. \
+Reply with the summary only.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CompactMode {
+    /// codex compacted the conversation in place
+    Builtin,
+    /// The conversation was summarized into a new conversation
+    Summary,
+}
+
+/// Result of `compact_session`, also emitted as `codex-compacted`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactResult {
+    /// Conversation to continue in; a new id in `Summary` mode, where the
+    /// previous conversation is archived
+    pub conversation_id: String,
+    pub previous_conversation_id: String,
+    pub mode: CompactMode,
+    pub before_tokens: Option<u64>,
+    pub after_tokens: Option<u64>,
+}
+
+/// Summarize `session_id` and continue in a new conversation seeded with
+/// the summary, returning the new conversation id
+async fn compact_by_summary(
+    state: &ClientState,
+    client: &CodexAppServerClient,
+    session_id: &str,
+) -> Result<String, String> {
+    let summary = run_turn(
+        state,
+        client,
+        session_id,
+        SUMMARY_PROMPT,
+        Some(COMPACT_TURN_TIMEOUT),
+    )
+    .await?
+    .filter(|summary| !summary.trim().is_empty())
+    .ok_or_else(|| "codex returned an empty summary".to_string())?;
+
+    let session = state.sessions.get(session_id).await?;
+    let params = serde_json::from_value(json!({
+        "cwd": session.cwd,
+        "model": session.model,
+    }))
+    .map_err(|err| err.to_string())?;
+    let response = client.new_conversation(params, None).await?;
+    let new_id = response.conversation_id.to_string();
+    state
+        .sessions
+        .set_config(&new_id, session.config.clone())
+        .await?;
+
+    // Stream the new conversation's events like the one it replaces
+    client
+        .send_request(
+            "addConversationListener",
+            Some(json!({ "conversationId": new_id, "experimentalRawEvents": false })),
+        )
+        .await?;
+
+    let seed = format!(
+        "This session continues an earlier one. Summary of the earlier session:\n\n{summary}"
+    );
+    run_turn(state, client, &new_id, &seed, Some(COMPACT_TURN_TIMEOUT)).await?;
+    Ok(new_id)
+}
+
+/// Compact a long session's context and emit `codex-compacted` with the
+/// context size before and after
+pub async fn compact_session(
+    state: &ClientState,
+    session_id: &str,
+) -> Result<CompactResult, String> {
+    let before_tokens = state.sessions.get(session_id).await?.context_tokens;
+    let client = get_client(state).await?;
+
+    let watcher = TurnWatcher::new(&state.event_bus, session_id).await;
+    let builtin = match client.compact_conversation(session_id).await {
+        Ok(builtin) => builtin,
+        Err(err) => {
+            watcher.cancel().await;
+            return Err(err);
+        }
+    };
+    let (mode, conversation_id) = if builtin {
+        watcher.wait(Some(COMPACT_TURN_TIMEOUT)).await?;
+        (CompactMode::Builtin, session_id.to_string())
+    } else {
+        watcher.cancel().await;
+        let new_id = compact_by_summary(state, &client, session_id).await?;
+        // The summarized conversation is done with; archiving it stops codex
+        // from keeping it loaded next to its replacement
+        if let Err(err) = archive_session(state, session_id).await {
+            log::warn!("Failed to archive {session_id} after compacting it: {err}");
+        }
+        (CompactMode::Summary, new_id)
+    };
+
+    let after_tokens = state
+        .sessions
+        .get(&conversation_id)
+        .await
+        .ok()
+        .and_then(|session| session.context_tokens);
+    let result = CompactResult {
+        conversation_id,
+        previous_conversation_id: session_id.to_string(),
+        mode,
+        before_tokens,
+        after_tokens,
+    };
+
+    match serde_json::to_value(&result) {
+        Ok(payload) => state.event_bus.emit("codex-compacted", payload).await,
+        Err(err) => log::error!("Failed to serialize codex-compacted payload: {err}"),
+    }
+    Ok(result)
+}
@@ -0,0 +1,80 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::out_of_tree::resolve_allowed_paths;
+use super::output_limit::validate_max_output_tokens;
+
+/// Codexia-side options attached to a conversation.
+///
+/// These never reach codex itself; they tune how the client treats the
+/// session. Every field is optional and disabled by default.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionConfig {
+    /// Reject user messages sent faster than this interval
+    #[serde(default)]
+    pub min_message_interval_ms: Option<u64>,
+    /// Stop the session after this long without user input or a running turn
+    #[serde(default)]
+    pub idle_timeout_secs: Option<u64>,
+    /// What stopping an idle session means
+    #[serde(default)]
+    pub idle_action: IdleAction,
+    /// Record the working tree before each turn that may write files, so
+    /// the turn can be undone
+    #[serde(default)]
+    pub auto_stash_before_turn: bool,
+    /// Turn checkpoints kept per session, oldest removed first; defaults to
+    /// 20
+    #[serde(default)]
+    pub checkpoint_retention: Option<usize>,
+    /// How long codex may take to show any sign of acting on a user message
+    /// before `codex-input-stalled` is emitted; defaults to 60 seconds
+    #[serde(default)]
+    pub input_stall_timeout_ms: Option<u64>,
+    /// Directory, relative to the working directory, codex is asked to write
+    /// generated files to. Unlike the other options this one reaches codex,
+    /// as part of the developer instructions, when the conversation is
+    /// started or reloaded.
+    #[serde(default)]
+    pub artifacts_dir: Option<String>,
+    /// Command `run_project_tests` asks codex to run, e.g. "cargo test";
+    /// codex works one out when unset
+    #[serde(default)]
+    pub test_command: Option<String>,
+    /// Paths, relative to the working directory, the session is limited to:
+    /// diffs and the change summary only cover them, and codex writing
+    /// elsewhere is reported. Empty for the whole working directory.
+    #[serde(default)]
+    pub allowed_paths: Vec<String>,
+    /// Most tokens the model may write in one response. Like
+    /// `artifacts_dir` this reaches codex, as the `model_max_output_tokens`
+    /// config override, when the conversation is started or reloaded.
+    #[serde(default)]
+    pub max_output_tokens: Option<u32>,
+}
+
+impl SessionConfig {
+    /// Check the options that depend on the conversation's working
+    /// directory and model
+    pub fn validate(&self, cwd: Option<&Path>, model: Option<&str>) -> Result<(), String> {
+        if !self.allowed_paths.is_empty() {
+            let cwd = cwd.ok_or("Allowed paths need a working directory")?;
+            resolve_allowed_paths(cwd, &self.allowed_paths)?;
+        }
+        if let Some(tokens) = self.max_output_tokens {
+            validate_max_output_tokens(tokens, model)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IdleAction {
+    #[default]
+    Close,
+    /// Close and move the rollout to codex's archived sessions
+    Archive,
+}
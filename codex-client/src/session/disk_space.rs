@@ -0,0 +1,94 @@
+//! Warning before a session's disk fills up
+//!
+//! codex writing into a full disk fails with errors that do not mention
+//! space. The disks holding session working directories are checked
+//! periodically and `codex-low-disk-warning` is emitted once per session
+//! when free space drops below `LOW_DISK_THRESHOLD`, and again only after
+//! it has recovered in between.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::warn;
+use serde_json::json;
+
+use crate::state::ClientState;
+use crate::utils::filesystem::{disk_status, DiskStatus};
+
+/// How often free space is checked
+const DISK_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long listing the mounted disks may take
+const DISK_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Free space below which sessions are warned
+pub const LOW_DISK_THRESHOLD: u64 = 1024 * 1024 * 1024;
+
+/// `disk_status` off the async runtime, `None` when it fails or hangs
+async fn probe(dir: PathBuf) -> Option<DiskStatus> {
+    let probe = tokio::task::spawn_blocking(move || disk_status(&dir));
+    tokio::time::timeout(DISK_PROBE_TIMEOUT, probe)
+        .await
+        .ok()?
+        .ok()?
+}
+
+/// Space on the disk holding the session's working directory
+pub async fn working_dir_disk_status(
+    state: &ClientState,
+    session_id: &str,
+) -> Result<DiskStatus, String> {
+    let session = state.sessions.get(session_id).await?;
+    let cwd = session
+        .cwd
+        .ok_or_else(|| format!("Session {session_id} has no working directory"))?;
+    probe(cwd.clone())
+        .await
+        .ok_or_else(|| format!("Could not read the disk space of {}", cwd.display()))
+}
+
+/// Check free space until the app exits
+pub async fn watch_disk_space(state: Arc<ClientState>) {
+    let mut warned: HashSet<String> = HashSet::new();
+    let mut ticks = tokio::time::interval(DISK_CHECK_INTERVAL);
+    loop {
+        ticks.tick().await;
+        let ids = state.sessions.ids().await;
+        warned.retain(|id| ids.contains(id));
+        for session_id in ids {
+            let Ok(session) = state.sessions.get(&session_id).await else {
+                continue;
+            };
+            let Some(cwd) = session.cwd else {
+                continue;
+            };
+            let Some(status) = probe(cwd.clone()).await else {
+                continue;
+            };
+            if status.available >= LOW_DISK_THRESHOLD {
+                warned.remove(&session_id);
+                continue;
+            }
+            if !warned.insert(session_id.clone()) {
+                continue;
+            }
+            warn!(
+                "Only {} bytes free on the disk of session {session_id}",
+                status.available
+            );
+            state
+                .event_bus
+                .emit(
+                    "codex-low-disk-warning",
+                    json!({
+                        "conversationId": session_id,
+                        "path": cwd,
+                        "status": status,
+                    }),
+                )
+                .await;
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! Output of the latest command codex ran in each session
+//!
+//! `exec_command_begin` starts a new record and drops the previous one;
+//! `exec_command_end` fills in the output codex captured. Each stream is
+//! capped at `MAX_STREAM_BYTES`, keeping its end, where errors usually are.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::RwLock;
+
+/// Bytes kept of stdout and of stderr
+const MAX_STREAM_BYTES: usize = 256 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecOutput {
+    /// codex's id of the command, unique within the conversation
+    pub call_id: String,
+    pub command: Vec<String>,
+    pub stdout: String,
+    pub stderr: String,
+    /// `None` while the command is still running
+    pub exit_code: Option<i64>,
+    pub duration_ms: Option<u64>,
+    /// Output beyond `MAX_STREAM_BYTES` was dropped from the start
+    pub truncated: bool,
+}
+
+struct Record {
+    started_at: Instant,
+    output: ExecOutput,
+}
+
+#[derive(Clone, Default)]
+pub struct ExecOutputs {
+    latest: Arc<RwLock<HashMap<String, Record>>>,
+}
+
+/// The last `MAX_STREAM_BYTES` of `text`, cut at a character boundary
+fn keep_tail(text: &str) -> (String, bool) {
+    if text.len() <= MAX_STREAM_BYTES {
+        return (text.to_string(), false);
+    }
+    let mut start = text.len() - MAX_STREAM_BYTES;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    (text[start..].to_string(), true)
+}
+
+fn command_of(msg: &Value) -> Vec<String> {
+    msg["command"]
+        .as_array()
+        .map(|parts| {
+            parts
+                .iter()
+                .filter_map(|part| part.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+impl ExecOutputs {
+    /// Follow the exec events of a conversation's event stream
+    pub async fn record_event(&self, conversation_id: &str, msg: &Value) {
+        let Some(call_id) = msg["call_id"].as_str() else {
+            return;
+        };
+        match msg["type"].as_str() {
+            Some("exec_command_begin") => {
+                let record = Record {
+                    started_at: Instant::now(),
+                    output: ExecOutput {
+                        call_id: call_id.to_string(),
+                        command: command_of(msg),
+                        stdout: String::new(),
+                        stderr: String::new(),
+                        exit_code: None,
+                        duration_ms: None,
+                        truncated: false,
+                    },
+                };
+                self.latest
+                    .write()
+                    .await
+                    .insert(conversation_id.to_string(), record);
+            }
+            Some("exec_command_end") => {
+                let mut latest = self.latest.write().await;
+                let Some(record) = latest
+                    .get_mut(conversation_id)
+                    .filter(|record| record.output.call_id == call_id)
+                else {
+                    return;
+                };
+                let (stdout, stdout_cut) = keep_tail(msg["stdout"].as_str().unwrap_or_default());
+                let (stderr, stderr_cut) = keep_tail(msg["stderr"].as_str().unwrap_or_default());
+                let output = &mut record.output;
+                output.stdout = stdout;
+                output.stderr = stderr;
+                output.truncated = stdout_cut || stderr_cut;
+                output.exit_code = msg["exit_code"].as_i64();
+                output.duration_ms = Some(record.started_at.elapsed().as_millis() as u64);
+            }
+            _ => {}
+        }
+    }
+
+    pub async fn latest(&self, conversation_id: &str) -> Option<ExecOutput> {
+        self.latest
+            .read()
+            .await
+            .get(conversation_id)
+            .map(|record| record.output.clone())
+    }
+
+    pub async fn forget(&self, conversation_id: &str) {
+        self.latest.write().await.remove(conversation_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keep_tail() {
+        assert_eq!(keep_tail("short"), ("short".to_string(), false));
+
+        let long = format!("é{}", "x".repeat(MAX_STREAM_BYTES));
+        let (tail, truncated) = keep_tail(&long);
+        assert!(truncated);
+        assert_eq!(tail.len(), MAX_STREAM_BYTES);
+        assert!(tail.chars().all(|c| c == 'x'));
+    }
+}
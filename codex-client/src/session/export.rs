@@ -0,0 +1,102 @@
+//! Self-contained export of a session for bug reports
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+
+use super::registry::SessionMetadata;
+use crate::config::{load_codex_config, CodexConfig};
+use crate::mcp::McpServerConfig;
+use crate::services::codex::check_codex_version;
+use crate::session_files::transcript::load_transcript;
+use crate::state::ClientState;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Everything needed to reproduce a reported issue with a session
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionBundle {
+    pub session: SessionMetadata,
+    /// ~/.codex/config.toml with secret env values redacted
+    pub codex_config: CodexConfig,
+    /// Rollout records in order
+    pub transcript: Vec<Value>,
+    pub token_usage: Option<Value>,
+    pub codex_version: Option<String>,
+    /// Diff of the working tree; filled in by the caller that owns git access
+    pub final_diff: Option<String>,
+    pub exported_at: DateTime<Utc>,
+}
+
+/// Env vars whose name suggests a credential
+fn is_secret_env_key(key: &str) -> bool {
+    let key = key.to_uppercase();
+    ["KEY", "TOKEN", "SECRET", "PASSWORD", "PASSWD", "CREDENTIAL", "AUTH"]
+        .iter()
+        .any(|marker| key.contains(marker))
+}
+
+fn redact_config(mut config: CodexConfig) -> CodexConfig {
+    for server in config.mcp_servers.values_mut() {
+        if let McpServerConfig::Stdio { env: Some(env), .. } = server {
+            for (key, value) in env.iter_mut() {
+                if is_secret_env_key(key) {
+                    *value = REDACTED.to_string();
+                }
+            }
+        }
+    }
+    config
+}
+
+pub async fn export_session_bundle(
+    state: &ClientState,
+    session_id: &str,
+) -> Result<SessionBundle, String> {
+    let session = state.sessions.get(session_id).await?;
+    let transcript = match &session.rollout_path {
+        Some(path) => load_transcript(path)?,
+        None => Vec::new(),
+    };
+
+    Ok(SessionBundle {
+        session: session.metadata(),
+        codex_config: redact_config(load_codex_config()?),
+        transcript,
+        token_usage: session.token_usage.clone(),
+        codex_version: check_codex_version().await.ok(),
+        final_diff: None,
+        exported_at: Utc::now(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_redact_config() {
+        let mut config = CodexConfig::default();
+        config.mcp_servers.insert(
+            "server".to_string(),
+            McpServerConfig::Stdio {
+                command: "npx".to_string(),
+                args: Vec::new(),
+                env: Some(HashMap::from([
+                    ("GITHUB_TOKEN".to_string(), "ghp_secret".to_string()),
+                    ("LOG_LEVEL".to_string(), "debug".to_string()),
+                ])),
+                enabled: true,
+            },
+        );
+
+        let redacted = redact_config(config);
+        let McpServerConfig::Stdio { env: Some(env), .. } = &redacted.mcp_servers["server"] else {
+            panic!("expected a stdio server");
+        };
+        assert_eq!(env["GITHUB_TOKEN"], REDACTED);
+        assert_eq!(env["LOG_LEVEL"], "debug");
+    }
+}
@@ -0,0 +1,157 @@
+//! Files sent along with a message as context
+//!
+//! Instead of pasting files into the message, the user names files in the
+//! session's working directory and their content is added to the message,
+//! one block per file. A file that is missing, binary, over
+//! `MAX_FILE_BYTES`, outside the working directory or past the
+//! `MAX_TOTAL_BYTES` budget gets an error of its own, and the message is
+//! only built when every file could be read.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use super::out_of_tree::resolve;
+use crate::state::ClientState;
+
+/// Largest single file that can be attached
+pub const MAX_FILE_BYTES: u64 = 256 * 1024;
+/// Largest combined size of a message's files
+pub const MAX_TOTAL_BYTES: u64 = 1024 * 1024;
+/// How much of the file is checked for NUL bytes, like git's own heuristic
+const BINARY_SNIFF_BYTES: usize = 8000;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachedFile {
+    pub path: String,
+    pub size: u64,
+    /// Why the file could not be attached
+    pub error: Option<String>,
+}
+
+pub struct FileContext {
+    pub files: Vec<AttachedFile>,
+    /// The message with the file blocks before it, `None` when any file
+    /// has an error
+    pub text: Option<String>,
+}
+
+/// Content of the text file at `path`, relative to `cwd`, if it is no
+/// bigger than `max_bytes`
+async fn read_context_file(cwd: &Path, path: &str, max_bytes: u64) -> Result<String, String> {
+    let full_path = resolve(cwd, Path::new(path));
+    let outside = || format!("{path} is outside the working directory");
+    if !full_path.starts_with(cwd) {
+        return Err(outside());
+    }
+    // Symlinks can still point outside; compare the canonical locations
+    if let (Ok(canonical_cwd), Ok(canonical)) = (cwd.canonicalize(), full_path.canonicalize()) {
+        if !canonical.starts_with(&canonical_cwd) {
+            return Err(outside());
+        }
+    }
+
+    let meta = tokio::fs::metadata(&full_path)
+        .await
+        .map_err(|e| format!("Failed to read {path}: {e}"))?;
+    if !meta.is_file() {
+        return Err(format!("{path} is not a file"));
+    }
+    if meta.len() > max_bytes {
+        return Err(format!(
+            "{path} is {} bytes, over the {max_bytes} byte limit",
+            meta.len()
+        ));
+    }
+    let bytes = tokio::fs::read(&full_path)
+        .await
+        .map_err(|e| format!("Failed to read {path}: {e}"))?;
+    if bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0) {
+        return Err(format!("{path} is a binary file"));
+    }
+    String::from_utf8(bytes).map_err(|_| format!("{path} is not UTF-8 text"))
+}
+
+fn context_block(path: &str, content: &str) -> String {
+    let newline = if content.ends_with('\n') { "" } else { "\n" };
+    format!("<file path=\"{path}\">\n{content}{newline}</file>\n\n")
+}
+
+/// Read `file_paths` from the session's working directory and put them in
+/// front of `message`
+pub async fn build_file_context(
+    state: &ClientState,
+    session_id: &str,
+    message: &str,
+    file_paths: &[String],
+) -> Result<FileContext, String> {
+    let session = state.sessions.get(session_id).await?;
+    let cwd = session
+        .cwd
+        .ok_or_else(|| format!("Session {session_id} has no working directory"))?;
+    let cwd = resolve(Path::new("/"), &cwd);
+
+    let mut files = Vec::with_capacity(file_paths.len());
+    let mut blocks = String::new();
+    let mut remaining = MAX_TOTAL_BYTES;
+    for path in file_paths {
+        match read_context_file(&cwd, path, MAX_FILE_BYTES.min(remaining)).await {
+            Ok(content) => {
+                let size = content.len() as u64;
+                remaining -= size;
+                blocks.push_str(&context_block(path, &content));
+                files.push(AttachedFile {
+                    path: path.clone(),
+                    size,
+                    error: None,
+                });
+            }
+            Err(error) => files.push(AttachedFile {
+                path: path.clone(),
+                size: 0,
+                error: Some(error),
+            }),
+        }
+    }
+
+    let text = files
+        .iter()
+        .all(|file| file.error.is_none())
+        .then(|| format!("{blocks}{message}"));
+    Ok(FileContext { files, text })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_context_file() {
+        let dir = std::env::temp_dir().join(format!("codexia-file-context-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("notes.md"), "# Notes").unwrap();
+        std::fs::write(dir.join("logo.png"), [0x89, b'P', b'N', b'G', 0, 0]).unwrap();
+
+        let content = read_context_file(&dir, "notes.md", 100).await.unwrap();
+        assert_eq!(
+            context_block("notes.md", &content),
+            "<file path=\"notes.md\">\n# Notes\n</file>\n\n"
+        );
+        assert!(read_context_file(&dir, "notes.md", 4)
+            .await
+            .unwrap_err()
+            .contains("limit"));
+        assert!(read_context_file(&dir, "logo.png", 100)
+            .await
+            .unwrap_err()
+            .contains("binary"));
+        assert!(read_context_file(&dir, "missing.rs", 100).await.is_err());
+        assert!(read_context_file(&dir, "../secret", 100)
+            .await
+            .unwrap_err()
+            .contains("outside"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,204 @@
+//! Messages lined up to run after the current turn
+//!
+//! Follow-ups of a session are sent one per turn, in order, each once the
+//! previous turn completed. A turn that fails or is interrupted stops the
+//! chain: the remaining follow-ups are dropped and reported in
+//! `codex-followup-skipped` instead of running against an unexpected state.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use serde_json::json;
+use tokio::sync::{mpsc, Mutex};
+
+use crate::state::{get_client, ClientState};
+
+#[derive(Clone, Default)]
+pub struct Followups {
+    queues: Arc<Mutex<HashMap<String, VecDeque<String>>>>,
+}
+
+impl Followups {
+    pub async fn list(&self, conversation_id: &str) -> Vec<String> {
+        self.queues
+            .lock()
+            .await
+            .get(conversation_id)
+            .map(|queue| queue.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    async fn push(&self, conversation_id: &str, message: String) -> Vec<String> {
+        let mut queues = self.queues.lock().await;
+        let queue = queues.entry(conversation_id.to_string()).or_default();
+        queue.push_back(message);
+        queue.iter().cloned().collect()
+    }
+
+    /// Remove the follow-up at `index`, 0 being next up
+    async fn cancel(&self, conversation_id: &str, index: usize) -> Result<Vec<String>, String> {
+        let mut queues = self.queues.lock().await;
+        let queue = queues.entry(conversation_id.to_string()).or_default();
+        queue
+            .remove(index)
+            .ok_or_else(|| format!("No follow-up at position {index}"))?;
+        Ok(queue.iter().cloned().collect())
+    }
+
+    async fn pop(&self, conversation_id: &str) -> Option<String> {
+        self.queues.lock().await.get_mut(conversation_id)?.pop_front()
+    }
+
+    async fn take_all(&self, conversation_id: &str) -> Vec<String> {
+        self.queues
+            .lock()
+            .await
+            .remove(conversation_id)
+            .map(Vec::from)
+            .unwrap_or_default()
+    }
+
+    pub async fn forget(&self, conversation_id: &str) {
+        self.queues.lock().await.remove(conversation_id);
+    }
+}
+
+pub(super) async fn send_message(
+    state: &ClientState,
+    session_id: &str,
+    message: &str,
+) -> Result<(), String> {
+    let params = serde_json::from_value(json!({
+        "conversationId": session_id,
+        "items": [{ "type": "text", "data": { "text": message } }],
+    }))
+    .map_err(|err| err.to_string())?;
+    let client = get_client(state).await?;
+    client.send_user_message(params).await?;
+    Ok(())
+}
+
+/// Line up `message` to run after the current turn, or send it right away
+/// when no turn is running. Returns the follow-ups still waiting.
+pub async fn enqueue_followup(
+    state: &ClientState,
+    session_id: &str,
+    message: String,
+) -> Result<Vec<String>, String> {
+    let session = state.sessions.get(session_id).await?;
+    let followups = state.sessions.followups();
+    if session.turn_active || !followups.list(session_id).await.is_empty() {
+        return Ok(followups.push(session_id, message).await);
+    }
+    send_message(state, session_id, &message).await?;
+    Ok(Vec::new())
+}
+
+/// Follow-ups of the session, next up first
+pub async fn get_followups(state: &ClientState, session_id: &str) -> Result<Vec<String>, String> {
+    state.sessions.get(session_id).await?;
+    Ok(state.sessions.followups().list(session_id).await)
+}
+
+pub async fn cancel_followup(
+    state: &ClientState,
+    session_id: &str,
+    index: usize,
+) -> Result<Vec<String>, String> {
+    state.sessions.get(session_id).await?;
+    state.sessions.followups().cancel(session_id, index).await
+}
+
+/// Send each session's next follow-up when its turn completes, until the
+/// app exits
+pub async fn watch_followups(state: Arc<ClientState>) {
+    let (tx, mut turn_ends) = mpsc::unbounded_channel::<(String, String)>();
+    state
+        .event_bus
+        .subscribe(
+            "codex:event",
+            Arc::new(move |_, data| {
+                let params = &data["params"];
+                let (Some(conversation_id), Some(msg_type)) = (
+                    params["conversationId"].as_str(),
+                    params["msg"]["type"].as_str(),
+                ) else {
+                    return Ok(());
+                };
+                if matches!(msg_type, "task_complete" | "turn_aborted" | "error") {
+                    let _ = tx.send((conversation_id.to_string(), msg_type.to_string()));
+                }
+                Ok(())
+            }),
+        )
+        .await;
+
+    let followups = state.sessions.followups().clone();
+    while let Some((session_id, msg_type)) = turn_ends.recv().await {
+        if msg_type != "task_complete" {
+            let skipped = followups.take_all(&session_id).await;
+            if skipped.is_empty() {
+                continue;
+            }
+            log::info!(
+                "Dropping {} follow-ups of {session_id} after {msg_type}",
+                skipped.len()
+            );
+            state
+                .event_bus
+                .emit(
+                    "codex-followup-skipped",
+                    json!({
+                        "conversationId": session_id,
+                        "reason": msg_type,
+                        "skipped": skipped,
+                    }),
+                )
+                .await;
+            continue;
+        }
+
+        let Some(message) = followups.pop(&session_id).await else {
+            continue;
+        };
+        if let Err(error) = send_message(&state, &session_id, &message).await {
+            log::warn!("Failed to send follow-up of {session_id}: {error}");
+            let mut skipped = vec![message];
+            skipped.extend(followups.take_all(&session_id).await);
+            state
+                .event_bus
+                .emit(
+                    "codex-followup-skipped",
+                    json!({
+                        "conversationId": session_id,
+                        "reason": error,
+                        "skipped": skipped,
+                    }),
+                )
+                .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_followup_queue() {
+        let followups = Followups::default();
+        followups.push("a", "first".to_string()).await;
+        followups.push("a", "second".to_string()).await;
+        followups.push("a", "third".to_string()).await;
+
+        assert_eq!(
+            followups.cancel("a", 1).await.unwrap(),
+            vec!["first".to_string(), "third".to_string()]
+        );
+        assert!(followups.cancel("a", 5).await.is_err());
+        assert_eq!(followups.pop("a").await.as_deref(), Some("first"));
+        assert_eq!(followups.take_all("a").await, vec!["third".to_string()]);
+        assert!(followups.list("a").await.is_empty());
+        assert_eq!(followups.pop("b").await, None);
+    }
+}
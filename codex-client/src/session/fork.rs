@@ -0,0 +1,179 @@
+//! Branching a conversation into a second, independent one
+//!
+//! `resumeConversation` reopens a rollout under the conversation id stored
+//! in it, so it cannot start a copy next to the running source. The fork is
+//! a new conversation in the same working directory with the same options,
+//! seeded with the source's transcript as its first message.
+
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use super::mcp_servers::with_mcp_servers;
+use super::turns::run_turn;
+use crate::session_files::transcript::load_transcript;
+use crate::state::{get_client, ClientState};
+
+/// Transcript characters passed to the fork; older turns are cut so the
+/// seed message stays well inside the model's context
+const MAX_SEED_CHARS: usize = 100_000;
+
+/// Upper bound for codex to acknowledge the seed message
+const SEED_TURN_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Result of `fork_session`, also emitted as `codex-session-forked`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForkResult {
+    pub conversation_id: String,
+    pub parent_conversation_id: String,
+    /// Older turns were left out of the seed
+    pub truncated: bool,
+}
+
+/// User and agent messages of a rollout as a plain-text transcript, keeping
+/// the most recent `max_chars` characters
+fn transcript_text(records: &[Value], max_chars: usize) -> (String, bool) {
+    let turns: Vec<String> = records
+        .iter()
+        .filter(|record| record["type"].as_str() == Some("event_msg"))
+        .filter_map(|record| {
+            let payload = &record["payload"];
+            let speaker = match payload["type"].as_str()? {
+                "user_message" => "User",
+                "agent_message" => "Assistant",
+                _ => return None,
+            };
+            Some(format!("{speaker}: {}", payload["message"].as_str()?.trim()))
+        })
+        .collect();
+
+    let mut kept = Vec::new();
+    let mut chars = 0;
+    for turn in turns.iter().rev() {
+        chars += turn.chars().count();
+        if chars > max_chars && !kept.is_empty() {
+            break;
+        }
+        kept.push(turn.as_str());
+    }
+    let truncated = kept.len() < turns.len();
+    kept.reverse();
+    (kept.join("\n\n"), truncated)
+}
+
+fn seed_message(rollout_path: &Path) -> Result<(String, bool), String> {
+    let (transcript, truncated) = transcript_text(&load_transcript(rollout_path)?, MAX_SEED_CHARS);
+    if transcript.is_empty() {
+        return Err("The session has no messages to fork".to_string());
+    }
+    let note = if truncated {
+        " Earlier turns were left out."
+    } else {
+        ""
+    };
+    let seed = format!(
+        "This session is a fork of an earlier one and continues from where it left off.{note} \
+         Transcript of the earlier session:\n\n{transcript}\n\nReply only with a short \
+         acknowledgement; the next message continues the work."
+    );
+    Ok((seed, truncated))
+}
+
+/// Start a new conversation from `source_session_id`'s working directory,
+/// options and transcript. Both sessions then continue independently.
+pub async fn fork_session(
+    state: &ClientState,
+    source_session_id: &str,
+) -> Result<ForkResult, String> {
+    let source = state.sessions.get(source_session_id).await?;
+    let rollout_path = source
+        .rollout_path
+        .clone()
+        .ok_or_else(|| format!("Session {source_session_id} has no transcript"))?;
+    let (seed, truncated) = seed_message(&rollout_path)?;
+
+    let client = get_client(state).await?;
+    let params = serde_json::from_value(json!({
+        "cwd": source.cwd,
+        "model": source.model,
+    }))
+    .map_err(|err| err.to_string())?;
+    let params = if source.mcp_servers.is_empty() {
+        params
+    } else {
+        with_mcp_servers(params, &source.mcp_servers)?
+    };
+    let response = client.new_conversation(params, None).await?;
+    let conversation_id = response.conversation_id.to_string();
+
+    state
+        .sessions
+        .set_config(&conversation_id, source.config.clone())
+        .await?;
+    state
+        .sessions
+        .update(&conversation_id, |session| {
+            session.mcp_servers = source.mcp_servers.clone();
+            session.log_level = source.log_level;
+        })
+        .await?;
+
+    client
+        .send_request(
+            "addConversationListener",
+            Some(json!({ "conversationId": conversation_id, "experimentalRawEvents": false })),
+        )
+        .await?;
+    run_turn(
+        state,
+        &client,
+        &conversation_id,
+        &seed,
+        Some(SEED_TURN_TIMEOUT),
+    )
+    .await?;
+
+    let result = ForkResult {
+        conversation_id,
+        parent_conversation_id: source_session_id.to_string(),
+        truncated,
+    };
+    match serde_json::to_value(&result) {
+        Ok(payload) => state.event_bus.emit("codex-session-forked", payload).await,
+        Err(err) => log::error!("Failed to serialize codex-session-forked payload: {err}"),
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(kind: &str, text: &str) -> Value {
+        json!({ "type": "event_msg", "payload": { "type": kind, "message": text } })
+    }
+
+    #[test]
+    fn test_transcript_text() {
+        let records = vec![
+            json!({ "type": "session_meta", "payload": {} }),
+            message("user_message", "fix the build"),
+            message("token_count", "ignored"),
+            message("agent_message", "done"),
+            message("user_message", "now add tests"),
+        ];
+        let (text, truncated) = transcript_text(&records, 1000);
+        assert_eq!(
+            text,
+            "User: fix the build\n\nAssistant: done\n\nUser: now add tests"
+        );
+        assert!(!truncated);
+
+        let (text, truncated) = transcript_text(&records, 20);
+        assert_eq!(text, "User: now add tests");
+        assert!(truncated);
+    }
+}
@@ -0,0 +1,51 @@
+//! Stopping sessions nobody uses
+//!
+//! A session with `idle_timeout_secs` in its config is closed, or archived,
+//! once that long has passed since the last user message, the end of the
+//! last turn or a keep-alive. Sessions running a turn are never idle.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{info, warn};
+use serde_json::json;
+
+use super::config::IdleAction;
+use super::lifecycle::{archive_session, close_session};
+use crate::state::ClientState;
+
+/// How often sessions are checked against their idle timeout
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Close idle sessions until the app exits, emitting
+/// `codex-session-idle-closed` before each one is stopped
+pub async fn watch_idle_sessions(state: Arc<ClientState>) {
+    let mut ticks = tokio::time::interval(IDLE_CHECK_INTERVAL);
+    loop {
+        ticks.tick().await;
+        for (session_id, action, idle) in state.sessions.idle_sessions().await {
+            info!("Stopping session {session_id} after {}s idle", idle.as_secs());
+            state
+                .event_bus
+                .emit(
+                    "codex-session-idle-closed",
+                    json!({
+                        "conversationId": session_id,
+                        "idleSecs": idle.as_secs(),
+                        "action": action,
+                    }),
+                )
+                .await;
+            if action == IdleAction::Archive {
+                match archive_session(&state, &session_id).await {
+                    Ok(_) => continue,
+                    // Close it anyway so it is not reported idle again
+                    Err(err) => warn!("Failed to archive idle session {session_id}: {err}"),
+                }
+            }
+            if let Err(err) = close_session(&state, &session_id, None).await {
+                warn!("Failed to stop idle session {session_id}: {err}");
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//! Standing instructions of a session
+//!
+//! Instructions reach codex as the `developer_instructions` config override
+//! of the conversation, which codex adds to the model's context ahead of
+//! the user's turns. codex reads them only when it loads a conversation, so
+//! changing them reloads the conversation from its rollout.
+
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use codex_app_server_protocol::NewConversationParams;
+
+use super::artifacts::{developer_instructions, session_artifacts_dir};
+use super::output_limit::MAX_OUTPUT_TOKENS_KEY;
+use super::Session;
+use crate::state::{get_client, ClientState};
+
+const INSTRUCTIONS_KEY: &str = "developer_instructions";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstructionsChange {
+    pub instructions: Option<String>,
+    /// False when the conversation could not be reloaded yet (a turn is
+    /// running, or there is no rollout); the instructions then apply the
+    /// next time the session is resumed
+    pub applied: bool,
+}
+
+/// Add `instructions` to the config overrides of a new conversation
+pub fn with_instructions(
+    params: NewConversationParams,
+    instructions: &str,
+) -> Result<NewConversationParams, String> {
+    with_config_override(params, INSTRUCTIONS_KEY, json!(instructions))
+}
+
+/// Set the config override `key` of a new conversation to `value`
+pub(super) fn with_config_override(
+    params: NewConversationParams,
+    key: &str,
+    value: Value,
+) -> Result<NewConversationParams, String> {
+    with_config_overrides(params, [(key.to_string(), value)])
+}
+
+/// Add `overrides` to the config overrides of a new conversation, replacing
+/// those with the same key
+pub(super) fn with_config_overrides(
+    params: NewConversationParams,
+    overrides: impl IntoIterator<Item = (String, Value)>,
+) -> Result<NewConversationParams, String> {
+    let mut params = serde_json::to_value(params).map_err(|err| err.to_string())?;
+    let config = params
+        .as_object_mut()
+        .ok_or("Conversation params are not an object")?
+        .entry("config")
+        .or_insert_with(|| json!({}));
+    if config.is_null() {
+        *config = json!({});
+    }
+    config
+        .as_object_mut()
+        .ok_or("Conversation config overrides are not an object")?
+        .extend(overrides);
+    serde_json::from_value(params).map_err(|err| err.to_string())
+}
+
+/// Replace the standing instructions of a session; `None` or an empty
+/// string removes them. An idle session is reloaded from its rollout right
+/// away so the next turn already follows them.
+pub async fn update_session_instructions(
+    state: &ClientState,
+    session_id: &str,
+    instructions: Option<String>,
+) -> Result<InstructionsChange, String> {
+    let instructions = instructions.filter(|text| !text.trim().is_empty());
+    let session = state
+        .sessions
+        .update(session_id, |session| {
+            session.instructions = instructions.clone();
+            session.clone()
+        })
+        .await?;
+
+    let Some(rollout_path) = session.rollout_path.clone().filter(|_| !session.turn_active) else {
+        return Ok(InstructionsChange {
+            instructions,
+            applied: false,
+        });
+    };
+
+    reload_conversation(state, &session, &rollout_path).await?;
+    Ok(InstructionsChange {
+        instructions,
+        applied: true,
+    })
+}
+
+/// Load an idle conversation again from its rollout with the session's
+/// current working directory, model, instructions, artifacts directory and
+/// output limit. codex keeps the conversation id, so the registry entry is
+/// updated in place.
+pub(super) async fn reload_conversation(
+    state: &ClientState,
+    session: &Session,
+    rollout_path: &Path,
+) -> Result<(), String> {
+    let mut overrides = json!({ "cwd": session.cwd, "model": session.model, "config": {} });
+    let artifacts_dir = session_artifacts_dir(session);
+    if let Some(instructions) =
+        developer_instructions(session.instructions.as_deref(), artifacts_dir.as_deref())
+    {
+        overrides["config"][INSTRUCTIONS_KEY] = json!(instructions);
+    }
+    if let Some(tokens) = session.config.max_output_tokens {
+        overrides["config"][MAX_OUTPUT_TOKENS_KEY] = json!(tokens);
+    }
+    let params = serde_json::from_value(json!({ "path": rollout_path, "overrides": overrides }))
+        .map_err(|err| err.to_string())?;
+    let client = get_client(state).await?;
+    client.resume_conversation(params, None).await?;
+    // The reloaded conversation streams its events to a new listener
+    client
+        .send_request(
+            "addConversationListener",
+            Some(json!({
+                "conversationId": session.conversation_id,
+                "experimentalRawEvents": false,
+            })),
+        )
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,90 @@
+//! The most recent error of each session
+//!
+//! Kept so a view of many sessions can mark the ones that hit a problem
+//! without replaying their events. Clearing an error only marks it as seen;
+//! the next error replaces it.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::RwLock;
+
+use crate::client::{CodexErrorCategory, CodexErrorPayload};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionError {
+    pub category: CodexErrorCategory,
+    pub message: String,
+    pub retryable: bool,
+    pub occurred_at: DateTime<Utc>,
+    /// Dismissed with `clear_session_error`
+    pub cleared: bool,
+}
+
+#[derive(Clone, Default)]
+pub struct LastErrors {
+    errors: Arc<RwLock<HashMap<String, SessionError>>>,
+}
+
+impl LastErrors {
+    pub async fn record(&self, conversation_id: &str, error: &CodexErrorPayload) {
+        self.errors.write().await.insert(
+            conversation_id.to_string(),
+            SessionError {
+                category: error.category,
+                message: error.message.clone(),
+                retryable: error.retryable,
+                occurred_at: Utc::now(),
+                cleared: false,
+            },
+        );
+    }
+
+    pub async fn get(&self, conversation_id: &str) -> Option<SessionError> {
+        self.errors.read().await.get(conversation_id).cloned()
+    }
+
+    pub async fn clear(&self, conversation_id: &str) {
+        if let Some(error) = self.errors.write().await.get_mut(conversation_id) {
+            error.cleared = true;
+        }
+    }
+
+    pub async fn forget(&self, conversation_id: &str) {
+        self.errors.write().await.remove(conversation_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(message: &str) -> CodexErrorPayload {
+        CodexErrorPayload {
+            conversation_id: Some("s".to_string()),
+            category: CodexErrorCategory::ToolFailure,
+            message: message.to_string(),
+            retryable: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_last_error_clear_and_replace() {
+        let errors = LastErrors::default();
+        assert!(errors.get("s").await.is_none());
+
+        errors.record("s", &payload("sandbox denied")).await;
+        errors.clear("s").await;
+        let error = errors.get("s").await.unwrap();
+        assert_eq!(error.message, "sandbox denied");
+        assert!(error.cleared);
+
+        errors.record("s", &payload("patch failed")).await;
+        let error = errors.get("s").await.unwrap();
+        assert_eq!(error.message, "patch failed");
+        assert!(!error.cleared);
+    }
+}
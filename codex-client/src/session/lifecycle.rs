@@ -0,0 +1,486 @@
+//! Session-level operations that combine the app-server client with the
+//! session registry.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::json;
+use tokio::task::JoinSet;
+
+use super::autosave::write_atomically;
+use super::followups::send_message;
+use super::instructions::reload_conversation;
+use super::registry::SessionMetadata;
+use crate::client::{CodexAppServerClient, ProcessInfo, ShutdownOutcome};
+use crate::error::CodexError;
+use crate::session_files::rollouts::split_last_turn;
+use crate::state::{get_client, take_client, ClientState};
+
+/// How long `close_session` waits for the app-server to exit on its own
+pub const DEFAULT_CLOSE_GRACE: Duration = Duration::from_secs(5);
+
+/// How long an interrupt waits for codex to acknowledge it
+pub const INTERRUPT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CloseSessionResult {
+    /// False when the app-server had to be killed
+    pub graceful: bool,
+    /// Whether the app-server process was stopped; it keeps running while
+    /// other sessions still use it
+    pub process_stopped: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CloseAllResult {
+    pub closed: Vec<String>,
+    /// Pinned sessions that were left running
+    pub kept: Vec<String>,
+    /// False when a running turn could not be interrupted or the app-server
+    /// had to be killed
+    pub graceful: bool,
+    /// Whether the app-server process was stopped, i.e. nothing was kept
+    pub process_stopped: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InterruptAndCloseResult {
+    /// A running turn was interrupted; false when the session was idle
+    pub interrupted: bool,
+    /// Why the running turn could not be interrupted
+    pub interrupt_error: Option<String>,
+    /// False when the interrupt failed or the app-server had to be killed
+    pub graceful: bool,
+    /// Whether the app-server process was stopped; it keeps running while
+    /// other sessions still use it
+    pub process_stopped: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoverResult {
+    /// A running turn was interrupted; false when the session was idle
+    pub interrupted: bool,
+    /// Why the running turn could not be interrupted; the session is
+    /// reloaded anyway
+    pub interrupt_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InterruptFailure {
+    pub session_id: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InterruptAllResult {
+    /// Sessions that were running a turn and were interrupted
+    pub interrupted: Vec<String>,
+    /// Sessions that had no turn running
+    pub idle: Vec<String>,
+    pub failed: Vec<InterruptFailure>,
+}
+
+/// Stop a conversation but keep its metadata in the archived list.
+///
+/// codex moves the rollout to `~/.codex/archived_sessions`; the archived
+/// entry points at the new location so it can still be resumed.
+pub async fn archive_session(
+    state: &ClientState,
+    session_id: &str,
+) -> Result<SessionMetadata, String> {
+    let session = state.sessions.get(session_id).await?;
+    let rollout_path = session
+        .rollout_path
+        .ok_or_else(|| format!("Session {session_id} has no rollout to archive"))?;
+
+    let client = get_client(state).await?;
+    client
+        .archive_conversation(session_id, &rollout_path)
+        .await?;
+
+    let archived_path = archived_rollout_path(&rollout_path).filter(|path| path.exists());
+    Ok(state.sessions.archive(session_id, archived_path).await?)
+}
+
+/// Close a session. A running turn is interrupted first so codex stops
+/// working on it. Closing the last one stops the shared app-server,
+/// escalating to killing it and its process group after `grace`, so no
+/// codex process outlives an explicit close.
+pub async fn close_session(
+    state: &ClientState,
+    session_id: &str,
+    grace: Option<Duration>,
+) -> Result<CloseSessionResult, String> {
+    state.sessions.get(session_id).await?;
+    if let Err(err) = interrupt_running_turn(state, session_id).await {
+        log::warn!("Closing session {session_id} without an interrupt: {err}");
+    }
+    remove_and_stop(state, session_id, grace).await
+}
+
+/// Drop a session from the registry and stop the app-server when it was
+/// the last one
+async fn remove_and_stop(
+    state: &ClientState,
+    session_id: &str,
+    grace: Option<Duration>,
+) -> Result<CloseSessionResult, String> {
+    state
+        .sessions
+        .remove(session_id)
+        .await
+        .ok_or_else(|| CodexError::UnknownSession(session_id.to_string()))?;
+
+    let still_running = CloseSessionResult {
+        graceful: true,
+        process_stopped: false,
+    };
+    if !state.sessions.ids().await.is_empty() {
+        return Ok(still_running);
+    }
+    let Some(client) = take_client(state).await else {
+        return Ok(still_running);
+    };
+
+    let outcome = client.shutdown(grace.unwrap_or(DEFAULT_CLOSE_GRACE)).await;
+    Ok(CloseSessionResult {
+        graceful: outcome == ShutdownOutcome::Graceful,
+        process_stopped: true,
+    })
+}
+
+/// Close every session except pinned ones, or all of them with `force`.
+/// Running turns of the closed sessions are interrupted concurrently first.
+/// Once none are left the app-server is stopped as in `close_session`.
+pub async fn close_all_sessions(
+    state: &ClientState,
+    force: bool,
+    grace: Option<Duration>,
+) -> Result<CloseAllResult, String> {
+    let mut result = CloseAllResult {
+        graceful: true,
+        ..CloseAllResult::default()
+    };
+    let mut closing = Vec::new();
+    for session_id in state.sessions.ids().await {
+        let pinned = state
+            .sessions
+            .get(&session_id)
+            .await
+            .is_ok_and(|session| session.pinned);
+        if pinned && !force {
+            result.kept.push(session_id);
+        } else {
+            closing.push(session_id);
+        }
+    }
+
+    let client = { state.client.lock().await.clone() };
+    let mut interrupts = JoinSet::new();
+    for session_id in &closing {
+        let busy = state
+            .sessions
+            .get(session_id)
+            .await
+            .is_ok_and(|session| session.turn_active);
+        if let (true, Some(client)) = (busy, client.clone()) {
+            let session_id = session_id.clone();
+            interrupts.spawn(async move {
+                let outcome = interrupt_with_timeout(&client, &session_id).await;
+                (session_id, outcome)
+            });
+        }
+    }
+    while let Some(joined) = interrupts.join_next().await {
+        if let Ok((session_id, Err(err))) = joined {
+            log::warn!("Closing session {session_id} without an interrupt: {err}");
+            result.graceful = false;
+        }
+    }
+
+    for session_id in closing {
+        if state.sessions.remove(&session_id).await.is_some() {
+            result.closed.push(session_id);
+        }
+    }
+    result.closed.sort();
+    result.kept.sort();
+
+    if !result.kept.is_empty() {
+        return Ok(result);
+    }
+    if let Some(client) = take_client(state).await {
+        let outcome = client.shutdown(grace.unwrap_or(DEFAULT_CLOSE_GRACE)).await;
+        result.graceful &= outcome == ShutdownOutcome::Graceful;
+        result.process_stopped = true;
+    }
+    Ok(result)
+}
+
+/// Resource usage of the app-server process hosting `session_id`. Every
+/// session shares the one process, so they all report the same numbers.
+pub async fn get_session_process_info(
+    state: &ClientState,
+    session_id: &str,
+) -> Result<ProcessInfo, String> {
+    state.sessions.get(session_id).await?;
+    let client = { state.client.lock().await.clone() }
+        .ok_or_else(|| "The codex app-server is not running".to_string())?;
+    Ok(client.process_info().await)
+}
+
+/// Run the session's last user turn again.
+///
+/// The turn, from the user's message to the end of the answer, is cut from
+/// the rollout and the conversation is reloaded from what is left, so codex
+/// no longer sees the previous answer. The message is then sent again like
+/// any other user message. `codex-regenerate` tells the UI to replace the
+/// old answer; it is only emitted once the message was accepted. When the
+/// send fails, the old answer is already gone from the conversation.
+pub async fn regenerate_last_turn(state: &ClientState, session_id: &str) -> Result<(), String> {
+    let session = state.sessions.get(session_id).await?;
+    if session.turn_active {
+        return Err(format!("Session {session_id} is still running a turn"));
+    }
+    let rollout_path = session
+        .rollout_path
+        .clone()
+        .ok_or_else(|| format!("Session {session_id} has no transcript"))?;
+    let content = tokio::fs::read_to_string(&rollout_path)
+        .await
+        .map_err(|e| format!("Failed to read rollout {}: {}", rollout_path.display(), e))?;
+    let (turn_start, message) = split_last_turn(&content)
+        .ok_or_else(|| format!("Session {session_id} has no user message to regenerate"))?;
+
+    // Mark the session busy so no other message lands between the reload
+    // and the resend
+    if state.sessions.begin_send(session_id).await {
+        return Err(format!("Session {session_id} is still running a turn"));
+    }
+    let truncated = content.as_bytes()[..turn_start].to_vec();
+    let path = rollout_path.clone();
+    let rolled_back = tokio::task::spawn_blocking(move || write_atomically(&path, &truncated))
+        .await
+        .map_err(|err| err.to_string())
+        .and_then(|result| result);
+    if let Err(err) = rolled_back {
+        state.sessions.abort_send(session_id).await;
+        return Err(err);
+    }
+    if let Err(err) = reload_conversation(state, &session, &rollout_path).await {
+        state.sessions.abort_send(session_id).await;
+        return Err(err);
+    }
+    if let Err(err) = send_message(state, session_id, &message).await {
+        state.sessions.abort_send(session_id).await;
+        return Err(err);
+    }
+
+    state
+        .event_bus
+        .emit(
+            "codex-regenerate",
+            json!({ "conversationId": session_id, "message": message }),
+        )
+        .await;
+    Ok(())
+}
+
+/// Recover a session whose input codex stopped reading, e.g. after
+/// `codex-input-stalled`.
+///
+/// Only the affected conversation is touched: its running turn, if any, is
+/// interrupted and it is loaded again from its rollout, so the other
+/// sessions sharing the app-server keep running. The running turn is lost.
+pub async fn recover_session(
+    state: &ClientState,
+    session_id: &str,
+) -> Result<RecoverResult, String> {
+    let session = state.sessions.get(session_id).await?;
+    let rollout_path = session
+        .rollout_path
+        .clone()
+        .ok_or_else(|| format!("Session {session_id} has no transcript to recover from"))?;
+
+    let client = get_client(state).await?;
+    let mut result = RecoverResult::default();
+    if session.turn_active {
+        match interrupt_with_timeout(&client, session_id).await {
+            Ok(()) => result.interrupted = true,
+            Err(err) => {
+                log::warn!("Reloading session {session_id} without an interrupt: {err}");
+                result.interrupt_error = Some(err);
+            }
+        }
+    }
+    state.sessions.end_turn(session_id).await;
+
+    log::warn!("Reloading session {session_id} to recover it");
+    let session = state.sessions.get(session_id).await?;
+    reload_conversation(state, &session, &rollout_path).await?;
+    Ok(result)
+}
+
+/// Interrupt the session's turn, waiting at most `INTERRUPT_TIMEOUT` for
+/// codex to acknowledge it
+async fn interrupt_with_timeout(
+    client: &CodexAppServerClient,
+    session_id: &str,
+) -> Result<(), String> {
+    let interrupt = async {
+        let params = serde_json::from_value(json!({ "conversationId": session_id }))
+            .map_err(|err| err.to_string())?;
+        client.interrupt_conversation(params).await
+    };
+    match tokio::time::timeout(INTERRUPT_TIMEOUT, interrupt).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(err)) => Err(err),
+        Err(_) => Err(format!("Timed out after {}s", INTERRUPT_TIMEOUT.as_secs())),
+    }
+}
+
+/// Interrupt the session's running turn, if any, waiting at most
+/// `INTERRUPT_TIMEOUT`. Returns whether a turn was interrupted; without a
+/// running app-server there is nothing to interrupt.
+async fn interrupt_running_turn(state: &ClientState, session_id: &str) -> Result<bool, String> {
+    let busy = state
+        .sessions
+        .get(session_id)
+        .await
+        .is_ok_and(|session| session.turn_active);
+    let client = { state.client.lock().await.clone() };
+    let (true, Some(client)) = (busy, client) else {
+        return Ok(false);
+    };
+    interrupt_with_timeout(&client, session_id).await?;
+    Ok(true)
+}
+
+/// Interrupt the session's running turn, if any, then close it.
+///
+/// A turn that does not acknowledge the interrupt within
+/// `INTERRUPT_TIMEOUT` does not block the close; it is reported in
+/// `interrupt_error` and the close is not graceful. Closing the last session
+/// stops the app-server as in `close_session`, killing it after `grace`.
+pub async fn interrupt_and_close(
+    state: &ClientState,
+    session_id: &str,
+    grace: Option<Duration>,
+) -> Result<InterruptAndCloseResult, String> {
+    state.sessions.get(session_id).await?;
+    let mut result = InterruptAndCloseResult::default();
+    match interrupt_running_turn(state, session_id).await {
+        Ok(interrupted) => result.interrupted = interrupted,
+        Err(err) => {
+            log::warn!("Closing session {session_id} without an interrupt: {err}");
+            result.interrupt_error = Some(err);
+        }
+    }
+
+    let closed = remove_and_stop(state, session_id, grace).await?;
+    result.graceful = closed.graceful && result.interrupt_error.is_none();
+    result.process_stopped = closed.process_stopped;
+    Ok(result)
+}
+
+/// Interrupt the running turn of every session at once. Interrupts run
+/// concurrently, each bounded by `INTERRUPT_TIMEOUT`, so one stuck session
+/// does not hold up the others.
+pub async fn interrupt_all_sessions(state: &ClientState) -> Result<InterruptAllResult, String> {
+    let mut result = InterruptAllResult::default();
+    let mut busy = Vec::new();
+    for session_id in state.sessions.ids().await {
+        match state.sessions.get(&session_id).await {
+            Ok(session) if session.turn_active => busy.push(session_id),
+            _ => result.idle.push(session_id),
+        }
+    }
+    if busy.is_empty() {
+        result.idle.sort();
+        return Ok(result);
+    }
+
+    let client = get_client(state).await?;
+    let mut tasks = JoinSet::new();
+    for session_id in busy {
+        let client = client.clone();
+        tasks.spawn(async move {
+            let outcome = interrupt_with_timeout(&client, &session_id).await;
+            (session_id, outcome)
+        });
+    }
+
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((session_id, Ok(()))) => result.interrupted.push(session_id),
+            Ok((session_id, Err(error))) => {
+                result.failed.push(InterruptFailure { session_id, error })
+            }
+            Err(err) => log::error!("Interrupt task failed: {err}"),
+        }
+    }
+    result.interrupted.sort();
+    result.idle.sort();
+    result.failed.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+    Ok(result)
+}
+
+pub async fn list_archived_sessions(state: &ClientState) -> Vec<SessionMetadata> {
+    state.sessions.archived().await
+}
+
+fn archived_rollout_path(rollout_path: &Path) -> Option<PathBuf> {
+    let home_dir = dirs::home_dir()?;
+    let file_name = rollout_path.file_name()?;
+    Some(
+        home_dir
+            .join(".codex")
+            .join("archived_sessions")
+            .join(file_name),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::Session;
+
+    #[tokio::test]
+    async fn test_close_all_keeps_pinned_sessions() {
+        let state = ClientState::new();
+        let mut pinned = Session::new("pinned");
+        pinned.pinned = true;
+        state.sessions.insert(pinned).await;
+        state.sessions.insert(Session::new("other")).await;
+
+        let result = close_all_sessions(&state, false, None).await.unwrap();
+        assert_eq!(result.closed, vec!["other".to_string()]);
+        assert_eq!(result.kept, vec!["pinned".to_string()]);
+        assert!(!result.process_stopped);
+        assert!(state.sessions.get("pinned").await.unwrap().metadata().pinned);
+
+        let forced = close_all_sessions(&state, true, None).await.unwrap();
+        assert_eq!(forced.closed, vec!["pinned".to_string()]);
+        assert!(state.sessions.ids().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_interrupt_and_close_idle_session() {
+        let state = ClientState::new();
+        state.sessions.insert(Session::new("idle")).await;
+
+        let result = interrupt_and_close(&state, "idle", None).await.unwrap();
+        assert!(!result.interrupted);
+        assert!(result.interrupt_error.is_none());
+        assert!(result.graceful);
+        assert!(state.sessions.get("idle").await.is_err());
+        assert!(interrupt_and_close(&state, "idle", None).await.is_err());
+    }
+}
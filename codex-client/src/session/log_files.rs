@@ -0,0 +1,202 @@
+//! codex's log lines of each session on disk
+//!
+//! The in-memory buffer only keeps the latest lines. Every line is also
+//! appended to `~/.codexia/logs/<conversation id>.log` by a writer task per
+//! session, fed through a channel so recording a line never waits on the
+//! disk. A file that would grow past `max_bytes` is renamed to
+//! `<id>.1.log`, older files shifting up and the ones past `max_files`
+//! deleted; lines arriving meanwhile wait in the channel and go to the new
+//! file.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::Mutex;
+
+use super::logs::LogLine;
+use super::registry::validate_session_id;
+
+pub const DEFAULT_MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+
+pub const DEFAULT_MAX_LOG_FILES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogRotation {
+    /// Size at which the current file is rotated
+    pub max_bytes: u64,
+    /// Rotated files kept besides the current one
+    pub max_files: usize,
+}
+
+impl Default for LogRotation {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_MAX_LOG_BYTES,
+            max_files: DEFAULT_MAX_LOG_FILES,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct SessionLogFiles {
+    writers: Arc<Mutex<HashMap<String, UnboundedSender<LogLine>>>>,
+    rotation: Arc<RwLock<LogRotation>>,
+}
+
+fn logs_dir() -> Result<PathBuf, String> {
+    let home_dir = dirs::home_dir().ok_or("Could not get home directory")?;
+    Ok(home_dir.join(".codexia").join("logs"))
+}
+
+/// The file the log lines of `conversation_id` are currently written to
+pub fn session_log_path(conversation_id: &str) -> Result<PathBuf, String> {
+    let conversation_id = validate_session_id(conversation_id)?;
+    Ok(logs_dir()?.join(format!("{conversation_id}.log")))
+}
+
+/// `<id>.<n>.log` for the `n`th most recent rotated file
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    path.with_extension(format!("{n}.log"))
+}
+
+fn rotate(path: &Path, max_files: usize) -> Result<(), String> {
+    let remove = |path: &Path| match std::fs::remove_file(path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Failed to remove {}: {}", path.display(), err))
+        }
+        _ => Ok(()),
+    };
+    if max_files == 0 {
+        return remove(path);
+    }
+    remove(&rotated_path(path, max_files))?;
+    for n in (1..max_files).rev() {
+        let from = rotated_path(path, n);
+        if from.exists() {
+            std::fs::rename(&from, rotated_path(path, n + 1))
+                .map_err(|e| format!("Failed to rotate {}: {}", from.display(), e))?;
+        }
+    }
+    std::fs::rename(path, rotated_path(path, 1))
+        .map_err(|e| format!("Failed to rotate {}: {}", path.display(), e))
+}
+
+/// Append `contents` to `path`, rotating first when they would not fit
+fn append(path: &Path, contents: &[u8], rotation: LogRotation) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let size = std::fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+    if size > 0 && size + contents.len() as u64 > rotation.max_bytes {
+        rotate(path, rotation.max_files)?;
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(contents))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn format_line(line: &LogLine) -> String {
+    format!("{} {}\n", line.timestamp.to_rfc3339(), line.line)
+}
+
+/// Write lines as they arrive until the session is forgotten, batching the
+/// ones that queued up during the previous write
+async fn run_writer(
+    path: PathBuf,
+    rotation: Arc<RwLock<LogRotation>>,
+    mut lines: UnboundedReceiver<LogLine>,
+) {
+    while let Some(line) = lines.recv().await {
+        let mut batch = format_line(&line);
+        while let Ok(line) = lines.try_recv() {
+            batch.push_str(&format_line(&line));
+        }
+        let rotation = *rotation.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let target = path.clone();
+        let written =
+            tokio::task::spawn_blocking(move || append(&target, batch.as_bytes(), rotation))
+                .await
+                .map_err(|err| err.to_string())
+                .and_then(|result| result);
+        if let Err(err) = written {
+            log::warn!("Failed to save codex log lines: {err}");
+        }
+    }
+}
+
+impl SessionLogFiles {
+    pub fn rotation(&self) -> LogRotation {
+        *self
+            .rotation
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Applies from the next write on
+    pub fn set_rotation(&self, rotation: LogRotation) {
+        *self
+            .rotation
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = rotation;
+    }
+
+    /// Queue a line for the session's log file
+    pub async fn record(&self, conversation_id: &str, line: &LogLine) {
+        let mut writers = self.writers.lock().await;
+        if !writers.contains_key(conversation_id) {
+            let path = match session_log_path(conversation_id) {
+                Ok(path) => path,
+                Err(err) => {
+                    log::warn!("Not saving codex logs of {conversation_id}: {err}");
+                    return;
+                }
+            };
+            let (sender, receiver) = unbounded_channel();
+            tokio::spawn(run_writer(path, self.rotation.clone(), receiver));
+            writers.insert(conversation_id.to_string(), sender);
+        }
+        if let Some(sender) = writers.get(conversation_id) {
+            let _ = sender.send(line.clone());
+        }
+    }
+
+    /// Stop writing for a closed session once its queued lines are written
+    pub async fn forget(&self, conversation_id: &str) {
+        self.writers.lock().await.remove(conversation_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_rotates_by_size() {
+        let dir = std::env::temp_dir().join(format!("codexia-log-test-{}", std::process::id()));
+        let path = dir.join("session.log");
+        let rotation = LogRotation {
+            max_bytes: 10,
+            max_files: 2,
+        };
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            append(&path, line.as_bytes(), rotation).unwrap();
+        }
+
+        let read = |path: PathBuf| std::fs::read_to_string(path).unwrap();
+        assert_eq!(read(path.clone()), "fourth\n");
+        assert_eq!(read(rotated_path(&path, 1)), "third\n");
+        assert_eq!(read(rotated_path(&path, 2)), "second\n");
+        assert!(!rotated_path(&path, 3).exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
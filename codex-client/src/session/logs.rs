@@ -0,0 +1,136 @@
+//! codex's own log output, kept per session
+//!
+//! The app-server's stderr is shared by every conversation it hosts. Lines
+//! that mention a conversation id go to that session's buffer; all other
+//! lines go to every live session, since any of them may be affected.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::log_files::session_log_path;
+use crate::state::ClientState;
+
+/// Verbosity passed to codex through `RUST_LOG`, least verbose first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "error" => Ok(LogLevel::Error),
+            "warn" => Ok(LogLevel::Warn),
+            "info" => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            "trace" => Ok(LogLevel::Trace),
+            other => Err(format!(
+                "Invalid log level '{other}': expected error, warn, info, debug or trace"
+            )),
+        }
+    }
+}
+
+/// One line of codex's stderr
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogLine {
+    pub timestamp: DateTime<Utc>,
+    pub line: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogLevelChange {
+    pub level: LogLevel,
+    /// False until the app-server restarts: codex reads `RUST_LOG` only at
+    /// startup and has no request to change it at runtime
+    pub applied: bool,
+}
+
+/// Ask for verbose codex logs for one session.
+///
+/// The shared app-server runs at the most verbose level any session asked
+/// for. A level the running process already covers applies immediately;
+/// otherwise it is picked up the next time the app-server is started.
+pub async fn set_session_log_level(
+    state: &ClientState,
+    session_id: &str,
+    level: &str,
+) -> Result<LogLevelChange, String> {
+    let level: LogLevel = level.parse()?;
+    state
+        .sessions
+        .update(session_id, |session| session.log_level = Some(level))
+        .await?;
+
+    let running = { state.client.lock().await.clone() };
+    let applied = running
+        .and_then(|client| client.log_level())
+        .is_some_and(|current| current >= level);
+    Ok(LogLevelChange { level, applied })
+}
+
+/// codex's stderr from the startup of the app-server hosting the session.
+/// Sessions share the process, so they share its startup output too.
+pub async fn get_session_startup_log(
+    state: &ClientState,
+    session_id: &str,
+) -> Result<Vec<LogLine>, String> {
+    state.sessions.get(session_id).await?;
+    let running = { state.client.lock().await.clone() };
+    let client = running.ok_or_else(|| "codex app-server is not running".to_string())?;
+    Ok(client.startup_log())
+}
+
+/// The file the session's codex log lines are being written to. It is
+/// created with the first line logged for the session.
+pub async fn get_session_log_path(
+    state: &ClientState,
+    session_id: &str,
+) -> Result<PathBuf, String> {
+    state.sessions.get(session_id).await?;
+    session_log_path(session_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_log_level() {
+        assert_eq!("debug".parse::<LogLevel>(), Ok(LogLevel::Debug));
+        assert_eq!(" TRACE ".parse::<LogLevel>(), Ok(LogLevel::Trace));
+        assert!("verbose".parse::<LogLevel>().is_err());
+        assert!(LogLevel::Trace > LogLevel::Info);
+    }
+}
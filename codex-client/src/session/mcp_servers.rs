@@ -0,0 +1,66 @@
+//! MCP tool servers available to a conversation
+//!
+//! Servers from config.toml apply to every conversation. A conversation can
+//! add its own on top through codex's per-conversation config overrides.
+
+use std::collections::HashMap;
+
+use codex_app_server_protocol::NewConversationParams;
+use serde::Serialize;
+
+use super::instructions::with_config_overrides;
+use crate::mcp::{mcp_config_overrides, read_mcp_servers, McpServerConfig};
+use crate::state::ClientState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum McpServerSource {
+    /// ~/.codex/config.toml
+    Config,
+    /// Added when the conversation was started
+    Session,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionMcpServer {
+    pub name: String,
+    pub config: McpServerConfig,
+    pub source: McpServerSource,
+}
+
+/// Add `servers` to the config overrides of a new conversation, after
+/// checking each definition
+pub fn with_mcp_servers(
+    params: NewConversationParams,
+    servers: &HashMap<String, McpServerConfig>,
+) -> Result<NewConversationParams, String> {
+    with_config_overrides(params, mcp_config_overrides(servers)?)
+}
+
+/// Enabled MCP servers of a conversation. A session server replaces a
+/// config.toml server of the same name, as codex's overrides do.
+pub async fn list_session_mcp_servers(
+    state: &ClientState,
+    session_id: &str,
+) -> Result<Vec<SessionMcpServer>, String> {
+    let session = state.sessions.get(session_id).await?;
+    let mut servers: Vec<SessionMcpServer> = read_mcp_servers()
+        .await?
+        .into_iter()
+        .filter(|(name, _)| !session.mcp_servers.contains_key(name))
+        .map(|(name, config)| SessionMcpServer {
+            name,
+            config,
+            source: McpServerSource::Config,
+        })
+        .chain(session.mcp_servers.into_iter().map(|(name, config)| SessionMcpServer {
+            name,
+            config,
+            source: McpServerSource::Session,
+        }))
+        .filter(|server| server.config.is_enabled())
+        .collect();
+    servers.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(servers)
+}
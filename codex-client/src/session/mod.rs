@@ -0,0 +1,98 @@
+//! Per-conversation session bookkeeping
+//!
+//! A single app-server process hosts every conversation. This module keeps
+//! the Codexia-side state for each of them (working directory, options and
+//! runtime counters), keyed by conversation id.
+
+mod artifacts;
+mod authorship;
+mod autosave;
+mod compact;
+mod config;
+mod disk_space;
+mod exec_output;
+mod export;
+mod file_context;
+mod followups;
+mod fork;
+mod idle;
+mod instructions;
+mod last_error;
+mod lifecycle;
+mod log_files;
+mod logs;
+mod mcp_servers;
+mod out_of_tree;
+mod output_limit;
+mod patch_progress;
+mod project_tests;
+mod proposed_commands;
+mod rate_limits;
+mod registry;
+mod relocate;
+mod replay;
+mod runtime_config;
+mod sandbox;
+mod saved_sessions;
+mod snapshot;
+mod suggestions;
+mod tools;
+mod turn_hook;
+mod turn_timings;
+mod turns;
+
+pub use artifacts::{
+    developer_instructions, prepare_artifacts_dir, resolve_artifacts_dir, session_artifacts_dir,
+};
+pub use authorship::{LineCounts, PatchLedger};
+pub use autosave::{transcript_path, TranscriptAutosave, DEFAULT_FLUSH_INTERVAL};
+pub use compact::{compact_session, CompactMode, CompactResult};
+pub use config::{IdleAction, SessionConfig};
+pub use disk_space::{watch_disk_space, working_dir_disk_status, LOW_DISK_THRESHOLD};
+pub use exec_output::{ExecOutput, ExecOutputs};
+pub use export::{export_session_bundle, SessionBundle};
+pub use file_context::{
+    build_file_context, AttachedFile, FileContext, MAX_FILE_BYTES, MAX_TOTAL_BYTES,
+};
+pub use followups::{
+    cancel_followup, enqueue_followup, get_followups, watch_followups, Followups,
+};
+pub use fork::{fork_session, ForkResult};
+pub use idle::watch_idle_sessions;
+pub use instructions::{update_session_instructions, with_instructions, InstructionsChange};
+pub use last_error::{LastErrors, SessionError};
+pub use lifecycle::*;
+pub use log_files::{
+    session_log_path, LogRotation, SessionLogFiles, DEFAULT_MAX_LOG_BYTES, DEFAULT_MAX_LOG_FILES,
+};
+pub use logs::{
+    get_session_log_path, get_session_startup_log, set_session_log_level, LogLevel,
+    LogLevelChange, LogLine,
+};
+pub use mcp_servers::{
+    list_session_mcp_servers, with_mcp_servers, McpServerSource, SessionMcpServer,
+};
+pub use out_of_tree::{resolve_allowed_paths, OutOfTreeWrites};
+pub use output_limit::{
+    is_output_truncated, model_output_limit, send_message_with_options,
+    validate_max_output_tokens, with_max_output_tokens, DEFAULT_MODEL_OUTPUT_LIMIT,
+};
+pub use patch_progress::{PatchChangeKind, PatchFile, PatchFileResult, PatchProgress};
+pub use project_tests::{parse_test_output, run_project_tests, TestCounts, TestResult};
+pub use proposed_commands::{ProposalState, ProposedCommand, ProposedCommands};
+pub use rate_limits::{RateLimitStatus, RateLimitWindow, RateLimits};
+pub use registry::{
+    validate_session_id, SequencedEvent, Session, SessionMetadata, SessionRegistry,
+};
+pub use relocate::move_session;
+pub use replay::{replay_transcript, ReplayResult};
+pub use runtime_config::{get_runtime_session_config, RuntimeConfig};
+pub use sandbox::{get_sandbox_policy, PolicySource, SandboxMode, SandboxPolicy};
+pub use saved_sessions::{
+    restore_sessions, saved_sessions, watch_session_map, RestoreResult, SavedSession,
+};
+pub use snapshot::{session_snapshot, SessionSnapshot};
+pub use suggestions::{get_suggested_actions, SuggestedAction};
+pub use tools::{get_session_tools, ToolInfo, ToolSource};
+pub use turn_hook::TurnHook;
+pub use turn_timings::{LatencyStats, TurnTiming, TurnTimings};
@@ -0,0 +1,180 @@
+//! Writes codex made outside the session's working directory
+//!
+//! With a full-access sandbox nothing stops codex from editing files
+//! anywhere. Each file of a patch codex starts applying, and the directory of
+//! each command it runs, is checked against the session's cwd; the ones
+//! outside it are kept per session and reported as `codex-out-of-tree-write`
+//! when first seen. A session limited to `allowed_paths` also has writes
+//! outside those paths reported. What a shell command writes outside its own
+//! directory is not visible in its events and is not detected.
+
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use serde_json::Value;
+use tokio::sync::RwLock;
+
+#[derive(Clone, Default)]
+pub struct OutOfTreeWrites {
+    /// Paths outside the working directory, by conversation, in the order seen
+    paths: Arc<RwLock<HashMap<String, Vec<String>>>>,
+}
+
+/// `path` joined onto `base` with `.` and `..` resolved, without touching
+/// the filesystem
+pub(super) fn resolve(base: &Path, path: &Path) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    for component in base.join(path).components() {
+        match component {
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::CurDir => {}
+            other => resolved.push(other),
+        }
+    }
+    resolved
+}
+
+/// Absolute locations of a session's `allowed_paths`, after checking each
+/// is inside `cwd`
+pub fn resolve_allowed_paths(cwd: &Path, paths: &[String]) -> Result<Vec<PathBuf>, String> {
+    let cwd = resolve(Path::new("/"), cwd);
+    paths
+        .iter()
+        .map(|path| {
+            let resolved = resolve(&cwd, Path::new(path));
+            if !resolved.starts_with(&cwd) {
+                return Err(format!(
+                    "Allowed path {path} is outside the working directory {}",
+                    cwd.display()
+                ));
+            }
+            Ok(resolved)
+        })
+        .collect()
+}
+
+/// Paths a codex event writes to or runs in
+fn touched_paths(msg: &Value) -> Vec<&str> {
+    match msg["type"].as_str() {
+        Some("patch_apply_begin") => msg["changes"]
+            .as_object()
+            .map(|changes| {
+                changes
+                    .iter()
+                    .flat_map(|(path, change)| {
+                        std::iter::once(path.as_str()).chain(change["update"]["move_path"].as_str())
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        Some("exec_command_begin") => msg["cwd"].as_str().into_iter().collect(),
+        _ => Vec::new(),
+    }
+}
+
+impl OutOfTreeWrites {
+    /// Check the paths of an event against `cwd` and, when there are any,
+    /// the `allowed` paths within it, returning the ones outside them that
+    /// were not seen before
+    pub async fn record_event(
+        &self,
+        conversation_id: &str,
+        msg: &Value,
+        cwd: &Path,
+        allowed: &[PathBuf],
+    ) -> Vec<String> {
+        let cwd = resolve(Path::new("/"), cwd);
+        let in_scope = |path: &PathBuf| {
+            path.starts_with(&cwd)
+                && (allowed.is_empty() || allowed.iter().any(|dir| path.starts_with(dir)))
+        };
+        let outside: Vec<String> = touched_paths(msg)
+            .into_iter()
+            .map(|path| resolve(&cwd, Path::new(path)))
+            .filter(|path| !in_scope(path))
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
+        if outside.is_empty() {
+            return outside;
+        }
+
+        let mut paths = self.paths.write().await;
+        let seen = paths.entry(conversation_id.to_string()).or_default();
+        let mut new_paths = Vec::new();
+        for path in outside {
+            if !seen.contains(&path) {
+                seen.push(path.clone());
+                new_paths.push(path);
+            }
+        }
+        new_paths
+    }
+
+    pub async fn list(&self, conversation_id: &str) -> Vec<String> {
+        self.paths
+            .read()
+            .await
+            .get(conversation_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub async fn forget(&self, conversation_id: &str) {
+        self.paths.write().await.remove(conversation_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_flags_paths_outside_cwd() {
+        let writes = OutOfTreeWrites::default();
+        let cwd = Path::new("/work/project");
+        let patch = json!({
+            "type": "patch_apply_begin",
+            "call_id": "p1",
+            "changes": {
+                "/work/project/src/main.rs": { "update": { "unified_diff": "" } },
+                "../other/lib.rs": { "add": { "content": "" } },
+                "/work/project/old.rs": { "update": { "move_path": "/tmp/old.rs" } },
+            },
+        });
+        let mut flagged = writes.record_event("s", &patch, cwd, &[]).await;
+        flagged.sort();
+        assert_eq!(flagged, vec!["/tmp/old.rs", "/work/other/lib.rs"]);
+
+        let exec = json!({ "type": "exec_command_begin", "call_id": "e1", "cwd": "/etc" });
+        assert_eq!(
+            writes.record_event("s", &exec, cwd, &[]).await,
+            vec!["/etc"]
+        );
+        // Already reported
+        assert!(writes.record_event("s", &exec, cwd, &[]).await.is_empty());
+        let inside =
+            json!({ "type": "exec_command_begin", "call_id": "e2", "cwd": "/work/project/./src" });
+        assert!(writes.record_event("s", &inside, cwd, &[]).await.is_empty());
+
+        assert_eq!(writes.list("s").await.len(), 3);
+        writes.forget("s").await;
+        assert!(writes.list("s").await.is_empty());
+
+        let allowed = resolve_allowed_paths(cwd, &["src".to_string()]).unwrap();
+        let docs =
+            json!({ "type": "exec_command_begin", "call_id": "e3", "cwd": "/work/project/docs" });
+        assert_eq!(
+            writes.record_event("s", &docs, cwd, &allowed).await,
+            vec!["/work/project/docs"]
+        );
+        assert!(writes
+            .record_event("s", &inside, cwd, &allowed)
+            .await
+            .is_empty());
+        assert!(resolve_allowed_paths(cwd, &["../other".to_string()]).is_err());
+    }
+}
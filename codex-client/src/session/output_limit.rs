@@ -0,0 +1,214 @@
+//! Limit on how much the model may write in one response
+//!
+//! The limit reaches codex as the `model_max_output_tokens` config override.
+//! codex reads config only when it loads a conversation, so a turn with a
+//! limit other than the session's reloads the conversation from its rollout
+//! before the message is sent and again once the turn ended. A response cut
+//! off by the limit ends in an error event, reported as
+//! `codex-output-truncated`.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use codex_app_server_protocol::NewConversationParams;
+use log::warn;
+use serde_json::json;
+use tokio::sync::mpsc;
+
+use super::followups::send_message;
+use super::instructions::{reload_conversation, with_config_override};
+use crate::state::ClientState;
+
+pub(super) const MAX_OUTPUT_TOKENS_KEY: &str = "model_max_output_tokens";
+
+/// Output limit of models not listed in `MODEL_OUTPUT_LIMITS`, and of
+/// sessions on codex's default model
+pub const DEFAULT_MODEL_OUTPUT_LIMIT: u32 = 128_000;
+
+/// Most tokens a model can write in one response, by model name prefix,
+/// most specific first
+const MODEL_OUTPUT_LIMITS: &[(&str, u32)] = &[
+    ("gpt-4.1", 32_768),
+    ("gpt-4o", 16_384),
+    ("codex-mini", 100_000),
+    ("o4-mini", 100_000),
+    ("o3", 100_000),
+    ("gpt-5", 128_000),
+];
+
+/// Output limit of `model`
+pub fn model_output_limit(model: Option<&str>) -> u32 {
+    model
+        .and_then(|model| {
+            MODEL_OUTPUT_LIMITS
+                .iter()
+                .find(|(prefix, _)| model.starts_with(prefix))
+        })
+        .map(|(_, limit)| *limit)
+        .unwrap_or(DEFAULT_MODEL_OUTPUT_LIMIT)
+}
+
+/// Check `tokens` is a usable output limit for `model`
+pub fn validate_max_output_tokens(tokens: u32, model: Option<&str>) -> Result<(), String> {
+    let limit = model_output_limit(model);
+    if tokens == 0 {
+        return Err("The output token limit must be positive".to_string());
+    }
+    if tokens > limit {
+        return Err(format!(
+            "{tokens} output tokens is over the {limit} token maximum of {}",
+            model.unwrap_or("the default model")
+        ));
+    }
+    Ok(())
+}
+
+/// Add the output limit to the config overrides of a new conversation
+pub fn with_max_output_tokens(
+    params: NewConversationParams,
+    tokens: u32,
+) -> Result<NewConversationParams, String> {
+    with_config_override(params, MAX_OUTPUT_TOKENS_KEY, json!(tokens))
+}
+
+/// Whether an error message is codex giving up on a response the output
+/// limit cut off, e.g. "Incomplete response returned, reason:
+/// max_output_tokens"
+pub fn is_output_truncated(message: &str) -> bool {
+    message.contains("max_output_tokens")
+}
+
+/// Load the conversation again with the session's own output limit after a
+/// turn that had another one
+async fn restore_output_limit(state: &ClientState, session_id: &str) {
+    let Ok(session) = state.sessions.get(session_id).await else {
+        return;
+    };
+    let Some(rollout_path) = session.rollout_path.clone() else {
+        return;
+    };
+    if let Err(err) = reload_conversation(state, &session, &rollout_path).await {
+        warn!("Failed to restore the output limit of {session_id}: {err}");
+    }
+}
+
+/// Longest a limited turn is waited for before the session's own limit is
+/// restored anyway
+const RESTORE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Send `message`, limiting the response to `max_output_tokens` for this
+/// turn only; `None` keeps the session's own limit. The session must be
+/// idle for the limit to change.
+pub async fn send_message_with_options(
+    state: Arc<ClientState>,
+    session_id: &str,
+    message: &str,
+    max_output_tokens: Option<u32>,
+) -> Result<(), String> {
+    let session = state.sessions.get(session_id).await?;
+    let tokens = match max_output_tokens {
+        Some(tokens) if Some(tokens) != session.config.max_output_tokens => tokens,
+        _ => return send_message(&state, session_id, message).await,
+    };
+    validate_max_output_tokens(tokens, session.model.as_deref())?;
+    let rollout_path = session
+        .rollout_path
+        .clone()
+        .ok_or_else(|| format!("Session {session_id} has no rollout to reload"))?;
+    // Mark the session busy first so no other message can start a turn
+    // under the changed limit
+    if state.sessions.begin_send(session_id).await {
+        return Err(format!(
+            "Session {session_id} is running a turn; its output limit can only change between turns"
+        ));
+    }
+    let mut limited = session.clone();
+    limited.config.max_output_tokens = Some(tokens);
+    if let Err(err) = reload_conversation(&state, &limited, &rollout_path).await {
+        state.sessions.abort_send(session_id).await;
+        return Err(err);
+    }
+
+    // Subscribe before sending so the end of the turn cannot be missed. The
+    // turn ends when it completes, is aborted or fails; when the app-server
+    // exits the limit went with it and there is nothing to restore.
+    let (tx, mut turn_end) = mpsc::unbounded_channel::<bool>();
+    let conversation_id = session_id.to_string();
+    let turn_tx = tx.clone();
+    let turn_subscription = state
+        .event_bus
+        .subscribe(
+            "codex:event",
+            Arc::new(move |_, data| {
+                let params = &data["params"];
+                if params["conversationId"].as_str() == Some(conversation_id.as_str())
+                    && matches!(
+                        params["msg"]["type"].as_str(),
+                        Some("task_complete" | "turn_aborted" | "error")
+                    )
+                {
+                    let _ = turn_tx.send(true);
+                }
+                Ok(())
+            }),
+        )
+        .await;
+    let exit_subscription = state
+        .event_bus
+        .subscribe(
+            "codex:process-exited",
+            Arc::new(move |_, _| {
+                let _ = tx.send(false);
+                Ok(())
+            }),
+        )
+        .await;
+
+    if let Err(err) = send_message(&state, session_id, message).await {
+        state.event_bus.remove_subscription(turn_subscription).await;
+        state.event_bus.remove_subscription(exit_subscription).await;
+        state.sessions.abort_send(session_id).await;
+        restore_output_limit(&state, session_id).await;
+        return Err(err);
+    }
+    let session_id = session_id.to_string();
+    tokio::spawn(async move {
+        let restore = match tokio::time::timeout(RESTORE_TIMEOUT, turn_end.recv()).await {
+            Ok(ended) => ended.unwrap_or(false),
+            Err(_) => {
+                warn!(
+                    "Turn of {session_id} did not end within {}s; restoring its output limit",
+                    RESTORE_TIMEOUT.as_secs()
+                );
+                true
+            }
+        };
+        state.event_bus.remove_subscription(turn_subscription).await;
+        state.event_bus.remove_subscription(exit_subscription).await;
+        if restore {
+            restore_output_limit(&state, &session_id).await;
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_max_output_tokens() {
+        assert!(validate_max_output_tokens(4096, Some("gpt-5-codex")).is_ok());
+        assert!(validate_max_output_tokens(128_000, None).is_ok());
+        assert!(validate_max_output_tokens(0, None).is_err());
+        assert!(validate_max_output_tokens(64_000, Some("gpt-4.1-mini"))
+            .unwrap_err()
+            .contains("32768"));
+        assert!(is_output_truncated(
+            "Incomplete response returned, reason: max_output_tokens"
+        ));
+        assert!(!is_output_truncated(
+            "stream disconnected before completion"
+        ));
+    }
+}
@@ -0,0 +1,193 @@
+//! Files a patch is about to change, and how applying it went
+//!
+//! `patch_apply_begin` arrives right before codex writes a patch, whether
+//! the user approved it or the approval policy let it through, and is
+//! reported as `codex-patch-applying` with the files it touches.
+//! `patch_apply_end` only says whether the whole patch applied. codex stops
+//! at the first file that fails, so in `codex-patch-applied` a failed
+//! patch's files named in codex's error output are reported as failed and
+//! the others as unknown.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::sync::RwLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PatchChangeKind {
+    Add,
+    Delete,
+    Update,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchFile {
+    pub path: String,
+    pub kind: PatchChangeKind,
+    /// Where an update moves the file
+    pub move_path: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchFileResult {
+    pub path: String,
+    /// `None` when the patch failed on another file and this one may or may
+    /// not have been written
+    pub success: Option<bool>,
+}
+
+#[derive(Clone, Default)]
+pub struct PatchProgress {
+    /// Files of patches being applied, by conversation and call id
+    pending: Arc<RwLock<HashMap<String, HashMap<String, Vec<PatchFile>>>>>,
+}
+
+/// Files of a `patch_apply_begin` event's changes, by path
+fn patch_files(changes: &Value) -> Vec<PatchFile> {
+    let Some(changes) = changes.as_object() else {
+        return Vec::new();
+    };
+    let mut files: Vec<PatchFile> = changes
+        .iter()
+        .filter_map(|(path, change)| {
+            let kind = if change.get("add").is_some() {
+                PatchChangeKind::Add
+            } else if change.get("delete").is_some() {
+                PatchChangeKind::Delete
+            } else if change.get("update").is_some() {
+                PatchChangeKind::Update
+            } else {
+                return None;
+            };
+            Some(PatchFile {
+                path: path.clone(),
+                kind,
+                move_path: change["update"]["move_path"].as_str().map(String::from),
+            })
+        })
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files
+}
+
+/// Outcome of each file of a patch that ended with `success`, judged from
+/// codex's error output when it failed
+fn file_results(files: &[PatchFile], success: bool, stderr: &str) -> Vec<PatchFileResult> {
+    files
+        .iter()
+        .map(|file| {
+            let named = stderr.contains(&file.path)
+                || file
+                    .move_path
+                    .as_deref()
+                    .is_some_and(|path| stderr.contains(path));
+            PatchFileResult {
+                path: file.path.clone(),
+                success: match (success, named) {
+                    (true, _) => Some(true),
+                    (false, true) => Some(false),
+                    (false, false) => None,
+                },
+            }
+        })
+        .collect()
+}
+
+impl PatchProgress {
+    /// Follow the patch events of a conversation's event stream, returning
+    /// the event to emit with its payload
+    pub async fn record_event(
+        &self,
+        conversation_id: &str,
+        msg: &Value,
+    ) -> Option<(&'static str, Value)> {
+        let call_id = msg["call_id"].as_str()?;
+        match msg["type"].as_str()? {
+            "patch_apply_begin" => {
+                let files = patch_files(&msg["changes"]);
+                let payload = json!({
+                    "conversationId": conversation_id,
+                    "callId": call_id,
+                    "files": files,
+                });
+                self.pending
+                    .write()
+                    .await
+                    .entry(conversation_id.to_string())
+                    .or_default()
+                    .insert(call_id.to_string(), files);
+                Some(("codex-patch-applying", payload))
+            }
+            "patch_apply_end" => {
+                let files = self
+                    .pending
+                    .write()
+                    .await
+                    .get_mut(conversation_id)?
+                    .remove(call_id)?;
+                let success = msg["success"].as_bool() == Some(true);
+                let stderr = msg["stderr"].as_str().unwrap_or_default().trim();
+                let payload = json!({
+                    "conversationId": conversation_id,
+                    "callId": call_id,
+                    "success": success,
+                    "files": file_results(&files, success, stderr),
+                    "error": (!success && !stderr.is_empty()).then_some(stderr),
+                });
+                Some(("codex-patch-applied", payload))
+            }
+            _ => None,
+        }
+    }
+
+    pub async fn forget(&self, conversation_id: &str) {
+        self.pending.write().await.remove(conversation_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_patch_progress_events() {
+        let progress = PatchProgress::default();
+        let begin = json!({
+            "type": "patch_apply_begin",
+            "call_id": "p1",
+            "auto_approved": true,
+            "changes": {
+                "/work/src/lib.rs": { "update": { "unified_diff": "", "move_path": null } },
+                "/work/src/new.rs": { "add": { "content": "" } },
+            },
+        });
+        let (event, payload) = progress.record_event("s", &begin).await.unwrap();
+        assert_eq!(event, "codex-patch-applying");
+        assert_eq!(payload["files"][0]["path"], "/work/src/lib.rs");
+        assert_eq!(payload["files"][1]["kind"], "add");
+
+        let end = json!({
+            "type": "patch_apply_end",
+            "call_id": "p1",
+            "stdout": "",
+            "stderr": "Failed to find expected lines in /work/src/lib.rs\n",
+            "success": false,
+        });
+        let (event, payload) = progress.record_event("s", &end).await.unwrap();
+        assert_eq!(event, "codex-patch-applied");
+        assert_eq!(payload["success"], false);
+        assert_eq!(payload["files"][0]["success"], false);
+        assert!(payload["files"][1]["success"].is_null());
+        assert_eq!(
+            payload["error"],
+            "Failed to find expected lines in /work/src/lib.rs"
+        );
+        // Already reported
+        assert!(progress.record_event("s", &end).await.is_none());
+    }
+}
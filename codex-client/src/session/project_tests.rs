@@ -0,0 +1,230 @@
+//! Running the project's tests through codex
+//!
+//! codex is asked to run the tests, so the run shows up in the conversation
+//! and its output reaches the client through the usual exec events. Once the
+//! turn ends, the output of the last command codex ran in that turn is
+//! parsed for the summary line of cargo test, jest or pytest and reported
+//! as `codex-test-result`; other runners only get their exit code.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use super::turns::run_turn;
+use crate::state::{get_client, ClientState};
+
+/// Upper bound for codex to run the tests and report back; long enough for
+/// slow suites while still ending a run whose command hangs
+const TEST_TURN_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestCounts {
+    /// "cargo", "jest" or "pytest"
+    pub runner: &'static str,
+    pub passed: u64,
+    pub failed: u64,
+    pub skipped: u64,
+}
+
+/// Result of `run_project_tests`, emitted as `codex-test-result`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestResult {
+    pub conversation_id: String,
+    /// Command codex ran last, normally the tests
+    pub command: Vec<String>,
+    pub exit_code: Option<i64>,
+    /// `None` when the output was not recognized
+    pub counts: Option<TestCounts>,
+    /// Why the run could not be reported, e.g. the turn was aborted
+    pub error: Option<String>,
+}
+
+/// The number before each of `labels` in a summary such as
+/// "3 passed, 1 failed"
+fn count_before(summary: &str, label: &str) -> u64 {
+    let words: Vec<&str> = summary
+        .split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+        .filter(|word| !word.is_empty())
+        .collect();
+    words
+        .windows(2)
+        .filter(|pair| pair[1].trim_end_matches('.') == label)
+        .filter_map(|pair| pair[0].parse::<u64>().ok())
+        .sum()
+}
+
+/// cargo prints one `test result:` line per test binary
+fn parse_cargo(output: &str) -> Option<TestCounts> {
+    let mut counts = TestCounts {
+        runner: "cargo",
+        ..Default::default()
+    };
+    let mut found = false;
+    for line in output.lines() {
+        let Some(summary) = line.trim().strip_prefix("test result: ") else {
+            continue;
+        };
+        found = true;
+        counts.passed += count_before(summary, "passed");
+        counts.failed += count_before(summary, "failed");
+        counts.skipped += count_before(summary, "ignored");
+    }
+    found.then_some(counts)
+}
+
+/// `Tests:       1 failed, 2 skipped, 10 passed, 13 total`
+fn parse_jest(output: &str) -> Option<TestCounts> {
+    let summary = output
+        .lines()
+        .rev()
+        .find_map(|line| line.trim().strip_prefix("Tests:"))?;
+    Some(TestCounts {
+        runner: "jest",
+        passed: count_before(summary, "passed"),
+        failed: count_before(summary, "failed"),
+        skipped: count_before(summary, "skipped") + count_before(summary, "todo"),
+    })
+}
+
+/// `===== 1 failed, 10 passed, 2 skipped in 0.52s =====`
+fn parse_pytest(output: &str) -> Option<TestCounts> {
+    let summary = output.lines().rev().find(|line| {
+        let line = line.trim();
+        line.starts_with('=') && line.ends_with('=') && line.contains(" in ")
+    })?;
+    let counts = TestCounts {
+        runner: "pytest",
+        passed: count_before(summary, "passed"),
+        failed: count_before(summary, "failed")
+            + count_before(summary, "error")
+            + count_before(summary, "errors"),
+        skipped: count_before(summary, "skipped") + count_before(summary, "xfailed"),
+    };
+    (counts.passed + counts.failed + counts.skipped > 0).then_some(counts)
+}
+
+/// Pass and fail counts from a test run's output, if the runner is known
+pub fn parse_test_output(output: &str) -> Option<TestCounts> {
+    parse_cargo(output)
+        .or_else(|| parse_jest(output))
+        .or_else(|| parse_pytest(output))
+}
+
+fn test_prompt(command: Option<&str>) -> String {
+    let run = match command {
+        Some(command) => format!("Run the project's tests with `{command}`."),
+        None => "Find out how this project's tests are run and run them.".to_string(),
+    };
+    format!(
+        "{run} Do not modify any files. Run the tests as the last command of this turn and \
+         reply with a short summary of the result."
+    )
+}
+
+/// Ask codex to run the session's tests, with `command` or the session's
+/// configured test command, or else a command codex picks. Returns once the
+/// request is sent; the outcome arrives as `codex-test-result`.
+pub async fn run_project_tests(
+    state: Arc<ClientState>,
+    session_id: &str,
+    command: Option<String>,
+) -> Result<(), String> {
+    let session = state.sessions.get(session_id).await?;
+    if session.turn_active {
+        return Err(format!("Session {session_id} is still running a turn"));
+    }
+    let command = command
+        .filter(|command| !command.trim().is_empty())
+        .or(session.config.test_command);
+    let prompt = test_prompt(command.as_deref());
+    let client = get_client(&state).await?;
+    // A command from an earlier turn must not pass for this test run
+    let previous_call = state
+        .sessions
+        .last_exec_output(session_id)
+        .await
+        .ok()
+        .map(|output| output.call_id);
+    let session_id = session_id.to_string();
+
+    tokio::spawn(async move {
+        let turn = run_turn(
+            &state,
+            &client,
+            &session_id,
+            &prompt,
+            Some(TEST_TURN_TIMEOUT),
+        )
+        .await;
+        let output = state
+            .sessions
+            .last_exec_output(&session_id)
+            .await
+            .ok()
+            .filter(|output| Some(&output.call_id) != previous_call.as_ref());
+        let result = TestResult {
+            conversation_id: session_id.clone(),
+            command: output
+                .as_ref()
+                .map(|output| output.command.clone())
+                .unwrap_or_default(),
+            exit_code: output.as_ref().and_then(|output| output.exit_code),
+            counts: output.as_ref().and_then(|output| {
+                parse_test_output(&format!("{}\n{}", output.stdout, output.stderr))
+            }),
+            error: match (turn, &output) {
+                (Err(err), _) => Some(err),
+                (Ok(_), None) => Some("codex did not run a command".to_string()),
+                (Ok(_), Some(_)) => None,
+            },
+        };
+        match serde_json::to_value(&result) {
+            Ok(payload) => state.event_bus.emit("codex-test-result", payload).await,
+            Err(err) => log::error!("Failed to serialize codex-test-result: {err}"),
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_test_output() {
+        let cargo = "running 3 tests\n\
+                     test result: FAILED. 2 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out\n\
+                     running 1 test\n\
+                     test result: ok. 1 passed; 0 failed; 1 ignored; 0 measured; 0 filtered out\n";
+        assert_eq!(
+            parse_test_output(cargo),
+            Some(TestCounts {
+                runner: "cargo",
+                passed: 3,
+                failed: 1,
+                skipped: 1,
+            })
+        );
+
+        let jest = "Test Suites: 1 failed, 3 passed, 4 total\n\
+                    Tests:       1 failed, 2 skipped, 10 passed, 13 total\n";
+        let counts = parse_test_output(jest).unwrap();
+        assert_eq!(
+            (counts.runner, counts.passed, counts.failed, counts.skipped),
+            ("jest", 10, 1, 2)
+        );
+
+        let pytest = "FAILED tests/test_api.py::test_login\n\
+                      ===== 1 failed, 10 passed, 2 skipped in 0.52s =====\n";
+        let counts = parse_test_output(pytest).unwrap();
+        assert_eq!(
+            (counts.runner, counts.passed, counts.failed, counts.skipped),
+            ("pytest", 10, 1, 2)
+        );
+
+        assert_eq!(parse_test_output("make: *** [check] Error 2"), None);
+    }
+}
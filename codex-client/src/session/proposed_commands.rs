@@ -0,0 +1,186 @@
+//! Commands codex proposed during a turn, with what became of each
+//!
+//! A command enters the list when codex asks to run it
+//! (`exec_approval_request`) or, when no approval was needed, when it
+//! starts (`exec_command_begin`). The list is cleared when the next turn
+//! starts unless history is kept.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::RwLock;
+
+/// Bytes kept of a command's output
+const MAX_OUTPUT_BYTES: usize = 4 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProposalState {
+    /// Waiting for the user to answer the approval request
+    Pending,
+    /// Approved, or allowed without asking, and not finished yet
+    Approved,
+    Denied,
+    Executed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProposedCommand {
+    pub call_id: String,
+    pub command: Vec<String>,
+    pub cwd: Option<PathBuf>,
+    pub reason: Option<String>,
+    pub state: ProposalState,
+    pub exit_code: Option<i64>,
+    /// End of the command's output once it has run
+    pub output: Option<String>,
+}
+
+fn command_of(msg: &Value) -> Vec<String> {
+    msg["command"]
+        .as_array()
+        .map(|parts| {
+            parts
+                .iter()
+                .filter_map(|part| part.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The last `MAX_OUTPUT_BYTES` of `text`, cut at a character boundary
+fn output_tail(text: &str) -> String {
+    let mut start = text.len().saturating_sub(MAX_OUTPUT_BYTES);
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    text[start..].to_string()
+}
+
+#[derive(Clone, Default)]
+pub struct ProposedCommands {
+    /// In the order codex proposed them
+    by_session: Arc<RwLock<HashMap<String, Vec<ProposedCommand>>>>,
+    retain_history: Arc<AtomicBool>,
+}
+
+impl ProposedCommands {
+    /// Keep the commands of earlier turns instead of clearing the list
+    /// when a turn starts
+    pub fn set_retain_history(&self, retain: bool) {
+        self.retain_history.store(retain, Ordering::Relaxed);
+    }
+
+    /// Follow the exec events of a conversation's event stream
+    pub async fn record_event(&self, conversation_id: &str, msg: &Value) {
+        let mut by_session = self.by_session.write().await;
+        let commands = by_session.entry(conversation_id.to_string()).or_default();
+        let kind = msg["type"].as_str().unwrap_or_default();
+        if kind == "task_started" {
+            if !self.retain_history.load(Ordering::Relaxed) {
+                commands.clear();
+            }
+            return;
+        }
+
+        let Some(call_id) = msg["call_id"].as_str() else {
+            return;
+        };
+        let state = match kind {
+            "exec_approval_request" => ProposalState::Pending,
+            "exec_command_begin" => ProposalState::Approved,
+            "exec_command_end" => ProposalState::Executed,
+            _ => return,
+        };
+        let index = match commands.iter().position(|command| command.call_id == call_id) {
+            Some(index) => index,
+            None => {
+                commands.push(ProposedCommand {
+                    call_id: call_id.to_string(),
+                    command: command_of(msg),
+                    cwd: msg["cwd"].as_str().map(PathBuf::from),
+                    reason: msg["reason"].as_str().map(String::from),
+                    state,
+                    exit_code: None,
+                    output: None,
+                });
+                commands.len() - 1
+            }
+        };
+        let command = &mut commands[index];
+        command.state = state;
+        if state == ProposalState::Executed {
+            command.exit_code = msg["exit_code"].as_i64();
+            let output = msg["aggregated_output"]
+                .as_str()
+                .or_else(|| msg["stdout"].as_str())
+                .unwrap_or_default();
+            command.output = Some(output_tail(output));
+        }
+    }
+
+    /// Record the user's answer to an approval request
+    pub async fn record_decision(&self, conversation_id: &str, call_id: &str, approved: bool) {
+        let mut by_session = self.by_session.write().await;
+        let Some(command) = by_session
+            .get_mut(conversation_id)
+            .and_then(|commands| commands.iter_mut().find(|c| c.call_id == call_id))
+        else {
+            return;
+        };
+        if command.state == ProposalState::Pending {
+            command.state = if approved {
+                ProposalState::Approved
+            } else {
+                ProposalState::Denied
+            };
+        }
+    }
+
+    pub async fn list(&self, conversation_id: &str) -> Vec<ProposedCommand> {
+        self.by_session
+            .read()
+            .await
+            .get(conversation_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub async fn forget(&self, conversation_id: &str) {
+        self.by_session.write().await.remove(conversation_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_proposed_command_states() {
+        let commands = ProposedCommands::default();
+        let request =
+            json!({ "type": "exec_approval_request", "call_id": "a", "command": ["rm", "x"] });
+        commands.record_event("s", &request).await;
+        let begin = json!({ "type": "exec_command_begin", "call_id": "b", "command": ["ls"] });
+        commands.record_event("s", &begin).await;
+        let end = json!({ "type": "exec_command_end", "call_id": "b", "exit_code": 0 });
+        commands.record_event("s", &end).await;
+        commands.record_decision("s", "a", false).await;
+
+        let list = commands.list("s").await;
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].state, ProposalState::Denied);
+        assert_eq!(list[0].command, vec!["rm", "x"]);
+        assert_eq!(list[1].state, ProposalState::Executed);
+        assert_eq!(list[1].exit_code, Some(0));
+
+        commands.record_event("s", &json!({ "type": "task_started" })).await;
+        assert!(commands.list("s").await.is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+//! Usage limits codex reports for each session
+//!
+//! codex attaches a `rate_limits` snapshot to its `token_count` events: for
+//! the primary (short) and secondary (weekly) window, the percentage used
+//! and when the window resets. Counts are not reported, only percentages.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::RwLock;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitWindow {
+    /// "primary" or "secondary"
+    pub name: String,
+    pub used_percent: f64,
+    pub window_minutes: Option<u64>,
+    pub reset_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitStatus {
+    /// Percent left of the most used window
+    pub remaining: f64,
+    /// When the most used window resets
+    pub reset_at: Option<DateTime<Utc>>,
+    /// Length of the most used window in minutes
+    pub limit: Option<u64>,
+    pub windows: Vec<RateLimitWindow>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl RateLimitStatus {
+    pub fn exhausted(&self) -> bool {
+        self.remaining <= 0.0
+    }
+}
+
+fn parse_window(name: &str, window: &Value, now: DateTime<Utc>) -> Option<RateLimitWindow> {
+    let used_percent = window["used_percent"].as_f64()?;
+    // Older codex versions send a relative reset time
+    let reset_at = match window["resets_at"].as_i64() {
+        Some(timestamp) => Utc.timestamp_opt(timestamp, 0).single(),
+        None => window["resets_in_seconds"]
+            .as_i64()
+            .map(|seconds| now + chrono::Duration::seconds(seconds)),
+    };
+    Some(RateLimitWindow {
+        name: name.to_string(),
+        used_percent,
+        window_minutes: window["window_minutes"].as_u64(),
+        reset_at,
+    })
+}
+
+/// Status from the `rate_limits` of a `token_count` event message
+fn parse_rate_limits(msg: &Value, now: DateTime<Utc>) -> Option<RateLimitStatus> {
+    if msg["type"].as_str() != Some("token_count") {
+        return None;
+    }
+    let snapshot = &msg["rate_limits"];
+    let windows: Vec<RateLimitWindow> = ["primary", "secondary"]
+        .iter()
+        .filter_map(|name| parse_window(name, &snapshot[*name], now))
+        .collect();
+    let tightest = windows
+        .iter()
+        .max_by(|a, b| a.used_percent.total_cmp(&b.used_percent))?
+        .clone();
+    Some(RateLimitStatus {
+        remaining: (100.0 - tightest.used_percent).max(0.0),
+        reset_at: tightest.reset_at,
+        limit: tightest.window_minutes,
+        windows,
+        updated_at: now,
+    })
+}
+
+#[derive(Clone, Default)]
+pub struct RateLimits {
+    latest: Arc<RwLock<HashMap<String, RateLimitStatus>>>,
+}
+
+impl RateLimits {
+    /// Follow the `token_count` events of a conversation's event stream
+    pub async fn record_event(&self, conversation_id: &str, msg: &Value) {
+        if let Some(status) = parse_rate_limits(msg, Utc::now()) {
+            self.latest
+                .write()
+                .await
+                .insert(conversation_id.to_string(), status);
+        }
+    }
+
+    pub async fn latest(&self, conversation_id: &str) -> Option<RateLimitStatus> {
+        self.latest.read().await.get(conversation_id).cloned()
+    }
+
+    pub async fn forget(&self, conversation_id: &str) {
+        self.latest.write().await.remove(conversation_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_rate_limits() {
+        let now = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let msg = json!({
+            "type": "token_count",
+            "rate_limits": {
+                "primary": {
+                    "used_percent": 42.5,
+                    "window_minutes": 300,
+                    "resets_in_seconds": 60
+                },
+                "secondary": {
+                    "used_percent": 100.0,
+                    "window_minutes": 10080,
+                    "resets_at": 1_700_003_600
+                }
+            }
+        });
+        let status = parse_rate_limits(&msg, now).unwrap();
+        assert_eq!(status.windows.len(), 2);
+        assert_eq!(status.windows[0].reset_at, Some(now + chrono::Duration::seconds(60)));
+        assert_eq!(status.remaining, 0.0);
+        assert!(status.exhausted());
+        assert_eq!(status.limit, Some(10080));
+        assert_eq!(status.reset_at, Utc.timestamp_opt(1_700_003_600, 0).single());
+
+        let no_limits = json!({ "type": "token_count", "info": null, "rate_limits": null });
+        assert!(parse_rate_limits(&no_limits, now).is_none());
+    }
+}
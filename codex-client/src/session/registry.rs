@@ -0,0 +1,941 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::sync::RwLock;
+
+use super::authorship::PatchLedger;
+use super::autosave::TranscriptAutosave;
+use super::config::{IdleAction, SessionConfig};
+use super::exec_output::{ExecOutput, ExecOutputs};
+use super::followups::Followups;
+use super::last_error::{LastErrors, SessionError};
+use super::log_files::SessionLogFiles;
+use super::logs::{LogLevel, LogLine};
+use super::out_of_tree::{resolve_allowed_paths, OutOfTreeWrites};
+use super::patch_progress::PatchProgress;
+use super::proposed_commands::{ProposedCommand, ProposedCommands};
+use super::rate_limits::{RateLimitStatus, RateLimits};
+use super::tools::{parse_mcp_tools, ToolInfo};
+use super::turn_hook::TurnHook;
+use super::turn_timings::{LatencyStats, TurnTiming, TurnTimings};
+use crate::client::CodexErrorPayload;
+use crate::error::CodexError;
+use crate::events::{event_conversation_id, EventRecorder};
+use crate::mcp::McpServerConfig;
+
+/// Events kept per session so a reattaching window can catch up
+const RECENT_EVENTS: usize = 256;
+
+/// codex log lines kept per session
+const LOG_LINES: usize = 1000;
+
+/// Longest accepted session id; codex's are 36-character UUIDs
+const MAX_SESSION_ID_LEN: usize = 128;
+
+/// Check an id received from the frontend before it is used as a map key,
+/// in a transcript file name or in a git ref, returning it without
+/// surrounding whitespace
+pub fn validate_session_id(id: &str) -> Result<&str, CodexError> {
+    let trimmed = id.trim();
+    let valid = !trimmed.is_empty()
+        && trimmed.len() <= MAX_SESSION_ID_LEN
+        && trimmed
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(trimmed)
+    } else {
+        Err(CodexError::InvalidSessionId(id.to_string()))
+    }
+}
+
+/// codex spells modes "workspace-write" in config and "workspaceWrite" in
+/// some protocol messages; keep the former
+fn kebab_case(value: &str) -> String {
+    let mut kebab = String::with_capacity(value.len() + 2);
+    for c in value.chars() {
+        if c.is_ascii_uppercase() {
+            kebab.push('-');
+            kebab.push(c.to_ascii_lowercase());
+        } else {
+            kebab.push(c);
+        }
+    }
+    kebab
+}
+
+/// Client-side state of one conversation
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub conversation_id: String,
+    pub cwd: Option<PathBuf>,
+    pub model: Option<String>,
+    pub rollout_path: Option<PathBuf>,
+    pub config: SessionConfig,
+    /// Alternate codex config file the app-server was started with
+    pub config_file: Option<PathBuf>,
+    /// Proxy the app-server reaches the model through, without credentials
+    pub proxy_url: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub archived_at: Option<DateTime<Utc>>,
+    pub last_user_message_at: Option<Instant>,
+    /// Last user message, end of a turn or keep-alive, for the idle timeout
+    pub last_activity_at: Instant,
+    /// Set by `continue_turn`: approve every request until the turn ends
+    pub auto_approve_turn: bool,
+    /// Whether a user message is on its way or codex is running a turn
+    pub turn_active: bool,
+    /// Tokens of the latest model request, i.e. the current context size
+    pub context_tokens: Option<u64>,
+    /// `total_token_usage` of the latest `token_count` event
+    pub token_usage: Option<Value>,
+    /// `seq` of the last event emitted for this session
+    pub event_seq: u64,
+    /// codex log level requested for this session
+    pub log_level: Option<LogLevel>,
+    /// MCP servers added for this conversation on top of config.toml
+    pub mcp_servers: HashMap<String, McpServerConfig>,
+    /// Standing instructions passed to codex with the conversation
+    pub instructions: Option<String>,
+    /// Current sandbox, e.g. "workspace-write"
+    pub sandbox_mode: Option<String>,
+    /// Current approval policy, e.g. "on-request"
+    pub approval_policy: Option<String>,
+    /// MCP tools from codex's latest `mcp_list_tools_response`
+    pub mcp_tools: Vec<ToolInfo>,
+    /// Left running by `close_all_sessions` unless it is forced
+    pub pinned: bool,
+}
+
+/// Serializable view of a session for the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionMetadata {
+    pub conversation_id: String,
+    pub cwd: Option<PathBuf>,
+    pub model: Option<String>,
+    pub rollout_path: Option<PathBuf>,
+    pub config: SessionConfig,
+    pub config_file: Option<PathBuf>,
+    pub proxy_url: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub archived_at: Option<DateTime<Utc>>,
+    pub event_seq: u64,
+    pub log_level: Option<LogLevel>,
+    pub instructions: Option<String>,
+    pub pinned: bool,
+}
+
+/// An emitted event as kept for replay
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SequencedEvent {
+    pub seq: u64,
+    pub event: String,
+    pub data: Value,
+}
+
+impl Session {
+    pub fn new(conversation_id: impl Into<String>) -> Self {
+        Self {
+            conversation_id: conversation_id.into(),
+            cwd: None,
+            model: None,
+            rollout_path: None,
+            config: SessionConfig::default(),
+            config_file: None,
+            proxy_url: None,
+            created_at: Utc::now(),
+            archived_at: None,
+            last_user_message_at: None,
+            last_activity_at: Instant::now(),
+            auto_approve_turn: false,
+            turn_active: false,
+            context_tokens: None,
+            token_usage: None,
+            event_seq: 0,
+            log_level: None,
+            mcp_servers: HashMap::new(),
+            instructions: None,
+            sandbox_mode: None,
+            approval_policy: None,
+            mcp_tools: Vec::new(),
+            pinned: false,
+        }
+    }
+
+    pub fn metadata(&self) -> SessionMetadata {
+        SessionMetadata {
+            conversation_id: self.conversation_id.clone(),
+            cwd: self.cwd.clone(),
+            model: self.model.clone(),
+            rollout_path: self.rollout_path.clone(),
+            config: self.config.clone(),
+            config_file: self.config_file.clone(),
+            proxy_url: self.proxy_url.clone(),
+            created_at: self.created_at,
+            archived_at: self.archived_at,
+            event_seq: self.event_seq,
+            log_level: self.log_level,
+            instructions: self.instructions.clone(),
+            pinned: self.pinned,
+        }
+    }
+}
+
+/// Shared map of sessions keyed by conversation id
+#[derive(Clone, Default)]
+pub struct SessionRegistry {
+    sessions: Arc<RwLock<HashMap<String, Session>>>,
+    /// Sessions whose process was stopped but whose metadata is kept
+    archived: Arc<RwLock<HashMap<String, Session>>>,
+    /// Latest events of each session, oldest first
+    recent_events: Arc<RwLock<HashMap<String, VecDeque<SequencedEvent>>>>,
+    /// Latest codex log lines of each session, oldest first
+    logs: Arc<RwLock<HashMap<String, VecDeque<LogLine>>>>,
+    log_files: SessionLogFiles,
+    autosave: TranscriptAutosave,
+    patches: PatchLedger,
+    patch_progress: PatchProgress,
+    exec_outputs: ExecOutputs,
+    rate_limits: RateLimits,
+    proposed_commands: ProposedCommands,
+    turn_timings: TurnTimings,
+    followups: Followups,
+    out_of_tree: OutOfTreeWrites,
+    last_errors: LastErrors,
+    turn_hook: Arc<std::sync::RwLock<Option<Arc<dyn TurnHook>>>>,
+}
+
+impl SessionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a session, keeping the options of a previous registration
+    /// under the same id (e.g. when a conversation or an archived session is
+    /// resumed).
+    pub async fn insert(&self, mut session: Session) {
+        let archived = self.archived.write().await.remove(&session.conversation_id);
+        let mut sessions = self.sessions.write().await;
+        if let Some(existing) = sessions.remove(&session.conversation_id).or(archived) {
+            session.config = existing.config;
+            session.created_at = existing.created_at;
+            // Keep numbering where it left off so `seq` never goes backwards
+            session.event_seq = existing.event_seq;
+            session.log_level = existing.log_level;
+            session.instructions = existing.instructions;
+            session.pinned = existing.pinned;
+            // A conversation reloaded while a message is being sent stays busy
+            session.turn_active = existing.turn_active;
+            if session.sandbox_mode.is_none() {
+                session.sandbox_mode = existing.sandbox_mode;
+            }
+            if session.approval_policy.is_none() {
+                session.approval_policy = existing.approval_policy;
+            }
+        }
+        sessions.insert(session.conversation_id.clone(), session);
+    }
+
+    pub async fn remove(&self, conversation_id: &str) -> Option<Session> {
+        self.autosave.forget(conversation_id).await;
+        self.patches.forget(conversation_id).await;
+        self.patch_progress.forget(conversation_id).await;
+        self.exec_outputs.forget(conversation_id).await;
+        self.rate_limits.forget(conversation_id).await;
+        self.proposed_commands.forget(conversation_id).await;
+        self.turn_timings.forget(conversation_id).await;
+        self.followups.forget(conversation_id).await;
+        self.out_of_tree.forget(conversation_id).await;
+        self.last_errors.forget(conversation_id).await;
+        self.recent_events.write().await.remove(conversation_id);
+        self.logs.write().await.remove(conversation_id);
+        self.log_files.forget(conversation_id).await;
+        self.sessions.write().await.remove(conversation_id)
+    }
+
+    pub async fn get(&self, conversation_id: &str) -> Result<Session, CodexError> {
+        let conversation_id = validate_session_id(conversation_id)?;
+        self.sessions
+            .read()
+            .await
+            .get(conversation_id)
+            .cloned()
+            .ok_or_else(|| CodexError::UnknownSession(conversation_id.to_string()))
+    }
+
+    /// Move a session to the archived list, optionally pointing it at the
+    /// rollout's new location.
+    pub async fn archive(
+        &self,
+        conversation_id: &str,
+        rollout_path: Option<PathBuf>,
+    ) -> Result<SessionMetadata, CodexError> {
+        let mut session = self
+            .remove(conversation_id)
+            .await
+            .ok_or_else(|| CodexError::UnknownSession(conversation_id.to_string()))?;
+        session.archived_at = Some(Utc::now());
+        if rollout_path.is_some() {
+            session.rollout_path = rollout_path;
+        }
+        let metadata = session.metadata();
+        self.archived
+            .write()
+            .await
+            .insert(conversation_id.to_string(), session);
+        Ok(metadata)
+    }
+
+    /// Add a session straight to the archived list, e.g. one saved before a
+    /// restart whose conversation cannot be loaded again
+    pub async fn insert_archived(&self, mut session: Session) {
+        session.archived_at.get_or_insert_with(Utc::now);
+        self.archived
+            .write()
+            .await
+            .insert(session.conversation_id.clone(), session);
+    }
+
+    /// Archived sessions, most recently archived first
+    pub async fn archived(&self) -> Vec<SessionMetadata> {
+        let mut archived: Vec<SessionMetadata> = self
+            .archived
+            .read()
+            .await
+            .values()
+            .map(Session::metadata)
+            .collect();
+        archived.sort_by(|a, b| b.archived_at.cmp(&a.archived_at));
+        archived
+    }
+
+    pub async fn ids(&self) -> Vec<String> {
+        self.sessions.read().await.keys().cloned().collect()
+    }
+
+    /// Run `f` against the mutable session registered under `conversation_id`
+    pub async fn update<R>(
+        &self,
+        conversation_id: &str,
+        f: impl FnOnce(&mut Session) -> R,
+    ) -> Result<R, CodexError> {
+        let conversation_id = validate_session_id(conversation_id)?;
+        let mut sessions = self.sessions.write().await;
+        let session = sessions
+            .get_mut(conversation_id)
+            .ok_or_else(|| CodexError::UnknownSession(conversation_id.to_string()))?;
+        Ok(f(session))
+    }
+
+    pub async fn set_config(
+        &self,
+        conversation_id: &str,
+        config: SessionConfig,
+    ) -> Result<(), CodexError> {
+        let session = self.get(conversation_id).await?;
+        config
+            .validate(session.cwd.as_deref(), session.model.as_deref())
+            .map_err(CodexError::InvalidConfig)?;
+        self.update(conversation_id, |session| session.config = config)
+            .await
+    }
+
+    /// Record the proxy of a restarted app-server on every session
+    pub async fn set_proxy_url(&self, proxy_url: Option<String>) {
+        for session in self.sessions.write().await.values_mut() {
+            session.proxy_url = proxy_url.clone();
+        }
+    }
+
+    /// Postpone a session's idle timeout as if the user had just sent a
+    /// message
+    pub async fn keep_alive(&self, conversation_id: &str) -> Result<(), CodexError> {
+        self.update(conversation_id, |session| session.last_activity_at = Instant::now())
+            .await
+    }
+
+    /// Sessions past their idle timeout, with what to do about each and how
+    /// long it has been idle
+    pub async fn idle_sessions(&self) -> Vec<(String, IdleAction, Duration)> {
+        self.sessions
+            .read()
+            .await
+            .values()
+            .filter(|session| !session.turn_active)
+            .filter_map(|session| {
+                let timeout = Duration::from_secs(session.config.idle_timeout_secs?);
+                let idle = session.last_activity_at.elapsed();
+                (idle >= timeout).then(|| {
+                    (session.conversation_id.clone(), session.config.idle_action, idle)
+                })
+            })
+            .collect()
+    }
+
+    /// Whether approvals for `conversation_id` are granted without asking
+    pub async fn auto_approves(&self, conversation_id: &str) -> bool {
+        self.sessions
+            .read()
+            .await
+            .get(conversation_id)
+            .is_some_and(|session| session.auto_approve_turn)
+    }
+
+    pub async fn begin_turn(&self, conversation_id: &str) {
+        if let Some(session) = self.sessions.write().await.get_mut(conversation_id) {
+            session.turn_active = true;
+        }
+    }
+
+    /// Mark the session busy as a user message is sent, before codex starts
+    /// the turn; returns whether it already was
+    pub(crate) async fn begin_send(&self, conversation_id: &str) -> bool {
+        match self.sessions.write().await.get_mut(conversation_id) {
+            Some(session) => std::mem::replace(&mut session.turn_active, true),
+            None => false,
+        }
+    }
+
+    /// Undo `begin_send` when codex did not accept the message
+    pub(crate) async fn abort_send(&self, conversation_id: &str) {
+        if let Some(session) = self.sessions.write().await.get_mut(conversation_id) {
+            session.turn_active = false;
+        }
+    }
+
+    /// Reset per-turn state once codex reports the turn finished
+    pub async fn end_turn(&self, conversation_id: &str) {
+        if let Some(session) = self.sessions.write().await.get_mut(conversation_id) {
+            session.turn_active = false;
+            session.auto_approve_turn = false;
+            session.last_activity_at = Instant::now();
+        }
+    }
+
+    /// Record the usage reported by the `info` of a `token_count` event
+    pub async fn record_token_usage(&self, conversation_id: &str, info: &Value) {
+        if let Some(session) = self.sessions.write().await.get_mut(conversation_id) {
+            if let Some(tokens) = info["last_token_usage"]["total_tokens"].as_u64() {
+                session.context_tokens = Some(tokens);
+            }
+            if !info["total_token_usage"].is_null() {
+                session.token_usage = Some(info["total_token_usage"].clone());
+            }
+        }
+    }
+
+    /// Replace the session's MCP tools with the `tools` codex listed
+    pub async fn record_mcp_tools(&self, conversation_id: &str, tools: &Value) {
+        if let Some(session) = self.sessions.write().await.get_mut(conversation_id) {
+            session.mcp_tools = parse_mcp_tools(tools);
+        }
+    }
+
+    /// Apply the model, sandbox and approval policy found in `settings`,
+    /// either the params of a turn that overrides them or a
+    /// `session_configured` event; absent keys leave the current value
+    pub async fn record_runtime_settings(&self, conversation_id: &str, settings: &Value) {
+        let Some(settings) = settings.as_object() else {
+            return;
+        };
+        let text = |keys: &[&str]| {
+            keys.iter()
+                .find_map(|key| settings.get(*key)?.as_str())
+                .map(kebab_case)
+        };
+        let model = settings.get("model").and_then(Value::as_str);
+        let approval_policy = text(&["approvalPolicy", "approval_policy"]);
+        let sandbox_mode = text(&["sandbox"]).or_else(|| {
+            ["sandboxPolicy", "sandbox_policy"]
+                .iter()
+                .filter_map(|key| settings.get(*key))
+                .find_map(|policy| policy["type"].as_str().or(policy["mode"].as_str()))
+                .map(kebab_case)
+        });
+
+        if let Some(session) = self.sessions.write().await.get_mut(conversation_id) {
+            if let Some(model) = model {
+                session.model = Some(model.to_string());
+            }
+            if approval_policy.is_some() {
+                session.approval_policy = approval_policy;
+            }
+            if sandbox_mode.is_some() {
+                session.sandbox_mode = sandbox_mode;
+            }
+        }
+    }
+
+    /// Stamp an event about to be emitted for `conversation_id` with the
+    /// session's next `seq` and keep it for `events_since`. Events of
+    /// unregistered conversations are left unchanged.
+    pub async fn record_event(&self, conversation_id: &str, event: &str, data: &mut Value) {
+        // The buffer stays locked while the event is stamped so it keeps
+        // events in `seq` order
+        let mut recent = self.recent_events.write().await;
+        let (seq, model) = {
+            let mut sessions = self.sessions.write().await;
+            let Some(session) = sessions.get_mut(conversation_id) else {
+                return;
+            };
+            session.event_seq += 1;
+            (session.event_seq, session.model.clone())
+        };
+        if let Some(object) = data.as_object_mut() {
+            object.insert("seq".to_string(), json!(seq));
+        }
+
+        let buffer = recent.entry(conversation_id.to_string()).or_default();
+        if buffer.len() == RECENT_EVENTS {
+            buffer.pop_front();
+        }
+        buffer.push_back(SequencedEvent {
+            seq,
+            event: event.to_string(),
+            data: data.clone(),
+        });
+        drop(recent);
+
+        if event == "codex:event" {
+            self.autosave.record(conversation_id, data).await;
+            let msg = &data["params"]["msg"];
+            self.patches.record_event(conversation_id, msg).await;
+            self.exec_outputs.record_event(conversation_id, msg).await;
+            self.rate_limits.record_event(conversation_id, msg).await;
+            self.proposed_commands.record_event(conversation_id, msg).await;
+            self.turn_timings
+                .record_event(conversation_id, msg, model.as_deref())
+                .await;
+        }
+    }
+
+    /// Check a codex event for writes outside the session's working
+    /// directory, returning the paths not reported before
+    pub async fn record_out_of_tree_writes(&self, conversation_id: &str, msg: &Value) -> Vec<String> {
+        let (cwd, allowed_paths) = match self.sessions.read().await.get(conversation_id) {
+            Some(Session {
+                cwd: Some(cwd),
+                config,
+                ..
+            }) => (cwd.clone(), config.allowed_paths.clone()),
+            _ => return Vec::new(),
+        };
+        let allowed = resolve_allowed_paths(&cwd, &allowed_paths).unwrap_or_default();
+        self.out_of_tree
+            .record_event(conversation_id, msg, &cwd, &allowed)
+            .await
+    }
+
+    /// Follow a codex event's patch progress, returning the
+    /// `codex-patch-applying` or `codex-patch-applied` event to emit
+    pub async fn record_patch_progress(
+        &self,
+        conversation_id: &str,
+        msg: &Value,
+    ) -> Option<(&'static str, Value)> {
+        self.patch_progress.record_event(conversation_id, msg).await
+    }
+
+    /// Paths outside its working directory codex wrote to or ran commands
+    /// in during the session
+    pub async fn out_of_tree_changes(&self, conversation_id: &str) -> Result<Vec<String>, CodexError> {
+        self.get(conversation_id).await?;
+        Ok(self.out_of_tree.list(conversation_id).await)
+    }
+
+    /// Remember an error codex reported for the session
+    pub async fn record_error(&self, conversation_id: &str, error: &CodexErrorPayload) {
+        self.last_errors.record(conversation_id, error).await;
+    }
+
+    /// The session's most recent error, cleared or not
+    pub async fn last_error(&self, conversation_id: &str) -> Result<Option<SessionError>, CodexError> {
+        self.get(conversation_id).await?;
+        Ok(self.last_errors.get(conversation_id).await)
+    }
+
+    /// Mark the session's last error as dismissed
+    pub async fn clear_error(&self, conversation_id: &str) -> Result<(), CodexError> {
+        self.get(conversation_id).await?;
+        self.last_errors.clear(conversation_id).await;
+        Ok(())
+    }
+
+    /// Output of the most recent command codex ran in a session
+    pub async fn last_exec_output(&self, conversation_id: &str) -> Result<ExecOutput, CodexError> {
+        self.get(conversation_id).await?;
+        self.exec_outputs
+            .latest(conversation_id)
+            .await
+            .ok_or_else(|| CodexError::NoExecOutput(conversation_id.to_string()))
+    }
+
+    /// Commands codex proposed in the session's current turn, or in every
+    /// turn when history is kept
+    pub async fn proposed_commands(
+        &self,
+        conversation_id: &str,
+    ) -> Result<Vec<ProposedCommand>, CodexError> {
+        self.get(conversation_id).await?;
+        Ok(self.proposed_commands.list(conversation_id).await)
+    }
+
+    /// Tracks the fate of every command codex proposes
+    pub fn command_proposals(&self) -> &ProposedCommands {
+        &self.proposed_commands
+    }
+
+    /// How long each recent turn of a session took
+    pub async fn turn_timings(&self, conversation_id: &str) -> Result<Vec<TurnTiming>, CodexError> {
+        self.get(conversation_id).await?;
+        Ok(self.turn_timings.list(conversation_id).await)
+    }
+
+    /// Time to first token of the session's turns, per model
+    pub async fn latency_stats(&self, conversation_id: &str) -> Result<Vec<LatencyStats>, CodexError> {
+        self.get(conversation_id).await?;
+        Ok(self.turn_timings.latency_stats(conversation_id).await)
+    }
+
+    /// Usage limits codex last reported for a session
+    pub async fn rate_limit_status(
+        &self,
+        conversation_id: &str,
+    ) -> Result<RateLimitStatus, CodexError> {
+        self.get(conversation_id).await?;
+        self.rate_limits
+            .latest(conversation_id)
+            .await
+            .ok_or_else(|| CodexError::NoRateLimitStatus(conversation_id.to_string()))
+    }
+
+    /// Lines codex's patches added, for telling them apart from other edits
+    pub fn patches(&self) -> &PatchLedger {
+        &self.patches
+    }
+
+    /// Saves each session's events to disk
+    pub fn autosave(&self) -> &TranscriptAutosave {
+        &self.autosave
+    }
+
+    /// Saves each session's codex log lines to disk
+    pub fn log_files(&self) -> &SessionLogFiles {
+        &self.log_files
+    }
+
+    /// Messages waiting to run after each session's current turn
+    pub fn followups(&self) -> &Followups {
+        &self.followups
+    }
+
+    /// Events emitted after `since`, for a window that missed some. Fails
+    /// when they were already dropped; the transcript is the fallback then.
+    pub async fn events_since(
+        &self,
+        conversation_id: &str,
+        since: u64,
+    ) -> Result<Vec<SequencedEvent>, CodexError> {
+        let current = self.get(conversation_id).await?.event_seq;
+        if since >= current {
+            return Ok(Vec::new());
+        }
+        let recent = self.recent_events.read().await;
+        let buffer = recent.get(conversation_id);
+        let oldest = buffer.and_then(|buffer| buffer.front()).map(|event| event.seq);
+        match (buffer, oldest) {
+            (Some(buffer), Some(oldest)) if oldest <= since + 1 => Ok(buffer
+                .iter()
+                .filter(|event| event.seq > since)
+                .cloned()
+                .collect()),
+            _ => Err(CodexError::EventsUnavailable {
+                conversation_id: conversation_id.to_string(),
+                since,
+            }),
+        }
+    }
+
+    /// Most verbose log level requested by any live session
+    pub async fn max_log_level(&self) -> Option<LogLevel> {
+        self.sessions
+            .read()
+            .await
+            .values()
+            .filter_map(|session| session.log_level)
+            .max()
+    }
+
+    /// Keep a line of codex's stderr for the sessions it concerns: the ones
+    /// it mentions by id, or every live session when it mentions none
+    pub async fn record_log_line(&self, line: &str) {
+        let ids = self.ids().await;
+        let mentioned: Vec<&String> = ids.iter().filter(|id| line.contains(id.as_str())).collect();
+        let targets = if mentioned.is_empty() {
+            ids.iter().collect()
+        } else {
+            mentioned
+        };
+
+        let entry = LogLine {
+            timestamp: Utc::now(),
+            line: line.to_string(),
+        };
+        let mut logs = self.logs.write().await;
+        for id in &targets {
+            let buffer = logs.entry(id.to_string()).or_default();
+            if buffer.len() == LOG_LINES {
+                buffer.pop_front();
+            }
+            buffer.push_back(entry.clone());
+        }
+        drop(logs);
+        for id in targets {
+            self.log_files.record(id, &entry).await;
+        }
+    }
+
+    /// Buffered codex log lines of a session, oldest first
+    pub async fn logs(&self, conversation_id: &str) -> Result<Vec<LogLine>, CodexError> {
+        self.get(conversation_id).await?;
+        Ok(self
+            .logs
+            .read()
+            .await
+            .get(conversation_id)
+            .map(|buffer| buffer.iter().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    /// Enforce `min_message_interval_ms` for an outgoing user message.
+    /// The interval runs from the last message codex accepted (see
+    /// `turn_sent`), so a failed send does not hold up its retry.
+    ///
+    /// Unknown sessions are let through: the guard only applies to
+    /// conversations Codexia registered itself.
+    pub async fn check_message_rate(&self, conversation_id: &str) -> Result<(), CodexError> {
+        let mut sessions = self.sessions.write().await;
+        let Some(session) = sessions.get_mut(conversation_id) else {
+            return Ok(());
+        };
+
+        let now = Instant::now();
+        if let (Some(interval_ms), Some(last)) = (
+            session.config.min_message_interval_ms,
+            session.last_user_message_at,
+        ) {
+            let interval = Duration::from_millis(interval_ms);
+            let elapsed = now.duration_since(last);
+            if elapsed < interval {
+                return Err(CodexError::RateLimited {
+                    retry_after: interval - elapsed,
+                });
+            }
+        }
+        session.last_activity_at = now;
+        Ok(())
+    }
+
+    /// Run `hook` before every user turn sent from now on
+    pub fn set_turn_hook(&self, hook: Arc<dyn TurnHook>) {
+        *self.turn_hook.write().unwrap_or_else(|e| e.into_inner()) = Some(hook);
+    }
+
+    /// Run the turn hook for the turn a message is about to start
+    pub(crate) async fn before_turn(&self, conversation_id: &str) {
+        let hook = self
+            .turn_hook
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        if let Some(hook) = hook {
+            let turn = self.turn_timings.next_turn(conversation_id).await;
+            hook.before_turn(conversation_id, turn).await;
+        }
+    }
+
+    /// Count a user turn once codex accepted its message, which also starts
+    /// timing it and the rate limit interval
+    pub(crate) async fn turn_sent(&self, conversation_id: &str) {
+        let model = match self.sessions.write().await.get_mut(conversation_id) {
+            Some(session) => {
+                session.last_user_message_at = Some(Instant::now());
+                session.model.clone()
+            }
+            None => None,
+        };
+        self.turn_timings.user_sent(conversation_id, model).await;
+    }
+}
+
+/// Events of a session are recorded as the event bus emits them, whichever
+/// part of the client emits them
+#[async_trait]
+impl EventRecorder for SessionRegistry {
+    async fn record(&self, event: &str, data: &mut Value) {
+        let Some(conversation_id) = event_conversation_id(data).map(String::from) else {
+            return;
+        };
+        self.record_event(&conversation_id, event, data).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_session_id() {
+        let id = "0199a7f2-3b4c-7d10-9e2f-5a6b7c8d9e0f";
+        assert_eq!(validate_session_id(id), Ok(id));
+        assert_eq!(validate_session_id(&format!(" {id}\n")), Ok(id));
+        for invalid in ["", "  ", "../etc", "a/b", "a\\b", "a.jsonl"] {
+            assert_eq!(
+                validate_session_id(invalid),
+                Err(CodexError::InvalidSessionId(invalid.to_string()))
+            );
+        }
+        assert!(validate_session_id(&"a".repeat(MAX_SESSION_ID_LEN + 1)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_disabled_by_default() {
+        let registry = SessionRegistry::new();
+        registry.insert(Session::new("a")).await;
+
+        assert!(registry.check_message_rate("a").await.is_ok());
+        assert!(registry.check_message_rate("a").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_rejects_fast_messages() {
+        let registry = SessionRegistry::new();
+        let mut session = Session::new("a");
+        session.config.min_message_interval_ms = Some(60_000);
+        registry.insert(session).await;
+
+        assert!(registry.check_message_rate("a").await.is_ok());
+        registry.turn_sent("a").await;
+        match registry.check_message_rate("a").await {
+            Err(CodexError::RateLimited { retry_after }) => {
+                assert!(retry_after <= Duration::from_millis(60_000));
+                assert!(retry_after > Duration::ZERO);
+            }
+            other => panic!("expected RateLimited, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_ignores_failed_sends() {
+        let registry = SessionRegistry::new();
+        let mut session = Session::new("a");
+        session.config.min_message_interval_ms = Some(60_000);
+        registry.insert(session).await;
+
+        // No `turn_sent`: codex never accepted the first message
+        assert!(registry.check_message_rate("a").await.is_ok());
+        assert!(registry.check_message_rate("a").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_busy_from_send() {
+        let registry = SessionRegistry::new();
+        registry.insert(Session::new("a")).await;
+
+        assert!(!registry.begin_send("a").await);
+        assert!(registry.get("a").await.unwrap().turn_active);
+        registry.abort_send("a").await;
+        assert!(!registry.get("a").await.unwrap().turn_active);
+
+        registry.begin_send("a").await;
+        registry.begin_turn("a").await;
+        assert!(registry.begin_send("a").await);
+        registry.end_turn("a").await;
+        assert!(!registry.get("a").await.unwrap().turn_active);
+    }
+
+    #[tokio::test]
+    async fn test_record_runtime_settings() {
+        let registry = SessionRegistry::new();
+        let mut session = Session::new("a");
+        session.model = Some("gpt-5".to_string());
+        session.sandbox_mode = Some("read-only".to_string());
+        registry.insert(session).await;
+
+        let turn = json!({
+            "threadId": "a",
+            "approvalPolicy": "onRequest",
+            "sandboxPolicy": { "type": "workspaceWrite", "networkAccess": false },
+        });
+        registry.record_runtime_settings("a", &turn).await;
+        let session = registry.get("a").await.unwrap();
+        assert_eq!(session.model.as_deref(), Some("gpt-5"));
+        assert_eq!(session.approval_policy.as_deref(), Some("on-request"));
+        assert_eq!(session.sandbox_mode.as_deref(), Some("workspace-write"));
+
+        let configured = json!({ "type": "session_configured", "model": "gpt-5-codex" });
+        registry.record_runtime_settings("a", &configured).await;
+        let session = registry.get("a").await.unwrap();
+        assert_eq!(session.model.as_deref(), Some("gpt-5-codex"));
+        assert_eq!(session.approval_policy.as_deref(), Some("on-request"));
+    }
+
+    #[tokio::test]
+    async fn test_event_seq_and_replay() {
+        let registry = SessionRegistry::new();
+        registry.insert(Session::new("a")).await;
+
+        for _ in 0..RECENT_EVENTS + 2 {
+            let mut data = json!({ "method": "codex/event/agent_message" });
+            registry.record_event("a", "codex:event", &mut data).await;
+        }
+        let last = (RECENT_EVENTS + 2) as u64;
+        assert_eq!(registry.get("a").await.unwrap().event_seq, last);
+
+        let missed = registry.events_since("a", last - 2).await.unwrap();
+        let seqs: Vec<u64> = missed.iter().map(|event| event.seq).collect();
+        assert_eq!(seqs, vec![last - 1, last]);
+        assert_eq!(missed[1].data["seq"], last);
+        assert!(registry.events_since("a", last).await.unwrap().is_empty());
+        assert!(matches!(
+            registry.events_since("a", 0).await,
+            Err(CodexError::EventsUnavailable { .. })
+        ));
+
+        let mut untracked = json!({});
+        registry.record_event("b", "codex:event", &mut untracked).await;
+        assert!(untracked.get("seq").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_bus_records_session_events() {
+        let registry = SessionRegistry::new();
+        registry.insert(Session::new("a")).await;
+        let bus = crate::events::EventBus::with_recorder(Arc::new(registry.clone()));
+
+        bus.emit(
+            "codex-error",
+            json!({ "conversationId": "a", "message": "boom" }),
+        )
+        .await;
+        bus.emit(
+            "codex:notification",
+            json!({ "method": "turn/started", "params": { "threadId": "a" } }),
+        )
+        .await;
+        bus.emit("codex:auth-status", json!({ "authMethod": null })).await;
+
+        let events = registry.events_since("a", 0).await.unwrap();
+        let names: Vec<&str> = events.iter().map(|event| event.event.as_str()).collect();
+        assert_eq!(names, vec!["codex-error", "codex:notification"]);
+        assert_eq!(events[1].data["seq"], 2);
+    }
+}
@@ -0,0 +1,34 @@
+//! Moving a session to another working directory
+
+use std::path::PathBuf;
+
+use super::instructions::reload_conversation;
+use crate::state::ClientState;
+
+/// Point a session at `new_dir`. An idle conversation with a rollout is
+/// reloaded by codex in the new directory right away and `true` is
+/// returned; otherwise only the stored directory changes and codex picks it
+/// up the next time the session is resumed.
+pub async fn move_session(
+    state: &ClientState,
+    session_id: &str,
+    new_dir: PathBuf,
+) -> Result<bool, String> {
+    let session = state.sessions.get(session_id).await?;
+    if session.turn_active {
+        return Err(format!("Session {session_id} is running a turn"));
+    }
+    let session = state
+        .sessions
+        .update(session_id, |session| {
+            session.cwd = Some(new_dir);
+            session.clone()
+        })
+        .await?;
+
+    let Some(rollout_path) = session.rollout_path.clone() else {
+        return Ok(false);
+    };
+    reload_conversation(state, &session, &rollout_path).await?;
+    Ok(true)
+}
@@ -0,0 +1,137 @@
+//! Re-running the user turns of a recorded session
+//!
+//! A new conversation is started in the recorded working directory and the
+//! user messages of the rollout are sent one at a time, each after the
+//! previous turn finished, to check whether a result reproduces.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::json;
+
+use super::turns::run_turn;
+use crate::session_files::file::read_first_line;
+use crate::session_files::rollouts::user_messages;
+use crate::session_files::utils::parse_session_project_path;
+use crate::state::{get_client, ClientState};
+
+/// Payload of `codex-replay-progress`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReplayProgress<'a> {
+    conversation_id: &'a str,
+    /// Index of the user turn
+    index: usize,
+    total: usize,
+    /// "started", "completed" or "failed"
+    status: &'static str,
+    error: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayResult {
+    /// The conversation the turns were replayed into
+    pub conversation_id: String,
+    /// User turns that finished
+    pub completed: usize,
+    pub total: usize,
+    /// Index of the turn that failed; the replay stops there
+    pub failed_index: Option<usize>,
+    pub error: Option<String>,
+}
+
+async fn emit_progress(
+    state: &ClientState,
+    conversation_id: &str,
+    (index, total): (usize, usize),
+    status: &'static str,
+    error: Option<&str>,
+) {
+    let progress = ReplayProgress {
+        conversation_id,
+        index,
+        total,
+        status,
+        error,
+    };
+    match serde_json::to_value(&progress) {
+        Ok(payload) => state.event_bus.emit("codex-replay-progress", payload).await,
+        Err(err) => log::error!("Failed to serialize codex-replay-progress payload: {err}"),
+    }
+}
+
+/// Working directory recorded in the rollout's session header
+fn recorded_cwd(transcript_path: &Path) -> Option<PathBuf> {
+    let first_line = read_first_line(transcript_path).ok()?;
+    parse_session_project_path(&first_line)
+        .map(PathBuf::from)
+        .filter(|cwd| cwd.is_dir())
+}
+
+/// Start a new session and send it the user messages of `transcript_path`
+/// in order. With `auto_approve`, every command and patch codex asks for is
+/// approved; otherwise turns wait for the user as usual, so turns have no
+/// time limit. codex assigns the new session's id, returned as the result's
+/// `conversation_id`; progress events carry it as soon as the session
+/// starts.
+pub async fn replay_transcript(
+    state: &ClientState,
+    transcript_path: &str,
+    auto_approve: bool,
+) -> Result<ReplayResult, String> {
+    let transcript_path = PathBuf::from(transcript_path);
+    let messages = user_messages(&transcript_path)?;
+    if messages.is_empty() {
+        return Err(format!(
+            "{} has no user messages to replay",
+            transcript_path.display()
+        ));
+    }
+    let cwd = recorded_cwd(&transcript_path);
+
+    let client = get_client(state).await?;
+    let params = serde_json::from_value(json!({ "cwd": cwd })).map_err(|err| err.to_string())?;
+    let response = client.new_conversation(params, None).await?;
+    let conversation_id = response.conversation_id.to_string();
+    client
+        .send_request(
+            "addConversationListener",
+            Some(json!({ "conversationId": conversation_id, "experimentalRawEvents": false })),
+        )
+        .await?;
+
+    let total = messages.len();
+    let mut result = ReplayResult {
+        conversation_id: conversation_id.clone(),
+        completed: 0,
+        total,
+        failed_index: None,
+        error: None,
+    };
+    for (index, message) in messages.iter().enumerate() {
+        let position = (index, total);
+        emit_progress(state, &conversation_id, position, "started", None).await;
+        if auto_approve {
+            // Reset by codex's end of turn, so it is set again for every turn
+            state
+                .sessions
+                .update(&conversation_id, |session| session.auto_approve_turn = true)
+                .await?;
+        }
+        match run_turn(state, &client, &conversation_id, message, None).await {
+            Ok(_) => {
+                emit_progress(state, &conversation_id, position, "completed", None).await;
+                result.completed += 1;
+            }
+            Err(err) => {
+                let error = Some(err.as_str());
+                emit_progress(state, &conversation_id, position, "failed", error).await;
+                result.failed_index = Some(index);
+                result.error = Some(err);
+                break;
+            }
+        }
+    }
+    Ok(result)
+}
@@ -0,0 +1,40 @@
+//! Settings a session is running with right now
+//!
+//! `SessionConfig` holds the options the session was started with. The
+//! model, sandbox and approval policy can change afterwards, through turns
+//! that override them or codex reconfiguring the session; the registry keeps
+//! the latest values and this is the view the session header renders.
+
+use serde::Serialize;
+
+use super::mcp_servers::{list_session_mcp_servers, SessionMcpServer};
+use crate::state::ClientState;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeConfig {
+    pub model: Option<String>,
+    /// e.g. "workspace-write"; `None` until codex or a turn has set one
+    pub sandbox_mode: Option<String>,
+    /// e.g. "on-request"; `None` until codex or a turn has set one
+    pub approval_policy: Option<String>,
+    pub instructions: Option<String>,
+    /// MCP servers from config.toml plus the ones added for this session
+    pub mcp_servers: Vec<SessionMcpServer>,
+}
+
+/// What the client believes the session is currently running with
+pub async fn get_runtime_session_config(
+    state: &ClientState,
+    session_id: &str,
+) -> Result<RuntimeConfig, String> {
+    let session = state.sessions.get(session_id).await?;
+    let mcp_servers = list_session_mcp_servers(state, session_id).await?;
+    Ok(RuntimeConfig {
+        model: session.model,
+        sandbox_mode: session.sandbox_mode,
+        approval_policy: session.approval_policy,
+        instructions: session.instructions,
+        mcp_servers,
+    })
+}
@@ -0,0 +1,203 @@
+//! What codex's sandbox lets a session touch
+//!
+//! The app-server does not report the policy it resolved, so it is derived
+//! the way codex derives it: the sandbox mode the conversation was started
+//! with, else `sandbox_mode` from config.toml (with the session's alternate
+//! config file on top), else workspace-write for trusted projects and
+//! read-only otherwise.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::get_config_path;
+use crate::state::ClientState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SandboxMode {
+    ReadOnly,
+    WorkspaceWrite,
+    DangerFullAccess,
+}
+
+impl SandboxMode {
+    pub fn parse(mode: &str) -> Option<SandboxMode> {
+        match mode.trim() {
+            "read-only" => Some(SandboxMode::ReadOnly),
+            "workspace-write" => Some(SandboxMode::WorkspaceWrite),
+            "danger-full-access" => Some(SandboxMode::DangerFullAccess),
+            _ => None,
+        }
+    }
+}
+
+/// Where the mode came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PolicySource {
+    Session,
+    Config,
+    Default,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SandboxPolicy {
+    pub mode: SandboxMode,
+    pub source: PolicySource,
+    /// codex can read the whole disk in every mode
+    pub full_read_access: bool,
+    /// Empty in read-only mode; `/` stands for everything
+    pub writable_roots: Vec<PathBuf>,
+    /// Paths under a writable root that stay read-only, such as `.git`
+    pub read_only_subpaths: Vec<PathBuf>,
+    pub network_access: bool,
+}
+
+/// `[sandbox_workspace_write]` of config.toml
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct WorkspaceWriteSettings {
+    writable_roots: Vec<PathBuf>,
+    network_access: bool,
+    exclude_tmpdir_env_var: bool,
+    exclude_slash_tmp: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TrustSettings {
+    trust_level: Option<String>,
+}
+
+/// The parts of config.toml that decide the sandbox
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct SandboxSettings {
+    sandbox_mode: Option<String>,
+    sandbox_workspace_write: WorkspaceWriteSettings,
+    projects: HashMap<String, TrustSettings>,
+}
+
+fn read_table(path: &Path) -> Result<toml::Table, String> {
+    if !path.exists() {
+        return Ok(toml::Table::new());
+    }
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?;
+    toml::from_str(&content)
+        .map_err(|e| format!("Failed to parse config file {}: {}", path.display(), e))
+}
+
+/// config.toml with the alternate file's top-level keys replacing its own,
+/// as the `-c` overrides do
+fn load_settings(config_file: Option<&Path>) -> Result<SandboxSettings, String> {
+    let mut table = read_table(&get_config_path()?)?;
+    if let Some(config_file) = config_file {
+        table.extend(read_table(config_file)?);
+    }
+    toml::Value::Table(table)
+        .try_into()
+        .map_err(|e| format!("Failed to read sandbox settings: {e}"))
+}
+
+fn resolve_policy(
+    session_mode: Option<SandboxMode>,
+    settings: &SandboxSettings,
+    cwd: Option<&Path>,
+    tmpdir: Option<PathBuf>,
+) -> SandboxPolicy {
+    let trusted = cwd.is_some_and(|cwd| {
+        settings
+            .projects
+            .get(cwd.to_string_lossy().as_ref())
+            .and_then(|project| project.trust_level.as_deref())
+            == Some("trusted")
+    });
+    let configured = settings.sandbox_mode.as_deref().and_then(SandboxMode::parse);
+    let (mode, source) = match (session_mode, configured) {
+        (Some(mode), _) => (mode, PolicySource::Session),
+        (None, Some(mode)) => (mode, PolicySource::Config),
+        (None, None) if trusted => (SandboxMode::WorkspaceWrite, PolicySource::Default),
+        (None, None) => (SandboxMode::ReadOnly, PolicySource::Default),
+    };
+
+    let workspace = &settings.sandbox_workspace_write;
+    let (writable_roots, read_only_subpaths, network_access) = match mode {
+        SandboxMode::ReadOnly => (Vec::new(), Vec::new(), false),
+        SandboxMode::DangerFullAccess => (vec![PathBuf::from("/")], Vec::new(), true),
+        SandboxMode::WorkspaceWrite => {
+            let mut roots: Vec<PathBuf> = cwd.map(Path::to_path_buf).into_iter().collect();
+            roots.extend(workspace.writable_roots.iter().cloned());
+            if !workspace.exclude_tmpdir_env_var {
+                roots.extend(tmpdir);
+            }
+            if cfg!(unix) && !workspace.exclude_slash_tmp {
+                roots.push(PathBuf::from("/tmp"));
+            }
+            roots.dedup();
+            let git_dirs = roots
+                .iter()
+                .map(|root| root.join(".git"))
+                .filter(|git| git.exists())
+                .collect();
+            (roots, git_dirs, workspace.network_access)
+        }
+    };
+
+    SandboxPolicy {
+        mode,
+        source,
+        full_read_access: true,
+        writable_roots,
+        read_only_subpaths,
+        network_access,
+    }
+}
+
+/// Roots the session's codex can write to and whether it may use the
+/// network, to explain blocked tool calls and approval prompts
+pub async fn get_sandbox_policy(
+    state: &ClientState,
+    session_id: &str,
+) -> Result<SandboxPolicy, String> {
+    let session = state.sessions.get(session_id).await?;
+    let session_mode = session.sandbox_mode.as_deref().and_then(SandboxMode::parse);
+    let settings = load_settings(session.config_file.as_deref())?;
+    let tmpdir = std::env::var_os("TMPDIR").map(PathBuf::from);
+    Ok(resolve_policy(session_mode, &settings, session.cwd.as_deref(), tmpdir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_policy() {
+        let settings: SandboxSettings = toml::from_str(
+            "[sandbox_workspace_write]\nwritable_roots = [\"/data\"]\nnetwork_access = true\n\
+             exclude_slash_tmp = true\n[projects.\"/work/app\"]\ntrust_level = \"trusted\"\n",
+        )
+        .unwrap();
+        let cwd = Path::new("/work/app");
+
+        let trusted = resolve_policy(None, &settings, Some(cwd), None);
+        assert_eq!(trusted.mode, SandboxMode::WorkspaceWrite);
+        assert_eq!(trusted.source, PolicySource::Default);
+        assert_eq!(
+            trusted.writable_roots,
+            vec![PathBuf::from("/work/app"), PathBuf::from("/data")]
+        );
+        assert!(trusted.network_access);
+
+        let untrusted = resolve_policy(None, &settings, Some(Path::new("/elsewhere")), None);
+        assert_eq!(untrusted.mode, SandboxMode::ReadOnly);
+        assert!(untrusted.writable_roots.is_empty());
+        assert!(!untrusted.network_access);
+
+        let session = resolve_policy(Some(SandboxMode::DangerFullAccess), &settings, None, None);
+        assert_eq!(session.source, PolicySource::Session);
+        assert!(session.network_access);
+    }
+}
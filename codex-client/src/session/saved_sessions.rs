@@ -0,0 +1,245 @@
+//! The session map kept on disk across restarts
+//!
+//! The registry only lives in memory, so a crash or a forced quit loses
+//! track of the open conversations. Every `SAVE_INTERVAL` the open and
+//! archived sessions are written to `~/.codexia/sessions.json` when they
+//! changed. At startup `restore_sessions` loads the open ones again from
+//! their rollouts in a new app-server; one whose rollout is gone or fails
+//! to load is restored archived, so it stays listed. A map that cannot be
+//! parsed is renamed to `sessions.json.bad` rather than overwritten.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::autosave::{transcript_path, write_atomically};
+use super::config::SessionConfig;
+use super::instructions::reload_conversation;
+use super::lifecycle::InterruptFailure;
+use super::registry::{validate_session_id, Session, SessionMetadata};
+use crate::state::{initialize_client, ClientState};
+
+/// How often the session map is checked for changes
+const SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A session as written to the session map
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedSession {
+    pub conversation_id: String,
+    pub cwd: Option<PathBuf>,
+    pub model: Option<String>,
+    pub rollout_path: Option<PathBuf>,
+    /// Codexia's own copy of the session's events
+    pub transcript_path: Option<PathBuf>,
+    #[serde(default)]
+    pub config: SessionConfig,
+    pub instructions: Option<String>,
+    #[serde(default)]
+    pub pinned: bool,
+    pub created_at: DateTime<Utc>,
+    /// Set when the session was already archived
+    pub archived_at: Option<DateTime<Utc>>,
+}
+
+impl SavedSession {
+    fn from_metadata(metadata: SessionMetadata) -> Self {
+        Self {
+            transcript_path: transcript_path(&metadata.conversation_id).ok(),
+            conversation_id: metadata.conversation_id,
+            cwd: metadata.cwd,
+            model: metadata.model,
+            rollout_path: metadata.rollout_path,
+            config: metadata.config,
+            instructions: metadata.instructions,
+            pinned: metadata.pinned,
+            created_at: metadata.created_at,
+            archived_at: metadata.archived_at,
+        }
+    }
+
+    fn into_session(self) -> Session {
+        let mut session = Session::new(self.conversation_id);
+        session.cwd = self.cwd;
+        session.model = self.model;
+        session.rollout_path = self.rollout_path;
+        session.config = self.config;
+        session.instructions = self.instructions;
+        session.pinned = self.pinned;
+        session.created_at = self.created_at;
+        session.archived_at = self.archived_at;
+        session
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreResult {
+    /// Sessions loaded again from their rollout
+    pub resumed: Vec<String>,
+    /// Sessions restored to the archived list, including the failed ones
+    pub archived: Vec<String>,
+    /// Open sessions whose rollout could not be loaded
+    pub failed: Vec<InterruptFailure>,
+}
+
+fn sessions_file() -> Result<PathBuf, String> {
+    let home_dir = dirs::home_dir().ok_or("Could not get home directory")?;
+    Ok(home_dir.join(".codexia").join("sessions.json"))
+}
+
+/// Open and archived sessions as they would be saved, by id
+pub async fn saved_sessions(state: &ClientState) -> Vec<SavedSession> {
+    let mut saved = Vec::new();
+    for id in state.sessions.ids().await {
+        if let Ok(session) = state.sessions.get(&id).await {
+            saved.push(SavedSession::from_metadata(session.metadata()));
+        }
+    }
+    saved.extend(
+        state
+            .sessions
+            .archived()
+            .await
+            .into_iter()
+            .map(SavedSession::from_metadata),
+    );
+    saved.sort_by(|a, b| a.conversation_id.cmp(&b.conversation_id));
+    saved
+}
+
+/// Write the session map unless it is the same as the `last` one written
+async fn save_session_map(state: &ClientState, last: &mut Option<Vec<u8>>) -> Result<(), String> {
+    let contents =
+        serde_json::to_vec_pretty(&saved_sessions(state).await).map_err(|e| e.to_string())?;
+    if last.as_ref() == Some(&contents) {
+        return Ok(());
+    }
+    let path = sessions_file()?;
+    let written = contents.clone();
+    tokio::task::spawn_blocking(move || write_atomically(&path, &written))
+        .await
+        .map_err(|err| err.to_string())??;
+    *last = Some(contents);
+    Ok(())
+}
+
+/// Keep the session map on disk up to date until the app exits. Started
+/// after `restore_sessions`, which reads the previous one.
+pub async fn watch_session_map(state: Arc<ClientState>) {
+    let mut last = None;
+    let mut ticks = tokio::time::interval(SAVE_INTERVAL);
+    loop {
+        ticks.tick().await;
+        if let Err(err) = save_session_map(&state, &mut last).await {
+            warn!("Failed to save the session map: {err}");
+        }
+    }
+}
+
+/// Load the sessions saved by the previous run that are not registered yet,
+/// emitting `codex-sessions-restored` when there were any. The app-server
+/// is only started when an open session has a rollout to resume.
+pub async fn restore_sessions(state: &ClientState) -> Result<RestoreResult, String> {
+    let path = sessions_file()?;
+    let contents = match tokio::fs::read(&path).await {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(RestoreResult::default())
+        }
+        Err(err) => return Err(format!("Failed to read {}: {}", path.display(), err)),
+    };
+    let saved: Vec<SavedSession> = match serde_json::from_slice(&contents) {
+        Ok(saved) => saved,
+        Err(err) => {
+            // Keep the unreadable map for inspection; the next save would
+            // replace it
+            let bad_path = path.with_extension("json.bad");
+            let kept = match tokio::fs::rename(&path, &bad_path).await {
+                Ok(()) => format!("moved to {}", bad_path.display()),
+                Err(rename_err) => format!("could not be moved aside: {rename_err}"),
+            };
+            return Err(format!("Failed to parse {}, {kept}: {err}", path.display()));
+        }
+    };
+
+    let mut result = RestoreResult::default();
+    let mut client_ready = None;
+    for saved in saved {
+        let id = saved.conversation_id.clone();
+        if validate_session_id(&id).is_err() || state.sessions.get(&id).await.is_ok() {
+            continue;
+        }
+        let rollout_path = saved
+            .rollout_path
+            .clone()
+            .filter(|path| saved.archived_at.is_none() && path.exists());
+        let Some(rollout_path) = rollout_path else {
+            state.sessions.insert_archived(saved.into_session()).await;
+            result.archived.push(id);
+            continue;
+        };
+
+        let session = saved.into_session();
+        state.sessions.insert(session.clone()).await;
+        if client_ready.is_none() {
+            client_ready = Some(initialize_client(state).await.map(|_| ()));
+        }
+        let resumed = match &client_ready {
+            Some(Err(err)) => Err(err.clone()),
+            _ => reload_conversation(state, &session, &rollout_path).await,
+        };
+        match resumed {
+            Ok(()) => result.resumed.push(id),
+            Err(error) => {
+                warn!("Failed to restore session {id}: {error}");
+                state.sessions.archive(&id, None).await?;
+                result.archived.push(id.clone());
+                result.failed.push(InterruptFailure {
+                    session_id: id,
+                    error,
+                });
+            }
+        }
+    }
+
+    if !result.resumed.is_empty() || !result.archived.is_empty() {
+        state
+            .event_bus
+            .emit("codex-sessions-restored", json!(result))
+            .await;
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saved_session_round_trip() {
+        let mut session = Session::new("a");
+        session.cwd = Some(PathBuf::from("/work/project"));
+        session.rollout_path = Some(PathBuf::from("/home/me/.codex/sessions/a.jsonl"));
+        session.config.test_command = Some("cargo test".to_string());
+        session.pinned = true;
+
+        let saved = SavedSession::from_metadata(session.metadata());
+        let json = serde_json::to_string(&saved).unwrap();
+        let restored = serde_json::from_str::<SavedSession>(&json)
+            .unwrap()
+            .into_session();
+        assert_eq!(restored.conversation_id, "a");
+        assert_eq!(restored.cwd, session.cwd);
+        assert_eq!(restored.rollout_path, session.rollout_path);
+        assert_eq!(restored.config.test_command.as_deref(), Some("cargo test"));
+        assert!(restored.pinned);
+        assert_eq!(restored.created_at, session.created_at);
+        assert!(restored.archived_at.is_none());
+    }
+}
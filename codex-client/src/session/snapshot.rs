@@ -0,0 +1,58 @@
+//! Everything a window needs to show a running session from scratch
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::registry::SessionMetadata;
+use crate::client::PendingApproval;
+use crate::session_files::transcript::load_transcript;
+use crate::state::ClientState;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSnapshot {
+    pub session: SessionMetadata,
+    /// Rollout records in order
+    pub transcript: Vec<Value>,
+    pub pending_approvals: Vec<PendingApproval>,
+    /// A turn is running
+    pub busy: bool,
+    /// `total_token_usage` of the latest `token_count` event
+    pub token_usage: Option<Value>,
+    pub context_tokens: Option<u64>,
+    /// Worktree summary; filled in by the caller that owns git access
+    pub worktree: Option<Value>,
+}
+
+/// Current state of a session, for a frontend that reloaded and lost it.
+/// Events with a `seq` above `session.event_seq` arrived after the snapshot.
+pub async fn session_snapshot(
+    state: &ClientState,
+    session_id: &str,
+) -> Result<SessionSnapshot, String> {
+    let session = state.sessions.get(session_id).await?;
+    let transcript = match &session.rollout_path {
+        Some(path) => {
+            let path = path.clone();
+            tokio::task::spawn_blocking(move || load_transcript(&path))
+                .await
+                .map_err(|err| err.to_string())??
+        }
+        None => Vec::new(),
+    };
+    let running = { state.client.lock().await.clone() };
+    let pending_approvals = match running {
+        Some(client) => client.pending_approvals(session_id).await,
+        None => Vec::new(),
+    };
+
+    Ok(SessionSnapshot {
+        session: session.metadata(),
+        transcript,
+        pending_approvals,
+        busy: session.turn_active,
+        token_usage: session.token_usage.clone(),
+        context_tokens: session.context_tokens,
+        worktree: None,
+    })
+}
@@ -0,0 +1,29 @@
+//! Follow-up actions suggested after a turn
+//!
+//! The UI shows each suggestion as a chip; choosing one sends its prompt as
+//! a user message. codex's protocol does not carry structured suggestions
+//! yet (turns end with plain `agent_message` text and `task_complete`), so
+//! every session has none until it does.
+
+use serde::Serialize;
+
+use crate::state::ClientState;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuggestedAction {
+    /// Short text for the chip
+    pub label: String,
+    /// Message sent when the suggestion is chosen
+    pub prompt: String,
+}
+
+/// Actions codex suggested after the session's last turn, empty when it
+/// suggested none
+pub async fn get_suggested_actions(
+    state: &ClientState,
+    session_id: &str,
+) -> Result<Vec<SuggestedAction>, String> {
+    state.sessions.get(session_id).await?;
+    Ok(Vec::new())
+}
@@ -0,0 +1,102 @@
+//! Tools codex can call in a conversation
+//!
+//! codex's built-in tools are the same for every conversation. MCP tools
+//! are whatever the servers reported in the latest
+//! `mcp_list_tools_response` event, keyed by codex as `server__tool`.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::state::ClientState;
+
+/// Separator between server and tool in codex's qualified MCP tool names
+const MCP_TOOL_DELIMITER: &str = "__";
+
+/// Built-in tools as (name, description)
+const BUILTIN_TOOLS: &[(&str, &str)] = &[
+    ("shell", "Run commands in the working directory, within the sandbox"),
+    ("apply_patch", "Create, edit and delete files"),
+    ("update_plan", "Keep a step-by-step plan of the task"),
+    ("view_image", "Look at an image file from the working directory"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ToolSource {
+    Builtin,
+    Mcp { server: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolInfo {
+    pub name: String,
+    pub description: Option<String>,
+    pub source: ToolSource,
+}
+
+/// MCP tools in the `tools` map of an `mcp_list_tools_response`, sorted by
+/// server and name
+pub(crate) fn parse_mcp_tools(tools: &Value) -> Vec<ToolInfo> {
+    let Some(tools) = tools.as_object() else {
+        return Vec::new();
+    };
+    let mut parsed: Vec<ToolInfo> = tools
+        .iter()
+        .map(|(qualified, tool)| {
+            let (server, name) = qualified
+                .split_once(MCP_TOOL_DELIMITER)
+                .unwrap_or(("", qualified));
+            ToolInfo {
+                name: tool["name"].as_str().unwrap_or(name).to_string(),
+                description: tool["description"].as_str().map(String::from),
+                source: ToolSource::Mcp {
+                    server: server.to_string(),
+                },
+            }
+        })
+        .collect();
+    parsed.sort_by(|a, b| (&a.source, &a.name).cmp(&(&b.source, &b.name)));
+    parsed
+}
+
+/// Built-in tools followed by the MCP tools codex last reported for the
+/// session; empty of MCP tools until codex has listed them
+pub async fn get_session_tools(
+    state: &ClientState,
+    session_id: &str,
+) -> Result<Vec<ToolInfo>, String> {
+    let session = state.sessions.get(session_id).await?;
+    let builtin = BUILTIN_TOOLS.iter().map(|(name, description)| ToolInfo {
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        source: ToolSource::Builtin,
+    });
+    Ok(builtin.chain(session.mcp_tools).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_mcp_tools() {
+        let tools = json!({
+            "github__search": { "name": "search", "description": "Search code" },
+            "docs__fetch": { "name": "fetch", "inputSchema": {} },
+        });
+        let parsed = parse_mcp_tools(&tools);
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].name, "fetch");
+        assert_eq!(parsed[0].description, None);
+        assert_eq!(
+            parsed[1].source,
+            ToolSource::Mcp {
+                server: "github".to_string()
+            }
+        );
+        assert_eq!(parsed[1].description.as_deref(), Some("Search code"));
+        assert!(parse_mcp_tools(&Value::Null).is_empty());
+    }
+}
@@ -0,0 +1,16 @@
+//! Work the app does before each user turn
+//!
+//! Every message that starts a user turn goes through the client's shared
+//! send path, whichever command or background task sends it. A hook set on
+//! the registry runs there right before the message is sent, e.g. to record
+//! the working tree so the turn can be undone. Turns are numbered like turn
+//! timings, from 1, and only counted once codex accepted the message.
+
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait TurnHook: Send + Sync {
+    /// Called before the message that would start turn `turn` of the
+    /// session is sent; the send waits for it
+    async fn before_turn(&self, conversation_id: &str, turn: u64);
+}
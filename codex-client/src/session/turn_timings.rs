@@ -0,0 +1,264 @@
+//! Wall-clock timing of each turn
+//!
+//! A turn starts when the user's message is accepted (or at `task_started`
+//! when codex starts one on its own) and ends at `task_complete` or
+//! `turn_aborted`. The first streamed output marks the time to first token;
+//! time between each command's begin and end adds up to the exec time.
+//!
+//! Times to first token are also kept for every turn of the session, not
+//! only the latest `MAX_TURNS`, grouped by model so the latency of models
+//! used in the same session can be compared.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Instant;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::RwLock;
+
+/// Turns kept per session, oldest dropped first
+const MAX_TURNS: usize = 100;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnTiming {
+    /// Position of the turn in the session, from 1
+    pub turn: u64,
+    pub model: Option<String>,
+    pub started_at: DateTime<Utc>,
+    /// From sending the message to codex's first streamed output
+    pub first_token_ms: Option<u64>,
+    /// From sending the message to the end of the turn; `None` while running
+    pub total_ms: Option<u64>,
+    /// Time spent running commands
+    pub exec_ms: u64,
+    pub aborted: bool,
+}
+
+/// Distribution of the times to first token of one model's turns
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyStats {
+    pub model: Option<String>,
+    pub count: usize,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub max_ms: u64,
+}
+
+/// Nearest-rank percentile of sorted, non-empty `values`
+fn percentile(sorted: &[u64], percent: usize) -> u64 {
+    let rank = (sorted.len() * percent).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+fn latency_stats(model: Option<String>, latencies: &[u64]) -> LatencyStats {
+    let mut sorted = latencies.to_vec();
+    sorted.sort_unstable();
+    LatencyStats {
+        model,
+        count: sorted.len(),
+        p50_ms: percentile(&sorted, 50),
+        p95_ms: percentile(&sorted, 95),
+        max_ms: sorted.last().copied().unwrap_or_default(),
+    }
+}
+
+struct OpenTurn {
+    started: Instant,
+    running_execs: HashMap<String, Instant>,
+}
+
+#[derive(Default)]
+struct SessionTurns {
+    turns: VecDeque<TurnTiming>,
+    open: Option<OpenTurn>,
+    count: u64,
+    /// Time to first token of every turn, by model
+    first_tokens: HashMap<Option<String>, Vec<u64>>,
+}
+
+impl SessionTurns {
+    fn begin(&mut self, model: Option<String>) {
+        self.count += 1;
+        if self.turns.len() == MAX_TURNS {
+            self.turns.pop_front();
+        }
+        self.turns.push_back(TurnTiming {
+            turn: self.count,
+            model,
+            started_at: Utc::now(),
+            first_token_ms: None,
+            total_ms: None,
+            exec_ms: 0,
+            aborted: false,
+        });
+        self.open = Some(OpenTurn {
+            started: Instant::now(),
+            running_execs: HashMap::new(),
+        });
+    }
+}
+
+fn is_output(kind: &str) -> bool {
+    matches!(
+        kind,
+        "agent_message_delta"
+            | "agent_message"
+            | "agent_reasoning_delta"
+            | "agent_reasoning"
+            | "agent_reasoning_raw_content_delta"
+    )
+}
+
+#[derive(Clone, Default)]
+pub struct TurnTimings {
+    by_session: Arc<RwLock<HashMap<String, SessionTurns>>>,
+}
+
+impl TurnTimings {
+    /// Start timing a turn when codex accepts a user message
+    pub async fn user_sent(&self, conversation_id: &str, model: Option<String>) {
+        let mut by_session = self.by_session.write().await;
+        by_session
+            .entry(conversation_id.to_string())
+            .or_default()
+            .begin(model);
+    }
+
+    /// Number the next user turn of the session would get
+    pub async fn next_turn(&self, conversation_id: &str) -> u64 {
+        self.by_session
+            .read()
+            .await
+            .get(conversation_id)
+            .map_or(0, |session| session.count)
+            + 1
+    }
+
+    /// Follow the turn events of a conversation's event stream
+    pub async fn record_event(&self, conversation_id: &str, msg: &Value, model: Option<&str>) {
+        let kind = msg["type"].as_str().unwrap_or_default();
+        let mut by_session = self.by_session.write().await;
+        let session = by_session.entry(conversation_id.to_string()).or_default();
+        if kind == "task_started" && session.open.is_none() {
+            session.begin(model.map(String::from));
+            return;
+        }
+        let (Some(open), Some(timing)) = (session.open.as_mut(), session.turns.back_mut()) else {
+            return;
+        };
+        let elapsed_ms = open.started.elapsed().as_millis() as u64;
+        match kind {
+            kind if is_output(kind) => {
+                if timing.first_token_ms.is_none() {
+                    timing.first_token_ms = Some(elapsed_ms);
+                    session
+                        .first_tokens
+                        .entry(timing.model.clone())
+                        .or_default()
+                        .push(elapsed_ms);
+                }
+            }
+            "exec_command_begin" => {
+                if let Some(call_id) = msg["call_id"].as_str() {
+                    open.running_execs.insert(call_id.to_string(), Instant::now());
+                }
+            }
+            "exec_command_end" => {
+                let started = msg["call_id"]
+                    .as_str()
+                    .and_then(|call_id| open.running_execs.remove(call_id));
+                if let Some(started) = started {
+                    timing.exec_ms += started.elapsed().as_millis() as u64;
+                }
+            }
+            "task_complete" | "turn_aborted" => {
+                timing.total_ms = Some(elapsed_ms);
+                timing.aborted = kind == "turn_aborted";
+                session.open = None;
+            }
+            _ => {}
+        }
+    }
+
+    pub async fn list(&self, conversation_id: &str) -> Vec<TurnTiming> {
+        self.by_session
+            .read()
+            .await
+            .get(conversation_id)
+            .map(|session| session.turns.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Time-to-first-token distribution of each model used in the session
+    pub async fn latency_stats(&self, conversation_id: &str) -> Vec<LatencyStats> {
+        let by_session = self.by_session.read().await;
+        let Some(session) = by_session.get(conversation_id) else {
+            return Vec::new();
+        };
+        let mut stats: Vec<LatencyStats> = session
+            .first_tokens
+            .iter()
+            .map(|(model, latencies)| latency_stats(model.clone(), latencies))
+            .collect();
+        stats.sort_by(|a, b| a.model.cmp(&b.model));
+        stats
+    }
+
+    pub async fn forget(&self, conversation_id: &str) {
+        self.by_session.write().await.remove(conversation_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_turn_timings() {
+        let timings = TurnTimings::default();
+        assert_eq!(timings.next_turn("s").await, 1);
+        timings.user_sent("s", Some("gpt-5".to_string())).await;
+        for msg in [
+            json!({ "type": "task_started" }),
+            json!({ "type": "agent_message_delta", "delta": "hi" }),
+            json!({ "type": "exec_command_begin", "call_id": "c" }),
+            json!({ "type": "exec_command_end", "call_id": "c" }),
+            json!({ "type": "task_complete" }),
+            json!({ "type": "task_started" }),
+            json!({ "type": "turn_aborted" }),
+        ] {
+            timings.record_event("s", &msg, Some("o3")).await;
+        }
+
+        let turns = timings.list("s").await;
+        assert_eq!(turns.len(), 2);
+        assert_eq!(turns[0].model.as_deref(), Some("gpt-5"));
+        assert!(turns[0].first_token_ms.is_some());
+        assert!(turns[0].total_ms >= turns[0].first_token_ms);
+        assert!(!turns[0].aborted);
+        assert_eq!((turns[1].turn, turns[1].model.as_deref()), (2, Some("o3")));
+        assert!(turns[1].aborted);
+        assert_eq!(turns[1].first_token_ms, None);
+        assert_eq!(timings.next_turn("s").await, 3);
+
+        let stats = timings.latency_stats("s").await;
+        assert_eq!(stats.len(), 1);
+        assert_eq!((stats[0].model.as_deref(), stats[0].count), (Some("gpt-5"), 1));
+    }
+
+    #[test]
+    fn test_latency_stats() {
+        let latencies: Vec<u64> = (1..=20).map(|n| n * 100).collect();
+        let stats = latency_stats(Some("o3".to_string()), &latencies);
+        assert_eq!(stats.count, 20);
+        assert_eq!(stats.p50_ms, 1000);
+        assert_eq!(stats.p95_ms, 1900);
+        assert_eq!(stats.max_ms, 2000);
+        assert_eq!(latency_stats(None, &[700]).p95_ms, 700);
+    }
+}
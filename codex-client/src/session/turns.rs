@@ -0,0 +1,138 @@
+//! Running a turn and waiting for it to end
+//!
+//! Used by work that drives a conversation one turn at a time: compaction,
+//! forks, transcript replays and test runs. Each caller picks how long a
+//! turn may take, since a summary, a replayed turn waiting for approval and
+//! a test suite differ by orders of magnitude.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::json;
+use tokio::sync::oneshot;
+
+use crate::client::CodexAppServerClient;
+use crate::events::{EventBus, SubscriptionId};
+use crate::state::ClientState;
+
+type TurnOutcome = Result<Option<String>, String>;
+
+/// Waits for the current turn of one conversation to finish
+pub(super) struct TurnWatcher<'a> {
+    event_bus: &'a EventBus,
+    subscriptions: [SubscriptionId; 2],
+    done: oneshot::Receiver<TurnOutcome>,
+}
+
+impl<'a> TurnWatcher<'a> {
+    /// Subscribe before the turn is started so its end cannot be missed
+    pub(super) async fn new(event_bus: &'a EventBus, conversation_id: &str) -> TurnWatcher<'a> {
+        let (tx, done) = oneshot::channel::<TurnOutcome>();
+        let tx = Arc::new(Mutex::new(Some(tx)));
+        let finish = move |outcome: TurnOutcome| {
+            if let Some(tx) = tx.lock().ok().and_then(|mut tx| tx.take()) {
+                let _ = tx.send(outcome);
+            }
+        };
+
+        let conversation_id = conversation_id.to_string();
+        let turn_finish = finish.clone();
+        let turn_subscription = event_bus
+            .subscribe(
+                "codex:event",
+                Arc::new(move |_, data| {
+                    let params = &data["params"];
+                    if params["conversationId"].as_str() != Some(conversation_id.as_str()) {
+                        return Ok(());
+                    }
+                    let msg = &params["msg"];
+                    match msg["type"].as_str() {
+                        Some("task_complete") => {
+                            turn_finish(Ok(msg["last_agent_message"].as_str().map(String::from)))
+                        }
+                        Some("turn_aborted") => {
+                            turn_finish(Err("codex aborted the turn".to_string()))
+                        }
+                        _ => {}
+                    }
+                    Ok(())
+                }),
+            )
+            .await;
+        // Without a timeout, nothing else would end the wait
+        let exit_subscription = event_bus
+            .subscribe(
+                "codex:process-exited",
+                Arc::new(move |_, _| {
+                    finish(Err(
+                        "The codex app-server exited during the turn".to_string()
+                    ));
+                    Ok(())
+                }),
+            )
+            .await;
+        TurnWatcher {
+            event_bus,
+            subscriptions: [turn_subscription, exit_subscription],
+            done,
+        }
+    }
+
+    /// Stop watching without waiting for the turn
+    pub(super) async fn cancel(self) {
+        for subscription in self.subscriptions {
+            self.event_bus.remove_subscription(subscription).await;
+        }
+    }
+
+    /// Last agent message of the finished turn. With `timeout`, a turn
+    /// still running after it counts as failed.
+    pub(super) async fn wait(self, timeout: Option<Duration>) -> TurnOutcome {
+        let outcome = match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, self.done).await,
+            None => Ok(self.done.await),
+        };
+        for subscription in self.subscriptions {
+            self.event_bus.remove_subscription(subscription).await;
+        }
+        match outcome {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err("Event stream closed before the turn finished".to_string()),
+            Err(_) => Err(format!(
+                "Timed out after {}s waiting for codex to finish the turn",
+                timeout.unwrap_or_default().as_secs()
+            )),
+        }
+    }
+}
+
+async fn send_text(
+    client: &CodexAppServerClient,
+    conversation_id: &str,
+    text: &str,
+) -> Result<(), String> {
+    let params = serde_json::from_value(json!({
+        "conversationId": conversation_id,
+        "items": [{ "type": "text", "data": { "text": text } }],
+    }))
+    .map_err(|err| err.to_string())?;
+    client.send_user_message(params).await?;
+    Ok(())
+}
+
+/// Send `text` and wait for the turn it starts to finish, for at most
+/// `timeout` when one is given
+pub(super) async fn run_turn(
+    state: &ClientState,
+    client: &CodexAppServerClient,
+    conversation_id: &str,
+    text: &str,
+    timeout: Option<Duration>,
+) -> TurnOutcome {
+    let watcher = TurnWatcher::new(&state.event_bus, conversation_id).await;
+    if let Err(err) = send_text(client, conversation_id, text).await {
+        watcher.cancel().await;
+        return Err(err);
+    }
+    watcher.wait(timeout).await
+}
@@ -1,11 +1,15 @@
 pub mod cache;
 pub mod file;
+pub mod rollouts;
 pub mod scanner;
+pub mod transcript;
 pub mod utils;
 
 // Re-export commonly used functions
 pub use cache::load_project_sessions;
 pub use scanner::scan_projects;
+pub use rollouts::{list_codex_rollouts, RolloutInfo};
+pub use transcript::{load_transcript_page, TranscriptEntry, TranscriptPage};
 
 // Re-export database functions
 pub use crate::db::{
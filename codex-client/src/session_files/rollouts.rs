@@ -0,0 +1,228 @@
+use super::file::get_sessions_path;
+use super::scanner::scan_jsonl_files;
+use super::utils::extract_datetime;
+use chrono::{DateTime, Utc};
+use log::warn;
+use serde::Serialize;
+use serde_json::Value;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// Maximum number of characters kept from the first user message
+const PREVIEW_CHARS: usize = 120;
+
+/// Metadata extracted from a single codex rollout file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RolloutInfo {
+    pub path: String,
+    pub conversation_id: String,
+    pub timestamp: Option<String>,
+    pub cwd: Option<String>,
+    pub model: Option<String>,
+    pub preview: Option<String>,
+    /// True when the rollout lives in `~/.codex/archived_sessions`
+    pub archived: bool,
+}
+
+fn get_archived_sessions_path() -> Result<PathBuf, String> {
+    let home_dir = dirs::home_dir().ok_or("Could not find home directory")?;
+    Ok(home_dir.join(".codex").join("archived_sessions"))
+}
+
+/// Parse the metadata of a rollout file.
+///
+/// The first line must be a `session_meta` record; the model comes from the
+/// first `turn_context` record and the preview from the first user message.
+pub fn parse_rollout<P: AsRef<Path>>(path: P, archived: bool) -> Result<RolloutInfo, String> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|e| format!("Failed to open file {:?}: {}", path, e))?;
+    let mut lines = BufReader::new(file).lines();
+
+    let first_line = lines
+        .next()
+        .transpose()
+        .map_err(|e| format!("Failed to read first line from {:?}: {}", path, e))?
+        .ok_or_else(|| format!("File {:?} is empty", path))?;
+    let meta: Value = serde_json::from_str(&first_line)
+        .map_err(|e| format!("Invalid session metadata in {:?}: {}", path, e))?;
+    let payload = &meta["payload"];
+    let conversation_id = payload["id"]
+        .as_str()
+        .ok_or_else(|| format!("Could not extract session id from {:?}", path))?
+        .to_string();
+
+    let path_str = path.to_string_lossy().to_string();
+    let timestamp = payload["timestamp"]
+        .as_str()
+        .or_else(|| meta["timestamp"].as_str())
+        .map(String::from)
+        .or_else(|| {
+            extract_datetime(&path_str).map(|dt| dt.format("%Y-%m-%dT%H:%M:%S").to_string())
+        });
+
+    let mut info = RolloutInfo {
+        path: path_str,
+        conversation_id,
+        timestamp,
+        cwd: payload["cwd"].as_str().map(String::from),
+        model: None,
+        preview: None,
+        archived,
+    };
+
+    // A partially written last line is expected while codex is still running,
+    // so unparsable lines after the header are skipped rather than fatal.
+    for line in lines.map_while(Result::ok) {
+        let Ok(value) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        match value["type"].as_str() {
+            Some("turn_context") if info.model.is_none() => {
+                info.model = value["payload"]["model"].as_str().map(String::from);
+            }
+            Some("event_msg")
+                if info.preview.is_none()
+                    && value["payload"]["type"].as_str() == Some("user_message") =>
+            {
+                info.preview = value["payload"]["message"]
+                    .as_str()
+                    .map(|message| message.chars().take(PREVIEW_CHARS).collect());
+            }
+            _ => {}
+        }
+        if info.model.is_some() && info.preview.is_some() {
+            break;
+        }
+    }
+
+    Ok(info)
+}
+
+/// Text of every user message recorded in a rollout, in order
+pub fn user_messages<P: AsRef<Path>>(path: P) -> Result<Vec<String>, String> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|e| format!("Failed to open file {:?}: {}", path, e))?;
+    Ok(BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str::<Value>(&line).ok())
+        .filter(|value| {
+            value["type"].as_str() == Some("event_msg")
+                && value["payload"]["type"].as_str() == Some("user_message")
+        })
+        .filter_map(|value| value["payload"]["message"].as_str().map(String::from))
+        .collect())
+}
+
+/// Whether a rollout record was written for the user's input of a turn:
+/// the `user_message` event, the input itself as a user `response_item`
+/// and the turn's `turn_context`
+fn is_turn_input(record: &Value) -> bool {
+    let payload = &record["payload"];
+    match record["type"].as_str() {
+        Some("event_msg") => payload["type"].as_str() == Some("user_message"),
+        Some("response_item") => {
+            payload["type"].as_str() == Some("message") && payload["role"].as_str() == Some("user")
+        }
+        Some("turn_context") => true,
+        _ => false,
+    }
+}
+
+/// Split a rollout's content before its last user turn. Returns the byte
+/// offset where that turn's records start and the turn's user message, or
+/// `None` when the rollout has no user message.
+pub fn split_last_turn(content: &str) -> Option<(usize, String)> {
+    let mut offset = 0;
+    let mut records = Vec::new();
+    for line in content.split_inclusive('\n') {
+        let record = serde_json::from_str::<Value>(line).unwrap_or(Value::Null);
+        records.push((offset, record));
+        offset += line.len();
+    }
+
+    let last = records.iter().rposition(|(_, record)| {
+        record["type"].as_str() == Some("event_msg")
+            && record["payload"]["type"].as_str() == Some("user_message")
+    })?;
+    let message = records[last].1["payload"]["message"].as_str()?.to_string();
+    // The input records codex wrote just before the event belong to the turn
+    let mut start = last;
+    while start > 0 && is_turn_input(&records[start - 1].1) {
+        start -= 1;
+    }
+    Some((records[start].0, message))
+}
+
+fn sort_key(info: &RolloutInfo) -> Option<DateTime<Utc>> {
+    info.timestamp
+        .as_deref()
+        .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+        .map(|dt| dt.with_timezone(&Utc))
+        .or_else(|| extract_datetime(&info.path).map(|dt| dt.and_utc()))
+}
+
+/// List every rollout under codex's sessions directories, newest first.
+///
+/// Corrupt or unreadable rollouts are skipped with a warning so a single bad
+/// file does not break the whole listing.
+pub async fn list_codex_rollouts() -> Result<Vec<RolloutInfo>, String> {
+    let roots = [
+        (get_sessions_path()?, false),
+        (get_archived_sessions_path()?, true),
+    ];
+
+    let mut rollouts = Vec::new();
+    for (root, archived) in roots {
+        if !root.exists() {
+            continue;
+        }
+        for entry in scan_jsonl_files(&root) {
+            match parse_rollout(entry.path(), archived) {
+                Ok(info) => rollouts.push(info),
+                Err(err) => warn!("Skipping rollout {:?}: {}", entry.path(), err),
+            }
+        }
+    }
+
+    rollouts.sort_by(|a, b| match (sort_key(a), sort_key(b)) {
+        (Some(a_dt), Some(b_dt)) => b_dt.cmp(&a_dt),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.path.cmp(&b.path),
+    });
+
+    Ok(rollouts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_last_turn() {
+        let lines = [
+            r#"{"type":"session_meta","payload":{"id":"c"}}"#,
+            r#"{"type":"response_item","payload":{"type":"message","role":"user"}}"#,
+            r#"{"type":"event_msg","payload":{"type":"user_message","message":"first"}}"#,
+            r#"{"type":"event_msg","payload":{"type":"agent_message","message":"one"}}"#,
+            r#"{"type":"turn_context","payload":{"model":"gpt-5"}}"#,
+            r#"{"type":"response_item","payload":{"type":"message","role":"user"}}"#,
+            r#"{"type":"event_msg","payload":{"type":"user_message","message":"second"}}"#,
+            r#"{"type":"event_msg","payload":{"type":"agent_message","message":"two"}}"#,
+        ];
+        let content = lines.join("\n") + "\n";
+
+        let (offset, message) = split_last_turn(&content).unwrap();
+        assert_eq!(message, "second");
+        assert_eq!(&content[..offset], lines[..4].join("\n") + "\n");
+
+        let (offset, message) = split_last_turn(&content[..offset]).unwrap();
+        assert_eq!(message, "first");
+        assert_eq!(&content[..offset], lines[0].to_string() + "\n");
+
+        assert!(split_last_turn(lines[0]).is_none());
+    }
+}
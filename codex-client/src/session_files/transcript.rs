@@ -0,0 +1,121 @@
+//! Paged reading of rollout transcripts
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Entries returned when the caller does not ask for a page size
+pub const DEFAULT_TRANSCRIPT_PAGE: usize = 200;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptEntry {
+    /// Position of the record in the rollout; rollouts are append-only, so
+    /// an index keeps pointing at the same record as the session grows.
+    /// Regenerating a turn cuts it from the rollout, shifting the indices
+    /// of that turn's records.
+    pub index: usize,
+    pub record: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptPage {
+    pub entries: Vec<TranscriptEntry>,
+    /// Index of the first entry of the page
+    pub offset: usize,
+    /// Number of records in the whole transcript
+    pub total: usize,
+}
+
+fn open_rollout(path: &Path) -> Result<BufReader<File>, String> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|e| format!("Failed to open rollout {}: {}", path.display(), e))
+}
+
+/// Non-empty lines of a rollout, numbered
+fn records(path: &Path) -> Result<impl Iterator<Item = (usize, String)>, String> {
+    Ok(open_rollout(path)?
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.trim().is_empty())
+        .enumerate())
+}
+
+/// Every record of a rollout in order
+pub fn load_transcript<P: AsRef<Path>>(path: P) -> Result<Vec<Value>, String> {
+    Ok(records(path.as_ref())?
+        .filter_map(|(_, line)| serde_json::from_str(&line).ok())
+        .collect())
+}
+
+/// Up to `limit` records starting at `offset`.
+///
+/// Without an offset the most recent `limit` records are returned, which is
+/// what a transcript view needs first; older pages follow by passing the
+/// previous page's offset minus the page size. Lines that are not valid JSON
+/// are skipped but keep their index, so indices never shift.
+pub fn load_transcript_page<P: AsRef<Path>>(
+    path: P,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<TranscriptPage, String> {
+    let path = path.as_ref();
+    let limit = limit.unwrap_or(DEFAULT_TRANSCRIPT_PAGE);
+    let total = records(path)?.count();
+    let offset = offset.unwrap_or_else(|| total.saturating_sub(limit));
+
+    let entries = records(path)?
+        .skip(offset)
+        .take(limit)
+        .filter_map(|(index, line)| {
+            serde_json::from_str(&line)
+                .ok()
+                .map(|record| TranscriptEntry { index, record })
+        })
+        .collect();
+
+    Ok(TranscriptPage {
+        entries,
+        offset,
+        total,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn rollout(lines: &[&str]) -> PathBuf {
+        let path = std::env::temp_dir()
+            .join(format!("codexia-transcript-test-{}.jsonl", std::process::id()));
+        std::fs::write(&path, lines.join("\n")).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_load_transcript_page() {
+        let file = rollout(&["{\"n\":0}", "{\"n\":1}", "not json", "", "{\"n\":3}"]);
+
+        let recent = load_transcript_page(&file, None, Some(2)).unwrap();
+        assert_eq!(recent.total, 4);
+        assert_eq!(recent.offset, 2);
+        assert_eq!(recent.entries.len(), 1);
+        assert_eq!(recent.entries[0].index, 3);
+        assert_eq!(recent.entries[0].record["n"], 3);
+
+        let first = load_transcript_page(&file, Some(0), Some(2)).unwrap();
+        let indices: Vec<usize> = first.entries.iter().map(|e| e.index).collect();
+        assert_eq!(indices, vec![0, 1]);
+
+        let past_end = load_transcript_page(&file, Some(10), None).unwrap();
+        assert!(past_end.entries.is_empty());
+
+        std::fs::remove_file(&file).unwrap();
+    }
+}
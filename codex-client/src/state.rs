@@ -1,9 +1,15 @@
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::{Mutex, RwLock};
-use log::info;
+use log::{info, warn};
 
-use crate::client::CodexAppServerClient;
+use crate::client::{CodexAppServerClient, OutputBufferConfig};
+use crate::config::proxy::{validate_proxy, ProxyConfig, ProxySettings};
+use crate::config::resolve_config_file;
+use crate::error::CodexError;
 use crate::events::EventBus;
+use crate::session::SessionRegistry;
 use codex_app_server_protocol::InitializeResponse;
 
 /// Client state - manages the codex app-server client and configuration
@@ -19,10 +25,29 @@ pub struct ClientState {
     pub active_client_name: Arc<RwLock<Option<String>>>,
     /// Event bus for emitting events (replaces Tauri's event system)
     pub event_bus: Arc<EventBus>,
+    /// Per-conversation bookkeeping shared with the active client
+    pub sessions: SessionRegistry,
+    /// Alternate codex config file; `None` keeps codex's default discovery
+    pub config_file: Arc<RwLock<Option<PathBuf>>>,
+    /// Proxy passed to the app-server; `None` keeps the inherited
+    /// environment. Not persisted.
+    pub proxy: Arc<RwLock<Option<ProxyConfig>>>,
+    /// How long the app-server may take to answer `initialize`
+    pub startup_timeout: Arc<RwLock<Duration>>,
+    /// Queue between codex's stdout and the message handlers; applies to
+    /// app-servers started afterwards
+    pub output_buffer: Arc<RwLock<OutputBufferConfig>>,
 }
 
+/// Long enough for a slow first login check, short enough to notice a hang
+pub const DEFAULT_STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Grace given to a hung app-server before its process group is killed
+const STARTUP_ABORT_GRACE: Duration = Duration::from_secs(2);
+
 impl ClientState {
     pub fn new() -> Self {
+        let sessions = SessionRegistry::new();
         Self {
             client: Arc::new(Mutex::new(None)),
             initialize_lock: Arc::new(Mutex::new(())),
@@ -30,7 +55,13 @@ impl ClientState {
             initialized_client_name: Arc::new(RwLock::new(None)),
             selected_client_name: Arc::new(RwLock::new("codex".to_string())),
             active_client_name: Arc::new(RwLock::new(None)),
-            event_bus: Arc::new(EventBus::new()),
+            // Every event of a session is stamped and kept by the registry
+            event_bus: Arc::new(EventBus::with_recorder(Arc::new(sessions.clone()))),
+            sessions,
+            config_file: Arc::new(RwLock::new(None)),
+            proxy: Arc::new(RwLock::new(None)),
+            startup_timeout: Arc::new(RwLock::new(DEFAULT_STARTUP_TIMEOUT)),
+            output_buffer: Arc::new(RwLock::new(OutputBufferConfig::default())),
         }
     }
 }
@@ -62,7 +93,18 @@ pub async fn get_client(state: &ClientState) -> Result<Arc<CodexAppServerClient>
 
     // Otherwise, (re)spawn the client matching the desired name
     info!("Starting {} app-server process", desired);
-    let client = CodexAppServerClient::spawn(state.event_bus.clone(), &desired).await?;
+    let config_file = { state.config_file.read().await.clone() };
+    let proxy = { state.proxy.read().await.clone() };
+    let output_buffer = { *state.output_buffer.read().await };
+    let client = CodexAppServerClient::spawn(
+        state.event_bus.clone(),
+        state.sessions.clone(),
+        &desired,
+        config_file,
+        proxy,
+        output_buffer,
+    )
+    .await?;
     info!("{} app-server spawned", desired);
 
     // Save client and its active name atomically
@@ -105,10 +147,116 @@ pub async fn set_client_name(state: &ClientState, name: String) -> Result<(), St
     }
 
     // Drop current active client; it will be respawned on demand
+    take_client(state).await;
+    Ok(())
+}
+
+pub async fn get_config_file(state: &ClientState) -> Option<PathBuf> {
+    state.config_file.read().await.clone()
+}
+
+/// Select an alternate codex config file (or `None` for the default) and
+/// reset the active client so the next get_client() applies it
+pub async fn set_config_file(
+    state: &ClientState,
+    path: Option<String>,
+) -> Result<Option<PathBuf>, String> {
+    let resolved = match path.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+        Some(path) => Some(resolve_config_file(path)?),
+        None => None,
+    };
     {
-        let mut client_guard = state.client.lock().await;
-        *client_guard = None;
+        let mut guard = state.config_file.write().await;
+        *guard = resolved.clone();
+    }
+    take_client(state).await;
+    Ok(resolved)
+}
+
+/// The proxy in use, with its password left out
+pub async fn get_proxy(state: &ClientState) -> Option<ProxySettings> {
+    state.proxy.read().await.as_ref().map(ProxySettings::from)
+}
+
+/// Route codex's network traffic through `proxy` (or `None` for the
+/// inherited environment) and reset the active client so the next
+/// get_client() applies it. The proxy is kept in memory only, so it applies
+/// until Codexia exits.
+pub async fn set_proxy(state: &ClientState, proxy: Option<ProxyConfig>) -> Result<(), String> {
+    let proxy = match proxy {
+        Some(proxy) => Some(ProxyConfig {
+            url: validate_proxy(&proxy)?,
+            ..proxy
+        }),
+        None => None,
+    };
+    let proxy_url = proxy.as_ref().map(|proxy| proxy.url.clone());
+    {
+        let mut guard = state.proxy.write().await;
+        *guard = proxy;
+    }
+    take_client(state).await;
+    // Every session is served by the app-server started next
+    state.sessions.set_proxy_url(proxy_url).await;
+    Ok(())
+}
+
+/// Start the app-server if needed and run the `initialize` handshake once,
+/// caching its response.
+///
+/// A process that does not answer within `startup_timeout` (e.g. codex stuck
+/// on a network auth check) is detached and stopped, and the call fails with
+/// `CodexError::StartupTimeout`; the next call starts a fresh process.
+pub async fn initialize_client(state: &ClientState) -> Result<InitializeResponse, String> {
+    let _guard = state.initialize_lock.lock().await;
+    if let Some(cached) = state.initialize_response.lock().await.clone() {
+        return Ok(cached);
     }
+
+    let timeout = *state.startup_timeout.read().await;
+    let client = get_client(state).await?;
+    let response = match tokio::time::timeout(timeout, client.initialize()).await {
+        Ok(result) => result?,
+        Err(_) => {
+            warn!("codex app-server did not initialize within {timeout:?}; stopping it");
+            if let Some(stuck) = take_client(state).await {
+                stuck.shutdown(STARTUP_ABORT_GRACE).await;
+            }
+            return Err(CodexError::StartupTimeout { timeout }.into());
+        }
+    };
+
+    *state.initialize_response.lock().await = Some(response.clone());
+    Ok(response)
+}
+
+pub async fn set_startup_timeout(state: &ClientState, timeout: Duration) -> Result<(), String> {
+    if timeout.is_zero() {
+        return Err("Startup timeout must be at least one second".to_string());
+    }
+    *state.startup_timeout.write().await = timeout;
+    Ok(())
+}
+
+/// Configure the stdout queue of the next app-server that is started
+pub async fn set_output_buffer(
+    state: &ClientState,
+    config: OutputBufferConfig,
+) -> Result<(), String> {
+    if config.capacity == 0 {
+        return Err("Output buffer capacity must be at least one line".to_string());
+    }
+    *state.output_buffer.write().await = config;
+    Ok(())
+}
+
+/// Detach the active client from the state so the next call to
+/// get_client() spawns a fresh one, returning the detached client
+pub async fn take_client(state: &ClientState) -> Option<Arc<CodexAppServerClient>> {
+    let client = {
+        let mut client_guard = state.client.lock().await;
+        client_guard.take()
+    };
     {
         let mut init_guard = state.initialize_response.lock().await;
         *init_guard = None;
@@ -121,5 +269,5 @@ pub async fn set_client_name(state: &ClientState, name: String) -> Result<(), St
         let mut name_guard = state.active_client_name.write().await;
         *name_guard = None;
     }
-    Ok(())
+    client
 }
@@ -0,0 +1,151 @@
+//! Detection of working directories on network filesystems, and the disk
+//! space left under them
+//!
+//! Only the filesystem type is queried, so a mount that has stopped
+//! answering can still block the call; callers on the async runtime should
+//! run it with `spawn_blocking`.
+
+use serde::Serialize;
+use std::path::Path;
+use sysinfo::Disks;
+
+/// Linux `statfs` magic numbers of network and cluster filesystems. FUSE
+/// mounts are not included: their magic is shared by local filesystems.
+#[cfg(target_os = "linux")]
+const LINUX_NETWORK_MAGIC: &[(u32, &str)] = &[
+    (0x6969, "nfs"),
+    (0x517B, "smb"),
+    (0xFF53_4D42, "cifs"),
+    (0xFE53_4D42, "smb2"),
+    (0x5346_414F, "afs"),
+    (0x7375_7245, "coda"),
+    (0x0102_1997, "9p"),
+    (0x00C3_6400, "ceph"),
+];
+
+/// `f_fstypename` values of network filesystems on macOS
+#[cfg(target_os = "macos")]
+const MACOS_NETWORK_TYPES: &[&str] = &["nfs", "smbfs", "afpfs", "webdav", "cifs", "ftp"];
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn statfs(path: &Path) -> Option<libc::statfs> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut buf = std::mem::MaybeUninit::<libc::statfs>::uninit();
+    // SAFETY: `path` is NUL-terminated and `buf` is written on success
+    let result = unsafe { libc::statfs(path.as_ptr(), buf.as_mut_ptr()) };
+    // SAFETY: a zero result means statfs filled in `buf`
+    (result == 0).then(|| unsafe { buf.assume_init() })
+}
+
+/// Type of the network filesystem `path` is on, `None` for local paths and
+/// paths that cannot be queried
+#[cfg(target_os = "linux")]
+pub fn network_filesystem(path: &Path) -> Option<String> {
+    // `f_type` is signed on some targets; the magic numbers fit in 32 bits
+    let magic = statfs(path)?.f_type as u32;
+    LINUX_NETWORK_MAGIC
+        .iter()
+        .find(|(known, _)| *known == magic)
+        .map(|(_, name)| name.to_string())
+}
+
+#[cfg(target_os = "macos")]
+pub fn network_filesystem(path: &Path) -> Option<String> {
+    let buf = statfs(path)?;
+    // SAFETY: the kernel NUL-terminates `f_fstypename`
+    let name = unsafe { std::ffi::CStr::from_ptr(buf.f_fstypename.as_ptr()) };
+    let name = name.to_string_lossy();
+    MACOS_NETWORK_TYPES
+        .contains(&name.as_ref())
+        .then(|| name.into_owned())
+}
+
+/// Only UNC paths (`\\server\share`, including `\\wsl$`) are detected;
+/// mapped drive letters look local
+#[cfg(windows)]
+pub fn network_filesystem(path: &Path) -> Option<String> {
+    use std::path::{Component, Prefix};
+
+    match path.components().next()? {
+        Component::Prefix(prefix) => match prefix.kind() {
+            Prefix::UNC(..) | Prefix::VerbatimUNC(..) => Some("unc".to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn network_filesystem(_path: &Path) -> Option<String> {
+    None
+}
+
+/// Space on the disk holding a directory, in bytes
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskStatus {
+    pub total: u64,
+    pub available: u64,
+    pub used_percent: f64,
+}
+
+/// Index of the mount point that holds `path`: the longest one it is under
+fn containing_mount(path: &Path, mount_points: &[&Path]) -> Option<usize> {
+    mount_points
+        .iter()
+        .enumerate()
+        .filter(|(_, mount)| path.starts_with(mount))
+        .max_by_key(|(_, mount)| mount.as_os_str().len())
+        .map(|(index, _)| index)
+}
+
+/// Space on the disk `path` is on, `None` when no mounted disk holds it.
+/// Lists every mount, so like `network_filesystem` it can block.
+pub fn disk_status(path: &Path) -> Option<DiskStatus> {
+    // Canonical Windows paths gain a `\\?\` prefix that mount points lack
+    let path = if cfg!(windows) {
+        path.to_path_buf()
+    } else {
+        path.canonicalize().ok()?
+    };
+    let disks = Disks::new_with_refreshed_list();
+    let mount_points: Vec<&Path> = disks.iter().map(|disk| disk.mount_point()).collect();
+    let disk = &disks.list()[containing_mount(&path, &mount_points)?];
+    let (total, available) = (disk.total_space(), disk.available_space());
+    let used_percent = match total {
+        0 => 0.0,
+        total => (total - available.min(total)) as f64 * 100.0 / total as f64,
+    };
+    Some(DiskStatus {
+        total,
+        available,
+        used_percent,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_containing_mount() {
+        let mounts = [
+            Path::new("/"),
+            Path::new("/home"),
+            Path::new("/home/me/data"),
+        ];
+        assert_eq!(
+            containing_mount(Path::new("/home/me/src"), &mounts),
+            Some(1)
+        );
+        assert_eq!(
+            containing_mount(Path::new("/home/me/data/x"), &mounts),
+            Some(2)
+        );
+        assert_eq!(containing_mount(Path::new("/tmp"), &mounts), Some(0));
+        assert_eq!(containing_mount(Path::new("/tmp"), &mounts[1..]), None);
+    }
+}
@@ -1,4 +1,5 @@
 pub mod codex_discovery;
 pub mod coder_discovery;
+pub mod filesystem;
 pub mod command;
 pub mod wsl;
@@ -1,5 +1,6 @@
 use tauri::State;
 use crate::codex_commands::state::CodexState;
+use codex_client::services::accounts::AccountInfo;
 use codex_client::codex_app_server_protocol::{
     CancelLoginAccountResponse,
     GetAccountRateLimitsResponse,
@@ -59,3 +60,19 @@ pub async fn logout_account(
     let client = codex_client::state::get_client(&state.client_state).await?;
     client.logout_account().await
 }
+
+#[tauri::command]
+pub async fn list_codex_accounts(
+    state: State<'_, CodexState>,
+) -> Result<Vec<AccountInfo>, String> {
+    codex_client::services::accounts::list_codex_accounts(&state.client_state).await
+}
+
+/// Choose the account for new sessions; running sessions keep theirs
+#[tauri::command]
+pub async fn switch_codex_account(
+    state: State<'_, CodexState>,
+    account_id: String,
+) -> Result<(), String> {
+    codex_client::services::accounts::switch_codex_account(&state.client_state, &account_id).await
+}
@@ -1,5 +1,10 @@
+use std::path::PathBuf;
 use tauri::State;
 use crate::codex_commands::state::CodexState;
+use codex_client::client::ProtocolMismatch;
+use codex_client::config::proxy::ProxySettings;
+use codex_client::ProxyConfig;
+use codex_client::services::codex::AuthStatus;
 
 #[tauri::command]
 pub async fn check_codex_version(
@@ -13,6 +18,11 @@ pub async fn check_coder_version(
     codex_client::services::coder::check_coder_version().await
 }
 
+#[tauri::command]
+pub async fn check_codex_auth() -> Result<AuthStatus, String> {
+    codex_client::services::codex::check_codex_auth().await
+}
+
 #[tauri::command]
 pub async fn get_client_name(state: State<'_, CodexState>) -> Result<String, String> {
     codex_client::state::get_client_name(&state.client_state).await
@@ -22,3 +32,48 @@ pub async fn get_client_name(state: State<'_, CodexState>) -> Result<String, Str
 pub async fn set_client_name(state: State<'_, CodexState>, name: String) -> Result<(), String> {
     codex_client::state::set_client_name(&state.client_state, name).await
 }
+
+#[tauri::command]
+pub async fn get_codex_config_file(
+    state: State<'_, CodexState>,
+) -> Result<Option<PathBuf>, String> {
+    Ok(codex_client::state::get_config_file(&state.client_state).await)
+}
+
+/// Start codex with an alternate config.toml; `None` restores the default
+#[tauri::command]
+pub async fn set_codex_config_file(
+    state: State<'_, CodexState>,
+    path: Option<String>,
+) -> Result<Option<PathBuf>, String> {
+    codex_client::state::set_config_file(&state.client_state, path).await
+}
+
+/// The proxy codex is started behind; the password is never sent back,
+/// only whether one is set
+#[tauri::command]
+pub async fn get_codex_proxy(
+    state: State<'_, CodexState>,
+) -> Result<Option<ProxySettings>, String> {
+    Ok(codex_client::state::get_proxy(&state.client_state).await)
+}
+
+/// Start codex behind an HTTP or SOCKS proxy; `None` restores the proxy
+/// settings Codexia inherited. The proxy lasts until Codexia exits; a
+/// password has to be given again with every change.
+#[tauri::command]
+pub async fn set_codex_proxy(
+    state: State<'_, CodexState>,
+    proxy: Option<ProxyConfig>,
+) -> Result<(), String> {
+    codex_client::state::set_proxy(&state.client_state, proxy).await
+}
+
+/// First message of the running codex that Codexia could not parse, if any
+#[tauri::command]
+pub async fn get_protocol_mismatch(
+    state: State<'_, CodexState>,
+) -> Result<Option<ProtocolMismatch>, String> {
+    let client = { state.client_state.client.lock().await.clone() };
+    Ok(client.and_then(|client| client.protocol_mismatch()))
+}
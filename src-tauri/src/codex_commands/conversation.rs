@@ -1,5 +1,14 @@
-use tauri::State;
+use tauri::{State, WebviewWindow};
 use crate::codex_commands::state::CodexState;
+use codex_client::{McpServerConfig, SessionConfig};
+use codex_client::session::{
+    build_file_context, developer_instructions, prepare_artifacts_dir, with_instructions,
+    with_max_output_tokens, with_mcp_servers, AttachedFile, InstructionsChange,
+    SessionMcpServer,
+};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::Path;
 use codex_client::codex_app_server_protocol::{
     InterruptConversationParams,
     InterruptConversationResponse,
@@ -13,31 +22,210 @@ use codex_client::codex_app_server_protocol::{
     TurnStartResponse,
 };
 
+/// Emit `codex-slow-filesystem-warning` when the session's working directory
+/// is on a network filesystem, where git and codex's file access are slow
+async fn warn_if_network_filesystem(state: &CodexState, conversation_id: &str) {
+    let Ok(session) = state.client_state.sessions.get(conversation_id).await else {
+        return;
+    };
+    let Some(cwd) = session.cwd else {
+        return;
+    };
+    if let Some(filesystem) = state.git.network_filesystem(&cwd).await {
+        state
+            .client_state
+            .event_bus
+            .emit(
+                "codex-slow-filesystem-warning",
+                json!({
+                    "conversationId": conversation_id,
+                    "path": cwd,
+                    "filesystem": filesystem,
+                }),
+            )
+            .await;
+    }
+}
 
 #[tauri::command]
 pub async fn new_conversation(
     params: NewConversationParams,
     overrides: Option<NewConversationParams>,
+    session_config: Option<SessionConfig>,
+    mcp_servers: Option<HashMap<String, McpServerConfig>>,
+    instructions: Option<String>,
     state: State<'_, CodexState>,
 ) -> Result<NewConversationResponse, String> {
+    let cwd = params
+        .cwd
+        .clone()
+        .or_else(|| overrides.as_ref()?.cwd.clone());
+    // Reject a bad config before codex starts a conversation for it
+    if let Some(config) = &session_config {
+        let model = params
+            .model
+            .as_deref()
+            .or_else(|| overrides.as_ref()?.model.as_deref());
+        config.validate(cwd.as_deref().map(Path::new), model)?;
+    }
+    let params = match &mcp_servers {
+        Some(servers) => with_mcp_servers(params, servers)?,
+        None => params,
+    };
+    let instructions = instructions.filter(|text| !text.trim().is_empty());
+    let artifacts_dir = match session_config
+        .as_ref()
+        .and_then(|config| config.artifacts_dir.as_deref())
+    {
+        Some(dir) => {
+            let cwd = cwd
+                .as_deref()
+                .ok_or("An artifacts directory needs the conversation's working directory")?;
+            Some(prepare_artifacts_dir(Path::new(cwd), dir)?)
+        }
+        None => None,
+    };
+    let params = match developer_instructions(instructions.as_deref(), artifacts_dir.as_deref()) {
+        Some(developer) => with_instructions(params, &developer)?,
+        None => params,
+    };
+    let params = match session_config
+        .as_ref()
+        .and_then(|config| config.max_output_tokens)
+    {
+        Some(tokens) => with_max_output_tokens(params, tokens)?,
+        None => params,
+    };
     let client = codex_client::state::get_client(&state.client_state).await?;
-    client.new_conversation(params, overrides).await
+    let response = client.new_conversation(params, overrides).await?;
+    let conversation_id = response.conversation_id.to_string();
+    if let Some(config) = session_config {
+        state
+            .client_state
+            .sessions
+            .set_config(&conversation_id, config)
+            .await?;
+    }
+    if let Some(servers) = mcp_servers {
+        state
+            .client_state
+            .sessions
+            .update(&conversation_id, |session| session.mcp_servers = servers)
+            .await?;
+    }
+    if instructions.is_some() {
+        state
+            .client_state
+            .sessions
+            .update(&conversation_id, |session| session.instructions = instructions)
+            .await?;
+    }
+    warn_if_network_filesystem(&state, &conversation_id).await;
+    Ok(response)
+}
+
+/// Replace a session's standing instructions for its following turns
+#[tauri::command]
+pub async fn update_session_instructions(
+    session_id: String,
+    instructions: Option<String>,
+    state: State<'_, CodexState>,
+) -> Result<InstructionsChange, String> {
+    codex_client::session::update_session_instructions(
+        &state.client_state,
+        &session_id,
+        instructions,
+    )
+    .await
+}
+
+/// MCP servers from config.toml plus the ones added for this session
+#[tauri::command]
+pub async fn list_session_mcp_servers(
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<Vec<SessionMcpServer>, String> {
+    codex_client::session::list_session_mcp_servers(&state.client_state, &session_id).await
 }
 
 #[tauri::command]
 pub async fn send_user_message(
     params: SendUserMessageParams,
+    window: WebviewWindow,
     state: State<'_, CodexState>,
 ) -> Result<SendUserMessageResponse, String> {
+    let conversation_id = params.conversation_id.to_string();
+    state
+        .observers
+        .ensure_can_send(window.label(), &conversation_id)
+        .await?;
     let client = codex_client::state::get_client(&state.client_state).await?;
     client.send_user_message(params).await
 }
 
+/// Send `message` with the content of `file_paths`, relative to the
+/// session's working directory, in front of it. When any file cannot be
+/// attached nothing is sent; the returned files carry the errors.
+#[tauri::command]
+pub async fn send_message_with_files(
+    session_id: String,
+    message: String,
+    file_paths: Vec<String>,
+    window: WebviewWindow,
+    state: State<'_, CodexState>,
+) -> Result<Vec<AttachedFile>, String> {
+    state
+        .observers
+        .ensure_can_send(window.label(), &session_id)
+        .await?;
+    let context =
+        build_file_context(&state.client_state, &session_id, &message, &file_paths).await?;
+    let Some(text) = context.text else {
+        return Ok(context.files);
+    };
+    let params = serde_json::from_value(json!({
+        "conversationId": session_id,
+        "items": [{ "type": "text", "data": { "text": text } }],
+    }))
+    .map_err(|e| e.to_string())?;
+    let client = codex_client::state::get_client(&state.client_state).await?;
+    client.send_user_message(params).await?;
+    Ok(context.files)
+}
+
+/// Send `message` with the model's response limited to
+/// `max_output_tokens` for this turn only
+#[tauri::command]
+pub async fn send_message_with_options(
+    session_id: String,
+    message: String,
+    max_output_tokens: Option<u32>,
+    window: WebviewWindow,
+    state: State<'_, CodexState>,
+) -> Result<(), String> {
+    state
+        .observers
+        .ensure_can_send(window.label(), &session_id)
+        .await?;
+    codex_client::session::send_message_with_options(
+        state.client_state.clone(),
+        &session_id,
+        &message,
+        max_output_tokens,
+    )
+    .await
+}
+
 #[tauri::command]
 pub async fn turn_start(
     params: TurnStartParams,
+    window: WebviewWindow,
     state: State<'_, CodexState>,
 ) -> Result<TurnStartResponse, String> {
+    state
+        .observers
+        .ensure_can_send(window.label(), &params.thread_id)
+        .await?;
     let client = codex_client::state::get_client(&state.client_state).await?;
     client.turn_start(params).await
 }
@@ -45,8 +233,13 @@ pub async fn turn_start(
 #[tauri::command]
 pub async fn interrupt_conversation(
     params: InterruptConversationParams,
+    window: WebviewWindow,
     state: State<'_, CodexState>,
 ) -> Result<InterruptConversationResponse, String> {
+    state
+        .observers
+        .ensure_can_send(window.label(), &params.conversation_id.to_string())
+        .await?;
     let client = codex_client::state::get_client(&state.client_state).await?;
     client.interrupt_conversation(params).await
 }
@@ -55,8 +248,38 @@ pub async fn interrupt_conversation(
 pub async fn resume_conversation(
     params: ResumeConversationParams,
     overrides: Option<NewConversationParams>,
+    session_config: Option<SessionConfig>,
     state: State<'_, CodexState>,
 ) -> Result<ResumeConversationResponse, String> {
     let client = codex_client::state::get_client(&state.client_state).await?;
-    client.resume_conversation(params, overrides).await
+    let response = client.resume_conversation(params, overrides).await?;
+    let conversation_id = response.conversation_id.to_string();
+    if let Some(config) = session_config {
+        state
+            .client_state
+            .sessions
+            .set_config(&conversation_id, config)
+            .await?;
+    }
+    warn_if_network_filesystem(&state, &conversation_id).await;
+    Ok(response)
+}
+
+/// Postpone the session's idle timeout, e.g. while its window is only in
+/// the background
+#[tauri::command]
+pub async fn keep_alive(
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<(), String> {
+    Ok(state.client_state.sessions.keep_alive(&session_id).await?)
+}
+
+#[tauri::command]
+pub async fn set_session_config(
+    session_id: String,
+    config: SessionConfig,
+    state: State<'_, CodexState>,
+) -> Result<(), String> {
+    Ok(state.client_state.sessions.set_config(&session_id, config).await?)
 }
@@ -0,0 +1,153 @@
+//! Per-window filtering of a session's events
+//!
+//! A window that only needs part of a session's activity, such as a feed
+//! of commands or an observer showing errors, can narrow what it receives
+//! to a set of event types. Windows without a filter get everything.
+
+use crate::codex_commands::state::CodexState;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+use tauri::{State, WebviewWindow};
+
+/// Types a filter may name
+pub const EVENT_TYPES: &[&str] = &[
+    "token",
+    "reasoning",
+    "message",
+    "exec",
+    "patch",
+    "approval",
+    "mcp",
+    "usage",
+    "turn",
+    "error",
+    "other",
+];
+
+/// Event types wanted per (window label, session id)
+#[derive(Default)]
+pub struct EventFilters {
+    filters: RwLock<HashMap<(String, String), HashSet<String>>>,
+}
+
+impl EventFilters {
+    /// Whether `window_label` wants `event` of `session_id`
+    pub fn allows(&self, window_label: &str, session_id: &str, event: &str, data: &Value) -> bool {
+        let filters = self.filters.read().unwrap_or_else(|e| e.into_inner());
+        match filters.get(&(window_label.to_string(), session_id.to_string())) {
+            Some(types) => types.contains(event_type(event, data)),
+            None => true,
+        }
+    }
+
+    /// Drop the filters of a closed window
+    pub fn remove_window(&self, window_label: &str) {
+        let mut filters = self.filters.write().unwrap_or_else(|e| e.into_inner());
+        filters.retain(|(label, _), _| label != window_label);
+    }
+
+    fn set(&self, window_label: &str, session_id: &str, types: HashSet<String>) {
+        let key = (window_label.to_string(), session_id.to_string());
+        let mut filters = self.filters.write().unwrap_or_else(|e| e.into_inner());
+        if types.is_empty() {
+            filters.remove(&key);
+        } else {
+            filters.insert(key, types);
+        }
+    }
+}
+
+/// Filter type of a bus event; `codex:event` payloads are classified by
+/// their protocol message
+pub fn event_type(event: &str, data: &Value) -> &'static str {
+    match event {
+        "codex:exec-command-request" | "codex:apply-patch-request" => return "approval",
+        "codex-error" | "codex:backend-error" => return "error",
+        "codex:event" => {}
+        _ => return "other",
+    }
+    let msg_type = data["params"]["msg"]["type"].as_str().unwrap_or_default();
+    match msg_type {
+        "agent_message_delta" => "token",
+        "agent_message" | "user_message" => "message",
+        "token_count" => "usage",
+        "task_started" | "task_complete" | "turn_aborted" => "turn",
+        "error" | "stream_error" => "error",
+        t if t.starts_with("agent_reasoning") => "reasoning",
+        t if t.starts_with("exec_command") => "exec",
+        t if t.ends_with("approval_request") => "approval",
+        t if t.starts_with("patch_apply") => "patch",
+        t if t.starts_with("mcp_tool_call") => "mcp",
+        _ => "other",
+    }
+}
+
+/// Limit the events of `session_id` this window receives to `types`; an
+/// empty list removes the filter
+#[tauri::command]
+pub async fn set_event_filter(
+    window: WebviewWindow,
+    state: State<'_, CodexState>,
+    session_id: String,
+    types: Vec<String>,
+) -> Result<(), String> {
+    state.client_state.sessions.get(&session_id).await?;
+    if let Some(unknown) = types.iter().find(|t| !EVENT_TYPES.contains(&t.as_str())) {
+        return Err(format!(
+            "Unknown event type {unknown}; expected one of {}",
+            EVENT_TYPES.join(", ")
+        ));
+    }
+    state
+        .event_filters
+        .set(window.label(), &session_id, types.into_iter().collect());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn codex_event(msg_type: &str) -> Value {
+        json!({ "params": { "conversationId": "s", "msg": { "type": msg_type } } })
+    }
+
+    fn msg_type(msg_type: &str) -> &'static str {
+        event_type("codex:event", &codex_event(msg_type))
+    }
+
+    #[test]
+    fn test_event_type() {
+        assert_eq!(msg_type("agent_message_delta"), "token");
+        assert_eq!(msg_type("agent_reasoning_delta"), "reasoning");
+        assert_eq!(msg_type("exec_command_end"), "exec");
+        assert_eq!(msg_type("exec_approval_request"), "approval");
+        assert_eq!(msg_type("stream_error"), "error");
+        assert_eq!(msg_type("session_configured"), "other");
+        assert_eq!(
+            event_type("codex:exec-command-request", &Value::Null),
+            "approval"
+        );
+    }
+
+    #[test]
+    fn test_empty_filter_allows_all() {
+        let filters = EventFilters::default();
+        let data = codex_event("exec_command_begin");
+        filters.set("main", "s", HashSet::from(["error".to_string()]));
+        assert!(!filters.allows("main", "s", "codex:event", &data));
+        assert!(filters.allows("other", "s", "codex:event", &data));
+
+        let approval = json!({ "requestToken": "t", "params": { "conversationId": "s" } });
+        assert!(!filters.allows("main", "s", "codex:exec-command-request", &approval));
+
+        filters.set("main", "s", HashSet::new());
+        assert!(filters.allows("main", "s", "codex:event", &data));
+
+        filters.set("main", "s", HashSet::from(["error".to_string()]));
+        filters.remove_window("main");
+        assert!(filters.allows("main", "s", "codex:event", &data));
+    }
+}
@@ -3,16 +3,54 @@
 //! This module sets up subscriptions on the codex-client EventBus
 //! and forwards them to Tauri's event system.
 
+use crate::codex_commands::event_filters::EventFilters;
+use codex_client::events::event_conversation_id;
 use codex_client::ClientState;
 use std::sync::Arc;
 use tauri::{AppHandle, Manager, Runtime};
 use tauri_remote_ui::EmitterExt;
 
+/// Bus events forwarded to the frontend under the same name
+const BRIDGED_EVENTS: &[&str] = &[
+    "codex:event",
+    "codex:notification",
+    "codex:auth-status",
+    "codex:login-complete",
+    "codex:exec-command-request",
+    "codex:apply-patch-request",
+    "codex:backend-error",
+    "codex-error",
+    "codex-compacted",
+    "codex-regenerate",
+    "codex-session-forked",
+    "codex-protocol-mismatch",
+    "codex-replay-progress",
+    "codex-slow-filesystem-warning",
+    "codex-rate-limited",
+    "codex-session-idle-closed",
+    "codex:process-exited",
+    "codex-low-disk-warning",
+    "codex-input-stalled",
+    "codex-followup-skipped",
+    "codex-out-of-tree-write",
+    "codex-test-result",
+    "codex-sessions-restored",
+    "codex-patch-applying",
+    "codex-patch-applied",
+    "codex-output-truncated",
+];
+
 /// Setup event bridge between codex-client EventBus and Tauri events
 ///
 /// This function subscribes to all codex-client events and forwards them
-/// to Tauri's event system so the frontend can receive them.
-pub fn setup_event_bridge<R: Runtime>(app: AppHandle<R>, client_state: Arc<ClientState>) {
+/// to Tauri's event system so the frontend can receive them. Session events
+/// the window has filtered out with `set_event_filter` are dropped, whichever
+/// bridge they come through.
+pub fn setup_event_bridge<R: Runtime>(
+    app: AppHandle<R>,
+    client_state: Arc<ClientState>,
+    event_filters: Arc<EventFilters>,
+) {
     let event_bus = client_state.event_bus.clone();
 
     // Get the main window for emitting events
@@ -26,170 +64,21 @@ pub fn setup_event_bridge<R: Runtime>(app: AppHandle<R>, client_state: Arc<Clien
     };
     log::info!("[EventBridge] Using window: {}", window.label());
 
-    // Bridge: codex:event
-    {
+    for &event_name in BRIDGED_EVENTS {
         let window = window.clone();
         let event_bus = event_bus.clone();
-        let event_name = "codex:event";
+        let event_filters = event_filters.clone();
         tauri::async_runtime::spawn(async move {
             log::info!("[EventBridge] Setting up subscription for: {}", event_name);
             event_bus
                 .subscribe(
                     event_name,
-                    Arc::new(move |_event, data| {
-                        let window = window.clone();
-                        let data = data.clone();
-                        tauri::async_runtime::spawn(async move {
-                            if let Err(err) =
-                                EmitterExt::emit(&window, event_name, data.clone()).await
-                            {
-                                log::error!("Failed to emit {}: {}", event_name, err);
-                            } else {
-                                log::debug!("[EventBridge] Successfully emitted: {}", event_name);
-                            }
-                        });
-                        Ok(())
-                    }),
-                )
-                .await;
-        });
-    }
-
-    // Bridge: codex:auth-status
-    {
-        let window = window.clone();
-        let event_bus = event_bus.clone();
-        let event_name = "codex:auth-status";
-        tauri::async_runtime::spawn(async move {
-            event_bus
-                .subscribe(
-                    event_name,
-                    Arc::new(move |_, data| {
-                        let window = window.clone();
-                        let data = data.clone();
-                        tauri::async_runtime::spawn(async move {
-                            // EmitterExt handles both remote and native modes
-                            if let Err(err) = EmitterExt::emit(&window, event_name, data).await {
-                                log::error!("Failed to emit {}: {}", event_name, err);
-                            }
-                        });
-                        Ok(())
-                    }),
-                )
-                .await;
-        });
-    }
-
-    // Bridge: codex:login-complete
-    {
-        let window = window.clone();
-        let event_bus = event_bus.clone();
-        let event_name = "codex:login-complete";
-        tauri::async_runtime::spawn(async move {
-            event_bus
-                .subscribe(
-                    event_name,
-                    Arc::new(move |_, data| {
-                        let window = window.clone();
-                        let data = data.clone();
-                        tauri::async_runtime::spawn(async move {
-                            // EmitterExt handles both remote and native modes
-                            if let Err(err) = EmitterExt::emit(&window, event_name, data).await {
-                                log::error!("Failed to emit {}: {}", event_name, err);
-                            }
-                        });
-                        Ok(())
-                    }),
-                )
-                .await;
-        });
-    }
-
-    // Bridge: codex:exec-command-request
-    {
-        let window = window.clone();
-        let event_bus = event_bus.clone();
-        let event_name = "codex:exec-command-request";
-        tauri::async_runtime::spawn(async move {
-            event_bus
-                .subscribe(
-                    event_name,
-                    Arc::new(move |_, data| {
-                        let window = window.clone();
-                        let data = data.clone();
-                        tauri::async_runtime::spawn(async move {
-                            // EmitterExt handles both remote and native modes
-                            if let Err(err) = EmitterExt::emit(&window, event_name, data).await {
-                                log::error!("Failed to emit {}: {}", event_name, err);
-                            }
-                        });
-                        Ok(())
-                    }),
-                )
-                .await;
-        });
-    }
-
-    // Bridge: codex:apply-patch-request
-    {
-        let window = window.clone();
-        let event_bus = event_bus.clone();
-        let event_name = "codex:apply-patch-request";
-        tauri::async_runtime::spawn(async move {
-            event_bus
-                .subscribe(
-                    event_name,
-                    Arc::new(move |_, data| {
-                        let window = window.clone();
-                        let data = data.clone();
-                        tauri::async_runtime::spawn(async move {
-                            // EmitterExt handles both remote and native modes
-                            if let Err(err) = EmitterExt::emit(&window, event_name, data).await {
-                                log::error!("Failed to emit {}: {}", event_name, err);
-                            }
-                        });
-                        Ok(())
-                    }),
-                )
-                .await;
-        });
-    }
-
-    // Bridge: codex:backend-error
-    {
-        let window = window.clone();
-        let event_bus = event_bus.clone();
-        let event_name = "codex:backend-error";
-        tauri::async_runtime::spawn(async move {
-            event_bus
-                .subscribe(
-                    event_name,
-                    Arc::new(move |_, data| {
-                        let window = window.clone();
-                        let data = data.clone();
-                        tauri::async_runtime::spawn(async move {
-                            // EmitterExt handles both remote and native modes
-                            if let Err(err) = EmitterExt::emit(&window, event_name, data).await {
-                                log::error!("Failed to emit {}: {}", event_name, err);
+                    Arc::new(move |event, data| {
+                        if let Some(session_id) = event_conversation_id(&data) {
+                            if !event_filters.allows(window.label(), session_id, event, &data) {
+                                return Ok(());
                             }
-                        });
-                        Ok(())
-                    }),
-                )
-                .await;
-        });
-    }
-
-    // Bridge: codex:process-exited
-    {
-        let window = window.clone();
-        let event_bus = event_bus.clone();
-        let event_name = "codex:process-exited";
-        tauri::async_runtime::spawn(async move {
-            event_bus
-                .subscribe(
-                    event_name,
-                    Arc::new(move |_, data| {
+                        }
                         let window = window.clone();
                         let data = data.clone();
                         tauri::async_runtime::spawn(async move {
@@ -1,27 +1,38 @@
 use tauri::State;
 use crate::codex_commands::state::CodexState;
+use codex_client::client::{OutputBufferConfig, OverflowPolicy};
 use codex_client::codex_app_server_protocol::InitializeResponse;
+use std::time::Duration;
 
 #[tauri::command]
 pub async fn initialize_client(
     state: State<'_, CodexState>,
 ) -> Result<InitializeResponse, String> {
-    // Check if already initialized
-    {
-        let response_guard = state.client_state.initialize_response.lock().await;
-        if let Some(cached_response) = response_guard.as_ref() {
-            return Ok(cached_response.clone());
-        }
-    }
-
-    let client = codex_client::state::get_client(&state.client_state).await?;
-    let response = client.initialize().await?;
+    codex_client::state::initialize_client(&state.client_state).await
+}
 
-    // Cache the response
-    {
-        let mut response_guard = state.client_state.initialize_response.lock().await;
-        *response_guard = Some(response.clone());
-    }
+/// How long codex may take to start before the attempt is abandoned
+#[tauri::command]
+pub async fn set_startup_timeout(
+    state: State<'_, CodexState>,
+    timeout_secs: u64,
+) -> Result<(), String> {
+    codex_client::state::set_startup_timeout(&state.client_state, Duration::from_secs(timeout_secs))
+        .await
+}
 
-    Ok(response)
+/// Lines of codex output buffered while events are being handled, and
+/// whether to drop streaming deltas or stall codex when the buffer is full.
+/// Takes effect when the app-server next starts.
+#[tauri::command]
+pub async fn set_output_buffer(
+    state: State<'_, CodexState>,
+    capacity: usize,
+    policy: Option<OverflowPolicy>,
+) -> Result<(), String> {
+    let config = OutputBufferConfig {
+        capacity,
+        policy: policy.unwrap_or_default(),
+    };
+    codex_client::state::set_output_buffer(&state.client_state, config).await
 }
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use codex_client::McpServerConfig;
+use codex_client::mcp::McpTestResult;
 
 
 #[tauri::command]
@@ -30,3 +31,20 @@ pub async fn set_mcp_server_enabled(
 ) -> Result<(), String> {
     codex_client::mcp::set_mcp_server_enabled(name, enabled).await
 }
+
+/// Launch a stdio MCP server, handshake and list its tools without saving it
+#[tauri::command]
+pub async fn test_mcp_server(
+    name: String,
+    command: String,
+    args: Option<Vec<String>>,
+    env: Option<HashMap<String, String>>,
+) -> Result<McpTestResult, String> {
+    Ok(codex_client::mcp::test_mcp_server(
+        &name,
+        &command,
+        &args.unwrap_or_default(),
+        env.as_ref(),
+    )
+    .await)
+}
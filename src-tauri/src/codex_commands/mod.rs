@@ -5,6 +5,7 @@
 
 pub mod state;
 pub mod events;
+pub mod event_filters;
 pub mod accounts;
 pub mod check;
 pub mod config;
@@ -12,8 +13,14 @@ pub mod conversation;
 pub mod initialize;
 pub mod listeners;
 pub mod mcp;
+pub mod observers;
+pub mod oneshot;
+pub mod preflight;
 pub mod reviews;
 pub mod session_files;
+pub mod sessions;
+pub mod templates;
+pub mod updates;
 
 pub use state::CodexState;
 pub use events::setup_event_bridge;
@@ -21,6 +28,7 @@ pub use events::setup_event_bridge;
 // Re-export all commands for tauri::generate_handler!
 pub use accounts::*;
 // pub use check::*; // check commands accessed via check:: prefix in lib.rs
+pub use event_filters::*;
 pub use config::profile::*;
 pub use config::project::*;
 pub use config::provider::*;
@@ -28,5 +36,11 @@ pub use conversation::*;
 pub use initialize::*;
 pub use listeners::*;
 pub use mcp::*;
+pub use observers::*;
+pub use oneshot::*;
+pub use preflight::*;
 pub use reviews::*;
 pub use session_files::*;
+pub use sessions::*;
+pub use templates::*;
+pub use updates::*;
@@ -0,0 +1,158 @@
+//! Read-only observers of a running session
+//!
+//! An observer window receives the events of one session as
+//! `codex:observer-event` but may not send input to it, which makes it
+//! suitable for pairing or screen sharing.
+
+use crate::codex_commands::state::CodexState;
+use codex_client::events::event_conversation_id;
+use codex_client::SubscriptionId;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::{Emitter, EventTarget, State, WebviewWindow};
+use tokio::sync::Mutex;
+
+/// Session events forwarded to observers
+const OBSERVED_EVENTS: &[&str] = &[
+    "codex:event",
+    "codex:notification",
+    "codex-error",
+    "codex:exec-command-request",
+    "codex:apply-patch-request",
+];
+
+/// Observer subscriptions keyed by (window label, session id)
+#[derive(Default)]
+pub struct ObserverRegistry {
+    observers: Mutex<HashMap<(String, String), Vec<SubscriptionId>>>,
+}
+
+impl ObserverRegistry {
+    /// Whether `window_label` only observes `session_id`
+    pub async fn is_observer(&self, window_label: &str, session_id: &str) -> bool {
+        self.observers
+            .lock()
+            .await
+            .contains_key(&(window_label.to_string(), session_id.to_string()))
+    }
+
+    /// Reject input from a window attached to the session as an observer
+    pub async fn ensure_can_send(&self, window_label: &str, session_id: &str) -> Result<(), String> {
+        if self.is_observer(window_label, session_id).await {
+            return Err(format!(
+                "Window {window_label} is observing session {session_id} and cannot send input"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reject a window that observes any session from starting one of its
+    /// own, e.g. by replaying a transcript
+    pub async fn ensure_not_observing(&self, window_label: &str) -> Result<(), String> {
+        let observing = self
+            .observers
+            .lock()
+            .await
+            .keys()
+            .any(|(label, _)| label == window_label);
+        if observing {
+            return Err(format!(
+                "Window {window_label} is an observer and cannot start sessions"
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[tauri::command]
+pub async fn attach_observer(
+    window: WebviewWindow,
+    state: State<'_, CodexState>,
+    session_id: String,
+) -> Result<(), String> {
+    state.client_state.sessions.get(&session_id).await?;
+
+    let key = (window.label().to_string(), session_id.clone());
+    let mut observers = state.observers.observers.lock().await;
+    if observers.contains_key(&key) {
+        return Ok(());
+    }
+
+    let event_bus = &state.client_state.event_bus;
+    let mut subscriptions = Vec::with_capacity(OBSERVED_EVENTS.len());
+    for event_name in OBSERVED_EVENTS {
+        let window = window.clone();
+        let session_id = session_id.clone();
+        let event_filters = state.event_filters.clone();
+        let id = event_bus
+            .subscribe(
+                *event_name,
+                Arc::new(move |event, data| {
+                    if event_conversation_id(&data) != Some(session_id.as_str()) {
+                        return Ok(());
+                    }
+                    if !event_filters.allows(window.label(), &session_id, event, &data) {
+                        return Ok(());
+                    }
+                    let payload = json!({
+                        "sessionId": session_id,
+                        "event": event,
+                        "data": data,
+                    });
+                    window
+                        .emit_to(
+                            EventTarget::webview_window(window.label()),
+                            "codex:observer-event",
+                            payload,
+                        )
+                        .map_err(|err| err.to_string())
+                }),
+            )
+            .await;
+        subscriptions.push(id);
+    }
+    observers.insert(key, subscriptions);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn detach_observer(
+    window: WebviewWindow,
+    state: State<'_, CodexState>,
+    session_id: String,
+) -> Result<(), String> {
+    let key = (window.label().to_string(), session_id);
+    let Some(subscriptions) = state.observers.observers.lock().await.remove(&key) else {
+        return Ok(());
+    };
+    for id in subscriptions {
+        state.client_state.event_bus.remove_subscription(id).await;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_observer_cannot_send() {
+        let registry = ObserverRegistry::default();
+        tauri::async_runtime::block_on(async {
+            registry
+                .observers
+                .lock()
+                .await
+                .insert(("viewer".to_string(), "s".to_string()), Vec::new());
+
+            let err = registry.ensure_can_send("viewer", "s").await.unwrap_err();
+            assert!(err.contains("observing session s"));
+            assert!(registry.ensure_can_send("viewer", "other").await.is_ok());
+            assert!(registry.ensure_can_send("main", "s").await.is_ok());
+
+            assert!(registry.ensure_not_observing("viewer").await.is_err());
+            assert!(registry.ensure_not_observing("main").await.is_ok());
+        });
+    }
+}
@@ -0,0 +1,8 @@
+use codex_client::services::oneshot::{self, OneshotConfig, OneshotResult};
+
+/// Answer a single prompt with a throwaway codex process; no session is
+/// created, so nothing shows up in the session list
+#[tauri::command]
+pub async fn codex_oneshot(config: OneshotConfig, prompt: String) -> Result<OneshotResult, String> {
+    oneshot::codex_oneshot(&config, &prompt).await
+}
@@ -0,0 +1,219 @@
+//! Checklist of everything a new session depends on, shown before the UI
+//! enables starting one
+
+use crate::codex_commands::state::CodexState;
+use crate::filesystem::git_runner::run_git;
+use crate::filesystem::git_worktree::find_git_root;
+use codex_client::config::validate::{ConfigIssue, SessionStartConfig};
+use codex_client::services::codex::{check_codex_auth, check_codex_version, AuthStatus};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckStatus {
+    Pass,
+    /// A session can start, but some features will not work
+    Warn,
+    /// A session cannot start
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreflightCheck {
+    pub id: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// What the user can do about a warning or failure
+    pub remediation: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreflightReport {
+    /// No check failed
+    pub ready: bool,
+    pub checks: Vec<PreflightCheck>,
+}
+
+fn check(
+    id: &'static str,
+    status: CheckStatus,
+    detail: impl Into<String>,
+    remediation: Option<&str>,
+) -> PreflightCheck {
+    PreflightCheck {
+        id,
+        status,
+        detail: detail.into(),
+        remediation: remediation.map(String::from),
+    }
+}
+
+async fn check_working_dir(
+    state: &CodexState,
+    working_dir: &Path,
+    git_available: bool,
+) -> Vec<PreflightCheck> {
+    if !working_dir.is_dir() {
+        return vec![check(
+            "workingDir",
+            CheckStatus::Fail,
+            format!("{} is not a directory", working_dir.display()),
+            Some("Choose an existing project directory"),
+        )];
+    }
+    let mut checks = vec![check(
+        "workingDir",
+        CheckStatus::Pass,
+        working_dir.display().to_string(),
+        None,
+    )];
+
+    let repo = if !git_available {
+        check(
+            "gitRepository",
+            CheckStatus::Warn,
+            "Skipped because git is not available",
+            None,
+        )
+    } else {
+        match find_git_root(&state.git, Some(working_dir)).await {
+            Some(root) => check("gitRepository", CheckStatus::Pass, root.display().to_string(), None),
+            None => check(
+                "gitRepository",
+                CheckStatus::Warn,
+                "Not a git repository; diffs and worktrees are unavailable",
+                Some("Run `git init` in the project directory"),
+            ),
+        }
+    };
+    checks.push(repo);
+    checks
+}
+
+fn auth_check(auth: Result<AuthStatus, String>) -> PreflightCheck {
+    match auth {
+        Ok(AuthStatus::Authenticated { method, account }) => {
+            let detail = match account {
+                Some(account) => format!("Logged in with {method} ({account})"),
+                None => format!("Logged in with {method}"),
+            };
+            check("auth", CheckStatus::Pass, detail, None)
+        }
+        Ok(AuthStatus::NotAuthenticated) => check(
+            "auth",
+            CheckStatus::Fail,
+            "Not logged in to codex",
+            Some("Sign in, or run `codex login` in a terminal"),
+        ),
+        Ok(AuthStatus::Unknown { reason }) => check(
+            "auth",
+            CheckStatus::Warn,
+            reason,
+            Some("Update codex to check the login state before starting"),
+        ),
+        Err(err) => check("auth", CheckStatus::Warn, err, None),
+    }
+}
+
+async fn model_check(state: &CodexState) -> PreflightCheck {
+    let models = match codex_client::state::get_client(&state.client_state).await {
+        Ok(client) => client.list_models().await,
+        Err(err) => Err(err),
+    };
+    match models {
+        Ok(Some(models)) => {
+            let count = models["data"].as_array().map(Vec::len).unwrap_or_default();
+            if count == 0 {
+                check(
+                    "models",
+                    CheckStatus::Fail,
+                    "The model provider offers no models",
+                    Some("Check the model provider in ~/.codex/config.toml"),
+                )
+            } else {
+                check("models", CheckStatus::Pass, format!("{count} models available"), None)
+            }
+        }
+        Ok(None) => check(
+            "models",
+            CheckStatus::Warn,
+            "This codex version cannot list models",
+            None,
+        ),
+        Err(err) => check(
+            "models",
+            CheckStatus::Fail,
+            err,
+            Some("Check the model provider in ~/.codex/config.toml"),
+        ),
+    }
+}
+
+/// Check the codex binary, git, the working directory, codex login and the
+/// available models
+#[tauri::command]
+pub async fn preflight_check(
+    working_dir: String,
+    state: State<'_, CodexState>,
+) -> Result<PreflightReport, String> {
+    let mut checks = Vec::new();
+
+    let codex_found = match check_codex_version().await {
+        Ok(version) => {
+            checks.push(check("codex", CheckStatus::Pass, version, None));
+            true
+        }
+        Err(err) => {
+            checks.push(check(
+                "codex",
+                CheckStatus::Fail,
+                err,
+                Some("Install codex (`npm install -g @openai/codex`) or add it to PATH"),
+            ));
+            false
+        }
+    };
+
+    let home = dirs::home_dir().unwrap_or_else(std::env::temp_dir);
+    let git_available = match run_git(&state.git, &home, &["--version"]).await {
+        Ok(output) => {
+            checks.push(check("git", CheckStatus::Pass, output.stdout.trim(), None));
+            true
+        }
+        Err(err) => {
+            checks.push(check(
+                "git",
+                CheckStatus::Warn,
+                err.to_string(),
+                Some("Install git and make sure it is on PATH"),
+            ));
+            false
+        }
+    };
+
+    checks.extend(check_working_dir(&state, &PathBuf::from(&working_dir), git_available).await);
+
+    // Both need a working codex binary; a missing one is already reported
+    if codex_found {
+        checks.push(auth_check(check_codex_auth().await));
+        checks.push(model_check(&state).await);
+    }
+
+    Ok(PreflightReport {
+        ready: checks.iter().all(|c| c.status != CheckStatus::Fail),
+        checks,
+    })
+}
+
+/// Errors and warnings for the new-session form, each naming its field
+#[tauri::command]
+pub async fn validate_config(
+    config: SessionStartConfig,
+    state: State<'_, CodexState>,
+) -> Result<Vec<ConfigIssue>, String> {
+    Ok(codex_client::config::validate::validate_config(&state.client_state, &config).await)
+}
@@ -1,6 +1,8 @@
-use tauri::State;
+use tauri::{State, WebviewWindow};
 use crate::codex_commands::state::CodexState;
+use codex_client::client::ExecApprovalDetails;
 use codex_client::codex_protocol::protocol::ReviewDecision;
+use serde::Serialize;
 
 fn parse_review_decision(decision: &str) -> Result<ReviewDecision, String> {
     let normalized = decision.trim().to_lowercase().replace('-', "_");
@@ -17,9 +19,15 @@ fn parse_review_decision(decision: &str) -> Result<ReviewDecision, String> {
 pub async fn respond_exec_command_request(
     request_token: String,
     decision: String,
+    window: WebviewWindow,
     state: State<'_, CodexState>,
 ) -> Result<(), String> {
     let client = codex_client::state::get_client(&state.client_state).await?;
+    let session_id = client.approval_conversation(&request_token).await?;
+    state
+        .observers
+        .ensure_can_send(window.label(), &session_id)
+        .await?;
     let parsed = parse_review_decision(&decision)?;
     client.respond_exec_command_request(&request_token, parsed).await
 }
@@ -28,9 +36,59 @@ pub async fn respond_exec_command_request(
 pub async fn respond_apply_patch_request(
     request_token: String,
     decision: String,
+    window: WebviewWindow,
     state: State<'_, CodexState>,
 ) -> Result<(), String> {
     let client = codex_client::state::get_client(&state.client_state).await?;
+    let session_id = client.approval_conversation(&request_token).await?;
+    state
+        .observers
+        .ensure_can_send(window.label(), &session_id)
+        .await?;
     let parsed = parse_review_decision(&decision)?;
     client.respond_apply_patch_request(&request_token, parsed).await
 }
+
+/// Re-fetch the command, cwd and reason of a pending exec approval
+#[tauri::command]
+pub async fn get_approval_details(
+    session_id: String,
+    approval_id: String,
+    state: State<'_, CodexState>,
+) -> Result<ExecApprovalDetails, String> {
+    let client = codex_client::state::get_client(&state.client_state).await?;
+    Ok(client.get_approval_details(&session_id, &approval_id).await?)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContinueTurnResult {
+    /// Number of pending approvals granted right away
+    pub approved: usize,
+    /// Semantics in effect. Currently always `autoApprove`: the app-server
+    /// protocol Codexia targets has no continue request, so the remaining
+    /// requests of the turn are approved without prompting.
+    pub mode: &'static str,
+    pub codex_version: Option<String>,
+}
+
+/// Let a turn paused on approvals proceed with everything it has planned
+#[tauri::command]
+pub async fn continue_turn(
+    session_id: String,
+    window: WebviewWindow,
+    state: State<'_, CodexState>,
+) -> Result<ContinueTurnResult, String> {
+    state
+        .observers
+        .ensure_can_send(window.label(), &session_id)
+        .await?;
+    let client = codex_client::state::get_client(&state.client_state).await?;
+    let approved = client.continue_turn(&session_id).await?;
+    let codex_version = codex_client::services::codex::check_codex_version().await.ok();
+    Ok(ContinueTurnResult {
+        approved,
+        mode: "autoApprove",
+        codex_version,
+    })
+}
@@ -3,9 +3,11 @@ pub mod scanner;
 pub mod update;
 pub mod usage;
 pub mod notes;
+pub mod transcript;
 
 pub use cache::*;
 pub use scanner::*;
 pub use update::*;
 pub use usage::*;
 pub use notes::*;
+pub use transcript::*;
@@ -1,6 +1,12 @@
+use codex_client::session_files::RolloutInfo;
 use serde_json::Value;
 
 #[tauri::command]
 pub async fn scan_projects() -> Result<Vec<Value>, String> {
     codex_client::session_files::scanner::scan_projects().await
 }
+
+#[tauri::command]
+pub async fn list_codex_rollouts() -> Result<Vec<RolloutInfo>, String> {
+    codex_client::session_files::list_codex_rollouts().await
+}
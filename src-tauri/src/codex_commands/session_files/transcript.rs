@@ -0,0 +1,12 @@
+use codex_client::session_files::TranscriptPage;
+
+/// A page of a rollout transcript; without `offset` the most recent
+/// `limit` entries are returned
+#[tauri::command]
+pub async fn load_session_transcript(
+    path: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<TranscriptPage, String> {
+    codex_client::session_files::load_transcript_page(path, offset, limit)
+}
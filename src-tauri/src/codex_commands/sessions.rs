@@ -0,0 +1,509 @@
+use tauri::{State, WebviewWindow};
+use tauri_remote_ui::EmitterExt;
+use crate::codex_commands::state::CodexState;
+use crate::filesystem::session_git::diff::{worktree_diff, IgnoreWhitespace};
+use crate::filesystem::session_git::generated::worktree_summary_entries;
+use codex_client::session::{
+    CloseAllResult, CloseSessionResult, CompactResult, ExecOutput, ForkResult, InterruptAllResult,
+    InterruptAndCloseResult, LatencyStats, LogLevelChange, LogLine, LogRotation,
+    ProposedCommand, RateLimitStatus, RecoverResult, ReplayResult, RuntimeConfig, SandboxPolicy,
+    SequencedEvent, SessionBundle, SessionError, SessionMetadata, SuggestedAction, ToolInfo,
+    TurnTiming,
+};
+use codex_client::client::ProcessInfo;
+use codex_client::utils::filesystem::DiskStatus;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[tauri::command]
+pub async fn archive_session(
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<SessionMetadata, String> {
+    codex_client::session::archive_session(&state.client_state, &session_id).await
+}
+
+#[tauri::command]
+pub async fn get_session_metadata(
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<SessionMetadata, String> {
+    Ok(state.client_state.sessions.get(&session_id).await?.metadata())
+}
+
+/// Events emitted after `seq`, for a window that detected a gap or
+/// reattached; fails when they are too old and the transcript must be
+/// reloaded instead
+#[tauri::command]
+pub async fn get_session_events_since(
+    session_id: String,
+    seq: u64,
+    state: State<'_, CodexState>,
+) -> Result<Vec<SequencedEvent>, String> {
+    Ok(state
+        .client_state
+        .sessions
+        .events_since(&session_id, seq)
+        .await?)
+}
+
+/// Built-in and MCP tools codex can call in the session
+#[tauri::command]
+pub async fn get_session_tools(
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<Vec<ToolInfo>, String> {
+    codex_client::session::get_session_tools(&state.client_state, &session_id).await
+}
+
+/// Space on the disk holding the session's working directory
+#[tauri::command]
+pub async fn get_working_dir_disk_status(
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<DiskStatus, String> {
+    codex_client::session::working_dir_disk_status(&state.client_state, &session_id).await
+}
+
+#[tauri::command]
+pub async fn list_archived_sessions(
+    state: State<'_, CodexState>,
+) -> Result<Vec<SessionMetadata>, String> {
+    Ok(codex_client::session::list_archived_sessions(&state.client_state).await)
+}
+
+#[tauri::command]
+pub async fn close_session(
+    session_id: String,
+    grace_ms: Option<u64>,
+    state: State<'_, CodexState>,
+) -> Result<CloseSessionResult, String> {
+    codex_client::session::close_session(
+        &state.client_state,
+        &session_id,
+        grace_ms.map(Duration::from_millis),
+    )
+    .await
+}
+
+/// Interrupt the session's running turn and close it in one step
+#[tauri::command]
+pub async fn interrupt_and_close(
+    session_id: String,
+    grace_ms: Option<u64>,
+    state: State<'_, CodexState>,
+) -> Result<InterruptAndCloseResult, String> {
+    codex_client::session::interrupt_and_close(
+        &state.client_state,
+        &session_id,
+        grace_ms.map(Duration::from_millis),
+    )
+    .await
+}
+
+/// Interrupt a session codex stopped responding to and reload it from its
+/// transcript, leaving the other sessions running
+#[tauri::command]
+pub async fn recover_session(
+    session_id: String,
+    window: WebviewWindow,
+    state: State<'_, CodexState>,
+) -> Result<RecoverResult, String> {
+    state
+        .observers
+        .ensure_can_send(window.label(), &session_id)
+        .await?;
+    codex_client::session::recover_session(&state.client_state, &session_id).await
+}
+
+/// Close every unpinned session, or every session with `force`
+#[tauri::command]
+pub async fn close_all_sessions(
+    force: Option<bool>,
+    grace_ms: Option<u64>,
+    state: State<'_, CodexState>,
+) -> Result<CloseAllResult, String> {
+    codex_client::session::close_all_sessions(
+        &state.client_state,
+        force.unwrap_or(false),
+        grace_ms.map(Duration::from_millis),
+    )
+    .await
+}
+
+/// Keep the session open through `close_all_sessions`
+#[tauri::command]
+pub async fn set_session_pinned(
+    session_id: String,
+    pinned: bool,
+    state: State<'_, CodexState>,
+) -> Result<(), String> {
+    Ok(state
+        .client_state
+        .sessions
+        .update(&session_id, |session| session.pinned = pinned)
+        .await?)
+}
+
+/// Interrupt every session that is running a turn
+#[tauri::command]
+pub async fn interrupt_all_sessions(
+    state: State<'_, CodexState>,
+) -> Result<InterruptAllResult, String> {
+    codex_client::session::interrupt_all_sessions(&state.client_state).await
+}
+
+#[tauri::command]
+pub async fn get_session_process_info(
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<ProcessInfo, String> {
+    codex_client::session::get_session_process_info(&state.client_state, &session_id).await
+}
+
+/// Model, sandbox, approval policy, instructions and MCP servers the
+/// session is currently running with
+#[tauri::command]
+pub async fn get_runtime_session_config(
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<RuntimeConfig, String> {
+    codex_client::session::get_runtime_session_config(&state.client_state, &session_id).await
+}
+
+/// Roots the session's codex may write to and whether it has network
+/// access under its sandbox mode
+#[tauri::command]
+pub async fn get_sandbox_policy(
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<SandboxPolicy, String> {
+    codex_client::session::get_sandbox_policy(&state.client_state, &session_id).await
+}
+
+#[tauri::command]
+pub async fn compact_session(
+    session_id: String,
+    window: WebviewWindow,
+    state: State<'_, CodexState>,
+) -> Result<CompactResult, String> {
+    state
+        .observers
+        .ensure_can_send(window.label(), &session_id)
+        .await?;
+    codex_client::session::compact_session(&state.client_state, &session_id).await
+}
+
+/// Branch a session into a new one with the same working directory, options
+/// and transcript; the new conversation id is assigned by codex
+#[tauri::command]
+pub async fn fork_session(
+    source_session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<ForkResult, String> {
+    codex_client::session::fork_session(&state.client_state, &source_session_id).await
+}
+
+/// Replay the user turns of a recorded transcript into a new session,
+/// reporting each turn as `codex-replay-progress`. The new session's id is
+/// assigned by codex and returned in the result.
+#[tauri::command]
+pub async fn replay_transcript(
+    transcript_path: String,
+    auto_approve: bool,
+    window: WebviewWindow,
+    state: State<'_, CodexState>,
+) -> Result<ReplayResult, String> {
+    state.observers.ensure_not_observing(window.label()).await?;
+    codex_client::session::replay_transcript(&state.client_state, &transcript_path, auto_approve)
+        .await
+}
+
+/// Session metadata, redacted config, transcript and the working tree diff
+/// in one serializable bundle
+#[tauri::command]
+pub async fn export_session_bundle(
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<SessionBundle, String> {
+    let mut bundle =
+        codex_client::session::export_session_bundle(&state.client_state, &session_id).await?;
+    if let Some(cwd) = bundle.session.cwd.clone() {
+        // Sessions outside a git repository simply have no diff
+        let pathspecs = &bundle.session.config.allowed_paths;
+        bundle.final_diff = worktree_diff(&state.git, &cwd, None, IgnoreWhitespace::None, pathspecs)
+            .await
+            .ok();
+    }
+    Ok(bundle)
+}
+
+/// Send the requesting window a `codex-session-snapshot` event with the
+/// session's transcript, pending approvals, busy state, worktree summary and
+/// token usage, so it can rebuild its view after a reload
+#[tauri::command]
+pub async fn resync_session(
+    window: WebviewWindow,
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<(), String> {
+    let mut snapshot =
+        codex_client::session::session_snapshot(&state.client_state, &session_id).await?;
+    // Sessions outside a git repository simply have no summary
+    if let Ok(entries) = worktree_summary_entries(&state, &session_id).await {
+        snapshot.worktree = serde_json::to_value(entries).ok();
+    }
+    // EmitterExt also reaches remote UI clients
+    EmitterExt::emit(&window, "codex-session-snapshot", snapshot)
+        .await
+        .map_err(|err| err.to_string())
+}
+
+/// Raise or lower codex's log level for a session; `applied` is false when
+/// it only takes effect after the app-server restarts
+#[tauri::command]
+pub async fn set_session_log_level(
+    session_id: String,
+    level: String,
+    state: State<'_, CodexState>,
+) -> Result<LogLevelChange, String> {
+    codex_client::session::set_session_log_level(&state.client_state, &session_id, &level).await
+}
+
+/// How long after the last event a session's transcript is written to disk
+#[tauri::command]
+pub async fn set_transcript_flush_interval(
+    interval_ms: u64,
+    state: State<'_, CodexState>,
+) -> Result<(), String> {
+    if interval_ms == 0 {
+        return Err("Flush interval must be at least one millisecond".to_string());
+    }
+    state
+        .client_state
+        .sessions
+        .autosave()
+        .set_interval(Duration::from_millis(interval_ms));
+    Ok(())
+}
+
+/// stdout, stderr and exit code of the last command codex ran
+#[tauri::command]
+pub async fn get_last_exec_output(
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<ExecOutput, String> {
+    Ok(state
+        .client_state
+        .sessions
+        .last_exec_output(&session_id)
+        .await?)
+}
+
+/// Most recent error codex reported for the session, for marking sessions
+/// that hit a problem
+#[tauri::command]
+pub async fn get_session_last_error(
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<Option<SessionError>, String> {
+    Ok(state.client_state.sessions.last_error(&session_id).await?)
+}
+
+/// Dismiss the session's last error; it is kept, marked as cleared
+#[tauri::command]
+pub async fn clear_session_error(
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<(), String> {
+    Ok(state.client_state.sessions.clear_error(&session_id).await?)
+}
+
+/// Files codex wrote, and directories it ran commands in, outside the
+/// session's working directory
+#[tauri::command]
+pub async fn get_out_of_tree_changes(
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<Vec<String>, String> {
+    Ok(state
+        .client_state
+        .sessions
+        .out_of_tree_changes(&session_id)
+        .await?)
+}
+
+/// Every command codex proposed in the current turn and what became of it
+#[tauri::command]
+pub async fn get_proposed_commands(
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<Vec<ProposedCommand>, String> {
+    Ok(state
+        .client_state
+        .sessions
+        .proposed_commands(&session_id)
+        .await?)
+}
+
+/// Keep proposed commands across turns instead of starting each turn empty
+#[tauri::command]
+pub async fn set_retain_command_history(
+    retain: bool,
+    state: State<'_, CodexState>,
+) -> Result<(), String> {
+    state
+        .client_state
+        .sessions
+        .command_proposals()
+        .set_retain_history(retain);
+    Ok(())
+}
+
+/// Time to first token, total time and time spent running commands of the
+/// session's recent turns, with the model each turn used
+#[tauri::command]
+pub async fn get_turn_timings(
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<Vec<TurnTiming>, String> {
+    Ok(state.client_state.sessions.turn_timings(&session_id).await?)
+}
+
+/// p50, p95 and max time to first token of the session's turns, one entry
+/// per model
+#[tauri::command]
+pub async fn get_latency_stats(
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<Vec<LatencyStats>, String> {
+    Ok(state.client_state.sessions.latency_stats(&session_id).await?)
+}
+
+/// Usage left in codex's rate-limit windows, as last reported by the session
+#[tauri::command]
+pub async fn get_rate_limit_status(
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<RateLimitStatus, String> {
+    Ok(state
+        .client_state
+        .sessions
+        .rate_limit_status(&session_id)
+        .await?)
+}
+
+#[tauri::command]
+pub async fn get_session_logs(
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<Vec<LogLine>, String> {
+    Ok(state.client_state.sessions.logs(&session_id).await?)
+}
+
+/// What codex printed while starting the session's app-server
+#[tauri::command]
+pub async fn get_session_startup_log(
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<Vec<LogLine>, String> {
+    codex_client::session::get_session_startup_log(&state.client_state, &session_id).await
+}
+
+/// File the session's codex log lines are written to, for opening them
+/// outside the app
+#[tauri::command]
+pub async fn get_session_log_path(
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<PathBuf, String> {
+    codex_client::session::get_session_log_path(&state.client_state, &session_id).await
+}
+
+/// Size at which session log files rotate and how many rotated files are
+/// kept
+#[tauri::command]
+pub async fn set_session_log_rotation(
+    rotation: LogRotation,
+    state: State<'_, CodexState>,
+) -> Result<(), String> {
+    if rotation.max_bytes == 0 {
+        return Err("Log files must be allowed at least one byte".to_string());
+    }
+    state.client_state.sessions.log_files().set_rotation(rotation);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn regenerate_last_turn(
+    session_id: String,
+    window: WebviewWindow,
+    state: State<'_, CodexState>,
+) -> Result<(), String> {
+    state
+        .observers
+        .ensure_can_send(window.label(), &session_id)
+        .await?;
+    codex_client::session::regenerate_last_turn(&state.client_state, &session_id).await
+}
+
+/// Ask codex to run the project's tests with `command`, the session's
+/// configured test command or one codex finds; the outcome is emitted as
+/// `codex-test-result`
+#[tauri::command]
+pub async fn run_project_tests(
+    session_id: String,
+    command: Option<String>,
+    window: WebviewWindow,
+    state: State<'_, CodexState>,
+) -> Result<(), String> {
+    state
+        .observers
+        .ensure_can_send(window.label(), &session_id)
+        .await?;
+    codex_client::session::run_project_tests(state.client_state.clone(), &session_id, command).await
+}
+
+/// Queue `message` to be sent once the session's current turn completes,
+/// or send it now if none is running; returns the follow-ups still queued
+#[tauri::command]
+pub async fn enqueue_followup(
+    session_id: String,
+    message: String,
+    window: WebviewWindow,
+    state: State<'_, CodexState>,
+) -> Result<Vec<String>, String> {
+    state
+        .observers
+        .ensure_can_send(window.label(), &session_id)
+        .await?;
+    codex_client::session::enqueue_followup(&state.client_state, &session_id, message).await
+}
+
+/// Follow-ups codex suggested after the session's last turn; choosing one
+/// sends its prompt with `send_user_message`
+#[tauri::command]
+pub async fn get_suggested_actions(
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<Vec<SuggestedAction>, String> {
+    codex_client::session::get_suggested_actions(&state.client_state, &session_id).await
+}
+
+#[tauri::command]
+pub async fn get_followups(
+    session_id: String,
+    state: State<'_, CodexState>,
+) -> Result<Vec<String>, String> {
+    codex_client::session::get_followups(&state.client_state, &session_id).await
+}
+
+/// Drop the queued follow-up at `index`, 0 being the next one to run
+#[tauri::command]
+pub async fn cancel_followup(
+    session_id: String,
+    index: usize,
+    state: State<'_, CodexState>,
+) -> Result<Vec<String>, String> {
+    codex_client::session::cancel_followup(&state.client_state, &session_id, index).await
+}
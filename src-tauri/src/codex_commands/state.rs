@@ -2,18 +2,39 @@
 //!
 //! Wraps ClientState in a Tauri-managed state container
 
+use crate::codex_commands::event_filters::EventFilters;
+use crate::codex_commands::observers::ObserverRegistry;
+use crate::codex_commands::updates::UpdateCheckCache;
+use crate::filesystem::git_runner::GitRunner;
+use crate::filesystem::session_git::generated::GeneratedFileRulesStore;
+use crate::filesystem::session_git::summary::WorktreeSummaryCache;
 use codex_client::ClientState;
 use std::sync::Arc;
 
 /// Tauri-managed state wrapper
 pub struct CodexState {
     pub client_state: Arc<ClientState>,
+    /// Settings shared by the git subprocesses run on behalf of sessions
+    pub git: Arc<GitRunner>,
+    /// Windows attached read-only to a session
+    pub observers: ObserverRegistry,
+    /// Event types each window wants per session
+    pub event_filters: Arc<EventFilters>,
+    pub(crate) worktree_summaries: WorktreeSummaryCache,
+    pub(crate) generated_file_rules: GeneratedFileRulesStore,
+    pub(crate) codex_update: UpdateCheckCache,
 }
 
 impl CodexState {
     pub fn new() -> Self {
         Self {
             client_state: Arc::new(ClientState::new()),
+            git: Arc::new(GitRunner::new()),
+            observers: ObserverRegistry::default(),
+            event_filters: Arc::new(EventFilters::default()),
+            worktree_summaries: WorktreeSummaryCache::default(),
+            generated_file_rules: GeneratedFileRulesStore::default(),
+            codex_update: UpdateCheckCache::default(),
         }
     }
 }
@@ -0,0 +1,32 @@
+use tauri::{State, WebviewWindow};
+use crate::codex_commands::state::CodexState;
+use codex_client::services::prompt_templates::{self, PromptTemplate};
+use std::collections::HashMap;
+
+#[tauri::command]
+pub async fn list_prompt_templates() -> Result<Vec<PromptTemplate>, String> {
+    prompt_templates::list_prompt_templates()
+}
+
+/// Create a template, or replace the one with the same name
+#[tauri::command]
+pub async fn save_prompt_template(name: String, body: String) -> Result<PromptTemplate, String> {
+    prompt_templates::save_prompt_template(&name, &body)
+}
+
+/// Fill a template's `{{variable}}` placeholders and send it as a user
+/// message; returns the text that was sent
+#[tauri::command]
+pub async fn send_template(
+    session_id: String,
+    name: String,
+    variables: HashMap<String, String>,
+    window: WebviewWindow,
+    state: State<'_, CodexState>,
+) -> Result<String, String> {
+    state
+        .observers
+        .ensure_can_send(window.label(), &session_id)
+        .await?;
+    prompt_templates::send_template(&state.client_state, &session_id, &name, &variables).await
+}
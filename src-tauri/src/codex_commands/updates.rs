@@ -0,0 +1,209 @@
+//! Whether a newer codex has been released
+//!
+//! The latest version is read from where the installed binary came from:
+//! the npm registry for npm and bun installs, Homebrew's cask or formula API
+//! for brew installs and GitHub releases otherwise. Nothing is installed; the UI only shows a
+//! badge.
+
+use crate::codex_commands::state::CodexState;
+use codex_client::utils::codex_discovery::discover_codex_command;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tauri::State;
+use tokio::sync::Mutex;
+
+/// How long a successful check is reused
+const UPDATE_CHECK_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum UpdateStatus {
+    UpToDate,
+    UpdateAvailable {
+        latest: String,
+        current: String,
+    },
+    /// Offline, or a version could not be read
+    Unknown {
+        reason: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InstallMethod {
+    Npm,
+    /// `brew install --cask codex`
+    HomebrewCask,
+    /// `brew install codex`
+    HomebrewFormula,
+    GitHub,
+}
+
+impl InstallMethod {
+    fn detect(binary: &Path) -> Self {
+        let binary = binary
+            .canonicalize()
+            .unwrap_or_else(|_| binary.to_path_buf());
+        let path = binary.to_string_lossy();
+        if path.contains("node_modules") {
+            Self::Npm
+        } else if path.contains("Caskroom") {
+            Self::HomebrewCask
+        } else if path.contains("Cellar") {
+            Self::HomebrewFormula
+        } else {
+            Self::GitHub
+        }
+    }
+
+    fn release_url(self) -> &'static str {
+        match self {
+            Self::Npm => "https://registry.npmjs.org/@openai/codex/latest",
+            Self::HomebrewCask => "https://formulae.brew.sh/api/cask/codex.json",
+            Self::HomebrewFormula => "https://formulae.brew.sh/api/formula/codex.json",
+            Self::GitHub => "https://api.github.com/repos/openai/codex/releases/latest",
+        }
+    }
+
+    /// Version in the response of `release_url`
+    fn latest_version(self, body: &Value) -> Option<String> {
+        let version = match self {
+            Self::Npm | Self::HomebrewCask => body["version"].as_str()?,
+            Self::HomebrewFormula => body["versions"]["stable"].as_str()?,
+            // Tags look like "rust-v0.46.0"
+            Self::GitHub => body["tag_name"].as_str()?.trim_start_matches("rust-v"),
+        };
+        Some(version.trim_start_matches('v').to_string())
+    }
+}
+
+/// Last successful check
+#[derive(Default)]
+pub(crate) struct UpdateCheckCache(Mutex<Option<(Instant, UpdateStatus)>>);
+
+/// Numeric components of a version such as "codex-cli 0.46.0"; pre-release
+/// suffixes are ignored
+fn parse_version(text: &str) -> Option<Vec<u64>> {
+    let version = text.split_whitespace().last()?.trim_start_matches('v');
+    let core = version.split(['-', '+']).next()?;
+    core.split('.').map(|part| part.parse().ok()).collect()
+}
+
+fn compare(current: &str, latest: &str) -> UpdateStatus {
+    match (parse_version(current), parse_version(latest)) {
+        (Some(installed), Some(released)) if released > installed => {
+            UpdateStatus::UpdateAvailable {
+                latest: latest.to_string(),
+                current: current
+                    .split_whitespace()
+                    .last()
+                    .unwrap_or(current)
+                    .to_string(),
+            }
+        }
+        (Some(_), Some(_)) => UpdateStatus::UpToDate,
+        _ => UpdateStatus::Unknown {
+            reason: format!("Cannot compare versions {current:?} and {latest:?}"),
+        },
+    }
+}
+
+async fn fetch_latest(method: InstallMethod) -> Result<String, String> {
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent("codexia")
+        .build()
+        .map_err(|err| err.to_string())?;
+    let body: Value = client
+        .get(method.release_url())
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| format!("Failed to query the latest codex release: {err}"))?
+        .json()
+        .await
+        .map_err(|err| format!("Failed to read the latest codex release: {err}"))?;
+    method
+        .latest_version(&body)
+        .ok_or_else(|| "The release response has no version".to_string())
+}
+
+/// Compare the installed codex with the latest release; checks that reach
+/// the release source are remembered for a day
+#[tauri::command]
+pub async fn check_codex_update(state: State<'_, CodexState>) -> Result<UpdateStatus, String> {
+    if let Some((checked_at, status)) = state.codex_update.0.lock().await.as_ref() {
+        if checked_at.elapsed() < UPDATE_CHECK_TTL {
+            return Ok(status.clone());
+        }
+    }
+
+    let current = codex_client::services::codex::check_codex_version().await?;
+    let method = discover_codex_command()
+        .map(|binary| InstallMethod::detect(&binary))
+        .unwrap_or(InstallMethod::GitHub);
+    let status = match fetch_latest(method).await {
+        Ok(latest) => compare(&current, &latest),
+        Err(reason) => return Ok(UpdateStatus::Unknown { reason }),
+    };
+    *state.codex_update.0.lock().await = Some((Instant::now(), status.clone()));
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(
+            compare("codex-cli 0.45.0", "0.46.0"),
+            UpdateStatus::UpdateAvailable {
+                latest: "0.46.0".to_string(),
+                current: "0.45.0".to_string(),
+            }
+        );
+        assert_eq!(
+            compare("codex-cli 0.46.0", "0.46.0"),
+            UpdateStatus::UpToDate
+        );
+        assert_eq!(
+            compare("codex-cli 0.47.0-alpha.1", "0.46.0"),
+            UpdateStatus::UpToDate
+        );
+        assert!(matches!(
+            compare("unknown", "0.46.0"),
+            UpdateStatus::Unknown { .. }
+        ));
+    }
+
+    #[test]
+    fn test_latest_version() {
+        let release = json!({ "tag_name": "rust-v0.46.0" });
+        assert_eq!(
+            InstallMethod::GitHub.latest_version(&release).as_deref(),
+            Some("0.46.0")
+        );
+        let package = json!({ "version": "0.46.0" });
+        assert_eq!(
+            InstallMethod::Npm.latest_version(&package).as_deref(),
+            Some("0.46.0")
+        );
+        let formula = json!({ "versions": { "stable": "0.46.0", "head": "HEAD" } });
+        assert_eq!(
+            InstallMethod::HomebrewFormula
+                .latest_version(&formula)
+                .as_deref(),
+            Some("0.46.0")
+        );
+        assert_eq!(
+            InstallMethod::HomebrewFormula.latest_version(&package),
+            None
+        );
+    }
+}
@@ -45,6 +45,10 @@ pub enum AppError {
     Message(String),
     #[error("Database error: {0}")]
     Database(String),
+    #[error("git {command} failed: {stderr}")]
+    Git { command: String, stderr: String },
+    #[error("GitTimeout: git {command} did not finish within {timeout_secs}s")]
+    GitTimeout { command: String, timeout_secs: u64 },
 }
 
 impl AppError {
@@ -0,0 +1,87 @@
+//! Features of the installed git
+//!
+//! Probed once with `git --version` and kept by the `GitRunner`, so helpers
+//! can pick flags an older git understands instead of failing on them.
+
+use serde::Serialize;
+
+/// Version and feature flags of the git binary
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitCapabilities {
+    /// As printed by git, e.g. "2.39.3 (Apple Git-146)"; empty when the
+    /// version could not be read
+    pub version: String,
+    /// `git worktree`, git 2.5
+    pub worktrees: bool,
+    /// `git status --porcelain=v2`, git 2.11
+    pub porcelain_v2: bool,
+}
+
+/// (major, minor, patch) of `git --version` output such as
+/// "git version 2.39.3 (Apple Git-146)" or "git version 2.45.1.windows.1"
+pub(crate) fn parse_git_version(output: &str) -> Option<(u32, u32, u32)> {
+    let version = output.trim().strip_prefix("git version ")?;
+    let mut parts = version
+        .split_whitespace()
+        .next()?
+        .split('.')
+        .map(|part| part.parse::<u32>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next()?.ok()?;
+    let patch = parts.next().and_then(Result::ok).unwrap_or(0);
+    Some((major, minor, patch))
+}
+
+impl GitCapabilities {
+    pub(crate) fn from_version_output(output: &str) -> Self {
+        let Some(version) = parse_git_version(output) else {
+            return Self::unknown();
+        };
+        let at_least = |major: u32, minor: u32, patch: u32| version >= (major, minor, patch);
+        Self {
+            version: output.trim().trim_start_matches("git version ").to_string(),
+            worktrees: at_least(2, 5, 0),
+            porcelain_v2: at_least(2, 11, 0),
+        }
+    }
+
+    /// Assume a git old enough that only long-standing flags are safe
+    pub(crate) fn unknown() -> Self {
+        Self {
+            version: String::new(),
+            worktrees: false,
+            porcelain_v2: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_git_version() {
+        assert_eq!(
+            parse_git_version("git version 2.39.3 (Apple Git-146)\n"),
+            Some((2, 39, 3))
+        );
+        assert_eq!(
+            parse_git_version("git version 2.45.1.windows.1"),
+            Some((2, 45, 1))
+        );
+        assert_eq!(parse_git_version("git version 2.10"), Some((2, 10, 0)));
+        assert_eq!(parse_git_version("not git"), None);
+    }
+
+    #[test]
+    fn test_capabilities_by_version() {
+        let old = GitCapabilities::from_version_output("git version 2.7.4");
+        assert!(old.worktrees);
+        assert!(!old.porcelain_v2);
+        assert_eq!(old.version, "2.7.4");
+        assert!(!GitCapabilities::unknown().worktrees);
+        let current = GitCapabilities::from_version_output("git version 2.43.0");
+        assert!(current.porcelain_v2);
+    }
+}
@@ -1,10 +1,15 @@
 use super::file_types::GitDiff;
+use super::git_runner::{run_git, run_git_unchecked};
+use crate::codex_commands::CodexState;
 use std::fs;
-use std::path::Path;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use tauri::State;
 
 #[tauri::command]
-pub async fn get_git_file_diff(file_path: String) -> Result<GitDiff, String> {
+pub async fn get_git_file_diff(
+    state: State<'_, CodexState>,
+    file_path: String,
+) -> Result<GitDiff, String> {
     let expanded_path = if file_path.starts_with("~/") {
         let home = dirs::home_dir().ok_or_else(|| "Cannot find home directory".to_string())?;
         home.join(&file_path[2..])
@@ -22,17 +27,14 @@ pub async fn get_git_file_diff(file_path: String) -> Result<GitDiff, String> {
         Err(e) => return Err(format!("Failed to read current file: {}", e)),
     };
 
-    // First check if we're in a git repository
-    let git_check = Command::new("git")
-        .arg("rev-parse")
-        .arg("--is-inside-work-tree")
-        .current_dir(&expanded_path.parent().unwrap_or_else(|| Path::new(".")))
-        .output();
+    let runner = &state.git;
+    let parent = expanded_path.parent().unwrap_or_else(|| Path::new("."));
 
-    let in_git_repo = match git_check {
-        Ok(output) => output.status.success(),
-        Err(_) => false,
-    };
+    // First check if we're in a git repository
+    let in_git_repo = run_git_unchecked(runner, parent, &["rev-parse", "--is-inside-work-tree"])
+        .await
+        .map(|output| output.success())
+        .unwrap_or(false);
 
     if !in_git_repo {
         return Ok(GitDiff {
@@ -42,55 +44,31 @@ pub async fn get_git_file_diff(file_path: String) -> Result<GitDiff, String> {
         });
     }
 
-    // Use git diff to get the original content from the index/HEAD
-    let git_show_output = Command::new("git")
-        .arg("show")
-        .arg(&format!(
-            "HEAD:{}",
-            expanded_path
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy()
-        ))
-        .current_dir(&expanded_path.parent().unwrap_or_else(|| Path::new(".")))
-        .output();
-
-    // If that fails, try with the full relative path
-    let output = if git_show_output.is_err() || !git_show_output.as_ref().unwrap().status.success()
-    {
-        // Get git root and calculate relative path
-        let git_root_output = Command::new("git")
-            .arg("rev-parse")
-            .arg("--show-toplevel")
-            .current_dir(&expanded_path.parent().unwrap_or_else(|| Path::new(".")))
-            .output();
+    // Use git show to get the original content from HEAD
+    let file_spec = format!(
+        "HEAD:{}",
+        expanded_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+    );
+    let mut git_show_output = run_git_unchecked(runner, parent, &["show", &file_spec]).await;
 
-        match git_root_output {
-            Ok(root_output) if root_output.status.success() => {
-                let git_root_string = String::from_utf8_lossy(&root_output.stdout);
-                let git_root = git_root_string.trim();
-                let git_root_path = Path::new(git_root);
-
-                if let Ok(rel_path) = expanded_path.strip_prefix(git_root_path) {
-                    Command::new("git")
-                        .arg("show")
-                        .arg(&format!("HEAD:{}", rel_path.to_string_lossy()))
-                        .current_dir(git_root_path)
-                        .output()
-                } else {
-                    git_show_output
-                }
+    // If that fails, try with the full path relative to the git root
+    if !matches!(&git_show_output, Ok(output) if output.success()) {
+        if let Ok(root_output) = run_git(runner, parent, &["rev-parse", "--show-toplevel"]).await {
+            let git_root_path = PathBuf::from(root_output.stdout.trim());
+            if let Ok(rel_path) = expanded_path.strip_prefix(&git_root_path) {
+                let rel_spec = format!("HEAD:{}", rel_path.to_string_lossy());
+                git_show_output = run_git_unchecked(runner, &git_root_path, &["show", &rel_spec]).await;
             }
-            _ => git_show_output,
         }
-    } else {
-        git_show_output
-    };
+    }
 
-    let original_content = match output {
+    let original_content = match git_show_output {
         Ok(output) => {
-            if output.status.success() {
-                String::from_utf8_lossy(&output.stdout).to_string()
+            if output.success() {
+                output.stdout
             } else {
                 // If git show fails, it might be a new file or not in git
                 String::new()
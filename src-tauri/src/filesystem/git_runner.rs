@@ -0,0 +1,341 @@
+//! Shared runner for git subprocesses
+//!
+//! Every git invocation goes through this module so spawning, timeouts and
+//! exit-code handling behave the same everywhere. Git runs asynchronously
+//! under a timeout so a hung repository (huge tree, network mount, stuck
+//! credential prompt) cannot pin the async runtime, and a shared semaphore
+//! caps how many git processes run at once across all sessions.
+
+use crate::error::AppError;
+use crate::filesystem::git_capabilities::GitCapabilities;
+use codex_client::utils::command::create_tokio_command;
+use codex_client::utils::filesystem::network_filesystem;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{OnceCell, OwnedSemaphorePermit, Semaphore};
+
+/// Default limit for a single git invocation
+pub const DEFAULT_GIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Minimum limit for git running in a directory on a network filesystem
+pub const NETWORK_GIT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Git commands that run without a timeout: `commit` runs the repository's
+/// hooks and may wait for a signing passphrase, both of which can
+/// legitimately take longer than any limit
+const UNTIMED_COMMANDS: &[&str] = &["commit"];
+
+/// How long querying a directory's filesystem type may take
+const FILESYSTEM_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default number of concurrent git processes: one per CPU
+pub fn default_git_concurrency() -> usize {
+    std::thread::available_parallelism()
+        .map(|count| count.get())
+        .unwrap_or(4)
+}
+
+/// Runtime settings shared by every git invocation
+pub struct GitRunner {
+    timeout_ms: AtomicU64,
+    /// Replaced wholesale when the limit changes; permits already handed out
+    /// return to the semaphore they came from
+    permits: RwLock<Arc<Semaphore>>,
+    /// Network filesystem type by directory, `None` for local directories
+    filesystems: Mutex<HashMap<PathBuf, Option<String>>>,
+    capabilities: OnceCell<GitCapabilities>,
+}
+
+impl GitRunner {
+    pub fn new() -> Self {
+        Self {
+            timeout_ms: AtomicU64::new(DEFAULT_GIT_TIMEOUT.as_millis() as u64),
+            permits: RwLock::new(Arc::new(Semaphore::new(default_git_concurrency()))),
+            filesystems: Mutex::new(HashMap::new()),
+            capabilities: OnceCell::new(),
+        }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms.load(Ordering::Relaxed))
+    }
+
+    pub fn set_timeout(&self, timeout: Duration) {
+        self.timeout_ms
+            .store(timeout.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Type of the network filesystem `dir` is on, `None` when it is local.
+    /// Each directory is queried once, off the async runtime.
+    pub async fn network_filesystem(&self, dir: &Path) -> Option<String> {
+        if let Some(known) = self.cached_filesystem(dir) {
+            return known;
+        }
+        let path = dir.to_path_buf();
+        let probe = tokio::task::spawn_blocking(move || network_filesystem(&path));
+        let detected = match tokio::time::timeout(FILESYSTEM_PROBE_TIMEOUT, probe).await {
+            Ok(result) => result.unwrap_or_default(),
+            // Only a remote mount takes this long to report its type
+            Err(_) => Some("unresponsive".to_string()),
+        };
+        self.filesystems
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(dir.to_path_buf(), detected.clone());
+        detected
+    }
+
+    fn cached_filesystem(&self, dir: &Path) -> Option<Option<String>> {
+        self.filesystems
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(dir)
+            .cloned()
+    }
+
+    /// Timeout for `git <args>` in `dir`: network mounts get at least
+    /// `NETWORK_GIT_TIMEOUT`, and `UNTIMED_COMMANDS` none at all
+    async fn timeout_for(&self, dir: &Path, args: &[&str]) -> Option<Duration> {
+        let subcommand = args.iter().find(|arg| !arg.starts_with('-'));
+        if subcommand.is_some_and(|subcommand| UNTIMED_COMMANDS.contains(subcommand)) {
+            return None;
+        }
+        let timeout = self.timeout();
+        Some(match self.network_filesystem(dir).await {
+            Some(_) => timeout.max(NETWORK_GIT_TIMEOUT),
+            None => timeout,
+        })
+    }
+
+    /// Features of the installed git, probed on first use. A git that
+    /// cannot report its version is treated as an old one.
+    pub async fn capabilities(&self) -> &GitCapabilities {
+        self.capabilities
+            .get_or_init(|| async {
+                match run_git(self, &std::env::temp_dir(), &["--version"]).await {
+                    Ok(output) => GitCapabilities::from_version_output(&output.stdout),
+                    Err(err) => {
+                        log::warn!("Failed to read the git version: {err}");
+                        GitCapabilities::unknown()
+                    }
+                }
+            })
+            .await
+    }
+
+    pub fn set_concurrency(&self, permits: usize) {
+        let mut semaphore = self.permits.write().unwrap_or_else(|e| e.into_inner());
+        *semaphore = Arc::new(Semaphore::new(permits));
+    }
+
+    /// Wait for a free git slot; the slot is released when the permit drops
+    async fn acquire(&self) -> OwnedSemaphorePermit {
+        let semaphore = self
+            .permits
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        semaphore
+            .acquire_owned()
+            .await
+            .expect("git semaphore is never closed")
+    }
+}
+
+impl Default for GitRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Captured result of a git invocation
+#[derive(Debug, Clone)]
+pub struct GitOutput {
+    pub stdout: String,
+    pub stderr: String,
+    /// Exit code, `None` when git was terminated by a signal
+    pub code: Option<i32>,
+}
+
+impl GitOutput {
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+}
+
+/// Which exit codes count as success for a git command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExitPolicy {
+    /// Only exit code 0
+    Zero,
+    /// 0 or 1: `git diff --no-index`, `--exit-code` and `--quiet` exit
+    /// with 1 when the inputs differ, which is not a failure
+    Diff,
+    /// Any exit code; the caller inspects `GitOutput::code` itself
+    Any,
+}
+
+async fn spawn_git(
+    runner: &GitRunner,
+    dir: &Path,
+    args: &[&str],
+    input: Option<&[u8]>,
+    policy: ExitPolicy,
+) -> Result<GitOutput, AppError> {
+    let command_line = args.join(" ");
+    // Held until this function returns, on success and error paths alike.
+    // Waiting for a slot does not count towards the timeout.
+    let _permit = runner.acquire().await;
+    let timeout = runner.timeout_for(dir, args).await;
+    let mut command = create_tokio_command("git");
+    command
+        .args(args)
+        .current_dir(dir)
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let run = async {
+        let mut child = command.spawn().map_err(|source| AppError::IoContext {
+            context: format!("Failed to execute git {command_line}"),
+            source,
+        })?;
+        // Feed stdin from its own task while the output is read: git may
+        // fill the stdout pipe before it has consumed all of its input
+        let writer = match (input, child.stdin.take()) {
+            (Some(input), Some(mut stdin)) => {
+                let input = input.to_vec();
+                // Dropping stdin at the end closes the pipe so git sees EOF
+                Some(tokio::spawn(async move { stdin.write_all(&input).await }))
+            }
+            _ => None,
+        };
+        let output = child
+            .wait_with_output()
+            .await
+            .map_err(|source| AppError::IoContext {
+                context: format!("Failed to wait for git {command_line}"),
+                source,
+            })?;
+        // A git that exited early with an error explains itself on stderr;
+        // the broken pipe that left behind is not worth reporting instead
+        let written = match writer {
+            Some(writer) => writer
+                .await
+                .map_err(std::io::Error::other)
+                .and_then(|written| written),
+            None => Ok(()),
+        };
+        match written {
+            Err(source) if output.status.success() => Err(AppError::IoContext {
+                context: format!("Failed to write to git {command_line} stdin"),
+                source,
+            }),
+            _ => Ok(output),
+        }
+    };
+
+    // On timeout the child is dropped, and `kill_on_drop` terminates it
+    let output = match timeout {
+        Some(timeout) => {
+            tokio::time::timeout(timeout, run)
+                .await
+                .map_err(|_| AppError::GitTimeout {
+                    command: command_line.clone(),
+                    timeout_secs: timeout.as_secs(),
+                })??
+        }
+        None => run.await?,
+    };
+
+    let output = GitOutput {
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        code: output.status.code(),
+    };
+
+    let accepted = match policy {
+        ExitPolicy::Zero => output.success(),
+        ExitPolicy::Diff => matches!(output.code, Some(0) | Some(1)),
+        ExitPolicy::Any => true,
+    };
+    if !accepted {
+        return Err(AppError::Git {
+            command: command_line,
+            stderr: output.stderr.trim().to_string(),
+        });
+    }
+    Ok(output)
+}
+
+/// Run `git <args>` in `dir`; any non-zero exit code is an error
+pub async fn run_git(runner: &GitRunner, dir: &Path, args: &[&str]) -> Result<GitOutput, AppError> {
+    spawn_git(runner, dir, args, None, ExitPolicy::Zero).await
+}
+
+/// Run a diff-like git command where exit code 1 means "differences found"
+pub async fn run_git_diff(
+    runner: &GitRunner,
+    dir: &Path,
+    args: &[&str],
+) -> Result<GitOutput, AppError> {
+    spawn_git(runner, dir, args, None, ExitPolicy::Diff).await
+}
+
+/// Run git and return its output whatever the exit code, for probes such as
+/// `rev-parse --verify` where failure is an answer rather than an error
+pub async fn run_git_unchecked(
+    runner: &GitRunner,
+    dir: &Path,
+    args: &[&str],
+) -> Result<GitOutput, AppError> {
+    spawn_git(runner, dir, args, None, ExitPolicy::Any).await
+}
+
+/// Run `git <args>` with `input` written to its stdin (e.g. `git apply`)
+pub async fn run_git_with_input(
+    runner: &GitRunner,
+    dir: &Path,
+    args: &[&str],
+    input: &[u8],
+) -> Result<GitOutput, AppError> {
+    spawn_git(runner, dir, args, Some(input), ExitPolicy::Zero).await
+}
+
+/// Version and features of the installed git
+#[tauri::command]
+pub async fn git_capabilities(
+    state: tauri::State<'_, crate::codex_commands::CodexState>,
+) -> Result<GitCapabilities, String> {
+    Ok(state.git.capabilities().await.clone())
+}
+
+#[tauri::command]
+pub async fn set_git_timeout(
+    state: tauri::State<'_, crate::codex_commands::CodexState>,
+    timeout_secs: u64,
+) -> Result<(), String> {
+    if timeout_secs == 0 {
+        return Err("Git timeout must be at least one second".to_string());
+    }
+    state.git.set_timeout(Duration::from_secs(timeout_secs));
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_git_concurrency(
+    state: tauri::State<'_, crate::codex_commands::CodexState>,
+    permits: usize,
+) -> Result<(), String> {
+    if permits == 0 {
+        return Err("Git concurrency must be at least 1".to_string());
+    }
+    state.git.set_concurrency(permits);
+    Ok(())
+}
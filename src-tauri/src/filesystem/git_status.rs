@@ -1,6 +1,8 @@
+use super::git_runner::run_git;
+use crate::codex_commands::CodexState;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
-use std::process::Command;
+use tauri::State;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GitStatus {
@@ -13,7 +15,10 @@ pub struct GitStatus {
 }
 
 #[tauri::command]
-pub async fn get_git_status(directory: String) -> Result<GitStatus, String> {
+pub async fn get_git_status(
+    state: State<'_, CodexState>,
+    directory: String,
+) -> Result<GitStatus, String> {
     let expanded_path = if directory.starts_with("~/") {
         let home = dirs::home_dir().ok_or_else(|| "Cannot find home directory".to_string())?;
         home.join(&directory[2..])
@@ -21,15 +26,9 @@ pub async fn get_git_status(directory: String) -> Result<GitStatus, String> {
         Path::new(&directory).to_path_buf()
     };
 
-    let output = Command::new("git")
-        .args(["status", "--porcelain"])
-        .current_dir(&expanded_path)
-        .output()
-        .map_err(|e| format!("Failed to execute git command: {}", e))?;
-
-    if !output.status.success() {
-        return Err("Not a git repository or git command failed".to_string());
-    }
+    let output = run_git(&state.git, &expanded_path, &["status", "--porcelain"])
+        .await
+        .map_err(|e| format!("Not a git repository or git command failed: {}", e))?;
 
     let mut git_status = GitStatus {
         staged: Vec::new(),
@@ -40,8 +39,7 @@ pub async fn get_git_status(directory: String) -> Result<GitStatus, String> {
         conflicted: Vec::new(),
     };
 
-    let status_output = String::from_utf8_lossy(&output.stdout);
-    for line in status_output.lines() {
+    for line in output.stdout.lines() {
         if line.len() < 3 {
             continue;
         }
@@ -1,26 +1,26 @@
+use super::git_runner::{run_git, run_git_diff, run_git_with_input, GitRunner};
+use crate::codex_commands::CodexState;
 use serde::Serialize;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
 use std::fs;
+use std::process::Stdio;
+use std::time::Duration;
+use codex_client::utils::command::create_tokio_command;
+use tauri::State;
+use tokio::io::AsyncWriteExt;
 
 /// Try to locate the git repository root starting from `start_dir` (or the
 /// current process directory if None) by calling `git rev-parse --show-toplevel`.
-fn find_git_root(start_dir: Option<&Path>) -> Option<PathBuf> {
+pub(crate) async fn find_git_root(runner: &GitRunner, start_dir: Option<&Path>) -> Option<PathBuf> {
     let dir = start_dir
         .map(|p| p.to_path_buf())
         .or_else(|| std::env::current_dir().ok())?;
 
-    let output = Command::new("git")
-        .arg("rev-parse")
-        .arg("--show-toplevel")
-        .current_dir(&dir)
-        .output()
+    let output = run_git(runner, &dir, &["rev-parse", "--show-toplevel"])
+        .await
         .ok()?;
 
-    if !output.status.success() {
-        return None;
-    }
-    let root = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let root = output.stdout.trim().to_string();
     if root.is_empty() {
         return None;
     }
@@ -85,14 +85,18 @@ pub struct PrepareWorktreeResult {
 }
 
 #[tauri::command]
-pub async fn prepare_git_worktree(turn_id: String, directory: Option<String>) -> Result<PrepareWorktreeResult, String> {
+pub async fn prepare_git_worktree(
+    state: State<'_, CodexState>,
+    turn_id: String,
+    directory: Option<String>,
+) -> Result<PrepareWorktreeResult, String> {
     // Resolve a starting directory if provided
     let start_dir = directory
         .as_deref()
         .map(expand_tilde);
     let start_dir_ref = start_dir.as_deref();
 
-    let git_root = match find_git_root(start_dir_ref) {
+    let git_root = match find_git_root(&state.git, start_dir_ref).await {
         Some(p) => p,
         None => {
             return Ok(PrepareWorktreeResult {
@@ -103,6 +107,14 @@ pub async fn prepare_git_worktree(turn_id: String, directory: Option<String>) ->
         }
     };
 
+    if !state.git.capabilities().await.worktrees {
+        return Ok(PrepareWorktreeResult {
+            prepared: false,
+            path: None,
+            reason: Some("git worktree needs git 2.5 or newer".into()),
+        });
+    }
+
     // Place worktrees in a dedicated directory under the user's home folder
     let base = expand_tilde("~/.codexia/worktrees");
 
@@ -123,15 +135,12 @@ pub async fn prepare_git_worktree(turn_id: String, directory: Option<String>) ->
     println!("worktree path {:?}", worktree_path);
 
     // Add a detached worktree at current HEAD
-    let status = Command::new("git")
-        .args(["worktree", "add", "--detach", worktree_path.to_string_lossy().as_ref()])
-        .current_dir(&git_root)
-        .status()
-        .map_err(|e| format!("Failed to execute git worktree add: {}", e))?;
-
-    if !status.success() {
-        return Err("git worktree add failed".into());
-    }
+    run_git(
+        &state.git,
+        &git_root,
+        &["worktree", "add", "--detach", worktree_path.to_string_lossy().as_ref()],
+    )
+    .await?;
 
     Ok(PrepareWorktreeResult {
         prepared: true,
@@ -150,13 +159,17 @@ pub struct CommitResult {
 /// Stage all changes and commit with the provided message in the repository
 /// resolved from `directory` (or current directory if None).
 #[tauri::command]
-pub async fn git_commit_changes(message: String, directory: Option<String>) -> Result<CommitResult, String> {
+pub async fn git_commit_changes(
+    state: State<'_, CodexState>,
+    message: String,
+    directory: Option<String>,
+) -> Result<CommitResult, String> {
     let start_dir = directory
         .as_deref()
         .map(expand_tilde);
     let start_dir_ref = start_dir.as_deref();
 
-    let git_root = match find_git_root(start_dir_ref) {
+    let git_root = match find_git_root(&state.git, start_dir_ref).await {
         Some(p) => p,
         None => {
             return Ok(CommitResult {
@@ -167,23 +180,12 @@ pub async fn git_commit_changes(message: String, directory: Option<String>) -> R
     };
 
     // Stage all changes
-    let add_status = Command::new("git")
-        .args(["add", "-A"])
-        .current_dir(&git_root)
-        .status()
-        .map_err(|e| format!("Failed to execute git add: {}", e))?;
-    if !add_status.success() {
-        return Err("git add failed".into());
-    }
+    run_git(&state.git, &git_root, &["add", "-A"]).await?;
 
     // Check if there is anything to commit
-    let diff_status = Command::new("git")
-        .args(["diff", "--cached", "--quiet"])
-        .current_dir(&git_root)
-        .status()
-        .map_err(|e| format!("Failed to execute git diff --cached: {}", e))?;
+    let diff_output = run_git_diff(&state.git, &git_root, &["diff", "--cached", "--quiet"]).await?;
 
-    if diff_status.success() {
+    if diff_output.success() {
         // Exit code 0 means no staged changes
         return Ok(CommitResult {
             committed: false,
@@ -192,15 +194,7 @@ pub async fn git_commit_changes(message: String, directory: Option<String>) -> R
     }
 
     // Commit the staged changes
-    let commit_status = Command::new("git")
-        .args(["commit", "-m", &message])
-        .current_dir(&git_root)
-        .status()
-        .map_err(|e| format!("Failed to execute git commit: {}", e))?;
-
-    if !commit_status.success() {
-        return Err("git commit failed".into());
-    }
+    run_git(&state.git, &git_root, &["commit", "-m", &message]).await?;
 
     Ok(CommitResult {
         committed: true,
@@ -208,56 +202,136 @@ pub async fn git_commit_changes(message: String, directory: Option<String>) -> R
     })
 }
 
+/// Upper bound for one `patch` invocation
+const PATCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Context lines `patch` may ignore when a fuzzy revert is allowed
+const REVERT_FUZZ: u32 = 3;
+
+/// Which attempt of `reverse_apply` succeeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RevertMethod {
+    GitApply,
+    Patch,
+    /// `patch` with fuzz: the context matched only approximately
+    FuzzyPatch,
+}
+
+/// Run `patch -R -p1` in `dir`. A dry run goes first so a failed attempt
+/// leaves neither partial changes nor `.rej` files behind.
+async fn run_patch_reverse(dir: &Path, patch: &str, fuzz: u32) -> Result<(), String> {
+    let fuzz_arg = format!("--fuzz={fuzz}");
+    for dry_run in [true, false] {
+        let mut command = create_tokio_command("patch");
+        command
+            .args(["-R", "-p1", "--batch", "--silent", &fuzz_arg])
+            .current_dir(dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if dry_run {
+            command.arg("--dry-run");
+        }
+        let mut child = command
+            .spawn()
+            .map_err(|e| format!("Failed to run patch: {}", e))?;
+        // Feed stdin from its own task while the output is read, as
+        // `spawn_git` does, so a chatty `patch` cannot stall on a full pipe
+        let writer = child.stdin.take().map(|mut stdin| {
+            let input = patch.to_owned();
+            tokio::spawn(async move { stdin.write_all(input.as_bytes()).await })
+        });
+        let output = tokio::time::timeout(PATCH_TIMEOUT, child.wait_with_output())
+            .await
+            .map_err(|_| "patch timed out".to_string())?
+            .map_err(|e| format!("Failed to run patch: {}", e))?;
+        // A `patch` that failed early reports why itself; the broken pipe
+        // it left behind only matters when it claims success
+        if let Some(writer) = writer {
+            let written = writer.await.map_err(std::io::Error::other).and_then(|w| w);
+            if let Err(e) = written {
+                if output.status.success() {
+                    return Err(format!("Failed to write patch input: {}", e));
+                }
+            }
+        }
+        if !output.status.success() {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("patch failed: {}{}", stdout.trim(), stderr.trim()));
+        }
+    }
+    Ok(())
+}
+
+/// Reverse-apply `patch` in the repository at `dir`: `git apply -R` first,
+/// then `patch -R -p1`, and with `allow_fuzzy` a last `patch` attempt that
+/// tolerates drifted context
+pub(crate) async fn reverse_apply(
+    runner: &GitRunner,
+    dir: &Path,
+    patch: &str,
+    allow_fuzzy: bool,
+) -> Result<RevertMethod, String> {
+    let git_error = match run_git_with_input(
+        runner,
+        dir,
+        &["apply", "--reverse", "--whitespace=nowarn"], // be lenient on whitespace
+        patch.as_bytes(),
+    )
+    .await
+    {
+        Ok(_) => return Ok(RevertMethod::GitApply),
+        Err(err) => err.to_string(),
+    };
+
+    if run_patch_reverse(dir, patch, 0).await.is_ok() {
+        return Ok(RevertMethod::Patch);
+    }
+    if allow_fuzzy && run_patch_reverse(dir, patch, REVERT_FUZZ).await.is_ok() {
+        return Ok(RevertMethod::FuzzyPatch);
+    }
+    Err(git_error)
+}
+
 /// Apply a reverse unified diff to the repository at `directory` (or current directory).
 #[tauri::command]
-pub async fn apply_reverse_patch(unified_diff: String, directory: Option<String>) -> Result<bool, String> {
+pub async fn apply_reverse_patch(
+    state: State<'_, CodexState>,
+    unified_diff: String,
+    directory: Option<String>,
+) -> Result<bool, String> {
     let start_dir = directory
         .as_deref()
         .map(expand_tilde);
     let start_dir_ref = start_dir.as_deref();
 
-    let git_root = match find_git_root(start_dir_ref) {
+    let git_root = match find_git_root(&state.git, start_dir_ref).await {
         Some(p) => p,
         None => return Err("Not a git repository".into()),
     };
 
-    let mut child = Command::new("git")
-        .args(["apply", "--reverse", "--whitespace=nowarn"]) // be lenient on whitespace
-        .current_dir(&git_root)
-        .stdin(Stdio::piped())
-        .stdout(Stdio::null())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to spawn git apply: {}", e))?;
-
-    use std::io::Write;
-    if let Some(mut stdin) = child.stdin.take() {
-        stdin
-            .write_all(unified_diff.as_bytes())
-            .map_err(|e| format!("Failed to write patch to stdin: {}", e))?;
-    }
-
-    let output = child
-        .wait_with_output()
-        .map_err(|e| format!("Failed to wait for git apply: {}", e))?;
-
-    if !output.status.success() {
-        let err = String::from_utf8_lossy(&output.stderr).to_string();
-        return Err(format!("git apply --reverse failed: {}", err));
-    }
+    reverse_apply(&state.git, &git_root, &unified_diff, false).await?;
 
     Ok(true)
 }
 
 #[tauri::command]
-pub async fn commit_changes_to_worktree(turn_id: String, message: String, directory: Option<String>) -> Result<PrepareWorktreeResult, String> {
+pub async fn commit_changes_to_worktree(
+    state: State<'_, CodexState>,
+    turn_id: String,
+    message: String,
+    directory: Option<String>,
+) -> Result<PrepareWorktreeResult, String> {
     // Resolve repository root
     let start_dir = directory
         .as_deref()
         .map(expand_tilde);
     let start_dir_ref = start_dir.as_deref();
 
-    let git_root = match find_git_root(start_dir_ref) {
+    let git_root = match find_git_root(&state.git, start_dir_ref).await {
         Some(p) => p,
         None => {
             return Ok(PrepareWorktreeResult {
@@ -277,39 +351,26 @@ pub async fn commit_changes_to_worktree(turn_id: String, message: String, direct
 
     // If not exists, add a detached worktree at current HEAD
     if !worktree_path.exists() {
-        let status = Command::new("git")
-            .args(["worktree", "add", "--detach", worktree_path.to_string_lossy().as_ref()])
-            .current_dir(&git_root)
-            .status()
-            .map_err(|e| format!("Failed to execute git worktree add: {}", e))?;
-
-        if !status.success() {
-            return Err("git worktree add failed".into());
-        }
+        run_git(
+            &state.git,
+            &git_root,
+            &["worktree", "add", "--detach", worktree_path.to_string_lossy().as_ref()],
+        )
+        .await?;
     }
 
     // Compute the diff of working tree changes against HEAD in the main repo
-    let diff_output = Command::new("git")
-        .args(["diff", "HEAD"])
-        .current_dir(&git_root)
-        .output()
-        .map_err(|e| format!("Failed to execute git diff HEAD: {}", e))?;
-
-    if !diff_output.status.success() {
-        return Err("git diff HEAD failed".into());
-    }
-    let patch = String::from_utf8_lossy(&diff_output.stdout).to_string();
+    let patch = run_git(&state.git, &git_root, &["diff", "HEAD"]).await?.stdout;
 
     // Also collect untracked files so we can include new files
-    let ls_others = Command::new("git")
-        .args(["ls-files", "--others", "--exclude-standard"])
-        .current_dir(&git_root)
-        .output()
-        .map_err(|e| format!("Failed to execute git ls-files: {}", e))?;
-    if !ls_others.status.success() {
-        return Err("git ls-files failed".into());
-    }
-    let untracked: Vec<String> = String::from_utf8_lossy(&ls_others.stdout)
+    let ls_others = run_git(
+        &state.git,
+        &git_root,
+        &["ls-files", "--others", "--exclude-standard"],
+    )
+    .await?;
+    let untracked: Vec<String> = ls_others
+        .stdout
         .lines()
         .map(|s| s.trim().to_string())
         .filter(|s| !s.is_empty())
@@ -326,29 +387,13 @@ pub async fn commit_changes_to_worktree(turn_id: String, message: String, direct
 
     // Apply the patch (tracked modifications/deletions) in the worktree
     if !patch.trim().is_empty() {
-        let mut apply_child = Command::new("git")
-            .args(["apply", "--index", "--whitespace=nowarn"]) // stage changes while applying
-            .current_dir(&worktree_path)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::null())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|e| format!("Failed to spawn git apply in worktree: {}", e))?;
-
-        use std::io::Write;
-        if let Some(mut stdin) = apply_child.stdin.take() {
-            stdin
-                .write_all(patch.as_bytes())
-                .map_err(|e| format!("Failed to write patch to worktree stdin: {}", e))?;
-        }
-        let apply_out = apply_child
-            .wait_with_output()
-            .map_err(|e| format!("Failed to wait for git apply in worktree: {}", e))?;
-
-        if !apply_out.status.success() {
-            let err = String::from_utf8_lossy(&apply_out.stderr).to_string();
-            return Err(format!("git apply in worktree failed: {}", err));
-        }
+        run_git_with_input(
+            &state.git,
+            &worktree_path,
+            &["apply", "--index", "--whitespace=nowarn"], // stage changes while applying
+            patch.as_bytes(),
+        )
+        .await?;
     }
 
     // Copy untracked files into the worktree so they can be added and committed
@@ -363,25 +408,10 @@ pub async fn commit_changes_to_worktree(turn_id: String, message: String, direct
     }
 
     // Ensure all new files are staged in the worktree
-    let add_status = Command::new("git")
-        .args(["add", "-A"])
-        .current_dir(&worktree_path)
-        .status()
-        .map_err(|e| format!("Failed to execute git add in worktree: {}", e))?;
-    if !add_status.success() {
-        return Err("git add in worktree failed".into());
-    }
+    run_git(&state.git, &worktree_path, &["add", "-A"]).await?;
 
     // Commit in worktree
-    let commit_status = Command::new("git")
-        .args(["commit", "-m", &message])
-        .current_dir(&worktree_path)
-        .status()
-        .map_err(|e| format!("Failed to execute git commit in worktree: {}", e))?;
-
-    if !commit_status.success() {
-        return Err("git commit in worktree failed".into());
-    }
+    run_git(&state.git, &worktree_path, &["commit", "-m", &message]).await?;
 
     Ok(PrepareWorktreeResult {
         prepared: true,
@@ -400,13 +430,17 @@ pub struct DeleteWorktreeResult {
 }
 
 #[tauri::command]
-pub async fn delete_git_worktree(turn_id: String, directory: Option<String>) -> Result<DeleteWorktreeResult, String> {
+pub async fn delete_git_worktree(
+    state: State<'_, CodexState>,
+    turn_id: String,
+    directory: Option<String>,
+) -> Result<DeleteWorktreeResult, String> {
     let start_dir = directory
         .as_deref()
         .map(expand_tilde);
     let start_dir_ref = start_dir.as_deref();
 
-    let git_root = match find_git_root(start_dir_ref) {
+    let git_root = match find_git_root(&state.git, start_dir_ref).await {
         Some(p) => p,
         None => {
             return Ok(DeleteWorktreeResult {
@@ -430,15 +464,12 @@ pub async fn delete_git_worktree(turn_id: String, directory: Option<String>) ->
 
     let remove_root = resolve_main_repo_root_for_worktree(&worktree_path).unwrap_or_else(|| git_root.clone());
 
-    let status = Command::new("git")
-        .args(["worktree", "remove", "--force", worktree_path.to_string_lossy().as_ref()])
-        .current_dir(&remove_root)
-        .status()
-        .map_err(|e| format!("Failed to execute git worktree remove: {}", e))?;
-
-    if !status.success() {
-        return Err("git worktree remove failed".into());
-    }
+    run_git(
+        &state.git,
+        &remove_root,
+        &["worktree", "remove", "--force", worktree_path.to_string_lossy().as_ref()],
+    )
+    .await?;
 
     if let Err(err) = fs::remove_dir_all(&worktree_path) {
         if err.kind() != std::io::ErrorKind::NotFound {
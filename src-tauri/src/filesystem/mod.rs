@@ -3,7 +3,10 @@ pub mod file_analysis;
 pub mod file_io;
 pub mod file_parsers;
 pub mod file_types;
+pub mod git_capabilities;
 pub mod git_diff;
+pub mod git_runner;
 pub mod git_status;
+pub mod session_git;
 pub mod watch;
 pub mod git_worktree;
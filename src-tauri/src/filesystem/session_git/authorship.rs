@@ -0,0 +1,126 @@
+use super::hunks::{file_diff, parse_hunks};
+use super::{resolve_in_dir, session_working_dir};
+use crate::codex_commands::CodexState;
+use crate::filesystem::git_runner::run_git_unchecked;
+use codex_client::session::LineCounts;
+use serde::Serialize;
+use tauri::State;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Author {
+    /// Written by one of codex's patches in this session
+    Codex,
+    /// Written by the user, another tool, or a codex shell command
+    External,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineAuthor {
+    /// 1-based line number in the current file
+    pub line: u32,
+    pub text: String,
+    pub author: Author,
+}
+
+/// Added lines of a unified diff with their new line numbers
+fn added_lines(diff: &str) -> Vec<(u32, String)> {
+    let mut added = Vec::new();
+    for hunk in parse_hunks(diff).1 {
+        let mut line = hunk.new_start;
+        for body_line in hunk.body.lines() {
+            if let Some(text) = body_line.strip_prefix('+') {
+                added.push((line, text.to_string()));
+                line += 1;
+            } else if body_line.starts_with(' ') {
+                line += 1;
+            }
+        }
+    }
+    added
+}
+
+/// Attribute each line to codex while its patches still account for an
+/// identical line; the rest were written some other way
+fn attribute(added: Vec<(u32, String)>, mut codex_lines: LineCounts) -> Vec<LineAuthor> {
+    added
+        .into_iter()
+        .map(|(line, text)| {
+            let author = match codex_lines.get_mut(&text) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    Author::Codex
+                }
+                _ => Author::External,
+            };
+            LineAuthor { line, text, author }
+        })
+        .collect()
+}
+
+/// Who wrote each line of `path` that differs from HEAD. Lines are matched
+/// by content against the patches codex applied in this session, so a line
+/// retyped by hand identically to one codex wrote counts as codex's.
+#[tauri::command]
+pub async fn get_file_authorship(
+    state: State<'_, CodexState>,
+    session_id: String,
+    path: String,
+) -> Result<Vec<LineAuthor>, String> {
+    let dir = session_working_dir(&state, &session_id).await?;
+    let absolute = resolve_in_dir(&dir, &path)?;
+    let runner = &state.git;
+
+    let tracked = run_git_unchecked(runner, &dir, &["ls-files", "--error-unmatch", "--", &path])
+        .await?
+        .success();
+    let added = if tracked {
+        added_lines(&file_diff(runner, &dir, &path).await?)
+    } else {
+        // Every line of an untracked file is new
+        let content = tokio::fs::read_to_string(&absolute)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        content
+            .lines()
+            .enumerate()
+            .map(|(index, text)| (index as u32 + 1, text.to_string()))
+            .collect()
+    };
+
+    let codex_lines = state
+        .client_state
+        .sessions
+        .patches()
+        .codex_lines(&session_id, &absolute)
+        .await;
+    Ok(attribute(added, codex_lines))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attribute_added_lines() {
+        let diff = "diff --git a/x.rs b/x.rs\n--- a/x.rs\n+++ b/x.rs\n\
+                    @@ -1,3 +1,4 @@\n keep\n-old\n+codex line\n+mine\n keep\n+codex line\n";
+        let added = added_lines(diff);
+        assert_eq!(
+            added,
+            vec![
+                (2, "codex line".to_string()),
+                (3, "mine".to_string()),
+                (5, "codex line".to_string()),
+            ]
+        );
+
+        let codex_lines = LineCounts::from([("codex line".to_string(), 1)]);
+        let authors: Vec<Author> = attribute(added, codex_lines)
+            .iter()
+            .map(|line| line.author)
+            .collect();
+        assert_eq!(authors, vec![Author::Codex, Author::External, Author::External]);
+    }
+}
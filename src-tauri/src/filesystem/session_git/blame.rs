@@ -0,0 +1,205 @@
+use super::{resolve_in_dir, session_working_dir};
+use crate::codex_commands::CodexState;
+use crate::filesystem::git_runner::run_git_unchecked;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use tauri::State;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlameLine {
+    /// 1-based line number in the working tree file
+    pub line: u32,
+    /// `None` for lines changed since the last commit
+    pub commit: Option<String>,
+    pub author: Option<String>,
+    pub author_email: Option<String>,
+    pub date: Option<DateTime<Utc>>,
+    /// First line of the commit message
+    pub summary: Option<String>,
+    pub content: String,
+}
+
+/// What porcelain blame prints once per commit
+#[derive(Debug, Clone, Default)]
+struct CommitInfo {
+    author: Option<String>,
+    author_email: Option<String>,
+    date: Option<DateTime<Utc>>,
+    summary: Option<String>,
+}
+
+/// Whether `sha` is blame's all-zero id for uncommitted lines
+fn is_uncommitted(sha: &str) -> bool {
+    sha.bytes().all(|b| b == b'0')
+}
+
+/// `<sha> <orig-line> <final-line> [<count>]`
+fn parse_line_header(line: &str) -> Option<(&str, u32)> {
+    let mut parts = line.split(' ');
+    let sha = parts.next()?;
+    if sha.len() < 40 || !sha.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let _orig_line = parts.next()?;
+    let final_line = parts.next()?.parse().ok()?;
+    Some((sha, final_line))
+}
+
+/// Parse `git blame --porcelain` output
+fn parse_blame(output: &str) -> Vec<BlameLine> {
+    let mut commits: HashMap<&str, CommitInfo> = HashMap::new();
+    let mut lines = Vec::new();
+    let mut current: Option<(&str, u32)> = None;
+
+    for line in output.lines() {
+        if let Some(content) = line.strip_prefix('\t') {
+            let Some((sha, line)) = current.take() else {
+                continue;
+            };
+            let info = commits.get(sha).cloned().unwrap_or_default();
+            let committed = !is_uncommitted(sha);
+            lines.push(BlameLine {
+                line,
+                commit: committed.then(|| sha.to_string()),
+                author: info.author.filter(|_| committed),
+                author_email: info.author_email.filter(|_| committed),
+                date: info.date.filter(|_| committed),
+                summary: info.summary.filter(|_| committed),
+                content: content.to_string(),
+            });
+        } else if let Some(header) = parse_line_header(line) {
+            commits.entry(header.0).or_default();
+            current = Some(header);
+        } else if let Some((sha, _)) = current {
+            let info = commits.entry(sha).or_default();
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            match key {
+                "author" => info.author = Some(value.to_string()),
+                "author-mail" => {
+                    let email = value.trim_start_matches('<').trim_end_matches('>');
+                    info.author_email = Some(email.to_string());
+                }
+                "author-time" => {
+                    info.date = value
+                        .parse()
+                        .ok()
+                        .and_then(|secs| DateTime::from_timestamp(secs, 0));
+                }
+                "summary" => info.summary = Some(value.to_string()),
+                _ => {}
+            }
+        }
+    }
+    lines
+}
+
+/// Who last changed lines `start_line..=end_line` of `path`. Lines changed
+/// since the last commit, and every line of a file git does not track yet,
+/// come back without a commit.
+#[tauri::command]
+pub async fn blame_region(
+    state: State<'_, CodexState>,
+    session_id: String,
+    path: String,
+    start_line: u32,
+    end_line: u32,
+) -> Result<Vec<BlameLine>, String> {
+    if start_line == 0 || end_line < start_line {
+        return Err(format!("Invalid line range {start_line}-{end_line}"));
+    }
+    let dir = session_working_dir(&state, &session_id).await?;
+    let absolute = resolve_in_dir(&dir, &path)?;
+    let content = tokio::fs::read_to_string(&absolute)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    // git refuses ranges past the end of the file
+    let line_count = content.lines().count() as u32;
+    if start_line > line_count {
+        return Ok(Vec::new());
+    }
+    let end_line = end_line.min(line_count);
+
+    let range = format!("{start_line},{end_line}");
+    let output = run_git_unchecked(
+        &state.git,
+        &dir,
+        &["blame", "--porcelain", "-L", &range, "--", &path],
+    )
+    .await?;
+    if output.success() {
+        return Ok(parse_blame(&output.stdout));
+    }
+
+    let tracked = run_git_unchecked(
+        &state.git,
+        &dir,
+        &["ls-files", "--error-unmatch", "--", &path],
+    )
+    .await?
+    .success();
+    if tracked {
+        return Err(format!("git blame failed: {}", output.stderr.trim()));
+    }
+    Ok(content
+        .lines()
+        .enumerate()
+        .skip(start_line as usize - 1)
+        .take((end_line - start_line + 1) as usize)
+        .map(|(index, text)| BlameLine {
+            line: index as u32 + 1,
+            commit: None,
+            author: None,
+            author_email: None,
+            date: None,
+            summary: None,
+            content: text.to_string(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_blame() {
+        let committed = "4f2c1b0e9d8a7c6b5a4f3e2d1c0b9a8f7e6d5c4b";
+        let uncommitted = "0000000000000000000000000000000000000000";
+        let output = format!(
+            "{committed} 10 10 2\n\
+             author Ada Lovelace\n\
+             author-mail <ada@example.com>\n\
+             author-time 1700000000\n\
+             author-tz +0000\n\
+             summary Add the engine\n\
+             filename src/engine.rs\n\
+             \tfn run() {{\n\
+             {committed} 11 11\n\
+             \t    step();\n\
+             {uncommitted} 12 12 1\n\
+             author Not Committed Yet\n\
+             author-mail <not.committed.yet>\n\
+             author-time 1700000100\n\
+             summary Version of src/engine.rs from src/engine.rs\n\
+             filename src/engine.rs\n\
+             \t    codex();\n"
+        );
+        let lines = parse_blame(&output);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].line, 10);
+        assert_eq!(lines[0].author.as_deref(), Some("Ada Lovelace"));
+        assert_eq!(lines[0].author_email.as_deref(), Some("ada@example.com"));
+        assert_eq!(
+            lines[0].date.map(|date| date.timestamp()),
+            Some(1_700_000_000)
+        );
+        assert_eq!(lines[1].commit.as_deref(), Some(committed));
+        assert_eq!(lines[1].summary.as_deref(), Some("Add the engine"));
+        assert_eq!(lines[1].content, "    step();");
+        assert_eq!(lines[2].commit, None);
+        assert_eq!(lines[2].author, None);
+        assert_eq!(lines[2].line, 12);
+    }
+}
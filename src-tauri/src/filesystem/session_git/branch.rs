@@ -0,0 +1,88 @@
+use super::session_working_dir;
+use crate::codex_commands::CodexState;
+use crate::filesystem::git_runner::{run_git, run_git_unchecked};
+use serde::Serialize;
+use tauri::State;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchStatus {
+    /// Current branch, `None` when HEAD is detached
+    pub branch: Option<String>,
+    /// Upstream tracking branch, `None` when none is configured
+    pub upstream: Option<String>,
+    pub ahead: u32,
+    pub behind: u32,
+    pub detached: bool,
+}
+
+/// Parse `git rev-list --left-right --count` output into (ahead, behind)
+fn parse_ahead_behind(output: &str) -> Option<(u32, u32)> {
+    let mut counts = output.split_whitespace();
+    let ahead = counts.next()?.parse().ok()?;
+    let behind = counts.next()?.parse().ok()?;
+    Some((ahead, behind))
+}
+
+/// Branch of the session's repository and how it relates to its upstream
+#[tauri::command]
+pub async fn get_branch_status(
+    state: State<'_, CodexState>,
+    session_id: String,
+) -> Result<BranchStatus, String> {
+    let dir = session_working_dir(&state, &session_id).await?;
+    let runner = &state.git;
+
+    let head = match run_git(runner, &dir, &["rev-parse", "--abbrev-ref", "HEAD"]).await {
+        Ok(output) => output.stdout.trim().to_string(),
+        // A branch without commits yet has no HEAD to resolve
+        Err(_) => run_git(runner, &dir, &["symbolic-ref", "--short", "HEAD"])
+            .await?
+            .stdout
+            .trim()
+            .to_string(),
+    };
+    if head == "HEAD" {
+        return Ok(BranchStatus {
+            branch: None,
+            upstream: None,
+            ahead: 0,
+            behind: 0,
+            detached: true,
+        });
+    }
+
+    let upstream_output = run_git_unchecked(
+        runner,
+        &dir,
+        &["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{upstream}"],
+    )
+    .await?;
+    let upstream = Some(upstream_output.stdout.trim().to_string())
+        .filter(|upstream| upstream_output.success() && !upstream.is_empty());
+
+    let (ahead, behind) = match &upstream {
+        Some(_) => {
+            let counts = run_git_unchecked(
+                runner,
+                &dir,
+                &["rev-list", "--left-right", "--count", "HEAD...@{upstream}"],
+            )
+            .await?;
+            if counts.success() {
+                parse_ahead_behind(&counts.stdout).unwrap_or((0, 0))
+            } else {
+                (0, 0)
+            }
+        }
+        None => (0, 0),
+    };
+
+    Ok(BranchStatus {
+        branch: Some(head),
+        upstream,
+        ahead,
+        behind,
+        detached: false,
+    })
+}
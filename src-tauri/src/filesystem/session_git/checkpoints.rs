@@ -0,0 +1,174 @@
+//! Per-turn undo of codex's file changes
+//!
+//! With `auto_stash_before_turn` in the session config, the tracked files of
+//! the working tree are recorded before each turn whose sandbox allows
+//! writes. A checkpoint is the commit `git stash create` makes, or HEAD when
+//! nothing has changed, kept under
+//! `refs/codexia/checkpoints/<session>/<turn>` so gc leaves it alone. Neither
+//! the working tree nor the stash list is touched while recording.
+//! Untracked files are not part of a checkpoint and restoring leaves them
+//! in place.
+
+use super::session_working_dir;
+use crate::codex_commands::CodexState;
+use crate::filesystem::git_runner::{run_git, run_git_unchecked, GitRunner};
+use async_trait::async_trait;
+use codex_client::session::{get_sandbox_policy, SandboxMode, TurnHook};
+use codex_client::ClientState;
+use std::path::Path;
+use std::sync::Arc;
+use tauri::State;
+
+/// Checkpoints kept per session when the config does not say
+const DEFAULT_RETENTION: usize = 20;
+
+const REF_PREFIX: &str = "refs/codexia/checkpoints";
+
+fn checkpoint_ref(session_id: &str, turn: u64) -> String {
+    format!("{REF_PREFIX}/{session_id}/{turn}")
+}
+
+/// Turn numbers of the refs listed by `for-each-ref --format=%(refname)`,
+/// oldest first
+fn checkpoint_turns(output: &str) -> Vec<u64> {
+    let mut turns: Vec<u64> = output
+        .lines()
+        .filter_map(|line| line.rsplit('/').next()?.parse().ok())
+        .collect();
+    turns.sort_unstable();
+    turns
+}
+
+/// Checkpoints beyond the newest `retention`
+fn expired_turns(turns: &[u64], retention: usize) -> &[u64] {
+    &turns[..turns.len().saturating_sub(retention)]
+}
+
+async fn record_checkpoint(
+    git: &GitRunner,
+    dir: &Path,
+    session_id: &str,
+    turn: u64,
+    retention: usize,
+) -> Result<(), String> {
+    let stash = run_git(git, dir, &["stash", "create"]).await?;
+    let commit = match stash.stdout.trim() {
+        "" => run_git(git, dir, &["rev-parse", "HEAD"]).await?.stdout,
+        commit => commit.to_string(),
+    };
+    let name = checkpoint_ref(session_id, turn);
+    run_git(git, dir, &["update-ref", &name, commit.trim()]).await?;
+
+    let prefix = format!("{REF_PREFIX}/{session_id}/");
+    let refs = run_git(git, dir, &["for-each-ref", "--format=%(refname)", &prefix]).await?;
+    for old in expired_turns(&checkpoint_turns(&refs.stdout), retention) {
+        run_git(
+            git,
+            dir,
+            &["update-ref", "-d", &checkpoint_ref(session_id, *old)],
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Records the working tree before every user turn, whichever command or
+/// background task sends it, when the session asked for it and its sandbox
+/// lets codex write. Failures are logged rather than returned so they never
+/// hold up the user's message.
+pub(crate) struct TurnCheckpoints {
+    client_state: Arc<ClientState>,
+    git: Arc<GitRunner>,
+}
+
+impl TurnCheckpoints {
+    pub(crate) fn new(state: &CodexState) -> Self {
+        Self {
+            client_state: state.client_state.clone(),
+            git: state.git.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl TurnHook for TurnCheckpoints {
+    async fn before_turn(&self, session_id: &str, turn: u64) {
+        let Ok(session) = self.client_state.sessions.get(session_id).await else {
+            return;
+        };
+        if !session.config.auto_stash_before_turn {
+            return;
+        }
+        if let Ok(policy) = get_sandbox_policy(&self.client_state, session_id).await {
+            if policy.mode == SandboxMode::ReadOnly {
+                return;
+            }
+        }
+        let Some(dir) = session.cwd.filter(|cwd| cwd.is_dir()) else {
+            log::warn!(
+                "Failed to checkpoint turn {turn} of session {session_id}: no working directory"
+            );
+            return;
+        };
+        let retention = session
+            .config
+            .checkpoint_retention
+            .unwrap_or(DEFAULT_RETENTION)
+            .max(1);
+        if let Err(err) = record_checkpoint(&self.git, &dir, session_id, turn, retention).await {
+            log::warn!("Failed to checkpoint turn {turn} of session {session_id}: {err}");
+        }
+    }
+}
+
+/// Put the session's tracked files and index back to how they were before
+/// turn `turn_index` (from 1) started. HEAD is not moved, so commits made
+/// since then stay in the history and the rollback shows up as changes.
+#[tauri::command]
+pub async fn restore_turn_checkpoint(
+    state: State<'_, CodexState>,
+    session_id: String,
+    turn_index: u64,
+) -> Result<(), String> {
+    let session = state.client_state.sessions.get(&session_id).await?;
+    if session.turn_active {
+        return Err(format!(
+            "Session {session_id} is running a turn; interrupt it before restoring"
+        ));
+    }
+    let dir = session_working_dir(&state, &session_id).await?;
+    let name = checkpoint_ref(&session_id, turn_index);
+    let commit =
+        run_git_unchecked(&state.git, &dir, &["rev-parse", "--verify", "-q", &name]).await?;
+    if !commit.success() {
+        return Err(format!(
+            "No checkpoint for turn {turn_index} of session {session_id}"
+        ));
+    }
+    let commit = commit.stdout.trim().to_string();
+
+    run_git(&state.git, &dir, &["read-tree", "-u", "--reset", &commit]).await?;
+    // A stash commit's second parent holds the index as it was
+    let index = format!("{commit}^2");
+    let staged =
+        run_git_unchecked(&state.git, &dir, &["rev-parse", "--verify", "-q", &index]).await?;
+    let index_tree = if staged.success() { &index } else { &commit };
+    run_git(&state.git, &dir, &["read-tree", index_tree]).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expired_turns() {
+        let output = "refs/codexia/checkpoints/s/10\n\
+                      refs/codexia/checkpoints/s/2\n\
+                      refs/codexia/checkpoints/s/9\n";
+        let turns = checkpoint_turns(output);
+        assert_eq!(turns, vec![2, 9, 10]);
+        assert_eq!(expired_turns(&turns, 2), &[2]);
+        assert!(expired_turns(&turns, 5).is_empty());
+    }
+}
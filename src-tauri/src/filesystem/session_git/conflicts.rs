@@ -0,0 +1,128 @@
+use super::session_working_dir;
+use crate::codex_commands::CodexState;
+use crate::filesystem::git_runner::{run_git, GitRunner};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use tauri::State;
+
+/// Files larger than this are not scanned for conflict markers
+const MAX_SCAN_BYTES: u64 = 2 * 1024 * 1024;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictFile {
+    pub path: String,
+    /// Number of `<<<<<<<` ... `>>>>>>>` regions found in the working copy
+    pub regions: usize,
+    /// True when git itself reports the path as unmerged
+    pub unmerged: bool,
+}
+
+/// Paths listed by a git command run with `-z`. NUL-separated output keeps
+/// names with spaces, newlines or non-ASCII characters unquoted.
+async fn git_paths(runner: &GitRunner, dir: &Path, args: &[&str]) -> Result<Vec<String>, String> {
+    let output = run_git(runner, dir, args).await?;
+    Ok(output
+        .stdout
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Count complete conflict regions (`<<<<<<<`, `=======`, `>>>>>>>`)
+pub(crate) fn count_conflict_regions(content: &str) -> usize {
+    #[derive(PartialEq)]
+    enum Stage {
+        Outside,
+        Ours,
+        Theirs,
+    }
+
+    let mut stage = Stage::Outside;
+    let mut regions = 0;
+    for line in content.lines() {
+        if line.starts_with("<<<<<<<") {
+            stage = Stage::Ours;
+        } else if line.starts_with("=======") && stage == Stage::Ours {
+            stage = Stage::Theirs;
+        } else if line.starts_with(">>>>>>>") && stage == Stage::Theirs {
+            regions += 1;
+            stage = Stage::Outside;
+        }
+    }
+    regions
+}
+
+/// Report files that are unmerged or still contain conflict markers
+#[tauri::command]
+pub async fn detect_conflicts(
+    state: State<'_, CodexState>,
+    session_id: String,
+) -> Result<Vec<ConflictFile>, String> {
+    let dir = session_working_dir(&state, &session_id).await?;
+
+    let runner = &state.git;
+    let unmerged = git_paths(
+        runner,
+        &dir,
+        &["diff", "--name-only", "-z", "--diff-filter=U"],
+    )
+    .await?;
+    // A repository without commits has no HEAD to diff against
+    let mut candidates = git_paths(runner, &dir, &["diff", "--name-only", "-z", "HEAD"])
+        .await
+        .unwrap_or_default();
+    candidates.extend(
+        git_paths(
+            runner,
+            &dir,
+            &["ls-files", "--others", "--exclude-standard", "-z"],
+        )
+        .await?,
+    );
+    candidates.extend(unmerged.iter().cloned());
+
+    let mut conflicts: BTreeMap<String, ConflictFile> = BTreeMap::new();
+    for path in candidates {
+        if conflicts.contains_key(&path) {
+            continue;
+        }
+        let full_path = dir.join(&path);
+        let regions = match fs::metadata(&full_path) {
+            Ok(meta) if meta.is_file() && meta.len() <= MAX_SCAN_BYTES => fs::read(&full_path)
+                .map(|bytes| count_conflict_regions(&String::from_utf8_lossy(&bytes)))
+                .unwrap_or(0),
+            _ => 0,
+        };
+        let is_unmerged = unmerged.contains(&path);
+        if regions > 0 || is_unmerged {
+            conflicts.insert(
+                path.clone(),
+                ConflictFile {
+                    path,
+                    regions,
+                    unmerged: is_unmerged,
+                },
+            );
+        }
+    }
+
+    Ok(conflicts.into_values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_conflict_regions() {
+        let content = "a\n<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> branch\nb\n\
+                       <<<<<<< HEAD\nx\n=======\ny\n>>>>>>> other\n";
+        assert_eq!(count_conflict_regions(content), 2);
+        assert_eq!(count_conflict_regions("plain\n=======\ntext\n"), 0);
+        assert_eq!(count_conflict_regions("<<<<<<< HEAD\nunterminated\n"), 0);
+    }
+}
@@ -0,0 +1,213 @@
+use super::diff::{worktree_diff, IgnoreWhitespace};
+use super::structured::{parse_structured_diff, DiffLineKind, FileDiff};
+use super::summary::FileChangeStatus;
+use super::{resolve_in_dir, session_pathspecs, session_working_dir};
+use crate::codex_commands::CodexState;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::State;
+
+/// Hit counts by line number, by file path as written in the report
+type LineHits = HashMap<String, HashMap<u32, u64>>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileCoverage {
+    pub path: String,
+    /// Whether the report has the file at all; when it does not, every
+    /// changed line counts as without data
+    pub in_report: bool,
+    /// Added or modified lines in the diff
+    pub changed_lines: u32,
+    pub covered: u32,
+    pub uncovered: u32,
+    /// Changed lines the report has no data for, such as comments and blank
+    /// lines, which are not executable
+    pub without_data: u32,
+    pub uncovered_lines: Vec<u32>,
+}
+
+fn record_hits(hits: &mut LineHits, file: &str, line: u32, count: u64) {
+    let lines = hits.entry(file.to_string()).or_default();
+    let entry = lines.entry(line).or_default();
+    *entry = (*entry).max(count);
+}
+
+/// `SF:`/`DA:` records of an lcov tracefile
+fn parse_lcov(report: &str) -> LineHits {
+    let mut hits = LineHits::new();
+    let mut file: Option<&str> = None;
+    for line in report.lines() {
+        let line = line.trim();
+        if let Some(path) = line.strip_prefix("SF:") {
+            file = Some(path);
+        } else if line == "end_of_record" {
+            file = None;
+        } else if let (Some(file), Some(data)) = (file, line.strip_prefix("DA:")) {
+            let mut fields = data.split(',');
+            let number = fields.next().and_then(|n| n.parse().ok());
+            // Some tools write fractional or negative counts
+            let count = fields
+                .next()
+                .and_then(|c| c.parse::<f64>().ok())
+                .map(|c| c.max(0.0) as u64);
+            if let (Some(number), Some(count)) = (number, count) {
+                record_hits(&mut hits, file, number, count);
+            }
+        }
+    }
+    hits
+}
+
+/// Value of attribute `name` in the start tag `tag`
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let start = tag.find(&format!(" {name}=\""))? + name.len() + 3;
+    let len = tag[start..].find('"')?;
+    Some(&tag[start..start + len])
+}
+
+/// `<line number=".." hits="..">` elements of a Cobertura XML report, under
+/// the `filename` of their `<class>`
+fn parse_cobertura(report: &str) -> LineHits {
+    let mut hits = LineHits::new();
+    let mut file: Option<&str> = None;
+    for tag in report.split('<').filter_map(|rest| rest.split('>').next()) {
+        if tag.starts_with("class ") {
+            file = attribute(tag, "filename");
+        } else if tag.starts_with("/class") {
+            file = None;
+        } else if let (Some(file), true) = (file, tag.starts_with("line ")) {
+            let number = attribute(tag, "number").and_then(|n| n.parse().ok());
+            let count = attribute(tag, "hits").and_then(|c| c.parse().ok());
+            if let (Some(number), Some(count)) = (number, count) {
+                record_hits(&mut hits, file, number, count);
+            }
+        }
+    }
+    hits
+}
+
+/// Line hits of a report, Cobertura when it is XML and lcov otherwise
+fn parse_coverage(report: &str) -> LineHits {
+    if report.trim_start().starts_with('<') {
+        parse_cobertura(report)
+    } else {
+        parse_lcov(report)
+    }
+}
+
+/// The report's lines for `path`. Reports name files absolute or relative
+/// to wherever the tests ran, so names are matched by their trailing path
+/// components.
+fn hits_for<'a>(hits: &'a LineHits, path: &str) -> Option<&'a HashMap<u32, u64>> {
+    let path = Path::new(path);
+    hits.iter()
+        .filter(|(file, _)| {
+            let file = Path::new(file.as_str());
+            file.ends_with(path) || (file.is_relative() && path.ends_with(file))
+        })
+        // The longest name is the most specific match
+        .max_by_key(|(file, _)| file.len())
+        .map(|(_, lines)| lines)
+}
+
+fn file_coverage(file: &FileDiff, hits: &LineHits) -> FileCoverage {
+    let lines = hits_for(hits, &file.path);
+    let mut coverage = FileCoverage {
+        path: file.path.clone(),
+        in_report: lines.is_some(),
+        changed_lines: 0,
+        covered: 0,
+        uncovered: 0,
+        without_data: 0,
+        uncovered_lines: Vec::new(),
+    };
+    let changed = file
+        .hunks
+        .iter()
+        .flat_map(|hunk| &hunk.lines)
+        .filter(|line| line.kind == DiffLineKind::Added)
+        .filter_map(|line| line.new_lineno);
+    for number in changed {
+        coverage.changed_lines += 1;
+        match lines.and_then(|lines| lines.get(&number)) {
+            Some(0) => {
+                coverage.uncovered += 1;
+                coverage.uncovered_lines.push(number);
+            }
+            Some(_) => coverage.covered += 1,
+            None => coverage.without_data += 1,
+        }
+    }
+    coverage
+}
+
+/// How many of the lines changed in the session's diff the coverage report
+/// at `coverage_file`, relative to the working directory, shows as run.
+/// lcov tracefiles and Cobertura XML are understood; deleted and binary
+/// files are left out.
+#[tauri::command]
+pub async fn diff_test_coverage(
+    state: State<'_, CodexState>,
+    session_id: String,
+    coverage_file: String,
+) -> Result<Vec<FileCoverage>, String> {
+    let dir = session_working_dir(&state, &session_id).await?;
+    let report_path = resolve_in_dir(&dir, &coverage_file)?;
+    let report = tokio::fs::read(&report_path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", coverage_file, e))?;
+    let hits = parse_coverage(&String::from_utf8_lossy(&report));
+
+    let pathspecs = session_pathspecs(&state, &session_id).await?;
+    let diff = worktree_diff(&state.git, &dir, None, IgnoreWhitespace::None, &pathspecs).await?;
+    Ok(parse_structured_diff(&diff)
+        .iter()
+        .filter(|file| !file.binary && file.status != FileChangeStatus::Deleted)
+        .map(|file| file_coverage(file, &hits))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "diff --git a/src/lib.rs b/src/lib.rs\n\
+                        --- a/src/lib.rs\n\
+                        +++ b/src/lib.rs\n\
+                        @@ -1,3 +1,6 @@\n\
+                        \x20fn main() {\n\
+                        -    old();\n\
+                        +    // Call the new code\n\
+                        +    new();\n\
+                        +    other();\n\
+                        \x20}\n\
+                        +fn spare() {}\n";
+
+    #[test]
+    fn test_file_coverage() {
+        let file = &parse_structured_diff(DIFF)[0];
+        let lcov = "TN:\nSF:/work/project/src/lib.rs\nDA:1,1\nDA:3,4\nDA:4,0\nDA:6,0\n\
+                    end_of_record\nSF:/work/project/src/other.rs\nDA:3,1\nend_of_record\n";
+        let cobertura = r#"<?xml version="1.0" ?>
+            <coverage><packages><package name="app"><classes>
+            <class name="lib" filename="src/lib.rs"><lines>
+            <line number="1" hits="1"/><line number="3" hits="4"/>
+            <line number="4" hits="0"/><line number="6" hits="0" branch="false"/>
+            </lines></class></classes></package></packages></coverage>"#;
+
+        for report in [lcov, cobertura] {
+            let coverage = file_coverage(file, &parse_coverage(report));
+            assert!(coverage.in_report);
+            assert_eq!(coverage.changed_lines, 4);
+            assert_eq!(coverage.covered, 1);
+            assert_eq!(coverage.uncovered_lines, vec![4, 6]);
+            assert_eq!(coverage.without_data, 1);
+        }
+
+        let missing = file_coverage(file, &parse_coverage("SF:src/main.rs\nDA:1,1\n"));
+        assert!(!missing.in_report);
+        assert_eq!(missing.without_data, 4);
+    }
+}
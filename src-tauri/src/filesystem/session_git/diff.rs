@@ -0,0 +1,757 @@
+use super::{
+    ensure_session_path, has_head, resolve_in_dir, session_pathspecs, session_working_dir,
+    unified_arg,
+};
+use crate::codex_commands::CodexState;
+use crate::filesystem::git_runner::{run_git, run_git_diff, run_git_unchecked, GitRunner};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+/// Check that `reference` names a commit in the repository at `dir`
+async fn verify_ref(runner: &GitRunner, dir: &Path, reference: &str) -> Result<(), String> {
+    let trimmed = reference.trim();
+    // Refuse anything git could parse as an option
+    if trimmed.is_empty() || trimmed.starts_with('-') {
+        return Err(format!("Invalid git ref: {reference:?}"));
+    }
+
+    let commit = format!("{trimmed}^{{commit}}");
+    let output =
+        run_git_unchecked(runner, dir, &["rev-parse", "--verify", "--quiet", &commit]).await?;
+
+    if !output.success() {
+        return Err(format!("Unknown git ref: {trimmed}"));
+    }
+    Ok(())
+}
+
+/// Diff two arbitrary refs (commits, branches, tags) of the session's repo
+#[tauri::command]
+pub async fn diff_refs(
+    state: State<'_, CodexState>,
+    session_id: String,
+    ref_a: String,
+    ref_b: String,
+    context_lines: Option<u32>,
+) -> Result<String, String> {
+    let dir = session_working_dir(&state, &session_id).await?;
+    verify_ref(&state.git, &dir, &ref_a).await?;
+    verify_ref(&state.git, &dir, &ref_b).await?;
+
+    let unified = unified_arg(context_lines);
+    let output = run_git(
+        &state.git,
+        &dir,
+        &["diff", &unified, ref_a.trim(), ref_b.trim(), "--"],
+    )
+    .await?;
+
+    Ok(output.stdout)
+}
+
+/// Which whitespace-only changes to leave out of a diff
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IgnoreWhitespace {
+    #[default]
+    None,
+    /// `-w`: ignore all whitespace
+    IgnoreAll,
+    /// `-b`: ignore changes in the amount of whitespace
+    IgnoreChange,
+    /// `--ignore-space-at-eol`
+    IgnoreEol,
+}
+
+impl IgnoreWhitespace {
+    fn flag(self) -> Option<&'static str> {
+        match self {
+            IgnoreWhitespace::None => None,
+            IgnoreWhitespace::IgnoreAll => Some("-w"),
+            IgnoreWhitespace::IgnoreChange => Some("-b"),
+            IgnoreWhitespace::IgnoreEol => Some("--ignore-space-at-eol"),
+        }
+    }
+}
+
+/// Combined diff of everything codex changed in `dir`: tracked changes
+/// against HEAD plus untracked files as new-file diffs. `--binary` keeps the
+/// result applicable with `git apply`, unless whitespace changes are ignored:
+/// that patch is for reading only. Non-empty `pathspecs` limit the diff to
+/// those paths.
+pub(crate) async fn worktree_diff(
+    runner: &GitRunner,
+    dir: &Path,
+    context_lines: Option<u32>,
+    ignore_whitespace: IgnoreWhitespace,
+    pathspecs: &[String],
+) -> Result<String, String> {
+    let unified = unified_arg(context_lines);
+    let mut base_args = vec!["diff", "--binary", unified.as_str()];
+    base_args.extend(ignore_whitespace.flag());
+    let pathspecs: Vec<&str> = pathspecs.iter().map(String::as_str).collect();
+
+    // A repository without commits has no HEAD; diff against the index then
+    let base: &[&str] = if has_head(runner, dir).await? {
+        &["HEAD", "--"]
+    } else {
+        &["--"]
+    };
+    let tracked_args = [&base_args[..], base, &pathspecs[..]].concat();
+    let mut patch = run_git(runner, dir, &tracked_args).await?.stdout;
+
+    let mut ls_args = vec!["ls-files", "--others", "--exclude-standard", "-z", "--"];
+    ls_args.extend(&pathspecs);
+    let untracked = run_git(runner, dir, &ls_args).await?;
+    for path in untracked.stdout.split('\0').filter(|p| !p.is_empty()) {
+        let args = [&base_args[..], &["--no-index", "--", "/dev/null", path][..]].concat();
+        let output = run_git_diff(runner, dir, &args).await?;
+        patch.push_str(&output.stdout);
+    }
+
+    Ok(patch)
+}
+
+/// Diff of the session's working tree, including untracked files
+#[tauri::command]
+pub async fn git_worktree_diff(
+    state: State<'_, CodexState>,
+    session_id: String,
+    context_lines: Option<u32>,
+    ignore_whitespace: Option<IgnoreWhitespace>,
+) -> Result<String, String> {
+    let dir = session_working_dir(&state, &session_id).await?;
+    let pathspecs = session_pathspecs(&state, &session_id).await?;
+    worktree_diff(
+        &state.git,
+        &dir,
+        context_lines,
+        ignore_whitespace.unwrap_or_default(),
+        &pathspecs,
+    )
+    .await
+}
+
+fn nul_separated(output: &str) -> BTreeSet<&str> {
+    output.split('\0').filter(|path| !path.is_empty()).collect()
+}
+
+/// Tracked and untracked, not ignored, files present in the working tree.
+/// Files whose content on disk matches the index map to their blob id, so
+/// they can be compared without being read; the others map to `None`.
+async fn worktree_files(
+    runner: &GitRunner,
+    dir: &Path,
+) -> Result<BTreeMap<String, Option<String>>, String> {
+    let list = |args: &'static [&'static str]| run_git(runner, dir, args);
+    let staged = list(&["ls-files", "--stage", "-z"]).await?.stdout;
+    let modified = list(&["ls-files", "--modified", "-z"]).await?.stdout;
+    let deleted = list(&["ls-files", "--deleted", "-z"]).await?.stdout;
+    let untracked = list(&["ls-files", "--others", "--exclude-standard", "-z"])
+        .await?
+        .stdout;
+    let (modified, deleted) = (nul_separated(&modified), nul_separated(&deleted));
+
+    let mut files = BTreeMap::new();
+    // Entries read "<mode> <object> <stage>\t<path>"
+    for entry in staged.split('\0') {
+        let Some((info, path)) = entry.split_once('\t') else {
+            continue;
+        };
+        let mut fields = info.split(' ');
+        let (Some(mode), Some(object), stage) = (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        // Submodules are not files of this tree
+        if mode == "160000" || deleted.contains(path) {
+            continue;
+        }
+        let clean = stage == Some("0") && !modified.contains(path);
+        files.insert(path.to_string(), clean.then(|| object.to_string()));
+    }
+    for path in nul_separated(&untracked) {
+        files.insert(path.to_string(), None);
+    }
+    Ok(files)
+}
+
+/// Read into `buf` until it is full or the reader is exhausted
+fn read_chunk(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..])? {
+            0 => break,
+            read => filled += read,
+        }
+    }
+    Ok(filled)
+}
+
+/// Whether two files have the same contents, stopping at the first
+/// difference
+fn same_contents(a: &Path, b: &Path) -> bool {
+    let (Ok(meta_a), Ok(meta_b)) = (fs::metadata(a), fs::metadata(b)) else {
+        return false;
+    };
+    if meta_a.len() != meta_b.len() {
+        return false;
+    }
+    let (Ok(mut file_a), Ok(mut file_b)) = (File::open(a), File::open(b)) else {
+        return false;
+    };
+    let (mut buf_a, mut buf_b) = (vec![0; 64 * 1024], vec![0; 64 * 1024]);
+    loop {
+        match (
+            read_chunk(&mut file_a, &mut buf_a),
+            read_chunk(&mut file_b, &mut buf_b),
+        ) {
+            (Ok(0), Ok(0)) => return true,
+            (Ok(len_a), Ok(len_b)) if buf_a[..len_a] == buf_b[..len_b] => {}
+            _ => return false,
+        }
+    }
+}
+
+/// Copy `paths` of `dir` into `target`, keeping their relative paths
+fn copy_files(dir: &Path, target: &Path, paths: &[&String]) -> std::io::Result<()> {
+    fs::create_dir_all(target)?;
+    for path in paths {
+        let copy = target.join(path);
+        if let Some(parent) = copy.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(dir.join(path), copy)?;
+    }
+    Ok(())
+}
+
+/// `git diff --no-index --no-prefix a b` names a file present on one side
+/// only by that side's path twice (`diff --git b/new.rs b/new.rs`). Give
+/// each such header an `a/` and a `b/` side like the `---`/`+++` lines.
+/// Without renames both paths are the same length, so the second one is
+/// found by position even when the path contains spaces.
+fn relabel(patch: &str) -> String {
+    let side = |path: &str, prefix: char| match path.strip_prefix('"') {
+        Some(quoted) => format!("\"{prefix}{}", &quoted[1..]),
+        None => format!("{prefix}{}", &path[1..]),
+    };
+    patch
+        .split_inclusive('\n')
+        .map(|line| {
+            let Some(paths) = line.strip_prefix("diff --git ") else {
+                return line.to_string();
+            };
+            let paths = paths.trim_end_matches('\n');
+            let second = &paths[paths.len().div_ceil(2)..];
+            let ending = if line.ends_with('\n') { "\n" } else { "" };
+            format!(
+                "diff --git {} {}{ending}",
+                side(second, 'a'),
+                side(second, 'b')
+            )
+        })
+        .collect()
+}
+
+/// Diff the working trees of two sessions, e.g. the same task run in two
+/// worktrees or clones. Files are compared as they are on disk, tracked and
+/// untracked alike, so the repositories need not share any history; `a/` is
+/// `session_a`, `b/` is `session_b`.
+///
+/// Files both index as the same blob are equal without being read. The
+/// files that differ are copied side by side into a temporary directory
+/// and diffed by one `git diff --no-index`.
+#[tauri::command]
+pub async fn diff_sessions(
+    state: State<'_, CodexState>,
+    session_a: String,
+    session_b: String,
+    context_lines: Option<u32>,
+) -> Result<String, String> {
+    let dir_a = session_working_dir(&state, &session_a).await?;
+    let dir_b = session_working_dir(&state, &session_b).await?;
+    let runner = &state.git;
+    let files_a = worktree_files(runner, &dir_a).await?;
+    let files_b = worktree_files(runner, &dir_b).await?;
+
+    let staging = tokio::task::spawn_blocking(move || -> std::io::Result<_> {
+        let differing: Vec<&String> = files_a
+            .keys()
+            .chain(files_b.keys().filter(|path| !files_a.contains_key(*path)))
+            .filter(|path| match (files_a.get(*path), files_b.get(*path)) {
+                (Some(Some(blob_a)), Some(Some(blob_b))) if blob_a.len() == blob_b.len() => {
+                    blob_a != blob_b
+                }
+                (Some(_), Some(_)) => !same_contents(&dir_a.join(path), &dir_b.join(path)),
+                _ => true,
+            })
+            .collect();
+        if differing.is_empty() {
+            return Ok(None);
+        }
+        let staging = tempfile::tempdir()?;
+        let on_side = |files: &BTreeMap<String, Option<String>>| -> Vec<&String> {
+            differing
+                .iter()
+                .copied()
+                .filter(|path| files.contains_key(*path))
+                .collect()
+        };
+        copy_files(&dir_a, &staging.path().join("a"), &on_side(&files_a))?;
+        copy_files(&dir_b, &staging.path().join("b"), &on_side(&files_b))?;
+        Ok(Some(staging))
+    })
+    .await
+    .map_err(|e| format!("Failed to compare the working trees: {e}"))?
+    .map_err(|e| format!("Failed to stage the differing files: {e}"))?;
+    let Some(staging) = staging else {
+        return Ok(String::new());
+    };
+
+    let unified = unified_arg(context_lines);
+    let output = run_git_diff(
+        runner,
+        staging.path(),
+        &[
+            "diff",
+            "--no-index",
+            "--no-prefix",
+            "--no-renames",
+            "--binary",
+            &unified,
+            "--",
+            "a",
+            "b",
+        ],
+    )
+    .await;
+    let _ = tokio::task::spawn_blocking(move || staging.close()).await;
+    Ok(relabel(&output?.stdout))
+}
+
+/// Paths of every changed and untracked file, deduped and sorted, without
+/// computing any diff bodies
+#[tauri::command]
+pub async fn list_changed_files(
+    state: State<'_, CodexState>,
+    session_id: String,
+) -> Result<Vec<String>, String> {
+    let dir = session_working_dir(&state, &session_id).await?;
+    let runner = &state.git;
+
+    let mut listings = Vec::new();
+    if has_head(runner, &dir).await? {
+        let args = ["diff", "--name-only", "-z", "HEAD", "--"];
+        listings.push(run_git(runner, &dir, &args).await?.stdout);
+    } else {
+        // No HEAD yet: everything staged or modified is new
+        for args in [
+            &["diff", "--name-only", "-z", "--cached", "--"][..],
+            &["diff", "--name-only", "-z", "--"][..],
+        ] {
+            listings.push(run_git(runner, &dir, args).await?.stdout);
+        }
+    }
+    listings.push(
+        run_git(runner, &dir, &["ls-files", "--others", "--exclude-standard", "-z"])
+            .await?
+            .stdout,
+    );
+
+    let paths: BTreeSet<String> = listings
+        .iter()
+        .flat_map(|listing| listing.split('\0'))
+        .filter(|path| !path.is_empty())
+        .map(String::from)
+        .collect();
+    Ok(paths.into_iter().collect())
+}
+
+/// Where a changed file came from, as reported by the file tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileDiffKind {
+    Tracked,
+    Untracked,
+    Renamed,
+}
+
+impl FileDiffKind {
+    /// Accepts porcelain codes ("M", "??", "R100") as well as words
+    fn from_status(status: &str) -> FileDiffKind {
+        let status = status.trim().to_lowercase();
+        if status == "??" || status == "untracked" {
+            FileDiffKind::Untracked
+        } else if status.starts_with('r') {
+            FileDiffKind::Renamed
+        } else {
+            FileDiffKind::Tracked
+        }
+    }
+}
+
+/// Previous path of `path` in `git diff --name-status -z` output. Entries
+/// are "R100\0old\0new\0" for renames and copies, "M\0path\0" otherwise.
+fn find_rename_source(name_status: &str, path: &str) -> Option<String> {
+    let mut fields = name_status.split('\0');
+    while let Some(status) = fields.next() {
+        if status.starts_with('R') || status.starts_with('C') {
+            let (old, new) = (fields.next()?, fields.next()?);
+            if new == path {
+                return Some(old.to_string());
+            }
+        } else {
+            fields.next()?;
+        }
+    }
+    None
+}
+
+/// Previous path of a file renamed since HEAD, if git detects the rename
+async fn rename_source(
+    runner: &GitRunner,
+    dir: &Path,
+    path: &str,
+) -> Result<Option<String>, String> {
+    let output = run_git(
+        runner,
+        dir,
+        &["diff", "--name-status", "-M", "-z", "HEAD", "--"],
+    )
+    .await?;
+    Ok(find_rename_source(&output.stdout, path))
+}
+
+/// Diff of one file of the session's working tree. `status` is the file's
+/// change status from the tree view; without it the file is diffed as a
+/// tracked file, or as a new file when git does not track it.
+#[tauri::command]
+pub async fn get_file_diff(
+    state: State<'_, CodexState>,
+    session_id: String,
+    path: String,
+    status: Option<String>,
+    context_lines: Option<u32>,
+) -> Result<String, String> {
+    let dir = session_working_dir(&state, &session_id).await?;
+    resolve_in_dir(&dir, &path)?;
+    ensure_session_path(&state, &session_id, &path).await?;
+    let runner = &state.git;
+    let unified = unified_arg(context_lines);
+
+    let kind = match status.as_deref() {
+        Some(status) => FileDiffKind::from_status(status),
+        None => {
+            let untracked = run_git(
+                runner,
+                &dir,
+                &["ls-files", "--others", "--exclude-standard", "--", &path],
+            )
+            .await?;
+            if untracked.stdout.trim().is_empty() {
+                FileDiffKind::Tracked
+            } else {
+                FileDiffKind::Untracked
+            }
+        }
+    };
+
+    if kind == FileDiffKind::Untracked {
+        let output = run_git_diff(
+            runner,
+            &dir,
+            &["diff", &unified, "--no-index", "--", "/dev/null", &path],
+        )
+        .await?;
+        return Ok(output.stdout);
+    }
+
+    let mut paths = vec![path.clone()];
+    if kind == FileDiffKind::Renamed {
+        if let Some(old) = rename_source(runner, &dir, &path).await? {
+            paths.insert(0, old);
+        }
+    }
+    let mut args = vec!["diff", "-M", unified.as_str()];
+    // No HEAD yet: diff against the index
+    if has_head(runner, &dir).await? {
+        args.push("HEAD");
+    }
+    args.push("--");
+    args.extend(paths.iter().map(String::as_str));
+    Ok(run_git(runner, &dir, &args).await?.stdout)
+}
+
+/// A file to act on, as listed in the tree view
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffTargetPayload {
+    /// Relative to the session's working directory
+    pub path: String,
+    /// Change status from the tree view; looked up in git when absent
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevertFailure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevertFilesResult {
+    pub reverted: Vec<String>,
+    pub failed: Vec<RevertFailure>,
+}
+
+impl RevertFilesResult {
+    fn fail(&mut self, paths: impl IntoIterator<Item = String>, error: &str) {
+        self.failed.extend(paths.into_iter().map(|path| RevertFailure {
+            path,
+            error: error.to_string(),
+        }));
+    }
+}
+
+/// Check out `paths` from HEAD in one call. When that fails, e.g. because
+/// one of them is not in HEAD, each is retried alone so the others are
+/// still reverted and the failure is reported against the right file.
+async fn checkout_from_head(
+    runner: &GitRunner,
+    dir: &Path,
+    paths: Vec<String>,
+    result: &mut RevertFilesResult,
+) {
+    if paths.is_empty() {
+        return;
+    }
+    let mut args = vec!["checkout", "HEAD", "--"];
+    args.extend(paths.iter().map(String::as_str));
+    if run_git(runner, dir, &args).await.is_ok() {
+        result.reverted.extend(paths);
+        return;
+    }
+    for path in paths {
+        match run_git(runner, dir, &["checkout", "HEAD", "--", &path]).await {
+            Ok(_) => result.reverted.push(path),
+            Err(error) => result.fail([path], &error),
+        }
+    }
+}
+
+/// Remove files that are not in HEAD from the index and the disk
+async fn delete_new_files(
+    runner: &GitRunner,
+    dir: &Path,
+    paths: Vec<String>,
+    result: &mut RevertFilesResult,
+) {
+    if paths.is_empty() {
+        return;
+    }
+    let mut args = vec!["rm", "--cached", "-r", "--quiet", "--ignore-unmatch", "--"];
+    args.extend(paths.iter().map(String::as_str));
+    if let Err(error) = run_git(runner, dir, &args).await {
+        result.fail(paths, &error);
+        return;
+    }
+    for path in paths {
+        let full_path = dir.join(&path);
+        let removed = if full_path.is_dir() {
+            tokio::fs::remove_dir_all(&full_path).await
+        } else {
+            tokio::fs::remove_file(&full_path).await
+        };
+        match removed {
+            Ok(()) => result.reverted.push(path),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => result.reverted.push(path),
+            Err(err) => result.fail([path], &format!("Failed to delete: {err}")),
+        }
+    }
+}
+
+/// Discard the changes of several files at once: tracked files are checked
+/// out from HEAD together and untracked files deleted, a rename reverting
+/// both its old and new path. Each file reports its own outcome.
+#[tauri::command]
+pub async fn revert_files(
+    state: State<'_, CodexState>,
+    session_id: String,
+    targets: Vec<DiffTargetPayload>,
+) -> Result<RevertFilesResult, String> {
+    if targets.is_empty() {
+        return Err("No files given".to_string());
+    }
+    let dir = session_working_dir(&state, &session_id).await?;
+    let runner = &state.git;
+    let mut result = RevertFilesResult::default();
+
+    // Reverting is destructive, so it is held to the session's allowed
+    // paths like the diffs that list the files
+    let mut checked = Vec::with_capacity(targets.len());
+    for target in targets {
+        let allowed = match resolve_in_dir(&dir, &target.path) {
+            Ok(_) => ensure_session_path(&state, &session_id, &target.path).await,
+            Err(error) => Err(error),
+        };
+        match allowed {
+            Ok(()) => checked.push(target),
+            Err(error) => result.fail([target.path], &error),
+        }
+    }
+    let mut targets = checked;
+
+    // Classify files without a status with one listing of untracked files
+    let unclassified: Vec<&str> = targets
+        .iter()
+        .filter(|target| target.status.is_none())
+        .map(|target| target.path.as_str())
+        .collect();
+    if !unclassified.is_empty() {
+        let mut args = vec!["ls-files", "--others", "--exclude-standard", "-z", "--"];
+        args.extend(unclassified);
+        let untracked = run_git(runner, &dir, &args).await?.stdout;
+        let untracked: BTreeSet<&str> = untracked.split('\0').collect();
+        for target in targets.iter_mut().filter(|target| target.status.is_none()) {
+            let status = if untracked.contains(target.path.as_str()) {
+                "??"
+            } else {
+                "M"
+            };
+            target.status = Some(status.to_string());
+        }
+    }
+
+    let mut tracked = Vec::new();
+    let mut new_files = Vec::new();
+    let mut renames = Vec::new();
+    for target in targets {
+        match FileDiffKind::from_status(target.status.as_deref().unwrap_or_default()) {
+            FileDiffKind::Tracked => tracked.push(target.path),
+            FileDiffKind::Untracked => new_files.push(target.path),
+            FileDiffKind::Renamed => renames.push(target.path),
+        }
+    }
+
+    if !renames.is_empty() {
+        let name_status = run_git(
+            runner,
+            &dir,
+            &["diff", "--name-status", "-M", "-z", "HEAD", "--"],
+        )
+        .await?
+        .stdout;
+        for path in renames {
+            match find_rename_source(&name_status, &path) {
+                Some(old) => {
+                    tracked.push(old);
+                    new_files.push(path);
+                }
+                // git no longer pairs them; treat it as an edit
+                None => tracked.push(path),
+            }
+        }
+    }
+
+    checkout_from_head(runner, &dir, tracked, &mut result).await;
+    delete_new_files(runner, &dir, new_files, &mut result).await;
+    result.reverted.sort();
+    result.failed.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(result)
+}
+
+fn expand_output_path(path: &str) -> Result<PathBuf, String> {
+    match path.strip_prefix("~/") {
+        Some(rest) => {
+            let home = dirs::home_dir().ok_or_else(|| "Cannot find home directory".to_string())?;
+            Ok(home.join(rest))
+        }
+        None => Ok(PathBuf::from(path)),
+    }
+}
+
+/// Write the session's working tree diff to a `.patch` file that applies
+/// with `git apply`. An existing file is only replaced when `overwrite` is set.
+#[tauri::command]
+pub async fn save_worktree_patch(
+    state: State<'_, CodexState>,
+    session_id: String,
+    output_path: String,
+    overwrite: Option<bool>,
+) -> Result<(), String> {
+    let output_path = expand_output_path(&output_path)?;
+    if output_path.is_dir() {
+        return Err(format!("{} is a directory", output_path.display()));
+    }
+    if output_path.exists() && !overwrite.unwrap_or(false) {
+        return Err(format!("{} already exists", output_path.display()));
+    }
+    match output_path.parent() {
+        Some(parent) if parent.as_os_str().is_empty() || parent.is_dir() => {}
+        _ => {
+            return Err(format!(
+                "Directory for {} does not exist",
+                output_path.display()
+            ))
+        }
+    }
+
+    let dir = session_working_dir(&state, &session_id).await?;
+    let pathspecs = session_pathspecs(&state, &session_id).await?;
+    let mut patch =
+        worktree_diff(&state.git, &dir, None, IgnoreWhitespace::None, &pathspecs).await?;
+    if patch.trim().is_empty() {
+        return Err("No changes to export".to_string());
+    }
+    if !patch.ends_with('\n') {
+        patch.push('\n');
+    }
+
+    tokio::fs::write(&output_path, patch)
+        .await
+        .map_err(|e| format!("Failed to write {}: {}", output_path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relabel() {
+        let patch = "diff --git a/old.rs a/old.rs\n\
+                     deleted file mode 100644\n\
+                     diff --git b/my notes.md b/my notes.md\n\
+                     +diff --git b/x b/x\n\
+                     diff --git \"b/caf\\303\\251\" \"b/caf\\303\\251\"\n\
+                     diff --git a/src/lib.rs b/src/lib.rs\n";
+        assert_eq!(
+            relabel(patch),
+            "diff --git a/old.rs b/old.rs\n\
+             deleted file mode 100644\n\
+             diff --git a/my notes.md b/my notes.md\n\
+             +diff --git b/x b/x\n\
+             diff --git \"a/caf\\303\\251\" \"b/caf\\303\\251\"\n\
+             diff --git a/src/lib.rs b/src/lib.rs\n"
+        );
+    }
+
+    #[test]
+    fn test_find_rename_source() {
+        let output = "M\0src/lib.rs\0R087\0src/old.rs\0src/new.rs\0A\0README.md\0";
+        assert_eq!(
+            find_rename_source(output, "src/new.rs"),
+            Some("src/old.rs".to_string())
+        );
+        assert_eq!(find_rename_source(output, "src/lib.rs"), None);
+        assert_eq!(find_rename_source(output, "README.md"), None);
+        assert_eq!(FileDiffKind::from_status("R100"), FileDiffKind::Renamed);
+        assert_eq!(FileDiffKind::from_status("??"), FileDiffKind::Untracked);
+        assert_eq!(FileDiffKind::from_status("M"), FileDiffKind::Tracked);
+    }
+}
@@ -0,0 +1,193 @@
+use super::{has_head, session_working_dir};
+use crate::codex_commands::CodexState;
+use crate::filesystem::git_runner::run_git;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::State;
+
+/// A tracked file that differs from HEAD
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackedEntry {
+    pub path: String,
+    /// Path before a rename or copy
+    pub old_path: Option<String>,
+    /// git's status letter: M, A, D, R, C or T
+    pub status: String,
+    /// `None` for binary files
+    pub additions: Option<u64>,
+    pub deletions: Option<u64>,
+    /// Old and new file mode when the permissions changed, with or without
+    /// a content change, e.g. ("100644", "100755")
+    pub mode_change: Option<(String, String)>,
+    /// A symlink before or after the change. Its "content" is the target
+    /// path, so it has no line counts and `symlink_target` is shown instead
+    pub is_symlink: bool,
+    /// Where the symlink points in the working tree; `None` once deleted
+    pub symlink_target: Option<String>,
+}
+
+/// Mode git reports for the missing side of an added or deleted file
+const NO_MODE: &str = "000000";
+/// Mode git records for symlinks
+const SYMLINK_MODE: &str = "120000";
+
+/// Parse `git diff --raw -z` output. Each entry is
+/// ":old_mode new_mode old_sha new_sha STATUS\0path\0", with a second path
+/// for renames and copies.
+fn parse_raw(output: &str) -> Vec<TrackedEntry> {
+    let mut entries = Vec::new();
+    let mut fields = output.split('\0');
+    while let Some(header) = fields.next() {
+        let Some(header) = header.strip_prefix(':') else {
+            continue;
+        };
+        let parts: Vec<&str> = header.split_whitespace().collect();
+        let [old_mode, new_mode, _, _, status] = parts[..] else {
+            continue;
+        };
+        let letter = &status[..1];
+        let (old_path, path) = if letter == "R" || letter == "C" {
+            let (Some(old), Some(new)) = (fields.next(), fields.next()) else {
+                break;
+            };
+            (Some(old.to_string()), new.to_string())
+        } else {
+            let Some(path) = fields.next() else {
+                break;
+            };
+            (None, path.to_string())
+        };
+        let mode_change = (old_mode != new_mode && old_mode != NO_MODE && new_mode != NO_MODE)
+            .then(|| (old_mode.to_string(), new_mode.to_string()));
+        entries.push(TrackedEntry {
+            path,
+            old_path,
+            status: letter.to_string(),
+            additions: None,
+            deletions: None,
+            mode_change,
+            is_symlink: old_mode == SYMLINK_MODE || new_mode == SYMLINK_MODE,
+            symlink_target: None,
+        });
+    }
+    entries
+}
+
+/// Parse `git diff --numstat -z` output into line counts by (new) path.
+/// Renames are "added\tdeleted\t\0old\0new\0"; binary files count as "-".
+fn parse_numstat(output: &str) -> HashMap<String, (Option<u64>, Option<u64>)> {
+    let mut counts = HashMap::new();
+    let mut fields = output.split('\0');
+    while let Some(record) = fields.next() {
+        let mut parts = record.splitn(3, '\t');
+        let (Some(added), Some(deleted), Some(path)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        let path = if path.is_empty() {
+            // Rename: skip the old path, keep the new one
+            let _ = fields.next();
+            match fields.next() {
+                Some(new) => new,
+                None => break,
+            }
+        } else {
+            path
+        };
+        counts.insert(path.to_string(), (added.parse().ok(), deleted.parse().ok()));
+    }
+    counts
+}
+
+/// Tracked files of the session's repository that differ from HEAD, with
+/// line counts and permission changes
+#[tauri::command]
+pub async fn git_tracked_entries(
+    state: State<'_, CodexState>,
+    session_id: String,
+) -> Result<Vec<TrackedEntry>, String> {
+    let dir = session_working_dir(&state, &session_id).await?;
+    let runner = &state.git;
+
+    // A repository without commits has no HEAD; compare the index instead
+    let base = if has_head(runner, &dir).await? {
+        "HEAD"
+    } else {
+        "--cached"
+    };
+    let raw = run_git(runner, &dir, &["diff", "--raw", "-z", "-M", base, "--"]).await?;
+    let numstat = run_git(runner, &dir, &["diff", "--numstat", "-z", "-M", base, "--"]).await?;
+
+    let root = run_git(runner, &dir, &["rev-parse", "--show-toplevel"]).await?;
+    let root = PathBuf::from(root.stdout.trim());
+
+    let counts = parse_numstat(&numstat.stdout);
+    let mut entries = parse_raw(&raw.stdout);
+    for entry in &mut entries {
+        if entry.is_symlink {
+            entry.symlink_target = tokio::fs::read_link(root.join(&entry.path))
+                .await
+                .ok()
+                .map(|target| target.to_string_lossy().into_owned());
+            continue;
+        }
+        match counts.get(&entry.path) {
+            Some((additions, deletions)) => {
+                entry.additions = *additions;
+                entry.deletions = *deletions;
+            }
+            // A pure mode change has no lines to count
+            None if entry.mode_change.is_some() => {
+                entry.additions = Some(0);
+                entry.deletions = Some(0);
+            }
+            None => {}
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_raw_mode_only_change() {
+        let raw = ":100644 100755 1111111 0000000 M\0scripts/build.sh\0\
+                   :100644 100644 2222222 0000000 M\0src/lib.rs\0\
+                   :000000 100644 0000000 3333333 A\0NEW.md\0\
+                   :100644 100644 4444444 5555555 R090\0old.rs\0new.rs\0";
+        let entries = parse_raw(raw);
+        assert_eq!(entries.len(), 4);
+        assert_eq!(
+            entries[0].mode_change,
+            Some(("100644".to_string(), "100755".to_string()))
+        );
+        assert_eq!(entries[1].mode_change, None);
+        assert_eq!(entries[2].mode_change, None);
+        assert_eq!(entries[3].status, "R");
+        assert_eq!(entries[3].old_path.as_deref(), Some("old.rs"));
+        assert_eq!(entries[3].path, "new.rs");
+        assert!(entries.iter().all(|entry| !entry.is_symlink));
+
+        let counts = parse_numstat("0\t0\tscripts/build.sh\03\t1\tsrc/lib.rs\0-\t-\t\0old.rs\0new.rs\0");
+        assert_eq!(counts["scripts/build.sh"], (Some(0), Some(0)));
+        assert_eq!(counts["src/lib.rs"], (Some(3), Some(1)));
+        assert_eq!(counts["new.rs"], (None, None));
+    }
+
+    #[test]
+    fn test_parse_raw_symlinks() {
+        let raw = ":000000 120000 0000000 1111111 A\0docs/latest\0\
+                   :120000 120000 2222222 3333333 M\0config/current\0\
+                   :100644 120000 4444444 5555555 T\0README\0";
+        let entries = parse_raw(raw);
+        assert_eq!(entries.len(), 3);
+        assert!(entries.iter().all(|entry| entry.is_symlink));
+        assert_eq!(entries[0].status, "A");
+        assert_eq!(entries[1].mode_change, None);
+        assert_eq!(entries[2].status, "T");
+    }
+}
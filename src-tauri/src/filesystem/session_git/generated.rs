@@ -0,0 +1,189 @@
+use super::summary::FileChangeStatus;
+use crate::codex_commands::CodexState;
+use codex_client::session::session_artifacts_dir;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+use tauri::State;
+
+/// Files above this size are flagged regardless of their name
+const DEFAULT_SIZE_THRESHOLD: u64 = 1024 * 1024;
+
+/// What makes a changed file look generated. A pattern ending in `/`
+/// matches a directory anywhere in the path; other patterns match the file
+/// name, or the whole path when they contain a `/`, with `*` standing for
+/// any run of characters.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneratedFileRules {
+    pub patterns: Vec<String>,
+    pub size_threshold_bytes: u64,
+}
+
+impl Default for GeneratedFileRules {
+    fn default() -> Self {
+        let patterns = [
+            "*.lock",
+            "package-lock.json",
+            "pnpm-lock.yaml",
+            "dist/",
+            "build/",
+            "node_modules/",
+            "target/",
+            "*.min.js",
+            "*.min.css",
+            "*.map",
+        ];
+        Self {
+            patterns: patterns.into_iter().map(String::from).collect(),
+            size_threshold_bytes: DEFAULT_SIZE_THRESHOLD,
+        }
+    }
+}
+
+/// Rules shared by every session; projects tune them from the settings
+#[derive(Default)]
+pub(crate) struct GeneratedFileRulesStore(RwLock<GeneratedFileRules>);
+
+impl GeneratedFileRulesStore {
+    fn get(&self) -> GeneratedFileRules {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn set(&self, rules: GeneratedFileRules) {
+        *self.0.write().unwrap_or_else(|e| e.into_inner()) = rules;
+    }
+}
+
+/// `*` wildcard match of the whole of `text`
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Whether `path`, relative to the repository root with `/` separators,
+/// matches `pattern` as described on `GeneratedFileRules`
+fn matches_pattern(pattern: &str, path: &str) -> bool {
+    if let Some(dir) = pattern.strip_suffix('/') {
+        let mut components: Vec<&str> = path.split('/').collect();
+        components.pop();
+        return components.iter().any(|component| wildcard_match(dir, component));
+    }
+    if pattern.contains('/') {
+        return wildcard_match(pattern, path);
+    }
+    let name = path.rsplit('/').next().unwrap_or(path);
+    wildcard_match(pattern, name)
+}
+
+/// A changed file with what the UI needs to collapse it by default
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorktreeSummaryEntry {
+    pub path: String,
+    #[serde(flatten)]
+    pub status: FileChangeStatus,
+    /// `None` for deleted files
+    pub size_bytes: Option<u64>,
+    pub likely_generated: bool,
+    /// Inside the session's artifacts directory
+    pub artifact: bool,
+}
+
+pub(crate) async fn worktree_summary_entries(
+    state: &CodexState,
+    session_id: &str,
+) -> Result<Vec<WorktreeSummaryEntry>, String> {
+    let summary = state.worktree_summaries.get_or_load(state, session_id).await?;
+    let rules = state.generated_file_rules.get();
+    let session = state.client_state.sessions.get(session_id).await?;
+    // Repository-relative prefix of the artifacts directory, when it is in
+    // the repository
+    let artifacts_prefix = session_artifacts_dir(&session).and_then(|dir| {
+        let relative = dir.strip_prefix(&summary.root).ok()?;
+        Some(format!("{}/", relative.to_string_lossy().replace('\\', "/")))
+    });
+
+    let mut entries = Vec::with_capacity(summary.files.len());
+    for (path, status) in &summary.files {
+        let size_bytes = tokio::fs::metadata(summary.root.join(path))
+            .await
+            .ok()
+            .map(|metadata| metadata.len());
+        let likely_generated = size_bytes.is_some_and(|size| size > rules.size_threshold_bytes)
+            || rules.patterns.iter().any(|pattern| matches_pattern(pattern, path));
+        entries.push(WorktreeSummaryEntry {
+            path: path.clone(),
+            status: status.clone(),
+            size_bytes,
+            likely_generated,
+            artifact: artifacts_prefix
+                .as_deref()
+                .is_some_and(|prefix| path.starts_with(prefix)),
+        });
+    }
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(entries)
+}
+
+/// Every changed file of the session's repository, sorted by path, with
+/// lockfiles, build output and oversized files flagged as likely generated
+#[tauri::command]
+pub async fn snapshot_worktree_summary(
+    state: State<'_, CodexState>,
+    session_id: String,
+) -> Result<Vec<WorktreeSummaryEntry>, String> {
+    worktree_summary_entries(&state, &session_id).await
+}
+
+#[tauri::command]
+pub async fn get_generated_file_rules(
+    state: State<'_, CodexState>,
+) -> Result<GeneratedFileRules, String> {
+    Ok(state.generated_file_rules.get())
+}
+
+/// Replace the patterns and size threshold used to flag generated files
+#[tauri::command]
+pub async fn set_generated_file_rules(
+    rules: GeneratedFileRules,
+    state: State<'_, CodexState>,
+) -> Result<(), String> {
+    if let Some(empty) = rules.patterns.iter().find(|p| p.trim().is_empty()) {
+        return Err(format!("Invalid pattern {empty:?}: patterns cannot be empty"));
+    }
+    state.generated_file_rules.set(rules);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("*.lock", "Cargo.lock"));
+        assert!(matches_pattern("*.lock", "crates/a/Cargo.lock"));
+        assert!(matches_pattern("package-lock.json", "web/package-lock.json"));
+        assert!(matches_pattern("dist/", "web/dist/app.js"));
+        assert!(!matches_pattern("dist/", "src/dist.rs"));
+        assert!(matches_pattern("*.min.js", "vendor/jquery.min.js"));
+        assert!(!matches_pattern("*.min.js", "src/admin.js"));
+        assert!(matches_pattern("src/gen/*.rs", "src/gen/schema.rs"));
+        assert!(!matches_pattern("src/gen/*.rs", "other/src/gen/schema.rs"));
+        assert!(!matches_pattern("*.map", "map"));
+    }
+}
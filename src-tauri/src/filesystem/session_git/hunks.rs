@@ -0,0 +1,243 @@
+use super::{ensure_session_path, has_head, resolve_in_dir, session_working_dir};
+use crate::codex_commands::CodexState;
+use crate::filesystem::git_runner::{run_git, GitRunner};
+use crate::filesystem::git_worktree::{reverse_apply, RevertMethod};
+use serde::Serialize;
+use std::path::Path;
+use tauri::State;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Hunk {
+    /// Position of the hunk in the file's diff, stable until the file changes
+    pub index: usize,
+    /// The `@@ -a,b +c,d @@` line
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    /// Context, removed and added lines following the header
+    pub body: String,
+}
+
+/// Parse `-a,b` / `+c,d` into start and line count; the count defaults to 1
+fn parse_range(range: &str) -> Option<(u32, u32)> {
+    let range = &range[1..];
+    match range.split_once(',') {
+        Some((start, lines)) => Some((start.parse().ok()?, lines.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+fn parse_hunk_header(line: &str) -> Option<(u32, u32, u32, u32)> {
+    let mut parts = line.strip_prefix("@@ ")?.split_whitespace();
+    let old = parts.next().filter(|part| part.starts_with('-'))?;
+    let new = parts.next().filter(|part| part.starts_with('+'))?;
+    let (old_start, old_lines) = parse_range(old)?;
+    let (new_start, new_lines) = parse_range(new)?;
+    Some((old_start, old_lines, new_start, new_lines))
+}
+
+/// Split a single-file unified diff into its file header and hunks
+pub(crate) fn parse_hunks(diff: &str) -> (String, Vec<Hunk>) {
+    let mut file_header = String::new();
+    let mut hunks: Vec<Hunk> = Vec::new();
+
+    for line in diff.split_inclusive('\n') {
+        if let Some((old_start, old_lines, new_start, new_lines)) = parse_hunk_header(line) {
+            hunks.push(Hunk {
+                index: hunks.len(),
+                header: line.trim_end_matches('\n').to_string(),
+                old_start,
+                old_lines,
+                new_start,
+                new_lines,
+                body: String::new(),
+            });
+        } else if let Some(hunk) = hunks.last_mut() {
+            hunk.body.push_str(line);
+        } else {
+            file_header.push_str(line);
+        }
+    }
+
+    (file_header, hunks)
+}
+
+/// A patch made of `file_header` and `hunks`
+pub(crate) fn hunks_patch<'a>(file_header: String, hunks: impl Iterator<Item = &'a Hunk>) -> String {
+    let mut patch = file_header;
+    for hunk in hunks {
+        patch.push_str(&hunk.header);
+        patch.push('\n');
+        patch.push_str(&hunk.body);
+    }
+    if !patch.ends_with('\n') {
+        patch.push('\n');
+    }
+    patch
+}
+
+/// Whether a hunk touches new-file lines `start..=end`. A hunk that only
+/// removes lines sits between `new_start` and the line after it.
+fn overlaps(hunk: &Hunk, start: u32, end: u32) -> bool {
+    let first = hunk.new_start.max(1);
+    let last = match hunk.new_lines {
+        0 => first,
+        lines => hunk.new_start + lines - 1,
+    };
+    first <= end && start <= last
+}
+
+/// Diff of `path` against HEAD, or against the index in a repository
+/// without commits
+pub(crate) async fn file_diff(runner: &GitRunner, dir: &Path, path: &str) -> Result<String, String> {
+    let mut args = vec!["diff"];
+    if has_head(runner, dir).await? {
+        args.push("HEAD");
+    }
+    args.extend(["--", path]);
+    Ok(run_git(runner, dir, &args).await?.stdout)
+}
+
+#[tauri::command]
+pub async fn get_file_hunks(
+    state: State<'_, CodexState>,
+    session_id: String,
+    path: String,
+) -> Result<Vec<Hunk>, String> {
+    let dir = session_working_dir(&state, &session_id).await?;
+    resolve_in_dir(&dir, &path)?;
+    ensure_session_path(&state, &session_id, &path).await?;
+    let diff = file_diff(&state.git, &dir, &path).await?;
+    Ok(parse_hunks(&diff).1)
+}
+
+/// The hunks of `path`'s diff that touch lines `start_line..=end_line` of
+/// the current file, as a patch; empty when those lines are unchanged
+#[tauri::command]
+pub async fn get_file_diff_range(
+    state: State<'_, CodexState>,
+    session_id: String,
+    path: String,
+    start_line: u32,
+    end_line: u32,
+) -> Result<String, String> {
+    if start_line == 0 || end_line < start_line {
+        return Err(format!("Invalid line range {start_line}-{end_line}"));
+    }
+    let dir = session_working_dir(&state, &session_id).await?;
+    resolve_in_dir(&dir, &path)?;
+    ensure_session_path(&state, &session_id, &path).await?;
+
+    let diff = file_diff(&state.git, &dir, &path).await?;
+    let (file_header, hunks) = parse_hunks(&diff);
+    let mut selected = hunks
+        .iter()
+        .filter(|hunk| overlaps(hunk, start_line, end_line))
+        .peekable();
+    if selected.peek().is_none() {
+        return Ok(String::new());
+    }
+    Ok(hunks_patch(file_header, selected))
+}
+
+/// Discard the selected hunks of `path` by reverse-applying a patch made of
+/// just those hunks
+#[tauri::command]
+pub async fn revert_hunks(
+    state: State<'_, CodexState>,
+    session_id: String,
+    path: String,
+    hunk_indices: Vec<usize>,
+) -> Result<(), String> {
+    if hunk_indices.is_empty() {
+        return Ok(());
+    }
+    let dir = session_working_dir(&state, &session_id).await?;
+    resolve_in_dir(&dir, &path)?;
+    ensure_session_path(&state, &session_id, &path).await?;
+
+    let diff = file_diff(&state.git, &dir, &path).await?;
+    let (file_header, hunks) = parse_hunks(&diff);
+    if let Some(index) = hunk_indices.iter().find(|index| **index >= hunks.len()) {
+        return Err(format!("Hunk {} does not exist in {}", index, path));
+    }
+
+    let selected = hunks.iter().filter(|hunk| hunk_indices.contains(&hunk.index));
+    let patch = hunks_patch(file_header, selected);
+    reverse_apply(&state.git, &dir, &patch, false).await?;
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevertResult {
+    pub method: RevertMethod,
+    /// The revert matched drifted context approximately; worth reviewing
+    pub fuzzy: bool,
+}
+
+/// Revert a file diff codex produced in the session's working tree. With
+/// `allow_fuzzy` the diff still reverts after small later edits around it.
+#[tauri::command]
+pub async fn revert_file_diff(
+    state: State<'_, CodexState>,
+    session_id: String,
+    unified_diff: String,
+    allow_fuzzy: Option<bool>,
+) -> Result<RevertResult, String> {
+    let dir = session_working_dir(&state, &session_id).await?;
+    let allow_fuzzy = allow_fuzzy.unwrap_or(false);
+    let method = reverse_apply(&state.git, &dir, &unified_diff, allow_fuzzy).await?;
+    Ok(RevertResult {
+        method,
+        fuzzy: method == RevertMethod::FuzzyPatch,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hunks() {
+        let diff = "diff --git a/f.txt b/f.txt\n\
+                    --- a/f.txt\n\
+                    +++ b/f.txt\n\
+                    @@ -1,2 +1,2 @@\n\
+                    -a\n\
+                    +b\n \
+                    c\n\
+                    @@ -10 +10,2 @@ fn main\n \
+                    x\n\
+                    +y\n";
+        let (header, hunks) = parse_hunks(diff);
+        assert!(header.starts_with("diff --git"));
+        assert!(header.ends_with("+++ b/f.txt\n"));
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].body, "-a\n+b\n c\n");
+        assert_eq!(
+            (hunks[1].index, hunks[1].old_start, hunks[1].old_lines, hunks[1].new_lines),
+            (1, 10, 1, 2)
+        );
+        assert_eq!(hunks[1].header, "@@ -10 +10,2 @@ fn main");
+
+        // Lines 10-11 in the new file
+        assert!(overlaps(&hunks[1], 11, 20));
+        assert!(!overlaps(&hunks[1], 3, 9));
+        let deletion = Hunk {
+            new_start: 4,
+            new_lines: 0,
+            ..hunks[0].clone()
+        };
+        assert!(overlaps(&deletion, 4, 4));
+        assert!(!overlaps(&deletion, 5, 8));
+        assert_eq!(
+            hunks_patch(header, hunks.iter().skip(1)),
+            "diff --git a/f.txt b/f.txt\n--- a/f.txt\n+++ b/f.txt\n\
+             @@ -10 +10,2 @@ fn main\n x\n+y\n"
+        );
+    }
+}
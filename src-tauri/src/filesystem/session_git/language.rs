@@ -0,0 +1,117 @@
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+/// Highlighter language of a file name, e.g. "rust" for `src/lib.rs`
+pub(crate) fn language_from_path(path: &str) -> Option<&'static str> {
+    let name = Path::new(path).file_name()?.to_str()?;
+    let language = match name {
+        "Dockerfile" | "Containerfile" => "dockerfile",
+        "Makefile" | "GNUmakefile" => "makefile",
+        "CMakeLists.txt" => "cmake",
+        "Gemfile" | "Rakefile" => "ruby",
+        ".bashrc" | ".bash_profile" | ".zshrc" | ".profile" => "shell",
+        _ => {
+            let (_, extension) = name.rsplit_once('.')?;
+            match extension.to_ascii_lowercase().as_str() {
+                "rs" => "rust",
+                "ts" | "mts" | "cts" => "typescript",
+                "tsx" => "tsx",
+                "js" | "mjs" | "cjs" => "javascript",
+                "jsx" => "jsx",
+                "py" | "pyi" => "python",
+                "go" => "go",
+                "java" => "java",
+                "kt" | "kts" => "kotlin",
+                "swift" => "swift",
+                "c" | "h" => "c",
+                "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => "cpp",
+                "cs" => "csharp",
+                "rb" => "ruby",
+                "php" => "php",
+                "lua" => "lua",
+                "sh" | "bash" | "zsh" => "shell",
+                "ps1" => "powershell",
+                "html" | "htm" => "html",
+                "css" => "css",
+                "scss" => "scss",
+                "vue" => "vue",
+                "svelte" => "svelte",
+                "json" | "jsonc" => "json",
+                "toml" => "toml",
+                "yaml" | "yml" => "yaml",
+                "xml" | "svg" => "xml",
+                "md" | "markdown" => "markdown",
+                "sql" => "sql",
+                "graphql" | "gql" => "graphql",
+                "proto" => "protobuf",
+                "dart" => "dart",
+                "scala" => "scala",
+                "hs" => "haskell",
+                "ex" | "exs" => "elixir",
+                "zig" => "zig",
+                _ => return None,
+            }
+        }
+    };
+    Some(language)
+}
+
+/// Language named by a `#!` line, e.g. "python" for `#!/usr/bin/env python3`
+pub(crate) fn language_from_shebang(first_line: &str) -> Option<&'static str> {
+    let command = first_line.strip_prefix("#!")?;
+    let mut words = command.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        // Skip options such as `env -S`
+        program = words.find(|word| !word.starts_with('-'))?;
+    }
+    let program = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    let language = match program {
+        "sh" | "bash" | "zsh" | "dash" | "ksh" => "shell",
+        "python" => "python",
+        "node" | "deno" | "bun" => "javascript",
+        "ruby" => "ruby",
+        "perl" => "perl",
+        "php" => "php",
+        "lua" => "lua",
+        "pwsh" => "powershell",
+        _ => return None,
+    };
+    Some(language)
+}
+
+/// Language of a file on disk by its name, or by its shebang when the name
+/// says nothing
+pub(crate) fn detect_language(path: &str, full_path: &Path) -> Option<&'static str> {
+    if let Some(language) = language_from_path(path) {
+        return Some(language);
+    }
+    let file = std::fs::File::open(full_path).ok()?;
+    let mut first_line = String::new();
+    // A binary file's first "line" can be arbitrarily long
+    BufReader::new(file)
+        .take(256)
+        .read_line(&mut first_line)
+        .ok()?;
+    language_from_shebang(first_line.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_detection() {
+        assert_eq!(language_from_path("src/lib.rs"), Some("rust"));
+        assert_eq!(language_from_path("web/App.TSX"), Some("tsx"));
+        assert_eq!(language_from_path("docker/Dockerfile"), Some("dockerfile"));
+        assert_eq!(language_from_path("bin/tool"), None);
+        assert_eq!(language_from_path("notes.unknown"), None);
+
+        assert_eq!(language_from_shebang("#!/bin/bash"), Some("shell"));
+        assert_eq!(language_from_shebang("#!/usr/bin/env python3"), Some("python"));
+        assert_eq!(language_from_shebang("#!/usr/bin/env -S node --harmony"), Some("javascript"));
+        assert_eq!(language_from_shebang("#!/usr/bin/python3.12"), Some("python"));
+        assert_eq!(language_from_shebang("print('hi')"), None);
+    }
+}
@@ -0,0 +1,283 @@
+use super::diff::{worktree_diff, IgnoreWhitespace};
+use super::hunks::{hunks_patch, parse_hunks, Hunk};
+use super::structured::split_file_sections;
+use super::{session_pathspecs, session_working_dir};
+use crate::codex_commands::CodexState;
+use crate::filesystem::git_runner::{run_git_with_input, GitRunner};
+use std::path::Path;
+use std::time::Duration;
+use tauri::State;
+
+/// Check command runs allowed when the caller sets no bound
+const DEFAULT_MAX_RUNS: u32 = 64;
+/// A single run of the check command is stopped after this long
+const CHECK_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// One file of the diff, split into hunks that can be left out separately
+struct FileSection {
+    header: String,
+    hunks: Vec<Hunk>,
+    /// The whole section, for binary, mode-only and pure rename diffs that
+    /// have no hunks
+    whole: String,
+}
+
+/// A piece of the diff: one hunk of a file, or a file without hunks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Unit {
+    file: usize,
+    hunk: Option<usize>,
+}
+
+fn split_units(diff: &str) -> (Vec<FileSection>, Vec<Unit>) {
+    let mut files = Vec::new();
+    let mut units = Vec::new();
+    for (file, section) in split_file_sections(diff).into_iter().enumerate() {
+        let (header, hunks) = parse_hunks(&section);
+        if hunks.is_empty() {
+            units.push(Unit { file, hunk: None });
+        } else {
+            units.extend(hunks.iter().map(|hunk| Unit {
+                file,
+                hunk: Some(hunk.index),
+            }));
+        }
+        files.push(FileSection {
+            header,
+            hunks,
+            whole: section,
+        });
+    }
+    (files, units)
+}
+
+/// The patch made of only `units`
+fn units_patch(files: &[FileSection], units: &[Unit]) -> String {
+    let mut patch = String::new();
+    for (index, file) in files.iter().enumerate() {
+        let selected: Vec<&Unit> = units.iter().filter(|unit| unit.file == index).collect();
+        if selected.is_empty() {
+            continue;
+        }
+        if file.hunks.is_empty() {
+            patch.push_str(&file.whole);
+            continue;
+        }
+        let hunks = file
+            .hunks
+            .iter()
+            .filter(|hunk| selected.iter().any(|unit| unit.hunk == Some(hunk.index)));
+        patch.push_str(&hunks_patch(file.header.clone(), hunks));
+    }
+    patch
+}
+
+/// `units` split into `n` nearly equal consecutive chunks
+fn chunks(units: &[Unit], n: usize) -> Vec<Vec<Unit>> {
+    let size = units.len().div_ceil(n);
+    units.chunks(size).map(<[Unit]>::to_vec).collect()
+}
+
+/// Whether `check` succeeds in `dir`, run by the platform shell
+async fn run_check(dir: &Path, check: &str) -> Result<bool, String> {
+    #[cfg(windows)]
+    let mut command = {
+        let mut command = tokio::process::Command::new("cmd");
+        command.args(["/C", check]);
+        command
+    };
+    #[cfg(not(windows))]
+    let mut command = {
+        let mut command = tokio::process::Command::new("sh");
+        command.args(["-c", check]);
+        command
+    };
+    command
+        .current_dir(dir)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .kill_on_drop(true);
+    let status = tokio::time::timeout(CHECK_TIMEOUT, command.status())
+        .await
+        .map_err(|_| format!("{check} did not finish within {}s", CHECK_TIMEOUT.as_secs()))?
+        .map_err(|e| format!("Failed to run {check}: {e}"))?;
+    Ok(status.success())
+}
+
+async fn apply(runner: &GitRunner, dir: &Path, patch: &str, reverse: bool) -> Result<(), String> {
+    if patch.is_empty() {
+        return Ok(());
+    }
+    let mut args = vec!["apply", "--whitespace=nowarn"];
+    if reverse {
+        args.push("--reverse");
+    }
+    run_git_with_input(runner, dir, &args, patch.as_bytes()).await?;
+    Ok(())
+}
+
+struct Search<'a> {
+    runner: &'a GitRunner,
+    dir: &'a Path,
+    check: &'a str,
+    files: &'a [FileSection],
+    /// Whether the check succeeds with every change applied
+    expected: bool,
+    runs_left: u32,
+}
+
+impl Search<'_> {
+    /// Whether the check still behaves as with the full diff when only
+    /// `units` are applied to the otherwise clean working tree
+    async fn reproduces(&mut self, units: &[Unit]) -> Result<bool, String> {
+        self.runs_left = self.runs_left.saturating_sub(1);
+        let patch = units_patch(self.files, units);
+        apply(self.runner, self.dir, &patch, false).await?;
+        let outcome = run_check(self.dir, self.check).await;
+        apply(self.runner, self.dir, &patch, true).await?;
+        Ok(outcome? == self.expected)
+    }
+
+    /// Delta debugging: try each chunk, then each complement, at finer and
+    /// finer granularity, keeping any smaller set that still reproduces
+    async fn minimize(&mut self, mut current: Vec<Unit>) -> Result<Vec<Unit>, String> {
+        let mut n = 2;
+        while current.len() >= 2 && self.runs_left > 0 {
+            let parts = chunks(&current, n);
+            let mut reduced = None;
+            for part in &parts {
+                if self.runs_left == 0 {
+                    break;
+                }
+                if self.reproduces(part).await? {
+                    reduced = Some((part.clone(), 2));
+                    break;
+                }
+            }
+            // With two parts each complement is the other part, already tried
+            if reduced.is_none() && parts.len() > 2 {
+                for part in &parts {
+                    if self.runs_left == 0 {
+                        break;
+                    }
+                    let complement: Vec<Unit> = current
+                        .iter()
+                        .filter(|unit| !part.contains(unit))
+                        .copied()
+                        .collect();
+                    if self.reproduces(&complement).await? {
+                        reduced = Some((complement, (n - 1).max(2)));
+                        break;
+                    }
+                }
+            }
+            match reduced {
+                Some((smaller, next_n)) => {
+                    current = smaller;
+                    n = next_n;
+                }
+                None if n >= current.len() => break,
+                None => n = (n * 2).min(current.len()),
+            }
+        }
+        Ok(current)
+    }
+}
+
+/// Shrink the session's diff to a small patch for which `check_command`
+/// still succeeds, or still fails, as it does with every change in place.
+///
+/// Hunks are left out delta-debugging style, running the check on the clean
+/// working tree plus the remaining hunks, at most `max_runs` times. The full
+/// working tree is restored afterwards; its diff is also saved to a file in
+/// the temp directory first, which a failed restore points to.
+#[tauri::command]
+pub async fn minimize_diff(
+    state: State<'_, CodexState>,
+    session_id: String,
+    check_command: String,
+    max_runs: Option<u32>,
+) -> Result<String, String> {
+    if check_command.trim().is_empty() {
+        return Err("No check command given".to_string());
+    }
+    if state.client_state.sessions.get(&session_id).await?.turn_active {
+        return Err(format!("Session {session_id} is running a turn"));
+    }
+    let dir = session_working_dir(&state, &session_id).await?;
+    let pathspecs = session_pathspecs(&state, &session_id).await?;
+    let runner = &state.git;
+    let full = worktree_diff(runner, &dir, None, IgnoreWhitespace::None, &pathspecs).await?;
+    if full.trim().is_empty() {
+        return Err("No changes to minimize".to_string());
+    }
+
+    // Make sure the changes can be taken out and put back before touching
+    // anything
+    run_git_with_input(
+        runner,
+        &dir,
+        &["apply", "--reverse", "--check", "--whitespace=nowarn"],
+        full.as_bytes(),
+    )
+    .await?;
+    let backup = std::env::temp_dir().join(format!("codexia-minimize-{session_id}.patch"));
+    tokio::fs::write(&backup, &full)
+        .await
+        .map_err(|e| format!("Failed to save {}: {}", backup.display(), e))?;
+
+    let expected = run_check(&dir, &check_command).await?;
+    let (files, units) = split_units(&full);
+    apply(runner, &dir, &full, true).await?;
+    let mut search = Search {
+        runner,
+        dir: &dir,
+        check: &check_command,
+        files: &files,
+        expected,
+        runs_left: max_runs.unwrap_or(DEFAULT_MAX_RUNS),
+    };
+    let minimized = search.minimize(units).await;
+
+    if let Err(err) = apply(runner, &dir, &full, false).await {
+        return Err(format!(
+            "Failed to restore the working tree: {err}. The changes are saved in {}",
+            backup.display()
+        ));
+    }
+    let _ = tokio::fs::remove_file(&backup).await;
+    Ok(units_patch(&files, &minimized?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_units_patch() {
+        let diff = "diff --git a/a.rs b/a.rs\n\
+                    --- a/a.rs\n\
+                    +++ b/a.rs\n\
+                    @@ -1,1 +1,1 @@\n\
+                    -one\n\
+                    +uno\n\
+                    @@ -10,1 +10,1 @@\n\
+                    -ten\n\
+                    +diez\n\
+                    diff --git a/logo.png b/logo.png\n\
+                    Binary files a/logo.png and b/logo.png differ\n";
+        let (files, units) = split_units(diff);
+        assert_eq!(units.len(), 3);
+        assert_eq!(units[2], Unit { file: 1, hunk: None });
+
+        let second_hunk = units_patch(&files, &units[1..2]);
+        assert!(second_hunk.starts_with("diff --git a/a.rs b/a.rs\n"));
+        assert!(second_hunk.contains("+diez\n"));
+        assert!(!second_hunk.contains("+uno\n"));
+        assert_eq!(units_patch(&files, &units), diff);
+
+        assert_eq!(chunks(&units, 2).len(), 2);
+        assert_eq!(chunks(&units, 3)[2], vec![units[2]]);
+    }
+}
@@ -0,0 +1,155 @@
+//! Git commands scoped to a codex session's working directory
+//!
+//! Every command takes a `session_id` and resolves the directory from the
+//! session registry instead of trusting a path sent by the frontend.
+
+pub mod authorship;
+pub mod blame;
+pub mod branch;
+pub mod checkpoints;
+pub mod conflicts;
+pub mod coverage;
+pub mod diff;
+pub mod entries;
+pub mod generated;
+pub mod hunks;
+pub mod language;
+pub mod minimize;
+pub mod preview;
+pub mod relocate;
+pub mod staging;
+pub mod structured;
+pub mod summary;
+
+use crate::codex_commands::CodexState;
+use crate::filesystem::git_runner::{run_git_unchecked, GitRunner};
+use std::path::{Component, Path, PathBuf};
+
+/// Default number of context lines, matching git's own default
+const DEFAULT_CONTEXT_LINES: u32 = 3;
+
+/// Resolve the working directory codex runs in for `session_id`
+pub(crate) async fn session_working_dir(
+    state: &CodexState,
+    session_id: &str,
+) -> Result<PathBuf, String> {
+    let session = state.client_state.sessions.get(session_id).await?;
+    let cwd = session
+        .cwd
+        .ok_or_else(|| format!("Session {session_id} has no working directory"))?;
+    if !cwd.is_dir() {
+        return Err(format!(
+            "Working directory {} does not exist",
+            cwd.display()
+        ));
+    }
+    Ok(cwd)
+}
+
+/// Pathspecs the session's diffs are limited to, relative to its working
+/// directory; empty when the whole working directory is in scope
+pub(crate) async fn session_pathspecs(
+    state: &CodexState,
+    session_id: &str,
+) -> Result<Vec<String>, String> {
+    let session = state.client_state.sessions.get(session_id).await?;
+    Ok(session.config.allowed_paths)
+}
+
+/// Whether `path` is covered by `pathspecs`; everything is when there are
+/// none
+fn in_pathspecs(pathspecs: &[String], path: &str) -> bool {
+    let path = Path::new(path.trim_start_matches("./"));
+    pathspecs.is_empty()
+        || pathspecs
+            .iter()
+            .any(|spec| path.starts_with(spec.trim_start_matches("./")))
+}
+
+/// Reject a file outside the paths the session is limited to, so single
+/// file diffs and reverts stay as scoped as the whole-tree ones
+pub(crate) async fn ensure_session_path(
+    state: &CodexState,
+    session_id: &str,
+    path: &str,
+) -> Result<(), String> {
+    if in_pathspecs(&session_pathspecs(state, session_id).await?, path) {
+        Ok(())
+    } else {
+        Err(format!("{path} is outside the session's allowed paths"))
+    }
+}
+
+/// Whether the repository at `dir` has a HEAD commit. Only a missing HEAD
+/// answers no: a timeout or a broken repository is an error, not a reason
+/// to diff against the index instead.
+pub(crate) async fn has_head(runner: &GitRunner, dir: &Path) -> Result<bool, String> {
+    let output =
+        run_git_unchecked(runner, dir, &["rev-parse", "--verify", "--quiet", "HEAD"]).await?;
+    match output.code {
+        Some(0) => Ok(true),
+        // `--quiet` exits with 1 and prints nothing when HEAD does not exist
+        Some(1) => Ok(false),
+        _ => Err(format!(
+            "git rev-parse HEAD failed in {}: {}",
+            dir.display(),
+            output.stderr.trim()
+        )),
+    }
+}
+
+/// Resolve `relative` inside `root`, rejecting absolute paths and `..`
+/// components so a request cannot reach outside the working directory.
+pub(crate) fn resolve_in_dir(root: &Path, relative: &str) -> Result<PathBuf, String> {
+    let relative_path = Path::new(relative);
+    for component in relative_path.components() {
+        match component {
+            Component::Normal(_) | Component::CurDir => {}
+            _ => return Err(format!("Path {relative} escapes the working directory")),
+        }
+    }
+    let joined = root.join(relative_path);
+    // Symlinks can still point outside; compare the canonical locations
+    if let (Ok(canonical_root), Ok(canonical)) = (root.canonicalize(), joined.canonicalize()) {
+        if !canonical.starts_with(&canonical_root) {
+            return Err(format!("Path {relative} escapes the working directory"));
+        }
+    }
+    Ok(joined)
+}
+
+/// Build the `--unified=N` flag for diff commands. git reads N into a C
+/// `int`, so larger values, used to mean "the whole file", are capped.
+pub(crate) fn unified_arg(context_lines: Option<u32>) -> String {
+    let lines = context_lines
+        .unwrap_or(DEFAULT_CONTEXT_LINES)
+        .min(i32::MAX as u32);
+    format!("--unified={lines}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_in_dir_rejects_traversal() {
+        let root = Path::new("/tmp/codexia-session");
+        assert_eq!(
+            resolve_in_dir(root, "src/main.rs").unwrap(),
+            root.join("src/main.rs")
+        );
+        assert!(resolve_in_dir(root, "../secret").is_err());
+        assert!(resolve_in_dir(root, "src/../../secret").is_err());
+        assert!(resolve_in_dir(root, "/etc/passwd").is_err());
+    }
+
+    #[test]
+    fn test_in_pathspecs() {
+        let specs = vec!["src/api".to_string(), "./docs/".to_string()];
+        assert!(in_pathspecs(&specs, "src/api/routes.rs"));
+        assert!(in_pathspecs(&specs, "./docs/guide.md"));
+        assert!(!in_pathspecs(&specs, "src/apiary.rs"));
+        assert!(!in_pathspecs(&specs, "Cargo.toml"));
+        assert!(in_pathspecs(&[], "Cargo.toml"));
+    }
+}
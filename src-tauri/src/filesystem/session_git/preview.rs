@@ -0,0 +1,153 @@
+use super::{resolve_in_dir, session_working_dir};
+use crate::codex_commands::CodexState;
+use crate::filesystem::git_runner::run_git;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+/// Files above this size are reported without content
+const MAX_PREVIEW_BYTES: u64 = 1024 * 1024;
+/// How much of the file is checked for NUL bytes, like git's own heuristic
+const BINARY_SNIFF_BYTES: usize = 8000;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilePreview {
+    pub path: String,
+    /// File content, `None` when the file is binary, too large or a symlink
+    pub content: Option<String>,
+    pub size: u64,
+    pub binary: bool,
+    pub too_large: bool,
+    pub is_symlink: bool,
+    /// Where a symlink points; it is reported instead of the target's content
+    pub symlink_target: Option<String>,
+}
+
+fn looks_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0)
+}
+
+/// `path` under `dir` like `resolve_in_dir`, except that a symlink itself
+/// is not followed, as it may point anywhere
+fn entry_path(dir: &Path, path: &str) -> Result<PathBuf, String> {
+    let joined = dir.join(path);
+    let is_symlink = joined
+        .symlink_metadata()
+        .is_ok_and(|meta| meta.file_type().is_symlink());
+    if !is_symlink {
+        return resolve_in_dir(dir, path);
+    }
+    let parent = Path::new(path).parent().unwrap_or(Path::new(""));
+    resolve_in_dir(dir, &parent.to_string_lossy())?;
+    Ok(joined)
+}
+
+/// Preview of the file at `full_path`, reported as too large when it is
+/// bigger than `max_bytes`. A symlink is previewed as its target path.
+async fn preview_file(
+    full_path: &Path,
+    path: String,
+    max_bytes: u64,
+) -> Result<FilePreview, String> {
+    if let Ok(target) = tokio::fs::read_link(full_path).await {
+        let target = target.to_string_lossy().into_owned();
+        return Ok(FilePreview {
+            path,
+            content: None,
+            size: target.len() as u64,
+            binary: false,
+            too_large: false,
+            is_symlink: true,
+            symlink_target: Some(target),
+        });
+    }
+    let meta = tokio::fs::metadata(full_path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    if !meta.is_file() {
+        return Err(format!("{} is not a file", path));
+    }
+
+    let size = meta.len();
+    if size > max_bytes {
+        return Ok(FilePreview {
+            path,
+            content: None,
+            size,
+            binary: false,
+            too_large: true,
+            is_symlink: false,
+            symlink_target: None,
+        });
+    }
+
+    let bytes = tokio::fs::read(full_path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let content = if looks_binary(&bytes) {
+        None
+    } else {
+        String::from_utf8(bytes).ok()
+    };
+
+    Ok(FilePreview {
+        path,
+        binary: content.is_none(),
+        content,
+        size,
+        too_large: false,
+        is_symlink: false,
+        symlink_target: None,
+    })
+}
+
+/// Read a file relative to the session's working directory for preview,
+/// e.g. an untracked file listed in the change summary
+#[tauri::command]
+pub async fn read_worktree_file(
+    state: State<'_, CodexState>,
+    session_id: String,
+    path: String,
+) -> Result<FilePreview, String> {
+    let dir = session_working_dir(&state, &session_id).await?;
+    let full_path = entry_path(&dir, &path)?;
+    preview_file(&full_path, path, MAX_PREVIEW_BYTES).await
+}
+
+/// Previews of every untracked file in the session's working directory, in
+/// path order, for reviewing what codex created. Text content counts
+/// against `max_total_bytes`; files that no longer fit are reported as too
+/// large, and smaller files after them are still read.
+#[tauri::command]
+pub async fn read_new_files(
+    state: State<'_, CodexState>,
+    session_id: String,
+    max_total_bytes: u64,
+) -> Result<Vec<FilePreview>, String> {
+    let dir = session_working_dir(&state, &session_id).await?;
+    let untracked = run_git(
+        &state.git,
+        &dir,
+        &["ls-files", "--others", "--exclude-standard", "-z"],
+    )
+    .await?;
+
+    let mut remaining = max_total_bytes;
+    let mut previews = Vec::new();
+    for path in untracked.stdout.split('\0').filter(|path| !path.is_empty()) {
+        let Ok(full_path) = entry_path(&dir, path) else {
+            continue;
+        };
+        let max_bytes = remaining.min(MAX_PREVIEW_BYTES);
+        // Removed since it was listed
+        let Ok(preview) = preview_file(&full_path, path.to_string(), max_bytes).await else {
+            continue;
+        };
+        if preview.content.is_some() {
+            remaining -= preview.size;
+        }
+        previews.push(preview);
+    }
+    Ok(previews)
+}
@@ -0,0 +1,118 @@
+use super::diff::{worktree_diff, IgnoreWhitespace};
+use super::session_working_dir;
+use crate::codex_commands::CodexState;
+use crate::filesystem::git_runner::{run_git, run_git_with_input, GitRunner};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelocateResult {
+    pub old_dir: PathBuf,
+    pub new_dir: PathBuf,
+    /// Whether there were uncommitted changes to carry over
+    pub applied_changes: bool,
+    /// False when codex only uses the new directory once the session is
+    /// resumed, because the session has no rollout to reload yet
+    pub restarted: bool,
+}
+
+async fn head_commit(runner: &GitRunner, dir: &Path) -> Result<String, String> {
+    let output = run_git(runner, dir, &["rev-parse", "--verify", "HEAD"])
+        .await
+        .map_err(|_| format!("{} has no commits", dir.display()))?;
+    Ok(output.stdout.trim().to_string())
+}
+
+/// Root commits identify a repository across clones
+async fn root_commits(runner: &GitRunner, dir: &Path) -> Result<Vec<String>, String> {
+    let output = run_git(runner, dir, &["rev-list", "--max-parents=0", "HEAD"]).await?;
+    let mut roots: Vec<String> = output.stdout.lines().map(String::from).collect();
+    roots.sort();
+    Ok(roots)
+}
+
+/// Check that `new_dir` is a clean checkout of the same repository at the
+/// same commit as `old_dir`, so the working tree diff applies as is
+async fn check_compatible(
+    runner: &GitRunner,
+    old_dir: &Path,
+    new_dir: &Path,
+) -> Result<(), String> {
+    let old_head = head_commit(runner, old_dir).await?;
+    let new_head = head_commit(runner, new_dir).await?;
+    if root_commits(runner, old_dir).await? != root_commits(runner, new_dir).await? {
+        return Err(format!(
+            "{} is not a clone of the same repository as {}",
+            new_dir.display(),
+            old_dir.display()
+        ));
+    }
+    if old_head != new_head {
+        return Err(format!(
+            "{} is at commit {} but the session is at {}; check out the same commit first",
+            new_dir.display(),
+            &new_head[..new_head.len().min(12)],
+            &old_head[..old_head.len().min(12)]
+        ));
+    }
+    let status = run_git(runner, new_dir, &["status", "--porcelain"]).await?;
+    if !status.stdout.trim().is_empty() {
+        return Err(format!("{} has uncommitted changes", new_dir.display()));
+    }
+    Ok(())
+}
+
+/// Carry the session's uncommitted changes over to a clone of its
+/// repository at `new_dir` and move the session there. The original
+/// working directory is left untouched.
+#[tauri::command]
+pub async fn relocate_session(
+    state: State<'_, CodexState>,
+    session_id: String,
+    new_dir: String,
+) -> Result<RelocateResult, String> {
+    let old_dir = session_working_dir(&state, &session_id).await?;
+    let new_dir = PathBuf::from(&new_dir)
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {new_dir}: {e}"))?;
+    if !new_dir.is_dir() {
+        return Err(format!("{} is not a directory", new_dir.display()));
+    }
+    if old_dir.canonicalize().ok().as_deref() == Some(new_dir.as_path()) {
+        return Err("The session already runs in that directory".to_string());
+    }
+    if state.client_state.sessions.get(&session_id).await?.turn_active {
+        return Err(format!("Session {session_id} is running a turn"));
+    }
+
+    let runner = &state.git;
+    check_compatible(runner, &old_dir, &new_dir).await?;
+
+    // Everything moves, whatever the session is limited to
+    let patch = worktree_diff(runner, &old_dir, None, IgnoreWhitespace::None, &[]).await?;
+    let applied_changes = !patch.trim().is_empty();
+    if applied_changes {
+        // Check first so a conflict leaves `new_dir` untouched
+        run_git_with_input(
+            runner,
+            &new_dir,
+            &["apply", "--check", "--whitespace=nowarn"],
+            patch.as_bytes(),
+        )
+        .await?;
+        run_git_with_input(runner, &new_dir, &["apply", "--whitespace=nowarn"], patch.as_bytes())
+            .await?;
+    }
+
+    let restarted =
+        codex_client::session::move_session(&state.client_state, &session_id, new_dir.clone())
+            .await?;
+    Ok(RelocateResult {
+        old_dir,
+        new_dir,
+        applied_changes,
+        restarted,
+    })
+}
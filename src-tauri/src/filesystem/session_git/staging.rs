@@ -0,0 +1,135 @@
+use super::{resolve_in_dir, session_working_dir};
+use crate::codex_commands::CodexState;
+use crate::filesystem::git_runner::run_git;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+/// Changed files of the repository by what a commit would include. A file
+/// with staged and unstaged changes appears in both lists. Paths are
+/// relative to the repository root.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StagingStatus {
+    pub staged: Vec<String>,
+    /// Includes untracked files
+    pub unstaged: Vec<String>,
+}
+
+/// Split `git status --porcelain=v1 -z` output by the index (X) and
+/// working tree (Y) columns
+fn parse_staging(output: &str) -> StagingStatus {
+    let mut status = StagingStatus::default();
+    let mut fields = output.split('\0');
+    while let Some(entry) = fields.next() {
+        if entry.len() < 4 {
+            continue;
+        }
+        let (code, path) = (entry.as_bytes(), &entry[3..]);
+        if code[0] == b'R' || code[0] == b'C' {
+            // Skip the old path of a rename or copy
+            let _ = fields.next();
+        }
+        if code[0] == b'?' {
+            status.unstaged.push(path.to_string());
+            continue;
+        }
+        if code[0] != b' ' {
+            status.staged.push(path.to_string());
+        }
+        if code[1] != b' ' {
+            status.unstaged.push(path.to_string());
+        }
+    }
+    status.staged.sort();
+    status.unstaged.sort();
+    status
+}
+
+/// Validate `paths` against the session's directory and return it
+async fn staging_dir(
+    state: &CodexState,
+    session_id: &str,
+    paths: &[String],
+) -> Result<PathBuf, String> {
+    if paths.is_empty() {
+        return Err("No paths given".to_string());
+    }
+    let dir = session_working_dir(state, session_id).await?;
+    for path in paths {
+        resolve_in_dir(&dir, path)?;
+    }
+    Ok(dir)
+}
+
+async fn staging_status(state: &CodexState, dir: &Path) -> Result<StagingStatus, String> {
+    let output = run_git(
+        &state.git,
+        dir,
+        &["status", "--porcelain=v1", "-z", "--untracked-files=all"],
+    )
+    .await?;
+    Ok(parse_staging(&output.stdout))
+}
+
+/// Add `paths`, relative to the session's directory, to the index,
+/// including deletions, and return the resulting split
+#[tauri::command]
+pub async fn stage_files(
+    state: State<'_, CodexState>,
+    session_id: String,
+    paths: Vec<String>,
+) -> Result<StagingStatus, String> {
+    let dir = staging_dir(&state, &session_id, &paths).await?;
+    let mut args = vec!["add", "--all", "--"];
+    args.extend(paths.iter().map(String::as_str));
+    run_git(&state.git, &dir, &args).await?;
+    staging_status(&state, &dir).await
+}
+
+/// Take `paths` back out of the index, keeping the working tree as is
+#[tauri::command]
+pub async fn unstage_files(
+    state: State<'_, CodexState>,
+    session_id: String,
+    paths: Vec<String>,
+) -> Result<StagingStatus, String> {
+    let dir = staging_dir(&state, &session_id, &paths).await?;
+    // `restore` needs a HEAD to restore from; before the first commit the
+    // index entries are simply removed
+    let has_head = run_git(&state.git, &dir, &["rev-parse", "--verify", "--quiet", "HEAD"])
+        .await
+        .is_ok();
+    let mut args = if has_head {
+        vec!["restore", "--staged", "--"]
+    } else {
+        vec!["rm", "--cached", "-r", "--quiet", "--"]
+    };
+    args.extend(paths.iter().map(String::as_str));
+    run_git(&state.git, &dir, &args).await?;
+    staging_status(&state, &dir).await
+}
+
+/// Staged and unstaged files of the session's repository
+#[tauri::command]
+pub async fn get_staging_status(
+    state: State<'_, CodexState>,
+    session_id: String,
+) -> Result<StagingStatus, String> {
+    let dir = session_working_dir(&state, &session_id).await?;
+    staging_status(&state, &dir).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_staging() {
+        let output = "M  staged.rs\0 M unstaged.rs\0MM both.rs\0\
+                      R  new.rs\0old.rs\0?? notes.md\0D  gone.rs\0";
+        let status = parse_staging(output);
+        assert_eq!(status.staged, vec!["both.rs", "gone.rs", "new.rs", "staged.rs"]);
+        assert_eq!(status.unstaged, vec!["both.rs", "notes.md", "unstaged.rs"]);
+    }
+}
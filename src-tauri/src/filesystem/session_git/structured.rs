@@ -0,0 +1,343 @@
+use super::diff::{worktree_diff, IgnoreWhitespace};
+use super::hunks::parse_hunks;
+use super::language::{detect_language, language_from_path};
+use super::{resolve_in_dir, session_pathspecs, session_working_dir};
+use super::summary::FileChangeStatus;
+use crate::codex_commands::CodexState;
+use serde::Serialize;
+use tauri::State;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffLineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    /// The line without its `+`, `-` or space marker and newline
+    pub content: String,
+    /// Line number before the change; `None` for added lines
+    pub old_lineno: Option<u32>,
+    /// Line number after the change; `None` for removed lines
+    pub new_lineno: Option<u32>,
+    /// git's `\ No newline at end of file` followed this line
+    pub no_newline: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffHunk {
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDiff {
+    /// Path after the change, relative to the repository root
+    pub path: String,
+    #[serde(flatten)]
+    pub status: FileChangeStatus,
+    /// Binary files have no hunks
+    pub binary: bool,
+    /// File modes such as "100755" when the mode changed, or of the new or
+    /// deleted file
+    pub old_mode: Option<String>,
+    pub new_mode: Option<String>,
+    /// Highlighter language such as "rust", from the extension or, for
+    /// files without one, the working copy's shebang
+    pub language: Option<String>,
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// Path of a `--- a/...` or `+++ b/...` line; `None` for /dev/null
+fn header_path(value: &str) -> Option<String> {
+    let value = value.trim_end().trim_matches('"');
+    if value == "/dev/null" {
+        return None;
+    }
+    let path = value
+        .strip_prefix("a/")
+        .or_else(|| value.strip_prefix("b/"))
+        .unwrap_or(value);
+    Some(path.to_string())
+}
+
+/// Lines of one hunk, numbered on both sides from the hunk's own header
+fn parse_lines(hunk_body: &str, old_start: u32, new_start: u32) -> Vec<DiffLine> {
+    let (mut old_line, mut new_line) = (old_start, new_start);
+    let mut lines: Vec<DiffLine> = Vec::new();
+    for line in hunk_body.lines() {
+        let (kind, content) = match line.split_at_checked(1) {
+            Some(("+", content)) => (DiffLineKind::Added, content),
+            Some(("-", content)) => (DiffLineKind::Removed, content),
+            Some((" ", content)) => (DiffLineKind::Context, content),
+            Some(("\\", _)) => {
+                if let Some(last) = lines.last_mut() {
+                    last.no_newline = true;
+                }
+                continue;
+            }
+            // An empty context line whose trailing space was stripped
+            _ => (DiffLineKind::Context, ""),
+        };
+        let (old, new) = match kind {
+            DiffLineKind::Added => (None, Some(new_line)),
+            DiffLineKind::Removed => (Some(old_line), None),
+            DiffLineKind::Context => (Some(old_line), Some(new_line)),
+        };
+        old_line += u32::from(old.is_some());
+        new_line += u32::from(new.is_some());
+        lines.push(DiffLine {
+            kind,
+            content: content.to_string(),
+            old_lineno: old,
+            new_lineno: new,
+            no_newline: false,
+        });
+    }
+    lines
+}
+
+/// One file's section of a diff, starting at its `diff --git` line
+fn parse_file(section: &str) -> Option<FileDiff> {
+    let (file_header, hunks) = parse_hunks(section);
+    let mut old_path = None;
+    let mut new_path = None;
+    let mut rename_from = None;
+    let mut old_mode = None;
+    let mut new_mode = None;
+    let (mut added, mut deleted, mut binary) = (false, false, false);
+
+    for line in file_header.lines() {
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            // Only used when there are no ---/+++ lines, e.g. mode changes
+            if let Some((_, b)) = paths.rsplit_once(" b/") {
+                new_path.get_or_insert_with(|| b.trim_matches('"').to_string());
+            }
+        } else if let Some(path) = line.strip_prefix("--- ") {
+            old_path = header_path(path);
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            if let Some(path) = header_path(path) {
+                new_path = Some(path);
+            }
+        } else if let Some(path) = line.strip_prefix("rename from ") {
+            rename_from = Some(path.trim_matches('"').to_string());
+        } else if let Some(path) = line.strip_prefix("rename to ") {
+            new_path = Some(path.trim_matches('"').to_string());
+        } else if let Some(mode) = line.strip_prefix("new file mode ") {
+            added = true;
+            new_mode = Some(mode.to_string());
+        } else if let Some(mode) = line.strip_prefix("deleted file mode ") {
+            deleted = true;
+            old_mode = Some(mode.to_string());
+        } else if let Some(mode) = line.strip_prefix("old mode ") {
+            old_mode = Some(mode.to_string());
+        } else if let Some(mode) = line.strip_prefix("new mode ") {
+            new_mode = Some(mode.to_string());
+        } else if line == "GIT binary patch" || line.starts_with("Binary files ") {
+            binary = true;
+        }
+    }
+
+    let status = if added {
+        FileChangeStatus::Added
+    } else if deleted {
+        FileChangeStatus::Deleted
+    } else if let Some(from) = rename_from {
+        FileChangeStatus::Renamed { from }
+    } else {
+        FileChangeStatus::Modified
+    };
+    let path = if deleted {
+        old_path.or(new_path)?
+    } else {
+        new_path.or(old_path)?
+    };
+
+    Some(FileDiff {
+        language: language_from_path(&path).map(String::from),
+        path,
+        status,
+        binary,
+        old_mode,
+        new_mode,
+        hunks: hunks
+            .into_iter()
+            .map(|hunk| DiffHunk {
+                lines: parse_lines(&hunk.body, hunk.old_start, hunk.new_start),
+                old_start: hunk.old_start,
+                old_lines: hunk.old_lines,
+                new_start: hunk.new_start,
+                new_lines: hunk.new_lines,
+            })
+            .collect(),
+    })
+}
+
+/// Split a multi-file diff into one section per file, each starting at its
+/// `diff --git` line
+pub(crate) fn split_file_sections(diff: &str) -> Vec<String> {
+    let mut sections: Vec<String> = Vec::new();
+    for line in diff.split_inclusive('\n') {
+        match sections.last_mut() {
+            Some(section) if !line.starts_with("diff --git ") => section.push_str(line),
+            _ => sections.push(line.to_string()),
+        }
+    }
+    sections
+}
+
+/// Parse a multi-file unified diff as produced by `git diff`
+pub(crate) fn parse_structured_diff(diff: &str) -> Vec<FileDiff> {
+    split_file_sections(diff)
+        .iter()
+        .filter_map(|section| parse_file(section))
+        .collect()
+}
+
+/// The session's working tree diff, untracked files included, parsed into
+/// files, hunks and lines
+#[tauri::command]
+pub async fn get_structured_diff(
+    state: State<'_, CodexState>,
+    session_id: String,
+) -> Result<Vec<FileDiff>, String> {
+    let dir = session_working_dir(&state, &session_id).await?;
+    let pathspecs = session_pathspecs(&state, &session_id).await?;
+    let diff = worktree_diff(&state.git, &dir, None, IgnoreWhitespace::None, &pathspecs).await?;
+    let files = parse_structured_diff(&diff);
+    // Files the name says nothing about are opened for their shebang, which
+    // is blocking I/O
+    tokio::task::spawn_blocking(move || {
+        let mut files = files;
+        for file in &mut files {
+            if file.language.is_some() || file.binary || file.status == FileChangeStatus::Deleted {
+                continue;
+            }
+            if let Ok(full_path) = resolve_in_dir(&dir, &file.path) {
+                file.language = detect_language(&file.path, &full_path).map(String::from);
+            }
+        }
+        files
+    })
+    .await
+    .map_err(|e| format!("Failed to detect file languages: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_structured_diff() {
+        let diff = "diff --git a/src/lib.rs b/src/lib.rs\n\
+                    index 1111111..2222222 100644\n\
+                    --- a/src/lib.rs\n\
+                    +++ b/src/lib.rs\n\
+                    @@ -1,3 +1,3 @@\n\
+                    \x20fn main() {\n\
+                    -    old();\n\
+                    +    new();\n\
+                    \x20}\n\
+                    \\ No newline at end of file\n\
+                    diff --git a/old.txt b/new.txt\n\
+                    similarity index 100%\n\
+                    rename from old.txt\n\
+                    rename to new.txt\n\
+                    diff --git a/run.sh b/run.sh\n\
+                    old mode 100644\n\
+                    new mode 100755\n\
+                    diff --git a/logo.png b/logo.png\n\
+                    new file mode 100644\n\
+                    index 0000000..3333333\n\
+                    GIT binary patch\n\
+                    literal 4\n\
+                    LcmZQz00001\n";
+        let files = parse_structured_diff(diff);
+        assert_eq!(files.len(), 4);
+
+        let lib = &files[0];
+        assert_eq!(lib.path, "src/lib.rs");
+        assert_eq!(lib.status, FileChangeStatus::Modified);
+        assert_eq!(lib.language.as_deref(), Some("rust"));
+        let lines = &lib.hunks[0].lines;
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1].kind, DiffLineKind::Removed);
+        assert_eq!((lines[1].old_lineno, lines[1].new_lineno), (Some(2), None));
+        assert_eq!(lines[2].kind, DiffLineKind::Added);
+        assert_eq!((lines[2].old_lineno, lines[2].new_lineno), (None, Some(2)));
+        assert_eq!(lines[3].content, "}");
+        assert!(lines[3].no_newline);
+
+        assert_eq!(files[1].path, "new.txt");
+        assert_eq!(
+            files[1].status,
+            FileChangeStatus::Renamed {
+                from: "old.txt".to_string()
+            }
+        );
+        assert_eq!(files[2].path, "run.sh");
+        assert_eq!(files[2].new_mode.as_deref(), Some("100755"));
+        assert_eq!(files[3].status, FileChangeStatus::Added);
+        assert!(files[3].binary);
+        assert!(files[3].hunks.is_empty());
+    }
+
+    #[test]
+    fn test_line_numbers_across_hunks() {
+        let diff = "diff --git a/src/lib.rs b/src/lib.rs\n\
+                    --- a/src/lib.rs\n\
+                    +++ b/src/lib.rs\n\
+                    @@ -1,2 +1,3 @@\n\
+                    +use std::fmt;\n\
+                    \x20mod a;\n\
+                    \x20mod b;\n\
+                    @@ -10 +11 @@\n\
+                    -fn old() {}\n\
+                    +fn new() {}\n\
+                    @@ -20,3 +21,2 @@ impl Foo {\n\
+                    \x20    a();\n\
+                    -    b();\n\
+                    -    c();\n\
+                    +    bc();\n\
+                    diff --git a/new.txt b/new.txt\n\
+                    new file mode 100644\n\
+                    --- /dev/null\n\
+                    +++ b/new.txt\n\
+                    @@ -0,0 +1,2 @@\n\
+                    +one\n\
+                    +two\n";
+        let files = parse_structured_diff(diff);
+        let numbers = |file: &FileDiff| -> Vec<(Option<u32>, Option<u32>)> {
+            file.hunks
+                .iter()
+                .flat_map(|hunk| &hunk.lines)
+                .map(|line| (line.old_lineno, line.new_lineno))
+                .collect()
+        };
+        assert_eq!(
+            numbers(&files[0]),
+            vec![
+                (None, Some(1)),
+                (Some(1), Some(2)),
+                (Some(2), Some(3)),
+                (Some(10), None),
+                (None, Some(11)),
+                (Some(20), Some(21)),
+                (Some(21), None),
+                (Some(22), None),
+                (None, Some(22)),
+            ]
+        );
+        assert_eq!(numbers(&files[1]), vec![(None, Some(1)), (None, Some(2))]);
+    }
+}
@@ -0,0 +1,411 @@
+use super::{session_pathspecs, session_working_dir};
+use crate::codex_commands::CodexState;
+use crate::filesystem::git_runner::run_git;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::State;
+use tokio::sync::Mutex;
+
+/// How long a summary answers repeated questions before git is asked again
+const SUMMARY_TTL: Duration = Duration::from_secs(2);
+
+/// How a file differs from HEAD
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum FileChangeStatus {
+    Unchanged,
+    Modified,
+    /// New in the index or untracked
+    Added,
+    Deleted,
+    Renamed { from: String },
+}
+
+/// Changed files of a repository, keyed by path relative to its root
+#[derive(Debug, Clone, Default)]
+pub(crate) struct WorktreeSummary {
+    pub root: PathBuf,
+    pub files: HashMap<String, FileChangeStatus>,
+}
+
+/// Classify a porcelain v1 `XY` code; `None` when the file ends up
+/// unchanged, e.g. added to the index and then deleted again
+fn classify(code: &str, from: Option<&str>) -> Option<FileChangeStatus> {
+    let mut chars = code.chars();
+    let (index, worktree) = (chars.next()?, chars.next()?);
+    let status = match (index, worktree) {
+        ('?', _) => FileChangeStatus::Added,
+        ('A', 'D') => return None,
+        ('R', _) => FileChangeStatus::Renamed {
+            from: from?.to_string(),
+        },
+        ('A', _) | ('C', _) => FileChangeStatus::Added,
+        ('D', _) | (_, 'D') => FileChangeStatus::Deleted,
+        _ => FileChangeStatus::Modified,
+    };
+    Some(status)
+}
+
+/// Parse `git status --porcelain=v1 -z` output. Renames and copies are
+/// "XY new\0old\0", every other entry "XY path\0".
+fn parse_porcelain(output: &str) -> HashMap<String, FileChangeStatus> {
+    let mut files = HashMap::new();
+    let mut fields = output.split('\0');
+    while let Some(entry) = fields.next() {
+        if entry.len() < 4 {
+            continue;
+        }
+        let (code, path) = (&entry[..2], &entry[3..]);
+        let from = if code.starts_with('R') || code.starts_with('C') {
+            fields.next()
+        } else {
+            None
+        };
+        if let Some(status) = classify(code, from) {
+            files.insert(path.to_string(), status);
+        }
+    }
+    files
+}
+
+/// Recent summaries by session, so an editor asking about each open file in
+/// turn costs one `git status`
+#[derive(Default)]
+pub(crate) struct WorktreeSummaryCache {
+    entries: Mutex<HashMap<String, (Instant, Arc<WorktreeSummary>)>>,
+}
+
+impl WorktreeSummaryCache {
+    pub(super) async fn get_or_load(
+        &self,
+        state: &CodexState,
+        session_id: &str,
+    ) -> Result<Arc<WorktreeSummary>, String> {
+        if let Some((loaded_at, summary)) = self.entries.lock().await.get(session_id) {
+            if loaded_at.elapsed() < SUMMARY_TTL {
+                return Ok(summary.clone());
+            }
+        }
+
+        let dir = session_working_dir(state, session_id).await?;
+        let runner = &state.git;
+        let root = run_git(runner, &dir, &["rev-parse", "--show-toplevel"]).await?;
+        let pathspecs = session_pathspecs(state, session_id).await?;
+        let mut args = vec!["status", "--porcelain=v1", "-z", "--untracked-files=all", "--"];
+        args.extend(pathspecs.iter().map(String::as_str));
+        let status = run_git(runner, &dir, &args).await?;
+        let summary = Arc::new(WorktreeSummary {
+            root: PathBuf::from(root.stdout.trim()),
+            files: parse_porcelain(&status.stdout),
+        });
+        self.entries
+            .lock()
+            .await
+            .insert(session_id.to_string(), (Instant::now(), summary.clone()));
+        Ok(summary)
+    }
+}
+
+/// `path` relative to the repository root, with `/` separators as git
+/// prints them. Relative paths are taken from the session's directory.
+fn repo_relative(root: &Path, session_dir: &Path, path: &str) -> Option<String> {
+    let absolute = session_dir.join(path);
+    let canonical_root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    // A deleted file cannot be canonicalized, so resolve its parent
+    let canonical = match absolute.canonicalize() {
+        Ok(canonical) => canonical,
+        Err(_) => absolute
+            .parent()?
+            .canonicalize()
+            .ok()?
+            .join(absolute.file_name()?),
+    };
+    let relative = canonical.strip_prefix(&canonical_root).ok()?;
+    Some(
+        relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+    )
+}
+
+/// Whether the session changed `path`, for editor decorations. Answers come
+/// from a summary of the repository's status that is reused for a couple
+/// of seconds.
+#[tauri::command]
+pub async fn is_path_modified(
+    state: State<'_, CodexState>,
+    session_id: String,
+    path: String,
+) -> Result<FileChangeStatus, String> {
+    let summary = state
+        .worktree_summaries
+        .get_or_load(&state, &session_id)
+        .await?;
+    let dir = session_working_dir(&state, &session_id).await?;
+    let relative = repo_relative(&summary.root, &dir, &path)
+        .ok_or_else(|| format!("Path {path} is outside the session's repository"))?;
+    Ok(summary
+        .files
+        .get(&relative)
+        .cloned()
+        .unwrap_or(FileChangeStatus::Unchanged))
+}
+
+/// Format `git status --porcelain=v1 -z --branch` output as a report.
+///
+/// The format is that of `git status --short --branch`, plus a totals line:
+///
+/// ```text
+/// ## main...origin/main [ahead 1]
+/// R  src/old.rs -> src/new.rs
+///  M src/lib.rs
+/// ?? notes.md
+/// # 3 changed: 1 staged, 1 unstaged, 1 untracked
+/// ```
+///
+/// Entries are sorted by path and never quoted. A file with staged and
+/// unstaged changes counts towards both.
+fn format_status_report(output: &str) -> String {
+    let mut branch = None;
+    let mut entries: Vec<(String, String)> = Vec::new();
+    let mut fields = output.split('\0');
+    while let Some(entry) = fields.next() {
+        if let Some(header) = entry.strip_prefix("## ") {
+            branch = Some(header.to_string());
+            continue;
+        }
+        if entry.len() < 4 {
+            continue;
+        }
+        let (code, path) = (&entry[..2], &entry[3..]);
+        let shown = if code.starts_with('R') || code.starts_with('C') {
+            match fields.next() {
+                Some(from) => format!("{from} -> {path}"),
+                None => path.to_string(),
+            }
+        } else {
+            path.to_string()
+        };
+        entries.push((code.to_string(), shown));
+    }
+    entries.sort_by(|a, b| a.1.cmp(&b.1));
+
+    let untracked = entries.iter().filter(|(code, _)| code == "??").count();
+    let staged = entries
+        .iter()
+        .filter(|(code, _)| code != "??" && !code.starts_with(' '))
+        .count();
+    let unstaged = entries
+        .iter()
+        .filter(|(code, _)| code != "??" && !code.ends_with(' '))
+        .count();
+
+    let mut report = format!("## {}\n", branch.as_deref().unwrap_or("HEAD (no branch)"));
+    for (code, path) in &entries {
+        report.push_str(&format!("{code} {path}\n"));
+    }
+    report.push_str(&format!(
+        "# {} changed: {staged} staged, {unstaged} unstaged, {untracked} untracked\n",
+        entries.len()
+    ));
+    report
+}
+
+/// The session's working tree status as text, in the format documented on
+/// `format_status_report`, for display or pasting into an issue. Only the
+/// session's allowed paths are listed.
+#[tauri::command]
+pub async fn worktree_status_report(
+    state: State<'_, CodexState>,
+    session_id: String,
+) -> Result<String, String> {
+    let dir = session_working_dir(&state, &session_id).await?;
+    let pathspecs = session_pathspecs(&state, &session_id).await?;
+    let mut args = vec![
+        "status",
+        "--porcelain=v1",
+        "-z",
+        "--branch",
+        "--untracked-files=all",
+        "--",
+    ];
+    args.extend(pathspecs.iter().map(String::as_str));
+    let status = run_git(&state.git, &dir, &args).await?;
+    Ok(format_status_report(&status.stdout))
+}
+
+/// Whether the session's working tree, within its allowed paths, has no
+/// changes relative to HEAD. Untracked files count as changes unless
+/// `ignore_untracked` is set.
+#[tauri::command]
+pub async fn is_worktree_clean(
+    state: State<'_, CodexState>,
+    session_id: String,
+    ignore_untracked: Option<bool>,
+) -> Result<bool, String> {
+    let dir = session_working_dir(&state, &session_id).await?;
+    // `normal` lists an untracked directory once instead of walking it
+    let untracked = if ignore_untracked.unwrap_or(false) {
+        "--untracked-files=no"
+    } else {
+        "--untracked-files=normal"
+    };
+    let pathspecs = session_pathspecs(&state, &session_id).await?;
+    let mut args = vec!["status", "--porcelain=v1", untracked, "--"];
+    args.extend(pathspecs.iter().map(String::as_str));
+    let status = run_git(&state.git, &dir, &args).await?;
+    Ok(status.stdout.trim().is_empty())
+}
+
+/// Paths in `git status --porcelain=v2 -z` output. Ordinary entries carry
+/// 8 fields before the path, renames and copies 9 followed by the old path,
+/// unmerged entries 10; untracked and ignored ones only the path.
+fn porcelain_v2_paths(output: &str) -> Vec<&str> {
+    let mut paths = Vec::new();
+    let mut fields = output.split('\0');
+    while let Some(entry) = fields.next() {
+        let path = match entry.split_once(' ') {
+            Some(("1", _)) => entry.splitn(9, ' ').nth(8),
+            Some(("2", _)) => {
+                let path = entry.splitn(10, ' ').nth(9);
+                // Skip the old path
+                let _ = fields.next();
+                path
+            }
+            Some(("u", _)) => entry.splitn(11, ' ').nth(10),
+            Some(("?", path)) | Some(("!", path)) => Some(path),
+            _ => None,
+        };
+        paths.extend(path);
+    }
+    paths
+}
+
+/// 64-bit FNV-1a, stable across runs and Rust versions unlike std's hasher
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// A key that changes whenever the session's working tree does: the
+/// status of every file plus the size and modification time of the ones
+/// that differ from HEAD, since editing a modified file again leaves its
+/// status as is. Equal keys mean cached diffs are still valid.
+#[tauri::command]
+pub async fn worktree_fingerprint(
+    state: State<'_, CodexState>,
+    session_id: String,
+) -> Result<String, String> {
+    let dir = session_working_dir(&state, &session_id).await?;
+    let runner = &state.git;
+    let root = run_git(runner, &dir, &["rev-parse", "--show-toplevel"]).await?;
+    let root = PathBuf::from(root.stdout.trim());
+
+    let porcelain_v2 = runner.capabilities().await.porcelain_v2;
+    let format = if porcelain_v2 {
+        "--porcelain=v2"
+    } else {
+        "--porcelain=v1"
+    };
+    // `--branch` brings in the HEAD commit, so a commit changes the key too
+    let pathspecs = session_pathspecs(&state, &session_id).await?;
+    let mut args = vec!["status", format, "--branch", "-z", "--untracked-files=all", "--"];
+    args.extend(pathspecs.iter().map(String::as_str));
+    let status = run_git(runner, &dir, &args).await?;
+    let mut paths: Vec<String> = if porcelain_v2 {
+        porcelain_v2_paths(&status.stdout)
+            .into_iter()
+            .map(String::from)
+            .collect()
+    } else {
+        // Leave out the "## branch" entry
+        let entries = match status.stdout.strip_prefix("## ") {
+            Some(rest) => rest.split_once('\0').map_or("", |(_, entries)| entries),
+            None => &status.stdout,
+        };
+        parse_porcelain(entries).into_keys().collect()
+    };
+    paths.sort();
+
+    // One stat per changed file; a large change set on a slow disk must not
+    // hold up the async runtime
+    let hash = tokio::task::spawn_blocking(move || {
+        let mut hash = fnv1a(FNV_OFFSET, status.stdout.as_bytes());
+        for path in &paths {
+            let Ok(meta) = std::fs::symlink_metadata(root.join(path)) else {
+                continue;
+            };
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+                .map_or(0, |since| since.as_nanos());
+            hash = fnv1a(hash, path.as_bytes());
+            hash = fnv1a(hash, &meta.len().to_le_bytes());
+            hash = fnv1a(hash, &modified.to_le_bytes());
+        }
+        hash
+    })
+    .await
+    .map_err(|e| format!("Failed to fingerprint the working tree: {e}"))?;
+    Ok(format!("{hash:016x}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_porcelain() {
+        let output = " M src/lib.rs\0R  src/new.rs\0src/old.rs\0\
+                      ?? notes.md\0 D gone.rs\0AD tmp.rs\0";
+        let files = parse_porcelain(output);
+        assert_eq!(files["src/lib.rs"], FileChangeStatus::Modified);
+        assert_eq!(
+            files["src/new.rs"],
+            FileChangeStatus::Renamed {
+                from: "src/old.rs".to_string()
+            }
+        );
+        assert_eq!(files["notes.md"], FileChangeStatus::Added);
+        assert_eq!(files["gone.rs"], FileChangeStatus::Deleted);
+        assert!(!files.contains_key("tmp.rs"));
+        assert!(!files.contains_key("src/old.rs"));
+    }
+
+    #[test]
+    fn test_porcelain_v2_paths() {
+        let output = "# branch.oid 4f2c1b0e\0\
+                      1 .M N... 100644 100644 100644 aaaa bbbb src/my lib.rs\0\
+                      2 R. N... 100644 100644 100644 aaaa aaaa R100 src/new.rs\0src/old.rs\0\
+                      ? notes.md\0";
+        assert_eq!(
+            porcelain_v2_paths(output),
+            vec!["src/my lib.rs", "src/new.rs", "notes.md"]
+        );
+        assert_ne!(fnv1a(FNV_OFFSET, b"a"), fnv1a(FNV_OFFSET, b"b"));
+    }
+
+    #[test]
+    fn test_format_status_report() {
+        let output = "## main...origin/main [ahead 1]\0 M src/lib.rs\0\
+                      R  src/new.rs\0src/old.rs\0?? notes.md\0MM both.rs\0";
+        assert_eq!(
+            format_status_report(output),
+            "## main...origin/main [ahead 1]\n\
+             MM both.rs\n\
+             ?? notes.md\n\
+             \x20M src/lib.rs\n\
+             R  src/old.rs -> src/new.rs\n\
+             # 4 changed: 2 staged, 2 unstaged, 1 untracked\n"
+        );
+    }
+}
@@ -18,7 +18,31 @@ use filesystem::{
     file_io::{read_file, read_text_file_lines, write_file},
     file_parsers::{csv::read_csv_content, pdf::read_pdf_content, xlsx::read_xlsx_content},
     git_diff::get_git_file_diff,
+    git_runner::{git_capabilities, set_git_concurrency, set_git_timeout},
     git_status::get_git_status,
+    session_git::{
+        authorship::get_file_authorship,
+        blame::blame_region,
+        branch::get_branch_status,
+        checkpoints::restore_turn_checkpoint,
+        conflicts::detect_conflicts,
+        coverage::diff_test_coverage,
+        diff::{
+            diff_refs, diff_sessions, get_file_diff, git_worktree_diff, list_changed_files,
+            revert_files, save_worktree_patch,
+        },
+        entries::git_tracked_entries,
+        generated::{get_generated_file_rules, set_generated_file_rules, snapshot_worktree_summary},
+        hunks::{get_file_diff_range, get_file_hunks, revert_file_diff, revert_hunks},
+        minimize::minimize_diff,
+        preview::{read_new_files, read_worktree_file},
+        relocate::relocate_session,
+        staging::{get_staging_status, stage_files, unstage_files},
+        structured::get_structured_diff,
+        summary::{
+            is_path_modified, is_worktree_clean, worktree_fingerprint, worktree_status_report,
+        },
+    },
     git_worktree::{
         apply_reverse_patch, commit_changes_to_worktree, delete_git_worktree, git_commit_changes,
         prepare_git_worktree,
@@ -69,12 +93,27 @@ pub fn run() {
         .manage(RemoteAccessState::default())
         .manage(WatchState::new())
         .manage(SleepState::default())
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Destroyed = event {
+                let codex_state = window.state::<CodexState>();
+                codex_state.event_filters.remove_window(window.label());
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             // Codexia native commands
             codex_commands::check::check_codex_version,
             codex_commands::check::check_coder_version,
+            codex_commands::check::check_codex_auth,
+            codex_commands::check_codex_update,
+            codex_commands::preflight_check,
+            codex_commands::validate_config,
             codex_commands::check::get_client_name,
             codex_commands::check::set_client_name,
+            codex_commands::check::get_codex_config_file,
+            codex_commands::check::set_codex_config_file,
+            codex_commands::check::get_codex_proxy,
+            codex_commands::check::set_codex_proxy,
+            codex_commands::check::get_protocol_mismatch,
             commands::window::create_new_window,
             read_directory,
             get_default_directories,
@@ -89,6 +128,42 @@ pub fn run() {
             read_xlsx_content,
             get_git_file_diff,
             get_git_status,
+            set_git_timeout,
+            git_capabilities,
+            set_git_concurrency,
+            diff_refs,
+            git_worktree_diff,
+            save_worktree_patch,
+            list_changed_files,
+            get_file_diff,
+            revert_files,
+            get_structured_diff,
+            diff_sessions,
+            git_tracked_entries,
+            snapshot_worktree_summary,
+            get_generated_file_rules,
+            set_generated_file_rules,
+            is_path_modified,
+            worktree_status_report,
+            is_worktree_clean,
+            worktree_fingerprint,
+            relocate_session,
+            restore_turn_checkpoint,
+            stage_files,
+            unstage_files,
+            get_staging_status,
+            get_file_hunks,
+            get_file_diff_range,
+            get_file_authorship,
+            blame_region,
+            revert_hunks,
+            revert_file_diff,
+            minimize_diff,
+            diff_test_coverage,
+            detect_conflicts,
+            get_branch_status,
+            read_worktree_file,
+            read_new_files,
             prepare_git_worktree,
             git_commit_changes,
             apply_reverse_patch,
@@ -104,6 +179,7 @@ pub fn run() {
             codex_commands::add_mcp_server,
             codex_commands::delete_mcp_server,
             codex_commands::set_mcp_server_enabled,
+            codex_commands::test_mcp_server,
             codex_commands::read_model_providers,
             codex_commands::read_profiles,
             codex_commands::get_provider_config,
@@ -116,25 +192,87 @@ pub fn run() {
             commands::remote::disable_remote_ui,
             commands::remote::get_remote_ui_status,
             codex_commands::send_user_message,
+            codex_commands::send_message_with_files,
+            codex_commands::send_message_with_options,
             codex_commands::turn_start,
             codex_commands::new_conversation,
             codex_commands::resume_conversation,
+            codex_commands::set_session_config,
+            codex_commands::keep_alive,
+            codex_commands::attach_observer,
+            codex_commands::set_event_filter,
+            codex_commands::detach_observer,
+            codex_commands::archive_session,
+            codex_commands::list_archived_sessions,
+            codex_commands::get_session_metadata,
+            codex_commands::get_working_dir_disk_status,
+            codex_commands::get_session_tools,
+            codex_commands::get_session_events_since,
+            codex_commands::set_session_log_level,
+            codex_commands::get_session_logs,
+            codex_commands::get_last_exec_output,
+            codex_commands::get_out_of_tree_changes,
+            codex_commands::get_session_last_error,
+            codex_commands::clear_session_error,
+            codex_commands::get_rate_limit_status,
+            codex_commands::get_turn_timings,
+            codex_commands::get_latency_stats,
+            codex_commands::get_proposed_commands,
+            codex_commands::set_retain_command_history,
+            codex_commands::set_transcript_flush_interval,
+            codex_commands::get_session_startup_log,
+            codex_commands::get_session_log_path,
+            codex_commands::set_session_log_rotation,
+            codex_commands::list_session_mcp_servers,
+            codex_commands::update_session_instructions,
+            codex_commands::close_session,
+            codex_commands::interrupt_all_sessions,
+            codex_commands::interrupt_and_close,
+            codex_commands::recover_session,
+            codex_commands::close_all_sessions,
+            codex_commands::set_session_pinned,
+            codex_commands::get_session_process_info,
+            codex_commands::get_runtime_session_config,
+            codex_commands::get_sandbox_policy,
+            codex_commands::compact_session,
+            codex_commands::fork_session,
+            codex_commands::replay_transcript,
+            codex_commands::export_session_bundle,
+            codex_commands::resync_session,
+            codex_commands::regenerate_last_turn,
+            codex_commands::run_project_tests,
+            codex_commands::enqueue_followup,
+            codex_commands::get_suggested_actions,
+            codex_commands::get_followups,
+            codex_commands::cancel_followup,
             codex_commands::interrupt_conversation,
             codex_commands::respond_exec_command_request,
             codex_commands::respond_apply_patch_request,
+            codex_commands::get_approval_details,
+            codex_commands::continue_turn,
+            codex_commands::codex_oneshot,
+            codex_commands::list_prompt_templates,
+            codex_commands::save_prompt_template,
+            codex_commands::send_template,
             codex_commands::get_account,
             codex_commands::login_account_chatgpt,
             codex_commands::login_account_api_key,
             codex_commands::cancel_login_account,
             codex_commands::logout_account,
+            codex_commands::list_codex_accounts,
+            codex_commands::switch_codex_account,
             codex_commands::add_conversation_listener,
             codex_commands::remove_conversation_listener,
             codex_commands::get_account_rate_limits,
             codex_commands::initialize_client,
+            codex_commands::set_startup_timeout,
+            codex_commands::set_output_buffer,
             commands::file::delete_file,
             commands::env::set_system_env,
             commands::env::get_system_env,
             codex_commands::scan_projects,
+            codex_commands::list_codex_rollouts,
+            codex_commands::load_session_transcript,
             codex_commands::load_project_sessions,
             codex_commands::update_cache_title,
             commands::terminal::open_terminal_with_command,
@@ -390,10 +528,32 @@ pub fn run() {
 
             // Setup event bridge between codex-client and Tauri
             let codex_state = app.state::<CodexState>();
+            codex_state.client_state.sessions.set_turn_hook(std::sync::Arc::new(
+                filesystem::session_git::checkpoints::TurnCheckpoints::new(&codex_state),
+            ));
             codex_commands::setup_event_bridge(
                 app.handle().clone(),
                 codex_state.client_state.clone(),
+                codex_state.event_filters.clone(),
             );
+            tauri::async_runtime::spawn(codex_client::session::watch_idle_sessions(
+                codex_state.client_state.clone(),
+            ));
+            tauri::async_runtime::spawn(codex_client::session::watch_disk_space(
+                codex_state.client_state.clone(),
+            ));
+            tauri::async_runtime::spawn(codex_client::session::watch_followups(
+                codex_state.client_state.clone(),
+            ));
+            // Bring back the sessions of the previous run before saving over
+            // their map
+            let client_state = codex_state.client_state.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(err) = codex_client::session::restore_sessions(&client_state).await {
+                    log::warn!("Failed to restore sessions: {}", err);
+                }
+                codex_client::session::watch_session_map(client_state).await;
+            });
 
             #[cfg(debug_assertions)]
             {